@@ -0,0 +1,66 @@
+//! Frame data delivery benchmarks
+//!
+//! Compares the per-frame cost of handing captured pixels to a consumer with
+//! a fresh allocation per frame (`FrameDataMode::Copy`) against storage
+//! recycled through a `FramePool` (`FrameDataMode::Pooled`). Zero-copy mode
+//! needs a live PipeWire stream and is not covered here.
+//!
+//! Run with `cargo bench -p lamco-pipewire --bench frame_data`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lamco_pipewire::{FrameData, FramePool, PixelFormat, PooledBuffer, VideoFrame};
+
+/// Frame sizes (width, height) benchmarked, BGRx
+const SIZES: [(u32, u32); 3] = [(1280, 720), (1920, 1080), (3840, 2160)];
+
+/// Build a frame the way the capture callback does, from a mapped PipeWire chunk
+fn deliver(storage: PooledBuffer, chunk: &[u8], width: u32, height: u32) -> VideoFrame {
+    let mut storage = storage;
+    storage.extend_from_slice(chunk);
+    VideoFrame {
+        data: FrameData::from(storage),
+        ..VideoFrame::new(0, width, height, width * 4, PixelFormat::BGRx, 0)
+    }
+}
+
+fn bench_frame_delivery(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_delivery");
+
+    for (width, height) in SIZES {
+        let chunk = vec![0x5Au8; (width * height * 4) as usize];
+        let label = format!("{}x{}", width, height);
+        group.throughput(Throughput::Bytes(chunk.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("copy", &label), &chunk, |b, chunk| {
+            b.iter(|| {
+                let frame = deliver(PooledBuffer::detached(chunk.len()), chunk, width, height);
+                black_box(frame.data.len());
+            });
+        });
+
+        let pool = FramePool::new(4);
+        group.bench_with_input(BenchmarkId::new("pooled", &label), &chunk, |b, chunk| {
+            b.iter(|| {
+                let frame = deliver(pool.acquire(chunk.len()), chunk, width, height);
+                black_box(frame.data.len());
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_frame_fanout(c: &mut Criterion) {
+    // Frames are cloned per consumer (router observers, damage tracking);
+    // clones share the storage instead of copying it
+    let pool = FramePool::new(4);
+    let chunk = vec![0x5Au8; 1920 * 1080 * 4];
+    let frame = deliver(pool.acquire(chunk.len()), &chunk, 1920, 1080);
+
+    c.bench_function("frame_clone_1080p", |b| {
+        b.iter(|| black_box(frame.clone()));
+    });
+}
+
+criterion_group!(benches, bench_frame_delivery, bench_frame_fanout);
+criterion_main!(benches);
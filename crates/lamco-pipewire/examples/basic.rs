@@ -55,9 +55,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // use lamco_portal::PortalManager;
     //
     // let portal = PortalManager::with_default().await?;
-    // let session = portal.create_session("example".to_string(), None).await?;
+    // let mut session = portal.create_session("example".to_string(), None).await?;
     //
-    // manager.connect(session.pipewire_fd()).await?;
+    // manager.connect(session.take_pipewire_fd().expect("FD not taken yet")).await?;
     //
     // for stream in session.streams() {
     //     let info = StreamInfo {
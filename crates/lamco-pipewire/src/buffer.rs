@@ -1,12 +1,13 @@
 //! Buffer Management
 //!
-//! Manages PipeWire buffers including DMA-BUF and memory-mapped buffers.
+//! Manages PipeWire buffers including DMA-BUF and memory-mapped buffers,
+//! and the pool of reusable storage that captured frames are copied into.
 
 use crate::error::{PipeWireError, Result};
 use crate::ffi::SpaDataType;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::os::fd::RawFd;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex, Weak};
 use std::time::SystemTime;
 use tokio::sync::Mutex;
 
@@ -470,6 +471,186 @@ impl SharedBufferManager {
     }
 }
 
+/// Frame pool statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramePoolStats {
+    /// Buffers handed out
+    pub acquisitions: u64,
+
+    /// Acquisitions served by a recycled buffer without allocating
+    pub reuses: u64,
+
+    /// Acquisitions that allocated (empty pool or recycled buffer too small)
+    pub allocations: u64,
+
+    /// Buffers returned to the pool on drop
+    pub returns: u64,
+
+    /// Buffers freed on drop because the pool was full
+    pub discards: u64,
+}
+
+/// Shared state of a [`FramePool`]
+struct FramePoolInner {
+    /// Recycled buffers, ready for reuse
+    free: StdMutex<Vec<Vec<u8>>>,
+
+    /// Maximum number of recycled buffers kept
+    max_free: usize,
+
+    /// Statistics
+    stats: StdMutex<FramePoolStats>,
+}
+
+/// Pool of reusable frame storage
+///
+/// Frame data is copied into buffers taken from the pool. When the last
+/// reference to a [`PooledBuffer`] goes away its storage returns to the pool
+/// instead of being freed, so steady-state capture does not allocate.
+///
+/// Cloning yields another handle to the same pool.
+#[derive(Clone)]
+pub struct FramePool {
+    inner: Arc<FramePoolInner>,
+}
+
+impl FramePool {
+    /// Create a pool keeping at most `max_free` recycled buffers
+    pub fn new(max_free: usize) -> Self {
+        Self {
+            inner: Arc::new(FramePoolInner {
+                free: StdMutex::new(Vec::with_capacity(max_free)),
+                max_free,
+                stats: StdMutex::new(FramePoolStats::default()),
+            }),
+        }
+    }
+
+    /// Take an empty buffer with room for at least `capacity` bytes
+    pub fn acquire(&self, capacity: usize) -> PooledBuffer {
+        let recycled = self.inner.free.lock().unwrap().pop();
+
+        let mut stats = self.inner.stats.lock().unwrap();
+        stats.acquisitions += 1;
+        let data = match recycled {
+            Some(data) if data.capacity() >= capacity => {
+                stats.reuses += 1;
+                data
+            }
+            Some(mut data) => {
+                stats.allocations += 1;
+                data.reserve(capacity);
+                data
+            }
+            None => {
+                stats.allocations += 1;
+                Vec::with_capacity(capacity)
+            }
+        };
+
+        PooledBuffer {
+            data,
+            pool: Some(Arc::downgrade(&self.inner)),
+        }
+    }
+
+    /// Number of recycled buffers ready for reuse
+    pub fn free_count(&self) -> usize {
+        self.inner.free.lock().unwrap().len()
+    }
+
+    /// Get pool statistics
+    pub fn stats(&self) -> FramePoolStats {
+        *self.inner.stats.lock().unwrap()
+    }
+}
+
+impl std::fmt::Debug for FramePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FramePool")
+            .field("max_free", &self.inner.max_free)
+            .field("free", &self.free_count())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// Buffer taken from a [`FramePool`]
+///
+/// Dereferences to the underlying `Vec<u8>`. The storage is cleared and
+/// returned to its pool on drop; buffers outliving their pool are freed.
+pub struct PooledBuffer {
+    data: Vec<u8>,
+    pool: Option<Weak<FramePoolInner>>,
+}
+
+impl PooledBuffer {
+    /// Create a buffer that is not part of any pool (freed on drop)
+    pub fn detached(capacity: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity),
+            pool: None,
+        }
+    }
+
+    /// Whether the storage returns to a pool on drop
+    pub fn is_pooled(&self) -> bool {
+        self.pool.is_some()
+    }
+}
+
+impl std::ops::Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.data
+    }
+}
+
+impl std::ops::DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+}
+
+impl std::fmt::Debug for PooledBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledBuffer")
+            .field("len", &self.data.len())
+            .field("capacity", &self.data.capacity())
+            .field("pooled", &self.is_pooled())
+            .finish()
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let Some(pool) = self.pool.take().and_then(|pool| pool.upgrade()) else {
+            return;
+        };
+
+        let mut data = std::mem::take(&mut self.data);
+        data.clear();
+
+        let returned = {
+            let mut free = pool.free.lock().unwrap();
+            if free.len() < pool.max_free {
+                free.push(data);
+                true
+            } else {
+                false
+            }
+        };
+
+        let mut stats = pool.stats.lock().unwrap();
+        if returned {
+            stats.returns += 1;
+        } else {
+            stats.discards += 1;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -530,6 +711,51 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_frame_pool_recycles_storage() {
+        let pool = FramePool::new(2);
+
+        let mut buffer = pool.acquire(1024);
+        buffer.extend_from_slice(&[1, 2, 3]);
+        let ptr = buffer.as_ptr();
+        drop(buffer);
+        assert_eq!(pool.free_count(), 1);
+
+        // Same allocation comes back, emptied
+        let buffer = pool.acquire(512);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), ptr);
+        drop(buffer);
+
+        let stats = pool.stats();
+        assert_eq!(stats.acquisitions, 2);
+        assert_eq!(stats.allocations, 1);
+        assert_eq!(stats.reuses, 1);
+        assert_eq!(stats.returns, 2);
+    }
+
+    #[test]
+    fn test_frame_pool_limits() {
+        let pool = FramePool::new(1);
+
+        let first = pool.acquire(16);
+        let second = pool.acquire(16);
+        drop(first);
+        drop(second);
+        assert_eq!(pool.free_count(), 1);
+        assert_eq!(pool.stats().discards, 1);
+
+        // Too small recycled buffers grow instead of being reused as-is
+        let buffer = pool.acquire(4096);
+        assert!(buffer.capacity() >= 4096);
+        assert_eq!(pool.stats().allocations, 3);
+
+        // Buffers outliving the pool, and detached buffers, are just freed
+        drop(pool);
+        drop(buffer);
+        assert!(!PooledBuffer::detached(16).is_pooled());
+    }
+
     #[tokio::test]
     async fn test_shared_buffer_manager() {
         let mgr = SharedBufferManager::new(5);
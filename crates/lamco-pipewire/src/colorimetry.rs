@@ -0,0 +1,351 @@
+//! Colorimetry
+//!
+//! Which colour matrix and quantization range a YUV frame was encoded
+//! with. Converting with the wrong pair shifts colours visibly: BT.709
+//! content decoded as BT.601 comes out with reds too orange and greens too
+//! yellow, and full-range content decoded as limited loses contrast.
+//!
+//! Producers announce both in the negotiated video info; see
+//! [`Colorimetry::from_spa`]. When they don't, the conventional choice for
+//! the frame size is used (BT.709 for HD, BT.601 for SD, limited range).
+//!
+//! # Usage
+//!
+//! ```rust
+//! use lamco_pipewire::colorimetry::{ColorMatrix, ColorRange, Colorimetry};
+//!
+//! let colorimetry = Colorimetry::new(ColorMatrix::Bt709, ColorRange::Full);
+//! assert_eq!(Colorimetry::for_size(1920, 1080), Colorimetry::BT709);
+//! assert_ne!(colorimetry, Colorimetry::BT709);
+//! ```
+
+use crate::ffi::spa_sys;
+
+/// YUV colour matrix (luma coefficients)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ColorMatrix {
+    /// ITU-R BT.601 (SD video)
+    #[default]
+    Bt601,
+    /// ITU-R BT.709 (HD video)
+    Bt709,
+    /// ITU-R BT.2020 (UHD and HDR video)
+    Bt2020,
+}
+
+impl ColorMatrix {
+    /// Map an SPA `spa_video_color_matrix` value
+    ///
+    /// FCC and SMPTE 240M map to the nearest supported matrix (BT.601 and
+    /// BT.709 respectively).
+    ///
+    /// # Returns
+    ///
+    /// `None` for unknown values and the identity (RGB) matrix
+    pub fn from_spa(value: u32) -> Option<Self> {
+        match value {
+            spa_sys::SPA_VIDEO_COLOR_MATRIX_BT601 | spa_sys::SPA_VIDEO_COLOR_MATRIX_FCC => Some(Self::Bt601),
+            spa_sys::SPA_VIDEO_COLOR_MATRIX_BT709 | spa_sys::SPA_VIDEO_COLOR_MATRIX_SMPTE240M => Some(Self::Bt709),
+            spa_sys::SPA_VIDEO_COLOR_MATRIX_BT2020 => Some(Self::Bt2020),
+            _ => None,
+        }
+    }
+
+    /// Matching SPA `spa_video_color_matrix` value
+    pub fn to_spa(self) -> u32 {
+        match self {
+            Self::Bt601 => spa_sys::SPA_VIDEO_COLOR_MATRIX_BT601,
+            Self::Bt709 => spa_sys::SPA_VIDEO_COLOR_MATRIX_BT709,
+            Self::Bt2020 => spa_sys::SPA_VIDEO_COLOR_MATRIX_BT2020,
+        }
+    }
+
+    /// Conventional matrix for a frame size
+    ///
+    /// BT.709 above 576 lines (HD), BT.601 otherwise.
+    pub fn for_size(_width: u32, height: u32) -> Self {
+        if height > 576 {
+            Self::Bt709
+        } else {
+            Self::Bt601
+        }
+    }
+
+    /// Luma weights of red and blue (`Kr`, `Kb`)
+    fn weights(self) -> (f64, f64) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+}
+
+/// YUV quantization range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ColorRange {
+    /// Limited ("TV" or "studio") range: Y in 16..=235, U and V in 16..=240
+    #[default]
+    Limited,
+    /// Full ("PC") range: every sample uses 0..=255
+    Full,
+}
+
+impl ColorRange {
+    /// Map an SPA `spa_video_color_range` value
+    ///
+    /// # Returns
+    ///
+    /// `None` for unknown values
+    pub fn from_spa(value: u32) -> Option<Self> {
+        match value {
+            spa_sys::SPA_VIDEO_COLOR_RANGE_16_235 => Some(Self::Limited),
+            spa_sys::SPA_VIDEO_COLOR_RANGE_0_255 => Some(Self::Full),
+            _ => None,
+        }
+    }
+
+    /// Matching SPA `spa_video_color_range` value
+    pub fn to_spa(self) -> u32 {
+        match self {
+            Self::Limited => spa_sys::SPA_VIDEO_COLOR_RANGE_16_235,
+            Self::Full => spa_sys::SPA_VIDEO_COLOR_RANGE_0_255,
+        }
+    }
+}
+
+/// Colour matrix and range of a YUV frame
+///
+/// The default is BT.601 limited range, which the conversions used before
+/// colorimetry was tracked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Colorimetry {
+    /// Colour matrix
+    pub matrix: ColorMatrix,
+
+    /// Quantization range
+    pub range: ColorRange,
+}
+
+impl Colorimetry {
+    /// BT.601, limited range
+    pub const BT601: Self = Self::new(ColorMatrix::Bt601, ColorRange::Limited);
+
+    /// BT.709, limited range
+    pub const BT709: Self = Self::new(ColorMatrix::Bt709, ColorRange::Limited);
+
+    /// BT.2020, limited range
+    pub const BT2020: Self = Self::new(ColorMatrix::Bt2020, ColorRange::Limited);
+
+    /// Create from a matrix and range
+    pub const fn new(matrix: ColorMatrix, range: ColorRange) -> Self {
+        Self { matrix, range }
+    }
+
+    /// Conventional colorimetry for a frame size (see [`ColorMatrix::for_size`]), limited range
+    pub fn for_size(width: u32, height: u32) -> Self {
+        Self::new(ColorMatrix::for_size(width, height), ColorRange::Limited)
+    }
+
+    /// Build from the SPA video info of a negotiated format
+    ///
+    /// `color_matrix` and `color_range` are the `spa_video_color_matrix` and
+    /// `spa_video_color_range` values; whichever is unknown falls back to
+    /// [`Colorimetry::for_size`].
+    pub fn from_spa(color_matrix: u32, color_range: u32, width: u32, height: u32) -> Self {
+        let fallback = Self::for_size(width, height);
+        Self {
+            matrix: ColorMatrix::from_spa(color_matrix).unwrap_or(fallback.matrix),
+            range: ColorRange::from_spa(color_range).unwrap_or(fallback.range),
+        }
+    }
+
+    /// Fixed-point conversion coefficients
+    pub(crate) fn coefficients(self) -> YuvCoefficients {
+        YuvCoefficients::new(self)
+    }
+}
+
+/// Integer YUV <-> RGB coefficients with 8 fractional bits
+///
+/// Decoding computes `(y_scale * (y - y_offset) + k * (u - 128) + ... + 128) >> 8`
+/// per channel; encoding computes `((k_r * r + k_g * g + k_b * b + 128) >> 8)`
+/// plus the luma offset or 128. The SIMD kernels use the same values, so
+/// both give identical results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct YuvCoefficients {
+    /// Luma black level (16 limited, 0 full)
+    pub(crate) y_offset: i16,
+    /// Luma gain
+    pub(crate) y_scale: i16,
+    /// V contribution to red
+    pub(crate) r_v: i16,
+    /// U contribution to green (negative)
+    pub(crate) g_u: i16,
+    /// V contribution to green (negative)
+    pub(crate) g_v: i16,
+    /// U contribution to blue
+    pub(crate) b_u: i16,
+    /// Red, green and blue weights of Y
+    pub(crate) y_rgb: [i16; 3],
+    /// Red, green and blue weights of U
+    pub(crate) u_rgb: [i16; 3],
+    /// Red, green and blue weights of V
+    pub(crate) v_rgb: [i16; 3],
+}
+
+impl YuvCoefficients {
+    /// Derive the coefficients of a colorimetry
+    ///
+    /// Each row of encode weights is balanced on its green term so that
+    /// white encodes to peak luma and grays carry no chroma.
+    fn new(colorimetry: Colorimetry) -> Self {
+        let (kr, kb) = colorimetry.matrix.weights();
+        let kg = 1.0 - kr - kb;
+        // Luma and chroma excursions relative to full range
+        let (luma, chroma) = match colorimetry.range {
+            ColorRange::Limited => (219.0 / 255.0, 224.0 / 255.0),
+            ColorRange::Full => (1.0, 1.0),
+        };
+        let fixed = |value: f64| (value * 256.0).round() as i16;
+
+        let y_r = fixed(kr * luma);
+        let y_b = fixed(kb * luma);
+        let u_r = fixed(-kr / (2.0 * (1.0 - kb)) * chroma);
+        let u_b = fixed(0.5 * chroma);
+        let v_r = u_b;
+        let v_b = fixed(-kb / (2.0 * (1.0 - kr)) * chroma);
+
+        Self {
+            y_offset: match colorimetry.range {
+                ColorRange::Limited => 16,
+                ColorRange::Full => 0,
+            },
+            y_scale: fixed(1.0 / luma),
+            r_v: fixed(2.0 * (1.0 - kr) / chroma),
+            g_u: fixed(-2.0 * kb * (1.0 - kb) / kg / chroma),
+            g_v: fixed(-2.0 * kr * (1.0 - kr) / kg / chroma),
+            b_u: fixed(2.0 * (1.0 - kb) / chroma),
+            y_rgb: [y_r, fixed(luma) - y_r - y_b, y_b],
+            u_rgb: [u_r, -u_r - u_b, u_b],
+            v_rgb: [v_r, -v_r - v_b, v_b],
+        }
+    }
+
+    /// Convert one YUV sample to RGB
+    #[inline]
+    pub(crate) fn yuv_to_rgb(&self, y: i32, u: i32, v: i32) -> (u8, u8, u8) {
+        let c = (y - i32::from(self.y_offset)) * i32::from(self.y_scale);
+        let d = u - 128;
+        let e = v - 128;
+
+        let r = (c + i32::from(self.r_v) * e + 128) >> 8;
+        let g = (c + i32::from(self.g_u) * d + i32::from(self.g_v) * e + 128) >> 8;
+        let b = (c + i32::from(self.b_u) * d + 128) >> 8;
+
+        (r.clamp(0, 255) as u8, g.clamp(0, 255) as u8, b.clamp(0, 255) as u8)
+    }
+
+    /// Convert one RGB pixel to YUV
+    #[inline]
+    pub(crate) fn rgb_to_yuv(&self, r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+        let weigh = |[kr, kg, kb]: [i16; 3]| {
+            (i32::from(kr) * i32::from(r) + i32::from(kg) * i32::from(g) + i32::from(kb) * i32::from(b) + 128) >> 8
+        };
+        let y = weigh(self.y_rgb) + i32::from(self.y_offset);
+        let u = weigh(self.u_rgb) + 128;
+        let v = weigh(self.v_rgb) + 128;
+
+        (y.clamp(0, 255) as u8, u.clamp(0, 255) as u8, v.clamp(0, 255) as u8)
+    }
+}
+
+impl Default for YuvCoefficients {
+    fn default() -> Self {
+        Colorimetry::default().coefficients()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bt601_limited_coefficients() {
+        // The classic integer BT.601 constants
+        let coefficients = Colorimetry::BT601.coefficients();
+        assert_eq!(
+            (
+                coefficients.y_scale,
+                coefficients.r_v,
+                coefficients.g_u,
+                coefficients.g_v,
+                coefficients.b_u
+            ),
+            (298, 409, -100, -208, 516)
+        );
+        assert_eq!(coefficients.y_rgb, [66, 129, 25]);
+        assert_eq!(coefficients.u_rgb, [-38, -74, 112]);
+        assert_eq!(coefficients.v_rgb, [112, -94, -18]);
+    }
+
+    #[test]
+    fn test_black_white_and_gray() {
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            for range in [ColorRange::Limited, ColorRange::Full] {
+                let coefficients = Colorimetry::new(matrix, range).coefficients();
+                let (black, peak) = match range {
+                    ColorRange::Limited => (16, 235),
+                    ColorRange::Full => (0, 255),
+                };
+
+                assert_eq!(coefficients.rgb_to_yuv(0, 0, 0), (black, 128, 128));
+                assert_eq!(coefficients.rgb_to_yuv(255, 255, 255), (peak, 128, 128));
+                assert_eq!(coefficients.yuv_to_rgb(i32::from(black), 128, 128), (0, 0, 0));
+                assert_eq!(coefficients.yuv_to_rgb(i32::from(peak), 128, 128), (255, 255, 255));
+
+                let (_, u, v) = coefficients.rgb_to_yuv(128, 128, 128);
+                assert_eq!((u, v), (128, 128), "{:?} {:?}", matrix, range);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matrices_differ() {
+        // Pure red encodes differently under each matrix
+        let red = |colorimetry: Colorimetry| colorimetry.coefficients().rgb_to_yuv(255, 0, 0);
+        assert_ne!(red(Colorimetry::BT601), red(Colorimetry::BT709));
+        assert_ne!(red(Colorimetry::BT709), red(Colorimetry::BT2020));
+
+        // And each matrix decodes its own encoding back
+        for colorimetry in [Colorimetry::BT601, Colorimetry::BT709, Colorimetry::BT2020] {
+            let coefficients = colorimetry.coefficients();
+            let (y, u, v) = coefficients.rgb_to_yuv(200, 40, 90);
+            let (r, g, b) = coefficients.yuv_to_rgb(i32::from(y), i32::from(u), i32::from(v));
+            assert!(r.abs_diff(200) <= 2 && g.abs_diff(40) <= 2 && b.abs_diff(90) <= 2);
+        }
+    }
+
+    #[test]
+    fn test_from_spa() {
+        let colorimetry = Colorimetry::from_spa(
+            spa_sys::SPA_VIDEO_COLOR_MATRIX_BT2020,
+            spa_sys::SPA_VIDEO_COLOR_RANGE_0_255,
+            1920,
+            1080,
+        );
+        assert_eq!(colorimetry, Colorimetry::new(ColorMatrix::Bt2020, ColorRange::Full));
+
+        // Unknown values fall back to the size's convention
+        let unknown = spa_sys::SPA_VIDEO_COLOR_MATRIX_UNKNOWN;
+        assert_eq!(Colorimetry::from_spa(unknown, unknown, 1920, 1080), Colorimetry::BT709);
+        assert_eq!(Colorimetry::from_spa(unknown, unknown, 720, 576), Colorimetry::BT601);
+
+        for matrix in [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020] {
+            assert_eq!(ColorMatrix::from_spa(matrix.to_spa()), Some(matrix));
+        }
+        for range in [ColorRange::Limited, ColorRange::Full] {
+            assert_eq!(ColorRange::from_spa(range.to_spa()), Some(range));
+        }
+    }
+}
@@ -24,6 +24,7 @@
 //! ```
 
 use crate::format::PixelFormat;
+use crate::frame::FrameDataMode;
 
 /// Configuration for PipeWire screen capture
 ///
@@ -86,14 +87,34 @@ pub struct PipeWireConfig {
 
     /// Connection timeout in milliseconds (default: 5000)
     ///
-    /// Maximum time to wait for PipeWire connection to establish.
+    /// Maximum time to wait for PipeWire connection to establish, and for
+    /// the PipeWire thread to answer a command (stream creation, removal,
+    /// renegotiation) before it fails with `PipeWireError::Timeout`.
     pub connection_timeout_ms: u64,
 
-    /// Enable automatic reconnection on disconnect (default: true)
+    /// Enable automatic recovery of failed streams (default: true)
+    ///
+    /// Streams that enter PipeWire's error state are re-created on the same
+    /// node with exponential backoff. A lost PipeWire connection cannot be
+    /// re-established from the consumed portal FD; it is reported as
+    /// `RecoveryAction::RequestNewSession` either way.
     pub auto_reconnect: bool,
 
-    /// Maximum reconnection attempts (default: 3)
+    /// Maximum consecutive recovery attempts per stream (default: 3)
     pub max_reconnect_attempts: u32,
+
+    /// How captured pixel data is handed to consumers (default: Pooled)
+    ///
+    /// `Pooled` copies frames into recycled storage, `ZeroCopy` lends the
+    /// PipeWire buffer itself until the consumer drops the frame, and `Copy`
+    /// allocates a fresh buffer per frame.
+    pub frame_data_mode: FrameDataMode,
+
+    /// Recycled frame buffers kept per stream in pooled mode (default: 8)
+    ///
+    /// Should cover the frames consumers hold at once; frames beyond it are
+    /// freed instead of recycled.
+    pub frame_pool_size: usize,
 }
 
 impl Default for PipeWireConfig {
@@ -111,6 +132,8 @@ impl Default for PipeWireConfig {
             connection_timeout_ms: 5000,
             auto_reconnect: true,
             max_reconnect_attempts: 3,
+            frame_data_mode: FrameDataMode::Pooled,
+            frame_pool_size: 8,
         }
     }
 }
@@ -188,6 +211,8 @@ pub struct PipeWireConfigBuilder {
     connection_timeout_ms: Option<u64>,
     auto_reconnect: Option<bool>,
     max_reconnect_attempts: Option<u32>,
+    frame_data_mode: Option<FrameDataMode>,
+    frame_pool_size: Option<usize>,
 }
 
 impl PipeWireConfigBuilder {
@@ -275,6 +300,20 @@ impl PipeWireConfigBuilder {
         self
     }
 
+    /// Set how captured pixel data is handed to consumers
+    #[must_use]
+    pub fn frame_data_mode(mut self, mode: FrameDataMode) -> Self {
+        self.frame_data_mode = Some(mode);
+        self
+    }
+
+    /// Set recycled frame buffers kept per stream
+    #[must_use]
+    pub fn frame_pool_size(mut self, size: usize) -> Self {
+        self.frame_pool_size = Some(size);
+        self
+    }
+
     /// Build the configuration
     ///
     /// Returns a [`PipeWireConfig`] with builder values overriding defaults.
@@ -295,6 +334,8 @@ impl PipeWireConfigBuilder {
             connection_timeout_ms: self.connection_timeout_ms.unwrap_or(defaults.connection_timeout_ms),
             auto_reconnect: self.auto_reconnect.unwrap_or(defaults.auto_reconnect),
             max_reconnect_attempts: self.max_reconnect_attempts.unwrap_or(defaults.max_reconnect_attempts),
+            frame_data_mode: self.frame_data_mode.unwrap_or(defaults.frame_data_mode),
+            frame_pool_size: self.frame_pool_size.unwrap_or(defaults.frame_pool_size),
         }
     }
 }
@@ -451,6 +492,7 @@ mod tests {
         assert!(config.use_dmabuf);
         assert_eq!(config.max_streams, 8);
         assert_eq!(config.stream_name_prefix, "lamco-pw");
+        assert_eq!(config.frame_data_mode, FrameDataMode::Pooled);
     }
 
     #[test]
@@ -460,12 +502,16 @@ mod tests {
             .use_dmabuf(false)
             .max_streams(4)
             .stream_name_prefix("test-capture")
+            .frame_data_mode(FrameDataMode::ZeroCopy)
+            .frame_pool_size(2)
             .build();
 
         assert_eq!(config.buffer_count, 5);
         assert!(!config.use_dmabuf);
         assert_eq!(config.max_streams, 4);
         assert_eq!(config.stream_name_prefix, "test-capture");
+        assert_eq!(config.frame_data_mode, FrameDataMode::ZeroCopy);
+        assert_eq!(config.frame_pool_size, 2);
     }
 
     #[test]
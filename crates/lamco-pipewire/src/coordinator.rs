@@ -1,18 +1,26 @@
 //! Multi-Stream Coordination
 //!
 //! Coordinates multiple PipeWire streams for multi-monitor setups.
+//!
+//! The coordinator runs on a [`PipeWireManager`]: each monitor gets a
+//! manager stream, whose frames are pumped into the [`FrameDispatcher`] and
+//! whose lifecycle events keep the monitor's [`StreamState`] and the
+//! coordinator statistics up to date. Monitor hot-plug is handled by
+//! [`MultiStreamCoordinator::handle_monitor_event`].
 
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, RwLock as StdRwLock};
+use std::time::Instant;
 use tokio::sync::Mutex;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
 
-use crate::connection::PipeWireConnection;
-use crate::error::{PipeWireError, Result};
+use crate::connection::PipeWireEvent;
+use crate::error::{PipeWireError, Result, RetryConfig};
 use crate::frame::VideoFrame;
-use crate::stream::{PipeWireStream, PwStreamState, StreamConfig};
+use crate::manager::PipeWireManager;
+use crate::stream::{PipeWireStream, PwStreamState};
 
 /// Stream information from portal session
 ///
@@ -98,7 +106,7 @@ pub enum MonitorEvent {
 /// Stream handle
 #[derive(Clone)]
 pub struct StreamHandle {
-    /// Stream ID
+    /// Stream ID (in the manager)
     pub id: u32,
 
     /// Stream reference
@@ -110,7 +118,7 @@ pub struct StreamHandle {
     /// Stream state
     pub state: StreamState,
 
-    /// Monitoring task handle
+    /// Task pumping the stream's frames into the dispatcher
     pub task: Option<Arc<JoinHandle<()>>>,
 }
 
@@ -129,8 +137,19 @@ pub enum StreamState {
     Closing,
 }
 
-/// Pending stream creation (for retry logic in future implementation)
-#[allow(dead_code)]
+impl From<PwStreamState> for StreamState {
+    fn from(state: PwStreamState) -> Self {
+        match state {
+            PwStreamState::Initializing | PwStreamState::Connecting | PwStreamState::Ready => Self::Initializing,
+            PwStreamState::Streaming => Self::Active,
+            PwStreamState::Paused => Self::Paused,
+            PwStreamState::Error => Self::Error,
+            PwStreamState::Closing => Self::Closing,
+        }
+    }
+}
+
+/// Monitor whose stream could not be created yet
 struct PendingStream {
     monitor: MonitorInfo,
     retry_count: u32,
@@ -185,10 +204,10 @@ impl Default for DispatcherConfig {
 
 /// Multi-stream coordinator
 pub struct MultiStreamCoordinator {
-    /// Active streams
+    /// Active streams, keyed by monitor ID
     streams: Arc<RwLock<HashMap<u32, StreamHandle>>>,
 
-    /// Pending streams
+    /// Monitors waiting for a stream creation retry
     pending_streams: Arc<Mutex<Vec<PendingStream>>>,
 
     /// Frame dispatcher
@@ -199,6 +218,9 @@ pub struct MultiStreamCoordinator {
 
     /// Statistics
     stats: Arc<Mutex<CoordinatorStats>>,
+
+    /// Health monitoring task (started with the first stream)
+    health_task: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl MultiStreamCoordinator {
@@ -210,110 +232,167 @@ impl MultiStreamCoordinator {
             frame_dispatcher: Arc::new(FrameDispatcher::new(config.dispatcher_config.clone())),
             config,
             stats: Arc::new(Mutex::new(CoordinatorStats::default())),
+            health_task: Mutex::new(None),
         })
     }
 
     /// Add a stream for a monitor
-    pub async fn add_stream(&self, monitor: MonitorInfo, connection: &mut PipeWireConnection) -> Result<u32> {
-        // Check stream limit
-        if self.streams.read().await.len() >= self.config.max_streams {
-            return Err(PipeWireError::TooManyStreams(self.config.max_streams));
-        }
-
-        // Create stream configuration
-        let stream_config = StreamConfig::new(monitor.name.clone())
-            .with_resolution(monitor.size.0, monitor.size.1)
-            .with_framerate(monitor.refresh_rate);
-
-        // Create PipeWire stream via connection
-        let stream_id = connection.create_stream(stream_config, monitor.node_id).await?;
-
-        // Get the stream
-        if let Some(stream_arc) = connection.get_stream(stream_id).await {
-            // Set up frame callback
-            let dispatcher = self.frame_dispatcher.clone();
-            let monitor_id = monitor.id;
-
-            {
-                let mut stream = stream_arc.lock().await;
-                stream.set_frame_callback(Box::new(move |frame| {
-                    dispatcher.dispatch_frame(monitor_id, frame);
-                }));
+    ///
+    /// Creates a manager stream for the monitor's node (using the manager's
+    /// defaults at the monitor's size and refresh rate) and dispatches its
+    /// frames to the monitor's receiver.
+    ///
+    /// # Returns
+    ///
+    /// The manager's stream ID
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stream limit is reached, the monitor already has
+    /// a stream, or the manager fails to create the stream
+    pub async fn add_stream(&self, monitor: MonitorInfo, manager: &mut PipeWireManager) -> Result<u32> {
+        {
+            let streams = self.streams.read().await;
+            if streams.len() >= self.config.max_streams {
+                return Err(PipeWireError::TooManyStreams(self.config.max_streams));
+            }
+            if streams.contains_key(&monitor.id) {
+                return Err(PipeWireError::InvalidState(format!(
+                    "Monitor {} already has a stream",
+                    monitor.id
+                )));
             }
+        }
 
-            // Create stream handle
-            let handle = StreamHandle {
-                id: stream_id,
-                stream: stream_arc.clone(),
-                monitor: monitor.clone(),
-                state: StreamState::Active,
-                task: None, // Monitoring task disabled for Send safety
-            };
+        // Subscribe before the stream exists so none of its events are missed
+        self.start_health_monitor(manager).await;
 
-            // Note: Monitoring task disabled in this implementation
-            // In production, use a separate monitoring service that checks stream health
-            // without needing to clone StreamHandle across thread boundaries
+        let stream_info = StreamInfo {
+            node_id: monitor.node_id,
+            position: monitor.position,
+            size: monitor.size,
+            source_type: SourceType::Monitor,
+        };
+        let stream_config = manager
+            .stream_config(monitor.name.clone(), monitor.size)
+            .with_framerate(monitor.refresh_rate);
+        let stream_id = manager.create_stream_with_config(&stream_info, stream_config).await?.id;
+
+        let Some((stream, frames)) = manager
+            .stream_control(stream_id)
+            .await
+            .zip(manager.frame_receiver(stream_id).await)
+        else {
+            return Err(PipeWireError::StreamCreationFailed(
+                "Stream not found after creation".to_string(),
+            ));
+        };
 
-            // Store stream
-            self.streams.write().await.insert(monitor.id, handle);
+        let task = tokio::spawn(pump_frames(frames, Arc::clone(&self.frame_dispatcher), monitor.id));
+        let handle = StreamHandle {
+            id: stream_id,
+            state: StreamState::from(stream.state()),
+            stream: Arc::new(Mutex::new(stream)),
+            monitor: monitor.clone(),
+            task: Some(Arc::new(task)),
+        };
 
-            // Update stats
-            self.stats.lock().await.streams_created += 1;
+        self.streams.write().await.insert(monitor.id, handle);
+        self.stats.lock().await.streams_created += 1;
 
-            Ok(stream_id)
-        } else {
-            Err(PipeWireError::StreamCreationFailed(
-                "Stream not found after creation".to_string(),
-            ))
-        }
+        info!(
+            "Monitor {} ({}) streaming as stream {}",
+            monitor.id, monitor.name, stream_id
+        );
+        Ok(stream_id)
     }
 
     /// Remove a stream
-    pub async fn remove_stream(&self, monitor_id: u32) -> Result<()> {
-        let mut streams = self.streams.write().await;
-
-        if let Some(mut handle) = streams.remove(&monitor_id) {
-            // Stop monitoring task
-            if let Some(task) = handle.task.take() {
-                if let Ok(task) = Arc::try_unwrap(task) {
-                    task.abort();
-                }
-            }
-
-            // Stop the stream
-            handle.stream.lock().await.stop().await?;
-
-            // Update stats
-            self.stats.lock().await.streams_destroyed += 1;
-
-            Ok(())
-        } else {
-            Err(PipeWireError::StreamNotFound(monitor_id))
-        }
+    ///
+    /// Destroys the monitor's manager stream and closes its frame receiver.
+    pub async fn remove_stream(&self, monitor_id: u32, manager: &mut PipeWireManager) -> Result<()> {
+        let handle = self
+            .streams
+            .write()
+            .await
+            .remove(&monitor_id)
+            .ok_or(PipeWireError::StreamNotFound(monitor_id))?;
+
+        self.frame_dispatcher.unregister_receiver(monitor_id).await;
+        self.stats.lock().await.streams_destroyed += 1;
+        stop_stream(handle, manager).await
     }
 
     /// Handle monitor change event
-    pub async fn handle_monitor_event(&self, event: MonitorEvent) -> Result<()> {
+    ///
+    /// - `Added` creates a stream for the monitor. If that fails, the error
+    ///   is returned and the monitor is queued for
+    ///   [`retry_pending_streams`](Self::retry_pending_streams).
+    /// - `Removed` removes the monitor's stream (or pending retry).
+    /// - `Changed` re-creates the stream if the node, size or refresh rate
+    ///   changed, keeping the monitor's frame receiver; otherwise it only
+    ///   updates the monitor information.
+    pub async fn handle_monitor_event(&self, event: MonitorEvent, manager: &mut PipeWireManager) -> Result<()> {
         match event {
             MonitorEvent::Added(monitor) => {
-                // Queue for stream creation
-                self.pending_streams.lock().await.push(PendingStream {
-                    monitor,
-                    retry_count: 0,
-                    last_attempt: Instant::now(),
-                });
+                if let Err(e) = self.add_stream(monitor.clone(), manager).await {
+                    warn!("Failed to add stream for monitor {}: {}", monitor.id, e);
+                    self.pending_streams.lock().await.push(PendingStream {
+                        monitor,
+                        retry_count: 1,
+                        last_attempt: Instant::now(),
+                    });
+                    return Err(e);
+                }
             }
 
             MonitorEvent::Removed(monitor_id) => {
-                // Remove stream
-                self.remove_stream(monitor_id).await?;
+                let was_pending = {
+                    let mut pending = self.pending_streams.lock().await;
+                    let before = pending.len();
+                    pending.retain(|p| p.monitor.id != monitor_id);
+                    pending.len() != before
+                };
+                let streaming = self.streams.read().await.contains_key(&monitor_id);
+                if streaming || !was_pending {
+                    self.remove_stream(monitor_id, manager).await?;
+                }
             }
 
             MonitorEvent::Changed(monitor) => {
-                // For now, just update monitor info
-                // Full implementation would reconfigure the stream
-                if let Some(handle) = self.streams.write().await.get_mut(&monitor.id) {
-                    handle.monitor = monitor;
+                let reconfigure = {
+                    let mut streams = self.streams.write().await;
+                    let Some(handle) = streams.get_mut(&monitor.id) else {
+                        // Not streaming: refresh a pending retry, if any
+                        if let Some(pending) = self
+                            .pending_streams
+                            .lock()
+                            .await
+                            .iter_mut()
+                            .find(|p| p.monitor.id == monitor.id)
+                        {
+                            pending.monitor = monitor;
+                        }
+                        return Ok(());
+                    };
+
+                    let current = &handle.monitor;
+                    let reconfigure = current.node_id != monitor.node_id
+                        || current.size != monitor.size
+                        || current.refresh_rate != monitor.refresh_rate;
+                    if !reconfigure {
+                        handle.monitor = monitor.clone();
+                    }
+                    reconfigure
+                };
+
+                if reconfigure {
+                    debug!("Monitor {} changed, re-creating its stream", monitor.id);
+                    if let Some(handle) = self.streams.write().await.remove(&monitor.id) {
+                        self.stats.lock().await.streams_destroyed += 1;
+                        stop_stream(handle, manager).await?;
+                    }
+                    self.add_stream(monitor, manager).await?;
                 }
             }
         }
@@ -321,6 +400,53 @@ impl MultiStreamCoordinator {
         Ok(())
     }
 
+    /// Retry creating streams for monitors whose `Added` event failed
+    ///
+    /// Monitors are retried with `RetryConfig` backoff and dropped after
+    /// `MultiStreamConfig::retry_attempts` attempts.
+    ///
+    /// # Returns
+    ///
+    /// Number of streams created
+    pub async fn retry_pending_streams(&self, manager: &mut PipeWireManager) -> usize {
+        let retry = RetryConfig::default();
+        let pending = std::mem::take(&mut *self.pending_streams.lock().await);
+        let mut still_pending = Vec::new();
+        let mut created = 0;
+
+        for mut entry in pending {
+            if entry.last_attempt.elapsed() < retry.delay_for_attempt(entry.retry_count) {
+                still_pending.push(entry);
+                continue;
+            }
+
+            match self.add_stream(entry.monitor.clone(), manager).await {
+                Ok(_) => created += 1,
+                Err(e) => {
+                    entry.retry_count += 1;
+                    entry.last_attempt = Instant::now();
+                    if entry.retry_count < self.config.retry_attempts {
+                        still_pending.push(entry);
+                    } else {
+                        warn!(
+                            "Giving up on monitor {} after {} attempts: {}",
+                            entry.monitor.id, entry.retry_count, e
+                        );
+                        self.stats.lock().await.stream_errors += 1;
+                    }
+                }
+            }
+        }
+
+        self.pending_streams.lock().await.extend(still_pending);
+        created
+    }
+
+    /// Get number of monitors waiting for a stream creation retry
+    pub async fn pending_streams(&self) -> usize {
+        self.pending_streams.lock().await.len()
+    }
+
     /// Get active stream count
     pub async fn active_streams(&self) -> usize {
         self.streams.read().await.len()
@@ -331,6 +457,11 @@ impl MultiStreamCoordinator {
         self.streams.read().await.get(&monitor_id).map(|h| h.stream.clone())
     }
 
+    /// Get the state of a monitor's stream
+    pub async fn stream_state(&self, monitor_id: u32) -> Option<StreamState> {
+        self.streams.read().await.get(&monitor_id).map(|h| h.state)
+    }
+
     /// Get frame receiver for a monitor
     pub async fn get_frame_receiver(&self, monitor_id: u32) -> Option<mpsc::Receiver<VideoFrame>> {
         self.frame_dispatcher.register_receiver(monitor_id).await
@@ -340,12 +471,52 @@ impl MultiStreamCoordinator {
     pub async fn stats(&self) -> CoordinatorStats {
         self.stats.lock().await.clone()
     }
+
+    /// Start watching the manager's lifecycle events, once
+    async fn start_health_monitor(&self, manager: &PipeWireManager) {
+        let mut health_task = self.health_task.lock().await;
+        if health_task.is_none() {
+            *health_task = Some(tokio::spawn(monitor_stream_health(
+                manager.subscribe_events(),
+                Arc::clone(&self.streams),
+                Arc::clone(&self.stats),
+            )));
+        }
+    }
+}
+
+impl Drop for MultiStreamCoordinator {
+    fn drop(&mut self) {
+        if let Some(task) = self.health_task.get_mut().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Stop a monitor's frame pump and destroy its manager stream
+async fn stop_stream(handle: StreamHandle, manager: &mut PipeWireManager) -> Result<()> {
+    if let Some(task) = handle.task {
+        task.abort();
+    }
+
+    match manager.remove_stream(handle.id).await {
+        // Already gone (e.g. the manager was shut down)
+        Ok(()) | Err(PipeWireError::StreamNotFound(_)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Forward a stream's frames to the dispatcher until the stream is removed
+async fn pump_frames(mut frames: mpsc::Receiver<VideoFrame>, dispatcher: Arc<FrameDispatcher>, monitor_id: u32) {
+    while let Some(frame) = frames.recv().await {
+        dispatcher.dispatch_frame(monitor_id, frame);
+    }
 }
 
 /// Frame dispatcher
 pub struct FrameDispatcher {
     /// Frame receivers indexed by monitor ID
-    receivers: Arc<RwLock<HashMap<u32, mpsc::Sender<VideoFrame>>>>,
+    receivers: Arc<StdRwLock<HashMap<u32, mpsc::Sender<VideoFrame>>>>,
 
     /// Configuration
     config: DispatcherConfig,
@@ -355,15 +526,18 @@ impl FrameDispatcher {
     /// Create new dispatcher
     pub fn new(config: DispatcherConfig) -> Self {
         Self {
-            receivers: Arc::new(RwLock::new(HashMap::new())),
+            receivers: Arc::new(StdRwLock::new(HashMap::new())),
             config,
         }
     }
 
     /// Dispatch frame to appropriate receiver
+    ///
+    /// Frames for monitors without a receiver, or whose receiver is full,
+    /// are dropped.
     pub fn dispatch_frame(&self, monitor_id: u32, frame: VideoFrame) {
         // Send to monitor-specific receiver
-        if let Some(tx) = self.receivers.blocking_read().get(&monitor_id) {
+        if let Some(tx) = self.receivers.read().unwrap().get(&monitor_id) {
             let _ = tx.try_send(frame);
         }
     }
@@ -371,13 +545,13 @@ impl FrameDispatcher {
     /// Register a new receiver for a monitor
     pub async fn register_receiver(&self, monitor_id: u32) -> Option<mpsc::Receiver<VideoFrame>> {
         let (tx, rx) = mpsc::channel(self.config.frame_buffer_size);
-        self.receivers.write().await.insert(monitor_id, tx);
+        self.receivers.write().unwrap().insert(monitor_id, tx);
         Some(rx)
     }
 
     /// Unregister receiver
     pub async fn unregister_receiver(&self, monitor_id: u32) {
-        self.receivers.write().await.remove(&monitor_id);
+        self.receivers.write().unwrap().remove(&monitor_id);
     }
 }
 
@@ -399,39 +573,52 @@ pub struct CoordinatorStats {
 
 /// Monitor stream health
 ///
-/// This function is currently unused but preserved for future health monitoring implementation.
-#[allow(dead_code)]
-async fn monitor_stream_health(handle: StreamHandle) {
-    let mut interval = tokio::time::interval(Duration::from_secs(1));
-    let mut _stall_count = 0;
-
+/// Tracks the coordinator's streams through the manager's lifecycle events:
+/// state changes, errors, recoveries and removal by the compositor.
+async fn monitor_stream_health(
+    mut events: broadcast::Receiver<PipeWireEvent>,
+    streams: Arc<RwLock<HashMap<u32, StreamHandle>>>,
+    stats: Arc<Mutex<CoordinatorStats>>,
+) {
     loop {
-        interval.tick().await;
-
-        // Check stream state
-        let state = handle.stream.lock().await.state();
-
-        match state {
-            PwStreamState::Error => {
-                tracing::warn!("Stream {} in error state", handle.id);
-                // Would trigger recovery here
-                break;
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Stream health monitor missed {} events", skipped);
+                continue;
             }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
 
-            PwStreamState::Closing => {
-                tracing::info!("Stream {} closing", handle.id);
-                break;
+        let (stream_id, new_state) = match event {
+            PipeWireEvent::StreamStateChanged(id, pw_state) => (id, StreamState::from(pw_state)),
+            PipeWireEvent::StreamError(id, _) | PipeWireEvent::RecoveryRequired(Some(id), _) => {
+                (id, StreamState::Error)
             }
+            PipeWireEvent::StreamRecovered(id) => (id, StreamState::Active),
+            PipeWireEvent::StreamRemoved(id) => (id, StreamState::Closing),
+            _ => continue,
+        };
 
-            _ => {
-                // Reset stall counter (for future stall detection)
-                _stall_count = 0;
-            }
-        }
+        let mut streams = streams.write().await;
+        let Some(handle) = streams.values_mut().find(|h| h.id == stream_id) else {
+            continue;
+        };
+        handle.state = new_state;
 
-        // Exit if not active
-        if handle.state != StreamState::Active {
-            break;
+        match event {
+            PipeWireEvent::StreamError(_, message) => {
+                warn!("Monitor {} stream error: {}", handle.monitor.id, message);
+                stats.lock().await.stream_errors += 1;
+            }
+            PipeWireEvent::StreamRecovered(_) => {
+                info!("Monitor {} stream recovered", handle.monitor.id);
+                stats.lock().await.reconnections += 1;
+            }
+            PipeWireEvent::StreamRemoved(_) => {
+                warn!("Monitor {} stream was removed by the compositor", handle.monitor.id);
+            }
+            _ => {}
         }
     }
 }
@@ -439,6 +626,49 @@ async fn monitor_stream_health(handle: StreamHandle) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::loop_signal::LoopSignal;
+    use crate::pw_thread::CommandSender;
+    use crate::stream::{StreamConfig, StreamControl};
+    use std::time::Duration;
+
+    fn monitor(id: u32) -> MonitorInfo {
+        MonitorInfo {
+            id,
+            name: format!("Monitor-{}", id),
+            position: (0, 0),
+            size: (1920, 1080),
+            refresh_rate: 60,
+            node_id: 40 + id,
+        }
+    }
+
+    /// Handle for a stream that is not backed by a PipeWire thread
+    fn detached_handle(id: u32, monitor: MonitorInfo) -> StreamHandle {
+        let (command_tx, _) = std::sync::mpsc::sync_channel(1);
+        let control = StreamControl {
+            commands: CommandSender::new(
+                command_tx,
+                LoopSignal::new().unwrap(),
+                Duration::from_secs(1),
+                Arc::default(),
+            ),
+            stream_configs: Arc::default(),
+            states: Arc::default(),
+            formats: Arc::default(),
+        };
+        StreamHandle {
+            id,
+            stream: Arc::new(Mutex::new(PipeWireStream::new(
+                id,
+                monitor.node_id,
+                StreamConfig::new("test"),
+                control,
+            ))),
+            monitor,
+            state: StreamState::Initializing,
+            task: None,
+        }
+    }
 
     #[tokio::test]
     async fn test_coordinator_creation() {
@@ -463,6 +693,14 @@ mod tests {
         assert_eq!(info.size, (1920, 1080));
     }
 
+    #[test]
+    fn test_stream_state_from_pipewire() {
+        assert_eq!(StreamState::from(PwStreamState::Connecting), StreamState::Initializing);
+        assert_eq!(StreamState::from(PwStreamState::Streaming), StreamState::Active);
+        assert_eq!(StreamState::from(PwStreamState::Paused), StreamState::Paused);
+        assert_eq!(StreamState::from(PwStreamState::Error), StreamState::Error);
+    }
+
     #[tokio::test]
     async fn test_frame_dispatcher() {
         let config = DispatcherConfig::default();
@@ -476,14 +714,72 @@ mod tests {
 
         let frame = VideoFrame::new(1, 100, 100, 400, PixelFormat::BGRA, 1);
 
-        // Spawn a task to dispatch the frame (to avoid blocking in sync context)
+        // Dispatch from another task, as the frame pumps do
         tokio::spawn(async move {
             dispatcher.dispatch_frame(1, frame);
+            dispatcher.dispatch_frame(2, VideoFrame::new(2, 100, 100, 400, PixelFormat::BGRA, 2));
         });
 
         // Should receive the frame
         let received = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
 
-        assert!(received.is_ok());
+        assert!(matches!(received, Ok(Some(ref frame)) if frame.frame_id == 1));
+    }
+
+    #[tokio::test]
+    async fn test_health_monitor_tracks_events() {
+        let streams = Arc::new(RwLock::new(HashMap::new()));
+        streams.write().await.insert(1, detached_handle(7, monitor(1)));
+        let stats = Arc::new(Mutex::new(CoordinatorStats::default()));
+
+        let (events_tx, events) = broadcast::channel(16);
+        let task = tokio::spawn(monitor_stream_health(events, Arc::clone(&streams), Arc::clone(&stats)));
+
+        events_tx
+            .send(PipeWireEvent::StreamStateChanged(7, PwStreamState::Streaming))
+            .unwrap();
+        events_tx
+            .send(PipeWireEvent::StreamError(7, "node error".to_string()))
+            .unwrap();
+        events_tx.send(PipeWireEvent::StreamRecovered(7)).unwrap();
+        // Streams that don't belong to the coordinator are ignored
+        events_tx
+            .send(PipeWireEvent::StreamError(9, "other".to_string()))
+            .unwrap();
+        events_tx
+            .send(PipeWireEvent::StreamStateChanged(7, PwStreamState::Paused))
+            .unwrap();
+        drop(events_tx);
+        task.await.unwrap();
+
+        assert_eq!(streams.read().await[&1].state, StreamState::Paused);
+        let stats = stats.lock().await.clone();
+        assert_eq!(stats.stream_errors, 1);
+        assert_eq!(stats.reconnections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_events_without_connection() {
+        let coordinator = MultiStreamCoordinator::new(MultiStreamConfig::default()).await.unwrap();
+        let mut manager = PipeWireManager::with_default().unwrap();
+
+        // The manager is not connected: the monitor is queued for a retry
+        let result = coordinator
+            .handle_monitor_event(MonitorEvent::Added(monitor(1)), &mut manager)
+            .await;
+        assert!(matches!(result, Err(PipeWireError::InvalidState(_))));
+        assert_eq!(coordinator.pending_streams().await, 1);
+        assert_eq!(coordinator.active_streams().await, 0);
+
+        coordinator
+            .handle_monitor_event(MonitorEvent::Removed(1), &mut manager)
+            .await
+            .unwrap();
+        assert_eq!(coordinator.pending_streams().await, 0);
+
+        let result = coordinator
+            .handle_monitor_event(MonitorEvent::Removed(1), &mut manager)
+            .await;
+        assert!(matches!(result, Err(PipeWireError::StreamNotFound(1))));
     }
 }
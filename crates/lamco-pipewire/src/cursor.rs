@@ -16,10 +16,10 @@
 //! ```rust,ignore
 //! use lamco_pipewire::cursor::{CursorExtractor, CursorInfo};
 //!
-//! let mut extractor = CursorExtractor::new();
-//!
-//! // Update from PipeWire frame metadata
-//! // extractor.update_from_meta(&cursor_meta);
+//! // With `PipeWireConfig::enable_cursor`, the manager feeds its extractor
+//! // from every stream's SPA_META_Cursor metadata
+//! let extractor = manager.cursor_extractor().unwrap();
+//! let extractor = extractor.lock().await;
 //!
 //! if let Some(cursor) = extractor.current_cursor() {
 //!     println!("Cursor at {:?}, visible: {}", cursor.position, cursor.visible);
@@ -27,6 +27,12 @@
 //!         // Render cursor bitmap at position
 //!     }
 //! }
+//!
+//! // Or consume one stream's updates directly
+//! let mut updates = manager.cursor_receiver(stream_id).unwrap();
+//! while let Some(update) = updates.recv().await {
+//!     // update.position, update.bitmap (BGRA) ...
+//! }
 //! ```
 
 use std::time::{Duration, Instant};
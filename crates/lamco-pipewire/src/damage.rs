@@ -38,6 +38,9 @@
 
 use std::time::Instant;
 
+use crate::ffi;
+use crate::frame::VideoFrame;
+
 /// A damaged (changed) region of the screen
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DamageRegion {
@@ -118,6 +121,18 @@ impl DamageRegion {
     }
 }
 
+impl From<ffi::DamageRegion> for DamageRegion {
+    /// Negative coordinates are clamped to 0 (shrinking the region)
+    fn from(region: ffi::DamageRegion) -> Self {
+        Self {
+            x: region.x.max(0).unsigned_abs(),
+            y: region.y.max(0).unsigned_abs(),
+            width: region.width.saturating_sub(region.x.min(0).unsigned_abs()),
+            height: region.height.saturating_sub(region.y.min(0).unsigned_abs()),
+        }
+    }
+}
+
 /// Damage tracking statistics
 #[derive(Debug, Clone, Default)]
 pub struct DamageStats {
@@ -270,6 +285,24 @@ impl DamageTracker {
         }
     }
 
+    /// Add the damage a captured frame carries
+    ///
+    /// Frames without damage metadata count as fully damaged.
+    pub fn add_frame_damage(&mut self, frame: &VideoFrame) {
+        if frame.damage_regions.is_empty() {
+            self.mark_full_damage(frame.width, frame.height);
+            return;
+        }
+
+        self.add_regions(
+            frame
+                .damage_regions
+                .iter()
+                .filter_map(|region| DamageRegion::from(*region).clip(frame.width, frame.height)),
+        );
+        self.stats.partial_damage_frames += 1;
+    }
+
     /// Mark entire frame as damaged
     pub fn mark_full_damage(&mut self, width: u32, height: u32) {
         self.regions.clear();
@@ -469,6 +502,25 @@ mod tests {
         assert!(tracker.should_full_update(frame_size));
     }
 
+    #[test]
+    fn test_add_frame_damage() {
+        use crate::format::PixelFormat;
+
+        let mut tracker = DamageTracker::new();
+        let mut frame = VideoFrame::new(0, 1920, 1080, 7680, PixelFormat::BGRx, 0);
+
+        // No metadata: full damage
+        tracker.add_frame_damage(&frame);
+        assert_eq!(tracker.damaged_regions(), &[DamageRegion::new(0, 0, 1920, 1080)]);
+        assert_eq!(tracker.stats().full_damage_frames, 1);
+        tracker.clear();
+
+        frame.add_damage_region(ffi::DamageRegion::new(-10, 100, 60, 20));
+        tracker.add_frame_damage(&frame);
+        assert_eq!(tracker.damaged_regions(), &[DamageRegion::new(0, 100, 50, 20)]);
+        assert_eq!(tracker.stats().partial_damage_frames, 1);
+    }
+
     #[test]
     fn test_bounding_box() {
         let mut tracker = DamageTracker::new();
@@ -0,0 +1,461 @@
+//! DMA-BUF CPU Access
+//!
+//! Reads DMA-BUF buffers from the CPU. The exporter (usually a GPU driver)
+//! may keep buffer contents in caches or still be writing them, so every CPU
+//! read is bracketed with `DMA_BUF_IOCTL_SYNC` start/end calls as required by
+//! the kernel's DMA-BUF documentation.
+//!
+//! Mappings are cached per stream and per PipeWire buffer: a stream owns one
+//! [`DmaBufCache`], and the mappings of a buffer are torn down when PipeWire
+//! removes that buffer. Each mapping remembers the file it maps (device and
+//! inode), so a reused FD number never returns a stale mapping.
+//!
+//! FDs that are not DMA-BUFs (memfd stand-ins, for example) reject the sync
+//! ioctl with `ENOTTY`; they are read without synchronisation.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
+use std::ptr::NonNull;
+
+use tracing::{debug, trace, warn};
+
+use crate::error::{PipeWireError, Result};
+
+/// `DMA_BUF_SYNC_READ`: the CPU reads the buffer
+const DMA_BUF_SYNC_READ: u64 = 1 << 0;
+
+/// `DMA_BUF_SYNC_START`: begin CPU access
+const DMA_BUF_SYNC_START: u64 = 0;
+
+/// `DMA_BUF_SYNC_END`: end CPU access
+const DMA_BUF_SYNC_END: u64 = 1 << 2;
+
+/// `DMA_BUF_IOCTL_SYNC`: `_IOW('b', 0, struct dma_buf_sync)`
+const DMA_BUF_IOCTL_SYNC: u64 = (1 << 30) | ((std::mem::size_of::<DmaBufSyncArgs>() as u64) << 16) | (0x62 << 8);
+
+/// `struct dma_buf_sync` from `<linux/dma-buf.h>`
+#[repr(C)]
+struct DmaBufSyncArgs {
+    flags: u64,
+}
+
+/// Issue `DMA_BUF_IOCTL_SYNC`, retrying while the kernel asks to
+fn dma_buf_sync(fd: RawFd, flags: u64) -> std::io::Result<()> {
+    let args = DmaBufSyncArgs { flags };
+    loop {
+        // SAFETY: args is a valid dma_buf_sync for the duration of the call;
+        // the kernel only reads from it.
+        let ret = unsafe { libc::ioctl(fd, DMA_BUF_IOCTL_SYNC as _, std::ptr::addr_of!(args)) };
+        if ret == 0 {
+            return Ok(());
+        }
+
+        let err = std::io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR | libc::EAGAIN) => continue,
+            _ => return Err(err),
+        }
+    }
+}
+
+/// CPU read access to a DMA-BUF
+///
+/// Created before reading a mapping and dropped after; ends the access with
+/// `DMA_BUF_SYNC_END` on drop.
+#[derive(Debug)]
+pub struct DmaBufSync<'fd> {
+    /// Buffer FD
+    fd: BorrowedFd<'fd>,
+
+    /// Whether the sync ioctl is supported (the FD is a real DMA-BUF)
+    synced: bool,
+}
+
+impl<'fd> DmaBufSync<'fd> {
+    /// Begin CPU read access
+    ///
+    /// # Errors
+    ///
+    /// Returns `DmaBufImportFailed` if the exporter rejects the sync. FDs that
+    /// are not DMA-BUFs are accepted unsynchronised.
+    pub fn begin_read(fd: BorrowedFd<'fd>) -> Result<Self> {
+        match dma_buf_sync(fd.as_raw_fd(), DMA_BUF_SYNC_START | DMA_BUF_SYNC_READ) {
+            Ok(()) => Ok(Self { fd, synced: true }),
+            Err(e) if e.raw_os_error() == Some(libc::ENOTTY) => {
+                trace!("FD={} is not a DMA-BUF, reading without sync", fd.as_raw_fd());
+                Ok(Self { fd, synced: false })
+            }
+            Err(e) => Err(PipeWireError::DmaBufImportFailed(format!(
+                "DMA_BUF_IOCTL_SYNC start failed on FD={}: {}",
+                fd.as_raw_fd(),
+                e
+            ))),
+        }
+    }
+
+    /// Whether the access is synchronised with the exporter
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+}
+
+impl Drop for DmaBufSync<'_> {
+    fn drop(&mut self) {
+        if self.synced {
+            if let Err(e) = dma_buf_sync(self.fd.as_raw_fd(), DMA_BUF_SYNC_END | DMA_BUF_SYNC_READ) {
+                warn!("DMA_BUF_IOCTL_SYNC end failed on FD={}: {}", self.fd.as_raw_fd(), e);
+            }
+        }
+    }
+}
+
+/// Identity of the file behind an FD: `(st_dev, st_ino)`
+fn file_identity(fd: BorrowedFd<'_>) -> Option<(libc::dev_t, libc::ino_t)> {
+    let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+    // SAFETY: stat points to writable memory for one libc::stat, which fstat
+    // fills completely on success.
+    let ret = unsafe { libc::fstat(fd.as_raw_fd(), stat.as_mut_ptr()) };
+    if ret != 0 {
+        return None;
+    }
+    // SAFETY: fstat succeeded, so stat is initialised
+    let stat = unsafe { stat.assume_init() };
+    Some((stat.st_dev, stat.st_ino))
+}
+
+/// Read-only mapping of a DMA-BUF, unmapped on drop
+#[derive(Debug)]
+pub struct DmaBufMapping {
+    /// Start of the mapping (FD offset 0)
+    ptr: NonNull<u8>,
+
+    /// Mapped length in bytes
+    len: usize,
+
+    /// FD the mapping was created from
+    fd: RawFd,
+
+    /// File identity of that FD
+    identity: (libc::dev_t, libc::ino_t),
+}
+
+impl DmaBufMapping {
+    /// Map the first `len` bytes of a buffer
+    ///
+    /// # Errors
+    ///
+    /// Returns `DmaBufImportFailed` if `len` is zero or mmap fails
+    pub fn map(fd: BorrowedFd<'_>, len: usize) -> Result<Self> {
+        use nix::sys::mman::{mmap, MapFlags, ProtFlags};
+
+        let size = NonZeroUsize::new(len)
+            .ok_or_else(|| PipeWireError::DmaBufImportFailed(format!("empty mapping for FD={}", fd.as_raw_fd())))?;
+        let identity = file_identity(fd)
+            .ok_or_else(|| PipeWireError::DmaBufImportFailed(format!("fstat failed on FD={}", fd.as_raw_fd())))?;
+
+        // SAFETY: the mapping is read-only, starts at offset 0 (page-aligned)
+        // and is owned by the returned value, which unmaps it on drop.
+        let ptr = unsafe { mmap(None, size, ProtFlags::PROT_READ, MapFlags::MAP_SHARED, Some(fd), 0) }
+            .map_err(|e| PipeWireError::DmaBufImportFailed(format!("mmap of FD={} failed: {}", fd.as_raw_fd(), e)))?;
+
+        Ok(Self {
+            ptr: NonNull::new(ptr.cast::<u8>()).ok_or_else(|| {
+                PipeWireError::DmaBufImportFailed(format!("mmap of FD={} returned null", fd.as_raw_fd()))
+            })?,
+            len,
+            fd: fd.as_raw_fd(),
+            identity,
+        })
+    }
+
+    /// Mapped length in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the mapping is empty (never true for a successful mapping)
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether this mapping maps the file currently behind `fd`
+    ///
+    /// Compares the file identity, not only the FD number, so an FD number
+    /// that was closed and reused for another buffer does not match.
+    pub fn maps(&self, fd: BorrowedFd<'_>) -> bool {
+        self.fd == fd.as_raw_fd() && file_identity(fd) == Some(self.identity)
+    }
+
+    /// Copy `size` bytes at `start` into `out` inside a synchronised read
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameExtractionFailed` if the range is outside the mapping,
+    /// or the sync error from [`DmaBufSync::begin_read`]
+    pub fn read_into(&self, fd: BorrowedFd<'_>, start: usize, size: usize, out: &mut Vec<u8>) -> Result<()> {
+        let end = start.checked_add(size).filter(|&end| end <= self.len).ok_or_else(|| {
+            PipeWireError::FrameExtractionFailed(format!(
+                "DMA-BUF chunk out of bounds: start={}, size={}, mapped={}",
+                start, size, self.len
+            ))
+        })?;
+
+        let _access = DmaBufSync::begin_read(fd)?;
+        // SAFETY: the mapping is live for &self and start..end lies inside it
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr.as_ptr().add(start), end - start) };
+        out.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Drop for DmaBufMapping {
+    fn drop(&mut self) {
+        // SAFETY: ptr and len describe the mapping created in map(), which
+        // nothing else references once its owner is dropped.
+        if let Err(e) = unsafe { nix::sys::mman::munmap(self.ptr.as_ptr().cast(), self.len) } {
+            warn!("Failed to munmap DMA-BUF FD={}: {}", self.fd, e);
+        }
+    }
+}
+
+/// DMA-BUF mappings of one stream, keyed by PipeWire buffer
+///
+/// Buffers are identified by an opaque key (the `pw_buffer` address). Planes
+/// of one buffer that share an FD share its mapping.
+#[derive(Debug, Default)]
+pub struct DmaBufCache {
+    /// Mappings per buffer
+    buffers: HashMap<usize, Vec<DmaBufMapping>>,
+}
+
+impl DmaBufCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy a chunk of a buffer into `out`
+    ///
+    /// Maps the buffer on first use, or again if the cached mapping is too
+    /// short or belongs to a different file than `fd`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameExtractionFailed` if the chunk lies outside `map_len`,
+    /// `DmaBufImportFailed` if mapping or synchronisation fails
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Buffer key
+    /// * `fd` - Buffer FD
+    /// * `map_len` - Bytes to map from offset 0 (`mapoffset + maxsize`)
+    /// * `start` - Chunk start from offset 0
+    /// * `size` - Chunk size
+    /// * `out` - Destination; left unchanged on error
+    pub fn read_chunk(
+        &mut self,
+        buffer: usize,
+        fd: BorrowedFd<'_>,
+        map_len: usize,
+        start: usize,
+        size: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        if !matches!(start.checked_add(size), Some(end) if end <= map_len) {
+            return Err(PipeWireError::FrameExtractionFailed(format!(
+                "DMA-BUF chunk out of bounds: start={}, size={}, buffer={}",
+                start, size, map_len
+            )));
+        }
+
+        let mappings = self.buffers.entry(buffer).or_default();
+
+        let index = match mappings.iter().position(|mapping| mapping.maps(fd)) {
+            Some(index) if mappings[index].len() >= map_len => index,
+            found => {
+                if let Some(index) = found {
+                    mappings.swap_remove(index);
+                }
+                // Drop mappings left behind by a closed FD with the same number
+                mappings.retain(|mapping| mapping.fd != fd.as_raw_fd());
+
+                debug!("DMA-BUF buffer: mmapping {} bytes from FD={}", map_len, fd.as_raw_fd());
+                mappings.push(DmaBufMapping::map(fd, map_len)?);
+                mappings.len() - 1
+            }
+        };
+
+        mappings[index].read_into(fd, start, size, out)
+    }
+
+    /// Unmap everything mapped for a buffer
+    ///
+    /// # Returns
+    ///
+    /// Number of mappings removed
+    pub fn remove_buffer(&mut self, buffer: usize) -> usize {
+        let removed = self.buffers.remove(&buffer).map_or(0, |mappings| mappings.len());
+        if removed > 0 {
+            debug!("Unmapped {} DMA-BUF mapping(s) of buffer {:#x}", removed, buffer);
+        }
+        removed
+    }
+
+    /// Unmap everything
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+    }
+
+    /// Number of live mappings
+    pub fn len(&self) -> usize {
+        self.buffers.values().map(Vec::len).sum()
+    }
+
+    /// Whether no mappings are live
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::fd::{AsFd, FromRawFd, OwnedFd};
+
+    /// Memfd stand-in for a DMA-BUF holding `contents`
+    fn memfd_with(contents: &[u8]) -> OwnedFd {
+        // SAFETY: the name is a valid C string; the returned FD is checked
+        let raw = unsafe { libc::memfd_create(c"lamco-dmabuf-test".as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(raw >= 0, "memfd_create failed");
+        // SAFETY: raw is a fresh FD owned by nobody else
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+        let mut file = std::fs::File::from(fd);
+        std::io::Write::write_all(&mut file, contents).unwrap();
+        file.into()
+    }
+
+    #[test]
+    fn test_sync_ioctl_number() {
+        // Value of DMA_BUF_IOCTL_SYNC in <linux/dma-buf.h>
+        assert_eq!(DMA_BUF_IOCTL_SYNC, 0x4008_6200);
+    }
+
+    #[test]
+    fn test_sync_accepts_non_dmabuf() {
+        let fd = memfd_with(&[0u8; 16]);
+        let access = DmaBufSync::begin_read(fd.as_fd()).unwrap();
+        assert!(!access.is_synced());
+    }
+
+    #[test]
+    fn test_read_chunk_caches_mapping() {
+        let contents: Vec<u8> = (0..=255).collect();
+        let fd = memfd_with(&contents);
+        let mut cache = DmaBufCache::new();
+
+        let mut out = Vec::new();
+        cache.read_chunk(1, fd.as_fd(), 256, 16, 8, &mut out).unwrap();
+        assert_eq!(out, &contents[16..24]);
+
+        out.clear();
+        cache.read_chunk(1, fd.as_fd(), 256, 100, 4, &mut out).unwrap();
+        assert_eq!(out, &contents[100..104]);
+        assert_eq!(cache.len(), 1);
+
+        // Chunks outside the buffer are rejected and leave the output untouched
+        out.clear();
+        assert!(cache.read_chunk(1, fd.as_fd(), 256, 250, 16, &mut out).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_remove_buffer_unmaps() {
+        let fd = memfd_with(&[7u8; 64]);
+        let mut cache = DmaBufCache::new();
+        let mut out = Vec::new();
+
+        cache.read_chunk(1, fd.as_fd(), 64, 0, 64, &mut out).unwrap();
+        cache.read_chunk(2, fd.as_fd(), 64, 0, 64, &mut out).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        assert_eq!(cache.remove_buffer(1), 1);
+        assert_eq!(cache.remove_buffer(1), 0);
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_reused_fd_number_is_remapped() {
+        let first = memfd_with(&[1u8; 32]);
+        let number = first.as_raw_fd();
+        let mut cache = DmaBufCache::new();
+        let mut out = Vec::new();
+
+        cache.read_chunk(1, first.as_fd(), 32, 0, 4, &mut out).unwrap();
+        assert_eq!(out, [1u8; 4]);
+
+        // Replace the file behind the same FD number without telling the cache
+        let second = memfd_with(&[2u8; 32]);
+        // SAFETY: both FDs are valid; dup2 atomically closes the first file
+        assert_eq!(unsafe { libc::dup2(second.as_raw_fd(), number) }, number);
+        drop(second);
+
+        out.clear();
+        cache.read_chunk(1, first.as_fd(), 32, 0, 4, &mut out).unwrap();
+        assert_eq!(out, [2u8; 4]);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_udmabuf_read_is_synced() {
+        // udmabuf turns a sealed memfd into a real DMA-BUF; skip where unavailable
+        let Ok(device) = std::fs::File::open("/dev/udmabuf") else {
+            return;
+        };
+
+        #[repr(C)]
+        struct UdmabufCreate {
+            memfd: u32,
+            flags: u32,
+            offset: u64,
+            size: u64,
+        }
+        const UDMABUF_CREATE: u64 =
+            (1 << 30) | ((std::mem::size_of::<UdmabufCreate>() as u64) << 16) | (0x75 << 8) | 0x42;
+
+        let size = 4096usize;
+        // SAFETY: the name is a valid C string; the returned FD is checked
+        let raw = unsafe { libc::memfd_create(c"lamco-udmabuf-test".as_ptr(), libc::MFD_ALLOW_SEALING) };
+        assert!(raw >= 0);
+        // SAFETY: raw is a fresh FD owned by nobody else
+        let memfd = unsafe { OwnedFd::from_raw_fd(raw) };
+        let mut file = std::fs::File::from(memfd);
+        std::io::Write::write_all(&mut file, &vec![5u8; size]).unwrap();
+        // SAFETY: plain fcntl on a valid FD
+        unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, libc::F_SEAL_SHRINK) };
+
+        let create = UdmabufCreate {
+            memfd: file.as_raw_fd() as u32,
+            flags: 1, // UDMABUF_FLAGS_CLOEXEC
+            offset: 0,
+            size: size as u64,
+        };
+        // SAFETY: create is a valid udmabuf_create for the duration of the call
+        let raw = unsafe { libc::ioctl(device.as_raw_fd(), UDMABUF_CREATE as _, std::ptr::addr_of!(create)) };
+        if raw < 0 {
+            return;
+        }
+        // SAFETY: raw is a fresh DMA-BUF FD owned by nobody else
+        let dmabuf = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        assert!(DmaBufSync::begin_read(dmabuf.as_fd()).unwrap().is_synced());
+
+        let mut cache = DmaBufCache::new();
+        let mut out = Vec::new();
+        cache.read_chunk(1, dmabuf.as_fd(), size, 0, 8, &mut out).unwrap();
+        assert_eq!(out, [5u8; 8]);
+    }
+}
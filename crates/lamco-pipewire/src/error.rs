@@ -4,6 +4,8 @@
 
 use thiserror::Error;
 
+use crate::format::PixelFormat;
+
 /// Result type for PipeWire operations
 pub type Result<T> = std::result::Result<T, PipeWireError>;
 
@@ -50,10 +52,18 @@ pub enum PipeWireError {
     #[error("Stream {0} stalled")]
     StreamStalled(u32),
 
+    /// Stream entered PipeWire's error state
+    #[error("Stream {0} failed: {1}")]
+    StreamFailed(u32, String),
+
     /// Format conversion failed
     #[error("Format conversion failed: {0}")]
     FormatConversionFailed(String),
 
+    /// No conversion exists between the two pixel formats
+    #[error("Unsupported conversion: {0:?} -> {1:?}")]
+    UnsupportedConversion(PixelFormat, PixelFormat),
+
     /// Timeout waiting for stream
     #[error("Timeout waiting for stream")]
     Timeout,
@@ -125,13 +135,16 @@ pub fn classify_error(error: &PipeWireError) -> ErrorType {
     match error {
         PipeWireError::ConnectionFailed(_) | PipeWireError::InitializationFailed(_) => ErrorType::Connection,
 
-        PipeWireError::StreamCreationFailed(_) | PipeWireError::StreamNotFound(_) | PipeWireError::StreamStalled(_) => {
-            ErrorType::Stream
-        }
+        PipeWireError::StreamCreationFailed(_)
+        | PipeWireError::StreamNotFound(_)
+        | PipeWireError::StreamStalled(_)
+        | PipeWireError::StreamFailed(..) => ErrorType::Stream,
 
         PipeWireError::BufferAllocationFailed(_) | PipeWireError::NoBuffersAvailable => ErrorType::Buffer,
 
-        PipeWireError::FormatNegotiationFailed(_) | PipeWireError::FormatConversionFailed(_) => ErrorType::Format,
+        PipeWireError::FormatNegotiationFailed(_)
+        | PipeWireError::FormatConversionFailed(_)
+        | PipeWireError::UnsupportedConversion(..) => ErrorType::Format,
 
         PipeWireError::TooManyStreams(_) | PipeWireError::DmaBufImportFailed(_) => ErrorType::Resource,
 
@@ -143,6 +156,30 @@ pub fn classify_error(error: &PipeWireError) -> ErrorType {
     }
 }
 
+/// Choose how to recover from an error
+///
+/// Stream, buffer, format and timeout errors restart the stream in
+/// `context.stream_id` (or retry the operation if there is none) until
+/// `context.attempt` reaches `retry.max_retries`. Connection and permission
+/// errors need a new portal session: the portal's PipeWire FD is consumed by
+/// the connection and cannot be reused. Everything else fails.
+pub fn recovery_action(error: &PipeWireError, context: &ErrorContext, retry: &RetryConfig) -> RecoveryAction {
+    match classify_error(error) {
+        ErrorType::Connection | ErrorType::Permission => RecoveryAction::RequestNewSession,
+
+        ErrorType::Stream | ErrorType::Buffer | ErrorType::Format | ErrorType::Timeout
+            if context.attempt < retry.max_retries =>
+        {
+            match context.stream_id {
+                Some(stream_id) => RecoveryAction::RestartStream(stream_id),
+                None => RecoveryAction::Retry(retry.clone()),
+            }
+        }
+
+        _ => RecoveryAction::Fail,
+    }
+}
+
 /// Error context for recovery decisions
 #[derive(Debug, Clone)]
 pub struct ErrorContext {
@@ -256,8 +293,8 @@ impl Default for RetryConfig {
 impl RetryConfig {
     /// Calculate delay for given attempt
     pub fn delay_for_attempt(&self, attempt: u32) -> std::time::Duration {
-        let delay = self.initial_delay_ms * (self.backoff_multiplier as u64).pow(attempt);
-        let delay = delay.min(self.max_delay_ms);
+        let factor = (self.backoff_multiplier as u64).saturating_pow(attempt);
+        let delay = self.initial_delay_ms.saturating_mul(factor).min(self.max_delay_ms);
         std::time::Duration::from_millis(delay)
     }
 }
@@ -301,5 +338,38 @@ mod tests {
 
         // Should cap at max_delay_ms
         assert_eq!(config.delay_for_attempt(10).as_millis(), 5000);
+        assert_eq!(config.delay_for_attempt(100).as_millis(), 5000);
+    }
+
+    #[test]
+    fn test_recovery_action() {
+        let retry = RetryConfig::default();
+        let stream_error = PipeWireError::StreamFailed(4, "error".to_string());
+
+        let ctx = ErrorContext::new().with_stream_id(4);
+        assert_eq!(
+            recovery_action(&stream_error, &ctx, &retry),
+            RecoveryAction::RestartStream(4)
+        );
+
+        // Attempts exhausted
+        let ctx = ErrorContext::new().with_stream_id(4).with_attempt(retry.max_retries);
+        assert_eq!(recovery_action(&stream_error, &ctx, &retry), RecoveryAction::Fail);
+
+        // No stream to restart
+        assert_eq!(
+            recovery_action(&PipeWireError::Timeout, &ErrorContext::new(), &retry),
+            RecoveryAction::Retry(retry.clone())
+        );
+
+        let lost = PipeWireError::ConnectionFailed("core disconnected".to_string());
+        assert_eq!(
+            recovery_action(&lost, &ErrorContext::new(), &retry),
+            RecoveryAction::RequestNewSession
+        );
+        assert_eq!(
+            recovery_action(&PipeWireError::TooManyStreams(8), &ErrorContext::new(), &retry),
+            RecoveryAction::Fail
+        );
     }
 }
@@ -15,11 +15,13 @@ pub use pipewire::{
 };
 
 pub use libspa::param::video::{VideoFormat, VideoInfoRaw};
-pub use libspa::param::{format::MediaSubtype, format::MediaType, ParamType};
+pub use libspa::param::{format::FormatProperties, format::MediaSubtype, format::MediaType, ParamType};
 pub use libspa::pod::{self as spa_pod, Pod as SpaPod};
-pub use libspa::utils::{Choice, ChoiceFlags, Direction, Fraction, Id, Rectangle};
+pub use libspa::utils::{Choice, ChoiceEnum, ChoiceFlags, Direction, Fraction, Id, Rectangle, SpaTypes};
 pub use libspa_sys as spa_sys;
 
+use crate::error::{PipeWireError, Result};
+
 /// DRM format modifiers for DMA-BUF
 pub mod drm_fourcc {
     pub const DRM_FORMAT_INVALID: u32 = 0;
@@ -31,6 +33,20 @@ pub mod drm_fourcc {
     pub const DRM_FORMAT_ARGB8888: u32 = 0x34325241; // AR24
     pub const DRM_FORMAT_XBGR8888: u32 = 0x34324258; // XB24
     pub const DRM_FORMAT_ABGR8888: u32 = 0x34324241; // AB24
+    pub const DRM_FORMAT_BGRX8888: u32 = 0x34325842; // BX24
+    pub const DRM_FORMAT_BGRA8888: u32 = 0x34324142; // BA24
+    pub const DRM_FORMAT_RGBX8888: u32 = 0x34325852; // RX24
+    pub const DRM_FORMAT_RGBA8888: u32 = 0x34324152; // RA24
+
+    // 10-bit RGB formats
+    pub const DRM_FORMAT_XRGB2101010: u32 = 0x30335258; // XR30
+    pub const DRM_FORMAT_ARGB2101010: u32 = 0x30335241; // AR30
+    pub const DRM_FORMAT_XBGR2101010: u32 = 0x30334258; // XB30
+    pub const DRM_FORMAT_ABGR2101010: u32 = 0x30334241; // AB30
+
+    // YUV formats
+    pub const DRM_FORMAT_NV12: u32 = 0x3231564e; // NV12
+    pub const DRM_FORMAT_P010: u32 = 0x30313050; // P010
 }
 
 /// SPA video format to DRM fourcc conversion
@@ -40,6 +56,16 @@ pub fn spa_video_format_to_drm_fourcc(format: VideoFormat) -> u32 {
         VideoFormat::BGRA => drm_fourcc::DRM_FORMAT_ARGB8888,
         VideoFormat::RGBx => drm_fourcc::DRM_FORMAT_XBGR8888,
         VideoFormat::RGBA => drm_fourcc::DRM_FORMAT_ABGR8888,
+        VideoFormat::xRGB => drm_fourcc::DRM_FORMAT_BGRX8888,
+        VideoFormat::ARGB => drm_fourcc::DRM_FORMAT_BGRA8888,
+        VideoFormat::xBGR => drm_fourcc::DRM_FORMAT_RGBX8888,
+        VideoFormat::ABGR => drm_fourcc::DRM_FORMAT_RGBA8888,
+        VideoFormat::xRGB_210LE => drm_fourcc::DRM_FORMAT_XRGB2101010,
+        VideoFormat::ARGB_210LE => drm_fourcc::DRM_FORMAT_ARGB2101010,
+        VideoFormat::xBGR_210LE => drm_fourcc::DRM_FORMAT_XBGR2101010,
+        VideoFormat::ABGR_210LE => drm_fourcc::DRM_FORMAT_ABGR2101010,
+        VideoFormat::NV12 => drm_fourcc::DRM_FORMAT_NV12,
+        VideoFormat::P010_10LE => drm_fourcc::DRM_FORMAT_P010,
         _ => drm_fourcc::DRM_FORMAT_INVALID,
     }
 }
@@ -51,6 +77,16 @@ pub fn drm_fourcc_to_spa_video_format(fourcc: u32) -> Option<VideoFormat> {
         drm_fourcc::DRM_FORMAT_ARGB8888 => Some(VideoFormat::BGRA),
         drm_fourcc::DRM_FORMAT_XBGR8888 => Some(VideoFormat::RGBx),
         drm_fourcc::DRM_FORMAT_ABGR8888 => Some(VideoFormat::RGBA),
+        drm_fourcc::DRM_FORMAT_BGRX8888 => Some(VideoFormat::xRGB),
+        drm_fourcc::DRM_FORMAT_BGRA8888 => Some(VideoFormat::ARGB),
+        drm_fourcc::DRM_FORMAT_RGBX8888 => Some(VideoFormat::xBGR),
+        drm_fourcc::DRM_FORMAT_RGBA8888 => Some(VideoFormat::ABGR),
+        drm_fourcc::DRM_FORMAT_XRGB2101010 => Some(VideoFormat::xRGB_210LE),
+        drm_fourcc::DRM_FORMAT_ARGB2101010 => Some(VideoFormat::ARGB_210LE),
+        drm_fourcc::DRM_FORMAT_XBGR2101010 => Some(VideoFormat::xBGR_210LE),
+        drm_fourcc::DRM_FORMAT_ABGR2101010 => Some(VideoFormat::ABGR_210LE),
+        drm_fourcc::DRM_FORMAT_NV12 => Some(VideoFormat::NV12),
+        drm_fourcc::DRM_FORMAT_P010 => Some(VideoFormat::P010_10LE),
         _ => None,
     }
 }
@@ -64,6 +100,8 @@ pub fn get_bytes_per_pixel(format: VideoFormat) -> usize {
         // YUV formats - return for Y plane
         VideoFormat::NV12 | VideoFormat::I420 => 1,
         VideoFormat::YUY2 => 2,
+        // 16-bit Y samples
+        VideoFormat::P010_10LE => 2,
         _ => 4, // Default to 4
     }
 }
@@ -89,7 +127,7 @@ pub fn calculate_buffer_size(width: u32, height: u32, format: VideoFormat) -> us
         | VideoFormat::GRAY8 => stride * height as usize,
 
         // YUV420 formats (1.5 bytes per pixel)
-        VideoFormat::NV12 | VideoFormat::I420 => (stride * height as usize * 3) / 2,
+        VideoFormat::NV12 | VideoFormat::I420 | VideoFormat::P010_10LE => (stride * height as usize * 3) / 2,
 
         // YUV422 formats (2 bytes per pixel)
         VideoFormat::YUY2 => stride * height as usize,
@@ -121,18 +159,56 @@ impl SpaDataType {
 }
 
 /// Buffer metadata structure
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
+///
+/// Decoded from the `SPA_META_Header` block the producer attaches to each
+/// buffer (see [`read_header_meta`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct BufferMetadata {
+    /// Presentation timestamp in nanoseconds (`CLOCK_MONOTONIC`), 0 if unset
     pub pts: u64,
+    /// Decode timestamp offset relative to `pts` (nanoseconds)
     pub dts_offset: i64,
-    pub seq: u32,
+    /// Producer sequence number
+    pub seq: u64,
+    /// `SPA_META_HEADER_FLAG_*` bits
     pub flags: u32,
 }
 
+impl BufferMetadata {
+    /// Data is not continuous with the previous buffer
+    pub const FLAG_DISCONT: u32 = 1 << 0;
+    /// Data might be corrupted
+    pub const FLAG_CORRUPTED: u32 = 1 << 1;
+
+    /// Decode a raw `spa_meta_header`
+    pub fn from_header(header: &spa_sys::spa_meta_header) -> Self {
+        Self {
+            pts: u64::try_from(header.pts).unwrap_or(0),
+            dts_offset: header.dts_offset,
+            seq: header.seq,
+            flags: header.flags,
+        }
+    }
+
+    /// Decode timestamp (`pts + dts_offset`, saturating)
+    pub fn dts(&self) -> u64 {
+        self.pts.saturating_add_signed(self.dts_offset)
+    }
+
+    /// Whether the producer flagged the buffer as corrupted
+    pub fn is_corrupted(&self) -> bool {
+        self.flags & Self::FLAG_CORRUPTED != 0
+    }
+
+    /// Whether the buffer follows a discontinuity
+    pub fn is_discont(&self) -> bool {
+        self.flags & Self::FLAG_DISCONT != 0
+    }
+}
+
 /// Damage region
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DamageRegion {
     pub x: i32,
     pub y: i32,
@@ -148,6 +224,51 @@ impl DamageRegion {
     pub fn is_valid(&self) -> bool {
         self.width > 0 && self.height > 0
     }
+
+    /// Clip the region to a `frame_width` x `frame_height` frame
+    ///
+    /// # Returns
+    ///
+    /// The clipped region, or `None` if nothing of it lies within the frame
+    pub fn clip(&self, frame_width: u32, frame_height: u32) -> Option<Self> {
+        let x0 = i64::from(self.x).max(0);
+        let y0 = i64::from(self.y).max(0);
+        let x1 = (i64::from(self.x) + i64::from(self.width)).min(i64::from(frame_width));
+        let y1 = (i64::from(self.y) + i64::from(self.height)).min(i64::from(frame_height));
+
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some(Self::new(x0 as i32, y0 as i32, (x1 - x0) as u32, (y1 - y0) as u32))
+    }
+}
+
+/// Cursor bitmap carried in `SPA_META_Cursor` metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorBitmapMeta {
+    /// Bitmap pixel format
+    pub format: VideoFormat,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Tightly packed pixel rows (`width * bpp` bytes each)
+    pub data: Vec<u8>,
+}
+
+/// Decoded `SPA_META_Cursor` metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorMeta {
+    /// Cursor ID (non-zero)
+    pub id: u32,
+    /// Position of the cursor hotspot in frame coordinates
+    pub position: (i32, i32),
+    /// Hotspot offset within the bitmap
+    pub hotspot: (i32, i32),
+    /// Whether the cursor is visible (a zero-sized bitmap hides it)
+    pub visible: bool,
+    /// New bitmap, or `None` if unchanged since the previous buffer
+    pub bitmap: Option<CursorBitmapMeta>,
 }
 
 /// Stream events listener trait
@@ -168,33 +289,578 @@ pub trait StreamEventsListener: Send + Sync {
     fn on_process(&mut self);
 }
 
-/// Helper to build format parameters
+/// Largest frame size advertised during format negotiation
+pub const MAX_VIDEO_SIZE: Rectangle = Rectangle {
+    width: 8192,
+    height: 8192,
+};
+
+/// Highest framerate advertised during format negotiation
+pub const MAX_VIDEO_FRAMERATE: Fraction = Fraction { num: 360, denom: 1 };
+
+/// Upper bound for the buffer count range in buffer parameters
+pub const MAX_BUFFER_COUNT: u32 = 16;
+
+/// Serialize a pod value into an owned byte buffer
+///
+/// The result can be turned back into a [`Pod`] with [`Pod::from_bytes`].
+fn serialize_pod(value: &spa_pod::Value) -> Result<Vec<u8>> {
+    spa_pod::serialize::PodSerializer::serialize(std::io::Cursor::new(Vec::new()), value)
+        .map(|(cursor, _)| cursor.into_inner())
+        .map_err(|e| PipeWireError::FormatNegotiationFailed(format!("Pod serialization failed: {:?}", e)))
+}
+
+/// The smaller of two fractions (a zero denominator counts as zero)
+fn min_fraction(a: Fraction, b: Fraction) -> Fraction {
+    let lhs = u64::from(a.num) * u64::from(b.denom);
+    let rhs = u64::from(b.num) * u64::from(a.denom);
+    if lhs <= rhs {
+        a
+    } else {
+        b
+    }
+}
+
+/// Build an `EnumFormat` parameter for raw video
+///
+/// The resulting pod offers `formats` in preference order, a size range
+/// from 1x1 up to [`MAX_VIDEO_SIZE`] (defaulting to `width`x`height`) and a
+/// framerate range from 0/1 (variable rate) up to `max_framerate`
+/// (defaulting to `framerate`, capped at `max_framerate`). Screen-cast
+/// producers negotiate a variable rate and honour the `maxFramerate`
+/// property instead, which is offered with the same cap.
+///
+/// When `modifiers` is non-empty a mandatory `VideoModifier` property is
+/// added, which restricts the pod to DMA-BUF capable producers. Callers that
+/// want a shared-memory fallback should additionally pass a pod built with
+/// an empty modifier list.
+///
+/// # Arguments
+///
+/// * `width` - Preferred width
+/// * `height` - Preferred height
+/// * `framerate` - Preferred framerate
+/// * `max_framerate` - Highest acceptable framerate, e.g. [`MAX_VIDEO_FRAMERATE`]
+/// * `formats` - Accepted formats, most preferred first
+/// * `modifiers` - DRM format modifiers, most preferred first
+///
+/// # Returns
+///
+/// Serialized pod bytes, suitable for [`Pod::from_bytes`]
+///
+/// # Errors
+///
+/// Returns `InvalidParameter` if `formats` is empty, or
+/// `FormatNegotiationFailed` if serialization fails
+pub fn build_format_params(
+    width: u32,
+    height: u32,
+    framerate: Fraction,
+    max_framerate: Fraction,
+    formats: &[VideoFormat],
+    modifiers: &[u64],
+) -> Result<Vec<u8>> {
+    let default_format = formats
+        .first()
+        .ok_or_else(|| PipeWireError::InvalidParameter("No video formats to negotiate".to_string()))?;
+
+    let mut properties = vec![
+        spa_pod::Property::new(
+            FormatProperties::MediaType.as_raw(),
+            spa_pod::Value::Id(Id(MediaType::Video.as_raw())),
+        ),
+        spa_pod::Property::new(
+            FormatProperties::MediaSubtype.as_raw(),
+            spa_pod::Value::Id(Id(MediaSubtype::Raw.as_raw())),
+        ),
+        spa_pod::Property::new(
+            FormatProperties::VideoFormat.as_raw(),
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Id(Choice(
+                ChoiceFlags::empty(),
+                ChoiceEnum::Enum {
+                    default: Id(default_format.as_raw()),
+                    alternatives: formats.iter().map(|f| Id(f.as_raw())).collect(),
+                },
+            ))),
+        ),
+    ];
+
+    if let Some(&default_modifier) = modifiers.first() {
+        properties.push(spa_pod::Property {
+            key: FormatProperties::VideoModifier.as_raw(),
+            flags: spa_pod::PropertyFlags::MANDATORY,
+            value: spa_pod::Value::Choice(spa_pod::ChoiceValue::Long(Choice(
+                ChoiceFlags::empty(),
+                ChoiceEnum::Enum {
+                    default: default_modifier as i64,
+                    alternatives: modifiers.iter().map(|&m| m as i64).collect(),
+                },
+            ))),
+        });
+    }
+
+    properties.push(spa_pod::Property::new(
+        FormatProperties::VideoSize.as_raw(),
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Rectangle(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Range {
+                default: Rectangle {
+                    width: width.clamp(1, MAX_VIDEO_SIZE.width),
+                    height: height.clamp(1, MAX_VIDEO_SIZE.height),
+                },
+                min: Rectangle { width: 1, height: 1 },
+                max: MAX_VIDEO_SIZE,
+            },
+        ))),
+    ));
+
+    properties.push(spa_pod::Property::new(
+        FormatProperties::VideoFramerate.as_raw(),
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Fraction(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Range {
+                default: min_fraction(framerate, max_framerate),
+                min: Fraction { num: 0, denom: 1 },
+                max: max_framerate,
+            },
+        ))),
+    ));
+
+    properties.push(spa_pod::Property::new(
+        FormatProperties::VideoMaxFramerate.as_raw(),
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Fraction(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Range {
+                default: max_framerate,
+                min: Fraction { num: 0, denom: 1 },
+                max: max_framerate,
+            },
+        ))),
+    ));
+
+    serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+        type_: SpaTypes::ObjectParamFormat.as_raw(),
+        id: ParamType::EnumFormat.as_raw(),
+        properties,
+    }))
+}
+
+/// Build a `Buffers` parameter
+///
+/// Requests `buffer_count` buffers (any count between 1 and
+/// [`MAX_BUFFER_COUNT`] is acceptable) backed by mapped memory, memfd or,
+/// if `support_dmabuf` is set, DMA-BUF. A `buffer_size` or `stride` of 0
+/// leaves that value up to the producer.
+///
+/// # Returns
+///
+/// Serialized pod bytes, suitable for [`Pod::from_bytes`]
+///
+/// # Errors
+///
+/// Returns `FormatNegotiationFailed` if serialization fails
+pub fn build_buffer_params(buffer_count: u32, buffer_size: u32, stride: u32, support_dmabuf: bool) -> Result<Vec<u8>> {
+    let mut data_types: i32 = (1 << SpaDataType::MemPtr as u32) | (1 << SpaDataType::MemFd as u32);
+    if support_dmabuf {
+        data_types |= 1 << SpaDataType::DmaBuf as u32;
+    }
+
+    let buffer_count = buffer_count.clamp(1, MAX_BUFFER_COUNT);
+    let mut properties = vec![spa_pod::Property::new(
+        spa_sys::SPA_PARAM_BUFFERS_buffers,
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Range {
+                default: buffer_count as i32,
+                min: 1,
+                max: MAX_BUFFER_COUNT as i32,
+            },
+        ))),
+    )];
+
+    if buffer_size > 0 {
+        properties.push(spa_pod::Property::new(
+            spa_sys::SPA_PARAM_BUFFERS_size,
+            spa_pod::Value::Int(buffer_size as i32),
+        ));
+    }
+
+    if stride > 0 {
+        properties.push(spa_pod::Property::new(
+            spa_sys::SPA_PARAM_BUFFERS_stride,
+            spa_pod::Value::Int(stride as i32),
+        ));
+    }
+
+    properties.push(spa_pod::Property::new(
+        spa_sys::SPA_PARAM_BUFFERS_dataType,
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(
+            ChoiceFlags::empty(),
+            ChoiceEnum::Flags {
+                default: data_types,
+                flags: Vec::new(),
+            },
+        ))),
+    ));
+
+    serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+        type_: SpaTypes::ObjectParamBuffers.as_raw(),
+        id: ParamType::Buffers.as_raw(),
+        properties,
+    }))
+}
+
+/// Size of the `SPA_META_Header` metadata block
+pub const META_HEADER_SIZE: usize = std::mem::size_of::<spa_sys::spa_meta_header>();
+
+/// Build a `Meta` parameter requesting a metadata block on every buffer
+///
+/// # Arguments
+///
+/// * `meta_type` - `SPA_META_*` type (e.g. `spa_sys::SPA_META_Header`)
+/// * `size` - Size of the metadata block in bytes
+///
+/// # Errors
+///
+/// Returns `FormatNegotiationFailed` if serialization fails
+pub fn build_meta_param(meta_type: u32, size: usize) -> Result<Vec<u8>> {
+    serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+        type_: SpaTypes::ObjectParamMeta.as_raw(),
+        id: ParamType::Meta.as_raw(),
+        properties: vec![
+            spa_pod::Property::new(spa_sys::SPA_PARAM_META_type, spa_pod::Value::Id(Id(meta_type))),
+            spa_pod::Property::new(spa_sys::SPA_PARAM_META_size, spa_pod::Value::Int(size as i32)),
+        ],
+    }))
+}
+
+/// Find a metadata block on a buffer
+///
+/// # Returns
+///
+/// Pointer to the block's data, or `None` if the buffer carries no block of
+/// `meta_type` at least `min_size` bytes large
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a valid `spa_buffer` whose `metas`
+/// array holds `n_metas` entries.
+pub unsafe fn find_meta(
+    buffer: *const spa_sys::spa_buffer,
+    meta_type: u32,
+    min_size: usize,
+) -> Option<std::ptr::NonNull<std::ffi::c_void>> {
+    // SAFETY: forwarded from the caller's contract
+    unsafe { find_meta_entry(buffer, meta_type) }
+        .filter(|meta| meta.size as usize >= min_size)
+        .and_then(|meta| std::ptr::NonNull::new(meta.data))
+}
+
+/// Find the `spa_meta` entry of `meta_type` on a buffer
+///
+/// # Safety
+///
+/// Same requirements as [`find_meta`]; the returned reference must not
+/// outlive the buffer.
+unsafe fn find_meta_entry<'a>(buffer: *const spa_sys::spa_buffer, meta_type: u32) -> Option<&'a spa_sys::spa_meta> {
+    // SAFETY: the caller guarantees buffer is null or valid
+    let buffer = unsafe { buffer.as_ref() }?;
+    if buffer.metas.is_null() {
+        return None;
+    }
+
+    // SAFETY: a valid spa_buffer's metas array holds n_metas entries
+    unsafe { std::slice::from_raw_parts(buffer.metas, buffer.n_metas as usize) }
+        .iter()
+        .find(|meta| meta.type_ == meta_type)
+}
+
+/// Read the `SPA_META_Header` block of a buffer
+///
+/// # Returns
+///
+/// The decoded header, or `None` if the producer didn't attach one
+///
+/// # Safety
+///
+/// Same requirements as [`find_meta`].
+pub unsafe fn read_header_meta(buffer: *const spa_sys::spa_buffer) -> Option<BufferMetadata> {
+    // SAFETY: forwarded from the caller's contract
+    let data = unsafe { find_meta(buffer, spa_sys::SPA_META_Header, META_HEADER_SIZE) }?;
+    // SAFETY: find_meta checked the block holds a whole spa_meta_header. The
+    // block is producer-owned shared memory; don't assume alignment.
+    let header = unsafe { data.as_ptr().cast::<spa_sys::spa_meta_header>().read_unaligned() };
+    Some(BufferMetadata::from_header(&header))
+}
+
+/// Size of one `SPA_META_VideoDamage` region entry
+pub const META_REGION_SIZE: usize = std::mem::size_of::<spa_sys::spa_meta_region>();
+
+/// Most damage regions requested per buffer
+pub const MAX_DAMAGE_REGIONS: usize = 16;
+
+/// Build a `Meta` parameter requesting `SPA_META_VideoDamage`
+///
+/// The metadata block size is offered as a range of 1 to `max_regions`
+/// region entries so the producer can pick what it supports.
+///
+/// # Errors
+///
+/// Returns `FormatNegotiationFailed` if serialization fails
+pub fn build_damage_meta_param(max_regions: usize) -> Result<Vec<u8>> {
+    let max_size = (META_REGION_SIZE * max_regions.max(1)) as i32;
+    serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+        type_: SpaTypes::ObjectParamMeta.as_raw(),
+        id: ParamType::Meta.as_raw(),
+        properties: vec![
+            spa_pod::Property::new(
+                spa_sys::SPA_PARAM_META_type,
+                spa_pod::Value::Id(Id(spa_sys::SPA_META_VideoDamage)),
+            ),
+            spa_pod::Property::new(
+                spa_sys::SPA_PARAM_META_size,
+                spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(
+                    ChoiceFlags::empty(),
+                    ChoiceEnum::Range {
+                        default: max_size,
+                        min: META_REGION_SIZE as i32,
+                        max: max_size,
+                    },
+                ))),
+            ),
+        ],
+    }))
+}
+
+/// Read the `SPA_META_VideoDamage` regions of a buffer
+///
+/// The region array ends at the first empty region (as in
+/// `spa_meta_region_is_valid`).
+///
+/// # Returns
+///
+/// Damaged regions in frame coordinates (unclipped), or `None` if the
+/// producer didn't attach damage metadata. An empty list means the producer
+/// reported no usable damage for this buffer.
 ///
-/// Note: This is a placeholder. Actual format parameter construction
-/// would be done using PipeWire stream builder in production code.
-/// The pipewire crate provides higher-level APIs for this.
-pub fn build_format_params(_width: u32, _height: u32, _framerate: Fraction, _formats: &[VideoFormat]) -> Vec<u8> {
-    // In production, use pipewire::stream::StreamBuilder with appropriate parameters
-    // This would use the PipeWire C API directly or through pipewire-rs
-    Vec::new()
+/// # Safety
+///
+/// Same requirements as [`find_meta`].
+pub unsafe fn read_damage_meta(buffer: *const spa_sys::spa_buffer) -> Option<Vec<DamageRegion>> {
+    // SAFETY: forwarded from the caller's contract
+    let meta = unsafe { find_meta_entry(buffer, spa_sys::SPA_META_VideoDamage) }.filter(|meta| !meta.data.is_null())?;
+
+    let count = meta.size as usize / META_REGION_SIZE;
+    let entries = meta.data.cast::<spa_sys::spa_meta_region>();
+
+    let mut regions = Vec::new();
+    for i in 0..count {
+        // SAFETY: i < count, so the entry lies within the meta.size byte block
+        let entry = unsafe { entries.add(i).read_unaligned() };
+        let region = DamageRegion::new(
+            entry.region.position.x,
+            entry.region.position.y,
+            entry.region.size.width,
+            entry.region.size.height,
+        );
+        if !region.is_valid() {
+            break;
+        }
+        regions.push(region);
+    }
+
+    Some(regions)
+}
+
+/// Default cursor bitmap size requested in `SPA_META_Cursor` (pixels per side)
+pub const DEFAULT_CURSOR_SIZE: u32 = 64;
+
+/// Largest cursor bitmap size accepted in `SPA_META_Cursor` (pixels per side)
+pub const MAX_CURSOR_SIZE: u32 = 1024;
+
+/// Size of a `SPA_META_Cursor` block holding a `size` x `size` 32-bit bitmap
+pub const fn cursor_meta_size(size: u32) -> usize {
+    std::mem::size_of::<spa_sys::spa_meta_cursor>()
+        + std::mem::size_of::<spa_sys::spa_meta_bitmap>()
+        + (size as usize * size as usize * 4)
+}
+
+/// Build a `Meta` parameter requesting `SPA_META_Cursor`
+///
+/// The block size is offered as a range fitting 1x1 up to
+/// [`MAX_CURSOR_SIZE`] bitmaps, defaulting to [`DEFAULT_CURSOR_SIZE`].
+///
+/// # Errors
+///
+/// Returns `FormatNegotiationFailed` if serialization fails
+pub fn build_cursor_meta_param() -> Result<Vec<u8>> {
+    serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+        type_: SpaTypes::ObjectParamMeta.as_raw(),
+        id: ParamType::Meta.as_raw(),
+        properties: vec![
+            spa_pod::Property::new(
+                spa_sys::SPA_PARAM_META_type,
+                spa_pod::Value::Id(Id(spa_sys::SPA_META_Cursor)),
+            ),
+            spa_pod::Property::new(
+                spa_sys::SPA_PARAM_META_size,
+                spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(
+                    ChoiceFlags::empty(),
+                    ChoiceEnum::Range {
+                        default: cursor_meta_size(DEFAULT_CURSOR_SIZE) as i32,
+                        min: cursor_meta_size(1) as i32,
+                        max: cursor_meta_size(MAX_CURSOR_SIZE) as i32,
+                    },
+                ))),
+            ),
+        ],
+    }))
+}
+
+/// Read a plain C struct from `block` at `offset`, bounds-checked
+fn read_struct<T: Copy>(block: &[u8], offset: usize) -> Option<T> {
+    let end = offset.checked_add(std::mem::size_of::<T>())?;
+    if end > block.len() {
+        return None;
+    }
+    // SAFETY: offset..end lies within block; T is a plain C struct, so any
+    // bit pattern is valid. read_unaligned handles arbitrary alignment.
+    Some(unsafe { block.as_ptr().add(offset).cast::<T>().read_unaligned() })
+}
+
+/// Decode a `SPA_META_Cursor` block
+///
+/// # Returns
+///
+/// The cursor, or `None` if the block holds no valid cursor (ID 0) or is
+/// malformed. A bitmap that doesn't fit the block is ignored.
+pub fn parse_cursor_meta(block: &[u8]) -> Option<CursorMeta> {
+    let cursor: spa_sys::spa_meta_cursor = read_struct(block, 0)?;
+    if cursor.id == 0 {
+        return None;
+    }
+
+    let mut meta = CursorMeta {
+        id: cursor.id,
+        position: (cursor.position.x, cursor.position.y),
+        hotspot: (cursor.hotspot.x, cursor.hotspot.y),
+        visible: true,
+        bitmap: None,
+    };
+
+    if cursor.bitmap_offset == 0 {
+        return Some(meta);
+    }
+
+    let bitmap_offset = cursor.bitmap_offset as usize;
+    let Some(bitmap) = read_struct::<spa_sys::spa_meta_bitmap>(block, bitmap_offset) else {
+        return Some(meta);
+    };
+
+    let (width, height) = (bitmap.size.width as usize, bitmap.size.height as usize);
+    if width == 0 || height == 0 {
+        meta.visible = false;
+        return Some(meta);
+    }
+
+    let format = VideoFormat(bitmap.format);
+    let row_bytes = width * get_bytes_per_pixel(format);
+    let stride = usize::try_from(bitmap.stride)
+        .ok()
+        .filter(|&s| s >= row_bytes)
+        .unwrap_or(row_bytes);
+    let data_offset = bitmap_offset + bitmap.offset as usize;
+    let data_end = stride
+        .checked_mul(height - 1)
+        .and_then(|len| len.checked_add(row_bytes))
+        .and_then(|len| len.checked_add(data_offset));
+    let Some(data_end) = data_end.filter(|&end| end <= block.len()) else {
+        return Some(meta);
+    };
+
+    let mut data = Vec::with_capacity(row_bytes * height);
+    for row in block[data_offset..data_end].chunks(stride) {
+        data.extend_from_slice(&row[..row_bytes]);
+    }
+
+    meta.bitmap = Some(CursorBitmapMeta {
+        format,
+        width: bitmap.size.width,
+        height: bitmap.size.height,
+        data,
+    });
+    Some(meta)
 }
 
-/// Helper to build buffer parameters
+/// Read the `SPA_META_Cursor` block of a buffer
+///
+/// # Returns
+///
+/// The decoded cursor, or `None` if the buffer carries no valid cursor
+///
+/// # Safety
 ///
-/// Note: This is a placeholder. Actual buffer parameter construction
-/// would be done using PipeWire stream builder in production code.
-pub fn build_buffer_params(_buffer_count: u32, _buffer_size: u32, _stride: u32, _support_dmabuf: bool) -> Vec<u8> {
-    // In production, use pipewire::stream::StreamBuilder with appropriate parameters
-    Vec::new()
+/// Same requirements as [`find_meta`]; the block must hold `size` readable
+/// bytes.
+pub unsafe fn read_cursor_meta(buffer: *const spa_sys::spa_buffer) -> Option<CursorMeta> {
+    // SAFETY: forwarded from the caller's contract
+    let meta = unsafe { find_meta_entry(buffer, spa_sys::SPA_META_Cursor) }.filter(|meta| !meta.data.is_null())?;
+    // SAFETY: the caller guarantees the block holds meta.size readable bytes
+    let block = unsafe { std::slice::from_raw_parts(meta.data.cast::<u8>(), meta.size as usize) };
+    parse_cursor_meta(block)
 }
 
 /// Parse video format from Pod
 ///
-/// Note: This is a placeholder. Actual format parsing would use
-/// the pipewire crate's built-in format parsing.
-pub fn parse_video_format(_pod: &Pod) -> Option<VideoInfoRaw> {
-    // In production, use pipewire's format parsing APIs
-    None
+/// Parses a negotiated `Format` parameter (as delivered to `param_changed`)
+/// into a [`VideoInfoRaw`] holding format, size, framerate and modifier.
+///
+/// # Returns
+///
+/// `None` if the pod is not a raw video format or cannot be parsed
+pub fn parse_video_format(pod: &Pod) -> Option<VideoInfoRaw> {
+    let (media_type, media_subtype) = libspa::param::format_utils::parse_format(pod).ok()?;
+    if media_type != MediaType::Video || media_subtype != MediaSubtype::Raw {
+        return None;
+    }
+
+    let mut info = VideoInfoRaw::new();
+    info.parse(pod).ok()?;
+    Some(info)
+}
+
+/// Extract the DRM modifier from a negotiated `Format` parameter
+///
+/// [`VideoInfoRaw::modifier`] cannot distinguish "no modifier" from
+/// `DRM_FORMAT_MOD_LINEAR` (both are 0), so the pod is inspected directly.
+///
+/// # Returns
+///
+/// `Some(modifier)` if the format carries a `VideoModifier` property
+/// (shared-memory formats don't), `None` otherwise
+pub fn parse_video_modifier(pod: &Pod) -> Option<u64> {
+    let object = match spa_pod::deserialize::PodDeserializer::deserialize_any_from(pod.as_bytes()) {
+        Ok((_, spa_pod::Value::Object(object))) => object,
+        _ => return None,
+    };
+
+    let property = object
+        .properties
+        .iter()
+        .find(|p| p.key == FormatProperties::VideoModifier.as_raw())?;
+
+    match &property.value {
+        spa_pod::Value::Long(modifier) => Some(*modifier as u64),
+        // Not fixated yet: the producer's preferred choice comes first
+        spa_pod::Value::Choice(spa_pod::ChoiceValue::Long(Choice(_, choice))) => match choice {
+            ChoiceEnum::None(modifier)
+            | ChoiceEnum::Range { default: modifier, .. }
+            | ChoiceEnum::Step { default: modifier, .. }
+            | ChoiceEnum::Enum { default: modifier, .. }
+            | ChoiceEnum::Flags { default: modifier, .. } => Some(*modifier as u64),
+        },
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +878,19 @@ mod tests {
             drm_fourcc_to_spa_video_format(drm_fourcc::DRM_FORMAT_XRGB8888),
             Some(VideoFormat::BGRx)
         );
+
+        for format in [
+            VideoFormat::xRGB,
+            VideoFormat::ABGR,
+            VideoFormat::ARGB_210LE,
+            VideoFormat::xBGR_210LE,
+            VideoFormat::NV12,
+            VideoFormat::P010_10LE,
+        ] {
+            let fourcc = spa_video_format_to_drm_fourcc(format);
+            assert_ne!(fourcc, drm_fourcc::DRM_FORMAT_INVALID);
+            assert_eq!(drm_fourcc_to_spa_video_format(fourcc), Some(format));
+        }
     }
 
     #[test]
@@ -219,6 +898,8 @@ mod tests {
         assert_eq!(get_bytes_per_pixel(VideoFormat::BGRA), 4);
         assert_eq!(get_bytes_per_pixel(VideoFormat::RGB), 3);
         assert_eq!(get_bytes_per_pixel(VideoFormat::GRAY8), 1);
+        assert_eq!(get_bytes_per_pixel(VideoFormat::P010_10LE), 2);
+        assert_eq!(calculate_buffer_size(16, 2, VideoFormat::P010_10LE), 96);
     }
 
     #[test]
@@ -236,6 +917,467 @@ mod tests {
         assert_eq!(calculate_buffer_size(1920, 1080, VideoFormat::BGRA), 7680 * 1080);
     }
 
+    fn deserialize_object(bytes: &[u8]) -> spa_pod::Object {
+        assert!(Pod::from_bytes(bytes).is_some());
+        match spa_pod::deserialize::PodDeserializer::deserialize_any_from(bytes) {
+            Ok((_, spa_pod::Value::Object(object))) => object,
+            other => unreachable!("expected object pod, got {:?}", other),
+        }
+    }
+
+    fn find_property(object: &spa_pod::Object, key: u32) -> Option<&spa_pod::Property> {
+        object.properties.iter().find(|p| p.key == key)
+    }
+
+    #[test]
+    fn test_build_format_params() {
+        let formats = [VideoFormat::BGRx, VideoFormat::BGRA];
+        let bytes = build_format_params(
+            1280,
+            720,
+            Fraction { num: 60, denom: 1 },
+            MAX_VIDEO_FRAMERATE,
+            &formats,
+            &[],
+        )
+        .unwrap();
+        let object = deserialize_object(&bytes);
+
+        assert_eq!(object.type_, SpaTypes::ObjectParamFormat.as_raw());
+        assert_eq!(object.id, ParamType::EnumFormat.as_raw());
+
+        let media_type = find_property(&object, FormatProperties::MediaType.as_raw()).unwrap();
+        assert_eq!(media_type.value, spa_pod::Value::Id(Id(MediaType::Video.as_raw())));
+
+        let format = find_property(&object, FormatProperties::VideoFormat.as_raw()).unwrap();
+        match &format.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Id(Choice(_, ChoiceEnum::Enum { default, alternatives }))) => {
+                assert_eq!(*default, Id(VideoFormat::BGRx.as_raw()));
+                assert_eq!(
+                    alternatives,
+                    &vec![Id(VideoFormat::BGRx.as_raw()), Id(VideoFormat::BGRA.as_raw())]
+                );
+            }
+            other => unreachable!("unexpected format value: {:?}", other),
+        }
+
+        let size = find_property(&object, FormatProperties::VideoSize.as_raw()).unwrap();
+        match &size.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Rectangle(Choice(
+                _,
+                ChoiceEnum::Range { default, min, max },
+            ))) => {
+                assert_eq!((default.width, default.height), (1280, 720));
+                assert_eq!((min.width, min.height), (1, 1));
+                assert_eq!((max.width, max.height), (MAX_VIDEO_SIZE.width, MAX_VIDEO_SIZE.height));
+            }
+            other => unreachable!("unexpected size value: {:?}", other),
+        }
+
+        let framerate = find_property(&object, FormatProperties::VideoFramerate.as_raw()).unwrap();
+        match &framerate.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Fraction(Choice(
+                _,
+                ChoiceEnum::Range { default, min, .. },
+            ))) => {
+                assert_eq!((default.num, default.denom), (60, 1));
+                assert_eq!(min.num, 0);
+            }
+            other => unreachable!("unexpected framerate value: {:?}", other),
+        }
+
+        // No modifiers requested, so the pod must stay usable for shared memory
+        assert!(find_property(&object, FormatProperties::VideoModifier.as_raw()).is_none());
+    }
+
+    #[test]
+    fn test_build_format_params_with_modifiers() {
+        let modifiers = [drm_fourcc::DRM_FORMAT_MOD_LINEAR, drm_fourcc::DRM_FORMAT_MOD_INVALID];
+        let bytes = build_format_params(
+            1920,
+            1080,
+            Fraction { num: 30, denom: 1 },
+            MAX_VIDEO_FRAMERATE,
+            &[VideoFormat::BGRx],
+            &modifiers,
+        )
+        .unwrap();
+        let object = deserialize_object(&bytes);
+
+        let modifier = find_property(&object, FormatProperties::VideoModifier.as_raw()).unwrap();
+        assert!(modifier.flags.contains(spa_pod::PropertyFlags::MANDATORY));
+        match &modifier.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Long(Choice(
+                _,
+                ChoiceEnum::Enum { default, alternatives },
+            ))) => {
+                assert_eq!(*default, drm_fourcc::DRM_FORMAT_MOD_LINEAR as i64);
+                assert_eq!(
+                    alternatives,
+                    &vec![
+                        drm_fourcc::DRM_FORMAT_MOD_LINEAR as i64,
+                        drm_fourcc::DRM_FORMAT_MOD_INVALID as i64
+                    ]
+                );
+            }
+            other => unreachable!("unexpected modifier value: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_build_format_params_max_framerate() {
+        let cap = Fraction { num: 5, denom: 1 };
+        let bytes = build_format_params(
+            1920,
+            1080,
+            Fraction { num: 30, denom: 1 },
+            cap,
+            &[VideoFormat::BGRx],
+            &[],
+        )
+        .unwrap();
+        let object = deserialize_object(&bytes);
+
+        for key in [
+            FormatProperties::VideoFramerate.as_raw(),
+            FormatProperties::VideoMaxFramerate.as_raw(),
+        ] {
+            let property = find_property(&object, key).unwrap();
+            match &property.value {
+                spa_pod::Value::Choice(spa_pod::ChoiceValue::Fraction(Choice(
+                    _,
+                    ChoiceEnum::Range { default, max, .. },
+                ))) => {
+                    // The preferred 30 fps is clamped to the cap
+                    assert_eq!((default.num, default.denom), (5, 1));
+                    assert_eq!((max.num, max.denom), (5, 1));
+                }
+                other => unreachable!("unexpected framerate value: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_format_params_requires_formats() {
+        let result = build_format_params(
+            1920,
+            1080,
+            Fraction { num: 30, denom: 1 },
+            MAX_VIDEO_FRAMERATE,
+            &[],
+            &[],
+        );
+        assert!(matches!(result, Err(PipeWireError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn test_build_buffer_params() {
+        let bytes = build_buffer_params(4, 7680 * 1080, 7680, true).unwrap();
+        let object = deserialize_object(&bytes);
+
+        assert_eq!(object.type_, SpaTypes::ObjectParamBuffers.as_raw());
+        assert_eq!(object.id, ParamType::Buffers.as_raw());
+
+        let buffers = find_property(&object, spa_sys::SPA_PARAM_BUFFERS_buffers).unwrap();
+        match &buffers.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(_, ChoiceEnum::Range { default, .. }))) => {
+                assert_eq!(*default, 4);
+            }
+            other => unreachable!("unexpected buffers value: {:?}", other),
+        }
+
+        let stride = find_property(&object, spa_sys::SPA_PARAM_BUFFERS_stride).unwrap();
+        assert_eq!(stride.value, spa_pod::Value::Int(7680));
+
+        let data_type = find_property(&object, spa_sys::SPA_PARAM_BUFFERS_dataType).unwrap();
+        match &data_type.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(_, ChoiceEnum::Flags { default, .. }))) => {
+                assert_ne!(*default & (1 << SpaDataType::DmaBuf as i32), 0);
+                assert_ne!(*default & (1 << SpaDataType::MemFd as i32), 0);
+            }
+            other => unreachable!("unexpected dataType value: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_build_buffer_params_without_dmabuf() {
+        let bytes = build_buffer_params(3, 0, 0, false).unwrap();
+        let object = deserialize_object(&bytes);
+
+        // Size and stride are left to the producer when not specified
+        assert!(find_property(&object, spa_sys::SPA_PARAM_BUFFERS_size).is_none());
+        assert!(find_property(&object, spa_sys::SPA_PARAM_BUFFERS_stride).is_none());
+
+        let data_type = find_property(&object, spa_sys::SPA_PARAM_BUFFERS_dataType).unwrap();
+        match &data_type.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(_, ChoiceEnum::Flags { default, .. }))) => {
+                assert_eq!(*default & (1 << SpaDataType::DmaBuf as i32), 0);
+            }
+            other => unreachable!("unexpected dataType value: {:?}", other),
+        }
+    }
+
+    fn fixed_format_pod(format: VideoFormat, width: u32, height: u32, modifier: Option<u64>) -> Vec<u8> {
+        let mut properties = vec![
+            spa_pod::Property::new(
+                FormatProperties::MediaType.as_raw(),
+                spa_pod::Value::Id(Id(MediaType::Video.as_raw())),
+            ),
+            spa_pod::Property::new(
+                FormatProperties::MediaSubtype.as_raw(),
+                spa_pod::Value::Id(Id(MediaSubtype::Raw.as_raw())),
+            ),
+            spa_pod::Property::new(
+                FormatProperties::VideoFormat.as_raw(),
+                spa_pod::Value::Id(Id(format.as_raw())),
+            ),
+            spa_pod::Property::new(
+                FormatProperties::VideoSize.as_raw(),
+                spa_pod::Value::Rectangle(Rectangle { width, height }),
+            ),
+            spa_pod::Property::new(
+                FormatProperties::VideoFramerate.as_raw(),
+                spa_pod::Value::Fraction(Fraction { num: 60, denom: 1 }),
+            ),
+        ];
+        if let Some(modifier) = modifier {
+            properties.push(spa_pod::Property::new(
+                FormatProperties::VideoModifier.as_raw(),
+                spa_pod::Value::Long(modifier as i64),
+            ));
+        }
+
+        serialize_pod(&spa_pod::Value::Object(spa_pod::Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::Format.as_raw(),
+            properties,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_video_format() {
+        let bytes = fixed_format_pod(VideoFormat::RGBx, 2560, 1440, None);
+        let info = parse_video_format(Pod::from_bytes(&bytes).unwrap()).unwrap();
+
+        assert_eq!(info.format(), VideoFormat::RGBx);
+        assert_eq!((info.size().width, info.size().height), (2560, 1440));
+        assert_eq!((info.framerate().num, info.framerate().denom), (60, 1));
+        assert_eq!(parse_video_modifier(Pod::from_bytes(&bytes).unwrap()), None);
+    }
+
+    #[test]
+    fn test_parse_video_modifier() {
+        let bytes = fixed_format_pod(VideoFormat::BGRx, 1920, 1080, Some(drm_fourcc::DRM_FORMAT_MOD_LINEAR));
+        assert_eq!(
+            parse_video_modifier(Pod::from_bytes(&bytes).unwrap()),
+            Some(drm_fourcc::DRM_FORMAT_MOD_LINEAR)
+        );
+    }
+
+    #[test]
+    fn test_build_meta_param() {
+        let bytes = build_meta_param(spa_sys::SPA_META_Header, META_HEADER_SIZE).unwrap();
+        let object = deserialize_object(&bytes);
+        assert_eq!(object.type_, SpaTypes::ObjectParamMeta.as_raw());
+        assert_eq!(object.id, ParamType::Meta.as_raw());
+
+        let meta_type = find_property(&object, spa_sys::SPA_PARAM_META_type).unwrap();
+        assert_eq!(meta_type.value, spa_pod::Value::Id(Id(spa_sys::SPA_META_Header)));
+
+        let size = find_property(&object, spa_sys::SPA_PARAM_META_size).unwrap();
+        assert_eq!(size.value, spa_pod::Value::Int(META_HEADER_SIZE as i32));
+    }
+
+    #[test]
+    fn test_read_header_meta() {
+        let mut header = spa_sys::spa_meta_header {
+            flags: BufferMetadata::FLAG_CORRUPTED,
+            offset: 0,
+            pts: 1_000_000_000,
+            dts_offset: -500,
+            seq: 42,
+        };
+        let mut metas = [spa_sys::spa_meta {
+            type_: spa_sys::SPA_META_Header,
+            size: META_HEADER_SIZE as u32,
+            data: std::ptr::addr_of_mut!(header).cast(),
+        }];
+        // SAFETY: spa_buffer is a plain C struct; all-zero is a valid (empty) value
+        let mut buffer: spa_sys::spa_buffer = unsafe { std::mem::zeroed() };
+        buffer.n_metas = 1;
+        buffer.metas = metas.as_mut_ptr();
+
+        // SAFETY: buffer points to one valid meta entry backed by `header`
+        let meta = unsafe { read_header_meta(&buffer) }.unwrap();
+        assert_eq!(meta.pts, 1_000_000_000);
+        assert_eq!(meta.dts(), 999_999_500);
+        assert_eq!(meta.seq, 42);
+        assert!(meta.is_corrupted());
+        assert!(!meta.is_discont());
+
+        // Too small or missing blocks are ignored
+        metas[0].size = 4;
+        buffer.metas = metas.as_mut_ptr();
+        // SAFETY: as above
+        assert!(unsafe { read_header_meta(&buffer) }.is_none());
+        buffer.n_metas = 0;
+        // SAFETY: as above
+        assert!(unsafe { read_header_meta(&buffer) }.is_none());
+        // SAFETY: null buffers are explicitly allowed
+        assert!(unsafe { read_header_meta(std::ptr::null()) }.is_none());
+    }
+
+    #[test]
+    fn test_build_damage_meta_param() {
+        let bytes = build_damage_meta_param(MAX_DAMAGE_REGIONS).unwrap();
+        let object = deserialize_object(&bytes);
+        assert_eq!(object.type_, SpaTypes::ObjectParamMeta.as_raw());
+
+        let meta_type = find_property(&object, spa_sys::SPA_PARAM_META_type).unwrap();
+        assert_eq!(meta_type.value, spa_pod::Value::Id(Id(spa_sys::SPA_META_VideoDamage)));
+
+        let size = find_property(&object, spa_sys::SPA_PARAM_META_size).unwrap();
+        match &size.value {
+            spa_pod::Value::Choice(spa_pod::ChoiceValue::Int(Choice(_, ChoiceEnum::Range { default, min, max }))) => {
+                assert_eq!(*min, META_REGION_SIZE as i32);
+                assert_eq!(*max, (META_REGION_SIZE * MAX_DAMAGE_REGIONS) as i32);
+                assert_eq!(*default, *max);
+            }
+            other => unreachable!("expected size range, got {:?}", other),
+        }
+    }
+
+    fn meta_region(x: i32, y: i32, width: u32, height: u32) -> spa_sys::spa_meta_region {
+        spa_sys::spa_meta_region {
+            region: spa_sys::spa_region {
+                position: spa_sys::spa_point { x, y },
+                size: spa_sys::spa_rectangle { width, height },
+            },
+        }
+    }
+
+    #[test]
+    fn test_read_damage_meta() {
+        let mut regions = [
+            meta_region(0, 0, 100, 50),
+            meta_region(200, 300, 10, 10),
+            meta_region(0, 0, 0, 0),
+            meta_region(5, 5, 5, 5),
+        ];
+        let mut metas = [spa_sys::spa_meta {
+            type_: spa_sys::SPA_META_VideoDamage,
+            size: std::mem::size_of_val(&regions) as u32,
+            data: regions.as_mut_ptr().cast(),
+        }];
+        // SAFETY: spa_buffer is a plain C struct; all-zero is a valid (empty) value
+        let mut buffer: spa_sys::spa_buffer = unsafe { std::mem::zeroed() };
+        buffer.n_metas = 1;
+        buffer.metas = metas.as_mut_ptr();
+
+        // SAFETY: buffer points to one valid meta entry backed by `regions`
+        let damage = unsafe { read_damage_meta(&buffer) }.unwrap();
+        // Iteration stops at the first empty region
+        assert_eq!(
+            damage,
+            vec![DamageRegion::new(0, 0, 100, 50), DamageRegion::new(200, 300, 10, 10)]
+        );
+
+        // Present but empty
+        regions[0] = meta_region(0, 0, 0, 0);
+        metas[0].data = regions.as_mut_ptr().cast();
+        buffer.metas = metas.as_mut_ptr();
+        // SAFETY: as above
+        assert_eq!(unsafe { read_damage_meta(&buffer) }, Some(Vec::new()));
+
+        // Absent
+        buffer.n_metas = 0;
+        // SAFETY: as above
+        assert!(unsafe { read_damage_meta(&buffer) }.is_none());
+    }
+
+    #[test]
+    fn test_damage_region_clip() {
+        let region = DamageRegion::new(-10, 1000, 100, 200);
+        assert_eq!(region.clip(1920, 1080), Some(DamageRegion::new(0, 1000, 90, 80)));
+        assert_eq!(DamageRegion::new(1920, 0, 10, 10).clip(1920, 1080), None);
+        assert_eq!(DamageRegion::new(-20, 0, 10, 10).clip(1920, 1080), None);
+    }
+
+    /// Serialize a cursor block the way a producer lays it out
+    fn cursor_block(id: u32, bitmap: Option<(u32, u32, i32, &[u8])>) -> Vec<u8> {
+        fn bytes_of<T>(value: &T) -> &[u8] {
+            // SAFETY: reading the bytes of a plain C struct
+            unsafe { std::slice::from_raw_parts((value as *const T).cast::<u8>(), std::mem::size_of::<T>()) }
+        }
+
+        let cursor_size = std::mem::size_of::<spa_sys::spa_meta_cursor>();
+        let bitmap_size = std::mem::size_of::<spa_sys::spa_meta_bitmap>();
+        let cursor = spa_sys::spa_meta_cursor {
+            id,
+            flags: 0,
+            position: spa_sys::spa_point { x: 100, y: 200 },
+            hotspot: spa_sys::spa_point { x: 2, y: 3 },
+            bitmap_offset: if bitmap.is_some() { cursor_size as u32 } else { 0 },
+        };
+
+        let mut block = bytes_of(&cursor).to_vec();
+        if let Some((width, height, stride, pixels)) = bitmap {
+            let header = spa_sys::spa_meta_bitmap {
+                format: VideoFormat::RGBA.as_raw(),
+                size: spa_sys::spa_rectangle { width, height },
+                stride,
+                offset: bitmap_size as u32,
+            };
+            block.extend_from_slice(bytes_of(&header));
+            block.extend_from_slice(pixels);
+        }
+        block
+    }
+
+    #[test]
+    fn test_build_cursor_meta_param() {
+        let bytes = build_cursor_meta_param().unwrap();
+        let object = deserialize_object(&bytes);
+
+        let meta_type = find_property(&object, spa_sys::SPA_PARAM_META_type).unwrap();
+        assert_eq!(meta_type.value, spa_pod::Value::Id(Id(spa_sys::SPA_META_Cursor)));
+        assert!(cursor_meta_size(1) < cursor_meta_size(DEFAULT_CURSOR_SIZE));
+    }
+
+    #[test]
+    fn test_parse_cursor_meta_position_only() {
+        let meta = parse_cursor_meta(&cursor_block(7, None)).unwrap();
+        assert_eq!(meta.id, 7);
+        assert_eq!(meta.position, (100, 200));
+        assert_eq!(meta.hotspot, (2, 3));
+        assert!(meta.visible);
+        assert!(meta.bitmap.is_none());
+
+        // ID 0 marks the block as invalid
+        assert!(parse_cursor_meta(&cursor_block(0, None)).is_none());
+        assert!(parse_cursor_meta(&[0u8; 4]).is_none());
+    }
+
+    #[test]
+    fn test_parse_cursor_meta_bitmap() {
+        // 2x2 RGBA with a padded stride of 12 bytes
+        let pixels: Vec<u8> = (0..24).collect();
+        let meta = parse_cursor_meta(&cursor_block(1, Some((2, 2, 12, &pixels)))).unwrap();
+        let bitmap = meta.bitmap.unwrap();
+        assert_eq!(bitmap.format, VideoFormat::RGBA);
+        assert_eq!((bitmap.width, bitmap.height), (2, 2));
+        assert_eq!(bitmap.data, [&pixels[0..8], &pixels[12..20]].concat());
+
+        // Truncated bitmap data is ignored rather than read out of bounds
+        let meta = parse_cursor_meta(&cursor_block(1, Some((2, 2, 12, &pixels[..10])))).unwrap();
+        assert!(meta.bitmap.is_none());
+
+        // Zero-sized bitmap hides the cursor
+        let meta = parse_cursor_meta(&cursor_block(1, Some((0, 0, 0, &[])))).unwrap();
+        assert!(!meta.visible);
+    }
+
     #[test]
     fn test_damage_region() {
         let region = DamageRegion::new(10, 20, 100, 200);
@@ -1,12 +1,20 @@
 //! Format Conversion Utilities
 //!
 //! Provides pixel format conversion between various video formats.
-//! Includes optimized SIMD implementations where available.
+//! The hot paths use the SIMD kernels in [`crate::simd`] where the CPU
+//! supports them.
 
+use crate::colorimetry::{Colorimetry, YuvCoefficients};
 use crate::error::{PipeWireError, Result};
+use crate::simd::{self, SimdLevel};
 use libspa::param::video::VideoFormat;
 
 /// Pixel format enum for our internal use
+///
+/// Names follow SPA: 8-bit RGB formats list their bytes in memory order,
+/// the 10-bit `_210LE` formats list the fields of a little-endian 32-bit
+/// word from the most significant bits down.
+#[allow(non_camel_case_types)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PixelFormat {
     /// BGRA 32-bit
@@ -17,14 +25,33 @@ pub enum PixelFormat {
     RGBA,
     /// RGBX 32-bit (no alpha)
     RGBx,
+    /// XRGB 32-bit (no alpha, padding byte first)
+    xRGB,
+    /// XBGR 32-bit (no alpha, padding byte first)
+    xBGR,
+    /// ARGB 32-bit
+    ARGB,
+    /// ABGR 32-bit
+    ABGR,
     /// RGB 24-bit
     RGB,
     /// BGR 24-bit
     BGR,
+    /// 10-bit RGB in a little-endian 32-bit word, 2 bits of padding
+    xRGB_210LE,
+    /// 10-bit BGR in a little-endian 32-bit word, 2 bits of padding
+    xBGR_210LE,
+    /// 10-bit RGB in a little-endian 32-bit word, 2-bit alpha
+    ARGB_210LE,
+    /// 10-bit BGR in a little-endian 32-bit word, 2-bit alpha
+    ABGR_210LE,
     /// Grayscale 8-bit
     GRAY8,
     /// YUV 4:2:0 semi-planar (NV12)
     NV12,
+    /// YUV 4:2:0 semi-planar, 10-bit samples in the high bits of 16-bit
+    /// little-endian words (P010)
+    P010,
     /// YUV 4:2:2 packed (YUY2)
     YUY2,
     /// YUV 4:2:0 planar (I420)
@@ -32,6 +59,29 @@ pub enum PixelFormat {
 }
 
 impl PixelFormat {
+    /// Every supported pixel format
+    pub const ALL: [Self; 19] = [
+        Self::BGRA,
+        Self::BGRx,
+        Self::RGBA,
+        Self::RGBx,
+        Self::xRGB,
+        Self::xBGR,
+        Self::ARGB,
+        Self::ABGR,
+        Self::RGB,
+        Self::BGR,
+        Self::xRGB_210LE,
+        Self::xBGR_210LE,
+        Self::ARGB_210LE,
+        Self::ABGR_210LE,
+        Self::GRAY8,
+        Self::NV12,
+        Self::P010,
+        Self::YUY2,
+        Self::I420,
+    ];
+
     /// Convert from SPA VideoFormat
     pub fn from_spa(format: VideoFormat) -> Option<Self> {
         match format {
@@ -39,10 +89,19 @@ impl PixelFormat {
             VideoFormat::BGRx => Some(Self::BGRx),
             VideoFormat::RGBA => Some(Self::RGBA),
             VideoFormat::RGBx => Some(Self::RGBx),
+            VideoFormat::xRGB => Some(Self::xRGB),
+            VideoFormat::xBGR => Some(Self::xBGR),
+            VideoFormat::ARGB => Some(Self::ARGB),
+            VideoFormat::ABGR => Some(Self::ABGR),
             VideoFormat::RGB => Some(Self::RGB),
             VideoFormat::BGR => Some(Self::BGR),
+            VideoFormat::xRGB_210LE => Some(Self::xRGB_210LE),
+            VideoFormat::xBGR_210LE => Some(Self::xBGR_210LE),
+            VideoFormat::ARGB_210LE => Some(Self::ARGB_210LE),
+            VideoFormat::ABGR_210LE => Some(Self::ABGR_210LE),
             VideoFormat::GRAY8 => Some(Self::GRAY8),
             VideoFormat::NV12 => Some(Self::NV12),
+            VideoFormat::P010_10LE => Some(Self::P010),
             VideoFormat::YUY2 => Some(Self::YUY2),
             VideoFormat::I420 => Some(Self::I420),
             _ => None,
@@ -56,28 +115,178 @@ impl PixelFormat {
             Self::BGRx => VideoFormat::BGRx,
             Self::RGBA => VideoFormat::RGBA,
             Self::RGBx => VideoFormat::RGBx,
+            Self::xRGB => VideoFormat::xRGB,
+            Self::xBGR => VideoFormat::xBGR,
+            Self::ARGB => VideoFormat::ARGB,
+            Self::ABGR => VideoFormat::ABGR,
             Self::RGB => VideoFormat::RGB,
             Self::BGR => VideoFormat::BGR,
+            Self::xRGB_210LE => VideoFormat::xRGB_210LE,
+            Self::xBGR_210LE => VideoFormat::xBGR_210LE,
+            Self::ARGB_210LE => VideoFormat::ARGB_210LE,
+            Self::ABGR_210LE => VideoFormat::ABGR_210LE,
             Self::GRAY8 => VideoFormat::GRAY8,
             Self::NV12 => VideoFormat::NV12,
+            Self::P010 => VideoFormat::P010_10LE,
             Self::YUY2 => VideoFormat::YUY2,
             Self::I420 => VideoFormat::I420,
         }
     }
 
+    /// Convert from a DRM fourcc (see [`crate::ffi::drm_fourcc`])
+    pub fn from_drm_fourcc(fourcc: u32) -> Option<Self> {
+        crate::ffi::drm_fourcc_to_spa_video_format(fourcc).and_then(Self::from_spa)
+    }
+
+    /// Convert to a DRM fourcc
+    ///
+    /// # Returns
+    ///
+    /// `None` for formats without a DRM equivalent
+    pub fn to_drm_fourcc(&self) -> Option<u32> {
+        match crate::ffi::spa_video_format_to_drm_fourcc(self.to_spa()) {
+            crate::ffi::drm_fourcc::DRM_FORMAT_INVALID => None,
+            fourcc => Some(fourcc),
+        }
+    }
+
     /// Get bytes per pixel (for packed formats)
     pub fn bytes_per_pixel(&self) -> usize {
         match self {
-            Self::BGRA | Self::BGRx | Self::RGBA | Self::RGBx => 4,
+            Self::BGRA
+            | Self::BGRx
+            | Self::RGBA
+            | Self::RGBx
+            | Self::xRGB
+            | Self::xBGR
+            | Self::ARGB
+            | Self::ABGR
+            | Self::xRGB_210LE
+            | Self::xBGR_210LE
+            | Self::ARGB_210LE
+            | Self::ABGR_210LE => 4,
             Self::RGB | Self::BGR => 3,
             Self::GRAY8 => 1,
             Self::NV12 | Self::I420 => 1, // Y plane
+            Self::P010 => 2,              // Y plane
             Self::YUY2 => 2,
         }
     }
+
+    /// Smallest row stride (of the first plane) that fits `width` pixels
+    ///
+    /// Chroma subsampling rounds odd widths up where samples cover two
+    /// pixels horizontally: YUY2 macropixels, and the interleaved UV plane
+    /// of NV12 and P010 that shares the luma stride.
+    ///
+    /// Returns `None` if the stride does not fit in a `u32`.
+    pub fn min_stride(&self, width: u32) -> Option<u32> {
+        let width = match self {
+            Self::NV12 | Self::YUY2 | Self::P010 => width.checked_add(width % 2)?,
+            _ => width,
+        };
+        width.checked_mul(self.bytes_per_pixel() as u32)
+    }
+
+    /// Bits per colour component
+    pub fn bit_depth(&self) -> u32 {
+        match self {
+            Self::xRGB_210LE | Self::xBGR_210LE | Self::ARGB_210LE | Self::ABGR_210LE | Self::P010 => 10,
+            _ => 8,
+        }
+    }
+
+    /// Whether pixels carry an alpha channel
+    pub fn has_alpha(&self) -> bool {
+        matches!(
+            self,
+            Self::BGRA | Self::RGBA | Self::ARGB | Self::ABGR | Self::ARGB_210LE | Self::ABGR_210LE
+        )
+    }
+
+    /// Whether the format is YUV (as opposed to RGB or grayscale)
+    pub fn is_yuv(&self) -> bool {
+        matches!(self, Self::NV12 | Self::P010 | Self::YUY2 | Self::I420)
+    }
+
+    /// Whether the format stores chroma in planes separate from luma
+    pub fn is_planar(&self) -> bool {
+        matches!(self, Self::NV12 | Self::P010 | Self::I420)
+    }
+
+    /// Number of planes (1 for packed formats)
+    pub fn plane_count(&self) -> usize {
+        match self {
+            Self::NV12 | Self::P010 => 2,
+            Self::I420 => 3,
+            _ => 1,
+        }
+    }
+
+    /// Default plane layout of a frame held in a single buffer
+    ///
+    /// Planes follow each other without gaps. `stride` is the row stride of
+    /// the first plane; chroma planes of I420 use half of it, the interleaved
+    /// UV plane of NV12 and P010 uses all of it. Sizes saturate rather than
+    /// overflow, so an oversized layout fails the size checks against the
+    /// frame data instead of wrapping around.
+    pub fn plane_layout(&self, height: u32, stride: u32) -> Vec<FramePlane> {
+        let luma_rows = height as usize;
+        let chroma_rows = luma_rows / 2 + luma_rows % 2;
+        let luma = FramePlane::new(0, stride, (stride as usize).saturating_mul(luma_rows));
+
+        match self {
+            Self::NV12 | Self::P010 => {
+                let uv = FramePlane::new(luma.size, stride, (stride as usize).saturating_mul(chroma_rows));
+                vec![luma, uv]
+            }
+            Self::I420 => {
+                let chroma_stride = stride / 2 + stride % 2;
+                let chroma_size = (chroma_stride as usize).saturating_mul(chroma_rows);
+                let u = FramePlane::new(luma.size, chroma_stride, chroma_size);
+                let v = FramePlane::new(luma.size.saturating_add(chroma_size), chroma_stride, chroma_size);
+                vec![luma, u, v]
+            }
+            _ => vec![luma],
+        }
+    }
+}
+
+/// Location of one plane within frame data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramePlane {
+    /// Byte offset of the plane's first row
+    pub offset: usize,
+
+    /// Row stride in bytes
+    pub stride: u32,
+
+    /// Plane size in bytes
+    pub size: usize,
+}
+
+impl FramePlane {
+    /// Create a plane descriptor
+    pub fn new(offset: usize, stride: u32, size: usize) -> Self {
+        Self { offset, stride, size }
+    }
+
+    /// Slice this plane out of frame data
+    ///
+    /// Returns `None` if the plane lies outside `data`.
+    pub fn slice<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        data.get(self.offset..self.offset.checked_add(self.size)?)
+    }
 }
 
 /// Convert pixel data from one format to another
+///
+/// Every pair of formats is supported. Planar sources and destinations
+/// (NV12, P010, I420) are expected in the default single-buffer layout for
+/// their stride (see [`PixelFormat::plane_layout`]); use
+/// [`convert_format_planes`] for sources with any other layout.
+/// `colorimetry` selects the YUV matrix and range (ignored between RGB
+/// formats).
 pub fn convert_format(
     src: &[u8],
     dst: &mut [u8],
@@ -87,180 +296,623 @@ pub fn convert_format(
     height: u32,
     src_stride: u32,
     dst_stride: u32,
+    colorimetry: Colorimetry,
 ) -> Result<()> {
-    // Fast path: no conversion needed
-    if src_format == dst_format && src_stride == dst_stride {
-        let row_bytes = (width * src_format.bytes_per_pixel() as u32) as usize;
-        if row_bytes == src_stride as usize {
-            // Can do a single memcpy
-            dst[..src.len()].copy_from_slice(src);
-        } else {
-            // Copy row by row
-            for y in 0..height {
-                let src_offset = (y * src_stride) as usize;
-                let dst_offset = (y * dst_stride) as usize;
-                dst[dst_offset..dst_offset + row_bytes].copy_from_slice(&src[src_offset..src_offset + row_bytes]);
-            }
+    let mut planes = src_format.plane_layout(height, src_stride);
+    // The final row need not be padded out to the full stride
+    if let Some(last) = planes.last_mut() {
+        last.size = last.size.min(src.len().saturating_sub(last.offset));
+    }
+    convert_format_planes(
+        src,
+        &planes,
+        dst,
+        src_format,
+        dst_format,
+        width,
+        height,
+        dst_stride,
+        colorimetry,
+    )
+}
+
+/// Convert pixel data with an explicit plane layout
+///
+/// `planes` locates each plane within `src` (see [`FramePlane`]), so planes
+/// delivered in separate buffers or padded independently are read correctly.
+/// The destination uses the default layout for `dst_stride`.
+///
+/// Conversions between RGB formats are exact apart from alpha or 10-bit
+/// precision the destination cannot hold. YUV uses the matrix and range
+/// of `colorimetry`, with chroma averaged over the pixels each sample
+/// covers.
+pub fn convert_format_planes(
+    src: &[u8],
+    planes: &[FramePlane],
+    dst: &mut [u8],
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+    width: u32,
+    height: u32,
+    dst_stride: u32,
+    colorimetry: Colorimetry,
+) -> Result<()> {
+    check_source_planes(src, planes, src_format, width, height)?;
+
+    let dst_planes = dst_format.plane_layout(height, dst_stride);
+    if src_format == dst_format {
+        return copy_planes(src, planes, dst, &dst_planes, src_format, width, height);
+    }
+
+    let is_bgra = |format| matches!(format, PixelFormat::BGRA | PixelFormat::BGRx);
+    let coefficients = colorimetry.coefficients();
+    match (src_format.is_yuv(), dst_format.is_yuv()) {
+        // RGB and grayscale convert pixel by pixel
+        (false, false) => convert_packed(
+            plane_slice(src, &planes[0])?,
+            planes[0].stride,
+            src_format,
+            dst,
+            dst_stride,
+            dst_format,
+            width,
+            height,
+        ),
+
+        // YUV decodes straight to BGRA/BGRx
+        (true, false) if is_bgra(dst_format) => {
+            decode_yuv(src, planes, src_format, dst, width, height, dst_stride, &coefficients)
         }
-        return Ok(());
+
+        // BGRA/BGRx encodes straight to YUV
+        (false, true) if is_bgra(src_format) => encode_yuv(
+            plane_slice(src, &planes[0])?,
+            planes[0].stride,
+            dst,
+            &dst_planes,
+            dst_format,
+            width,
+            height,
+            &coefficients,
+        ),
+
+        // Everything else goes through an intermediate BGRA frame
+        _ => {
+            let (stride, size) = bgra_frame_size(width, height)?;
+            let mut bgra = vec![0u8; size];
+            convert_format_planes(
+                src,
+                planes,
+                &mut bgra,
+                src_format,
+                PixelFormat::BGRA,
+                width,
+                height,
+                stride,
+                colorimetry,
+            )?;
+            convert_format(
+                &bgra,
+                dst,
+                PixelFormat::BGRA,
+                dst_format,
+                width,
+                height,
+                stride,
+                dst_stride,
+                colorimetry,
+            )
+        }
+    }
+}
+
+/// Check that `src` holds every plane of a `width` x `height` frame
+///
+/// Run before allocating anything sized from the frame dimensions, so a
+/// small buffer claiming a huge frame is rejected up front.
+pub(crate) fn check_source_planes(
+    src: &[u8],
+    planes: &[FramePlane],
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+) -> Result<()> {
+    if planes.len() < format.plane_count() {
+        return Err(PipeWireError::FormatConversionFailed(format!(
+            "{:?} needs {} planes, got {}",
+            format,
+            format.plane_count(),
+            planes.len()
+        )));
+    }
+
+    for (index, plane) in planes.iter().take(format.plane_count()).enumerate() {
+        let data = plane_slice(src, plane)?;
+        let (row_bytes, rows) = plane_extent(format, index, width, height);
+        check_rows("Source", data.len(), plane.stride, rows, row_bytes)?;
     }
+    Ok(())
+}
 
-    // Conversion needed
-    match (src_format, dst_format) {
-        // RGB to BGRA
-        (PixelFormat::RGB, PixelFormat::BGRA) => convert_rgb_to_bgra(src, dst, width, height, src_stride, dst_stride),
+/// Stride and size of a tightly packed BGRA frame
+pub(crate) fn bgra_frame_size(width: u32, height: u32) -> Result<(u32, usize)> {
+    width
+        .checked_mul(4)
+        .and_then(|stride| Some((stride, (stride as usize).checked_mul(height as usize)?)))
+        .ok_or_else(|| PipeWireError::FormatConversionFailed(format!("{}x{} BGRA frame is too large", width, height)))
+}
 
-        // RGBA to BGRA
-        (PixelFormat::RGBA, PixelFormat::BGRA) => convert_rgba_to_bgra(src, dst, width, height, src_stride, dst_stride),
+/// Slice a plane out of frame data
+fn plane_slice<'a>(src: &'a [u8], plane: &FramePlane) -> Result<&'a [u8]> {
+    plane.slice(src).ok_or_else(|| {
+        PipeWireError::FormatConversionFailed(format!(
+            "Plane at offset {} ({} bytes) exceeds frame data ({} bytes)",
+            plane.offset,
+            plane.size,
+            src.len()
+        ))
+    })
+}
 
-        // BGR to BGRA
-        (PixelFormat::BGR, PixelFormat::BGRA) => convert_bgr_to_bgra(src, dst, width, height, src_stride, dst_stride),
+/// Split frame data into mutable planes
+///
+/// `planes` must be in ascending order without overlap, as produced by
+/// [`PixelFormat::plane_layout`]. The last slice ends early if the data
+/// does, since the final row need not be padded out to the full stride.
+fn split_planes_mut<'a>(data: &'a mut [u8], planes: &[FramePlane]) -> Result<Vec<&'a mut [u8]>> {
+    let total = data.len();
+    let mut rest = data;
+    let mut rest_offset = 0;
+    let mut slices = Vec::with_capacity(planes.len());
 
-        // NV12 to BGRA
-        (PixelFormat::NV12, PixelFormat::BGRA) => convert_nv12_to_bgra(src, dst, width, height),
+    for plane in planes {
+        let start = plane
+            .offset
+            .checked_sub(rest_offset)
+            .filter(|start| *start <= rest.len())
+            .ok_or_else(|| {
+                PipeWireError::FormatConversionFailed(format!(
+                    "Plane at offset {} ({} bytes) exceeds destination ({} bytes)",
+                    plane.offset, plane.size, total
+                ))
+            })?;
+        let end = start.saturating_add(plane.size).min(rest.len());
+        let (head, tail) = std::mem::take(&mut rest).split_at_mut(end);
+        slices.push(&mut head[start..]);
+        rest = tail;
+        rest_offset += end;
+    }
+    Ok(slices)
+}
 
-        // YUY2 to BGRA
-        (PixelFormat::YUY2, PixelFormat::BGRA) => convert_yuy2_to_bgra(src, dst, width, height, src_stride, dst_stride),
+/// Bytes per row and number of rows of one plane
+fn plane_extent(format: PixelFormat, index: usize, width: u32, height: u32) -> (usize, usize) {
+    let w = width as usize;
+    let h = height as usize;
+    let chroma_w = (w + 1) / 2;
+    let chroma_h = (h + 1) / 2;
 
-        // I420 to BGRA
-        (PixelFormat::I420, PixelFormat::BGRA) => convert_i420_to_bgra(src, dst, width, height),
+    match (format, index) {
+        (PixelFormat::NV12, 1) => (chroma_w * 2, chroma_h),
+        (PixelFormat::P010, 1) => (chroma_w * 4, chroma_h),
+        (PixelFormat::I420, 1 | 2) => (chroma_w, chroma_h),
+        // Each YUY2 macropixel covers two pixels
+        (PixelFormat::YUY2, _) => (chroma_w * 4, h),
+        _ => (w * format.bytes_per_pixel(), h),
+    }
+}
 
-        _ => Err(PipeWireError::FormatConversionFailed(format!(
-            "Unsupported conversion: {:?} -> {:?}",
-            src_format, dst_format
+/// Check that a buffer holds `rows` rows of `row_bytes` bytes at `stride`
+fn check_rows(what: &str, len: usize, stride: u32, rows: usize, row_bytes: usize) -> Result<()> {
+    let stride = stride as usize;
+    if rows == 0 {
+        return Ok(());
+    }
+    if stride < row_bytes {
+        return Err(PipeWireError::FormatConversionFailed(format!(
+            "{} stride {} is smaller than a row ({} bytes)",
+            what, stride, row_bytes
+        )));
+    }
+
+    match stride.checked_mul(rows - 1).and_then(|n| n.checked_add(row_bytes)) {
+        Some(needed) if len >= needed => Ok(()),
+        Some(needed) => Err(PipeWireError::FormatConversionFailed(format!(
+            "{} too small: need {} bytes, got {}",
+            what, needed, len
+        ))),
+        None => Err(PipeWireError::FormatConversionFailed(format!(
+            "{} too small: {} rows of {} bytes overflow",
+            what, rows, stride
         ))),
     }
 }
 
-/// Convert RGB to BGRA
-fn convert_rgb_to_bgra(
+/// Copy frame data between two layouts of the same format
+fn copy_planes(
     src: &[u8],
+    src_planes: &[FramePlane],
     dst: &mut [u8],
+    dst_planes: &[FramePlane],
+    format: PixelFormat,
     width: u32,
     height: u32,
-    src_stride: u32,
-    dst_stride: u32,
 ) -> Result<()> {
-    for y in 0..height {
-        let src_row = &src[(y * src_stride) as usize..];
-        let dst_row = &mut dst[(y * dst_stride) as usize..];
+    let dst_slices = split_planes_mut(dst, dst_planes)?;
 
-        for x in 0..width as usize {
-            let src_idx = x * 3;
-            let dst_idx = x * 4;
+    for (index, (dst_data, dst_plane)) in dst_slices.into_iter().zip(dst_planes).enumerate() {
+        let src_plane = &src_planes[index];
+        let src_data = plane_slice(src, src_plane)?;
+        let (row_bytes, rows) = plane_extent(format, index, width, height);
+        let src_stride = src_plane.stride as usize;
+        let dst_stride = dst_plane.stride as usize;
 
-            dst_row[dst_idx] = src_row[src_idx + 2]; // B
-            dst_row[dst_idx + 1] = src_row[src_idx + 1]; // G
-            dst_row[dst_idx + 2] = src_row[src_idx]; // R
-            dst_row[dst_idx + 3] = 255; // A
+        check_rows("Source", src_data.len(), src_plane.stride, rows, row_bytes)?;
+        check_rows("Destination", dst_data.len(), dst_plane.stride, rows, row_bytes)?;
+
+        if src_stride == row_bytes && dst_stride == row_bytes {
+            // Can do a single memcpy
+            let len = row_bytes * rows;
+            dst_data[..len].copy_from_slice(&src_data[..len]);
+        } else {
+            // Copy row by row
+            for y in 0..rows {
+                dst_data[y * dst_stride..][..row_bytes].copy_from_slice(&src_data[y * src_stride..][..row_bytes]);
+            }
         }
     }
     Ok(())
 }
 
-/// Convert RGBA to BGRA
-fn convert_rgba_to_bgra(
+/// Where the channels of an RGB or grayscale pixel are stored
+#[derive(Debug, Clone, Copy)]
+enum RgbLayout {
+    /// One byte per channel, at these byte offsets within the pixel; `a` is
+    /// the alpha byte, or padding when `alpha` is false
+    Bytes {
+        r: usize,
+        g: usize,
+        b: usize,
+        a: Option<usize>,
+        alpha: bool,
+    },
+
+    /// 10-bit channels of a little-endian 32-bit word, at these bit shifts;
+    /// the top 2 bits are alpha, or padding when `alpha` is false
+    Words210 { r: u32, g: u32, b: u32, alpha: bool },
+
+    /// A single luma byte
+    Gray,
+}
+
+impl RgbLayout {
+    /// Layout of an RGB or grayscale format, `None` for YUV
+    fn of(format: PixelFormat) -> Option<Self> {
+        let alpha = format.has_alpha();
+        let bytes = |r, g, b, a| Some(Self::Bytes { r, g, b, a, alpha });
+        let words = |r, b| Some(Self::Words210 { r, g: 10, b, alpha });
+
+        match format {
+            PixelFormat::BGRA | PixelFormat::BGRx => bytes(2, 1, 0, Some(3)),
+            PixelFormat::RGBA | PixelFormat::RGBx => bytes(0, 1, 2, Some(3)),
+            PixelFormat::ARGB | PixelFormat::xRGB => bytes(1, 2, 3, Some(0)),
+            PixelFormat::ABGR | PixelFormat::xBGR => bytes(3, 2, 1, Some(0)),
+            PixelFormat::RGB => bytes(0, 1, 2, None),
+            PixelFormat::BGR => bytes(2, 1, 0, None),
+            PixelFormat::ARGB_210LE | PixelFormat::xRGB_210LE => words(20, 0),
+            PixelFormat::ABGR_210LE | PixelFormat::xBGR_210LE => words(0, 20),
+            PixelFormat::GRAY8 => Some(Self::Gray),
+            PixelFormat::NV12 | PixelFormat::P010 | PixelFormat::YUY2 | PixelFormat::I420 => None,
+        }
+    }
+
+    /// Read one pixel as BGRA
+    ///
+    /// 10-bit channels keep their 8 most significant bits; pixels without
+    /// alpha are opaque.
+    #[inline]
+    fn decode(self, px: &[u8]) -> [u8; 4] {
+        match self {
+            Self::Bytes { r, g, b, a, alpha } => {
+                let a = match a {
+                    Some(a) if alpha => px[a],
+                    _ => 255,
+                };
+                [px[b], px[g], px[r], a]
+            }
+            Self::Words210 { r, g, b, alpha } => {
+                let word = u32::from_le_bytes([px[0], px[1], px[2], px[3]]);
+                let channel = |shift: u32| ((word >> shift & 0x3ff) >> 2) as u8;
+                // Scale the 2-bit alpha to 0, 85, 170, 255
+                let a = if alpha { ((word >> 30) * 0x55) as u8 } else { 255 };
+                [channel(b), channel(g), channel(r), a]
+            }
+            Self::Gray => [px[0], px[0], px[0], 255],
+        }
+    }
+
+    /// Write one BGRA pixel
+    ///
+    /// 8-bit channels widen to 10 bits by replicating their top bits, so
+    /// they read back unchanged. Padding is written as all ones.
+    #[inline]
+    fn encode(self, [b_val, g_val, r_val, a_val]: [u8; 4], px: &mut [u8]) {
+        match self {
+            Self::Bytes { r, g, b, a, alpha } => {
+                px[r] = r_val;
+                px[g] = g_val;
+                px[b] = b_val;
+                if let Some(a) = a {
+                    px[a] = if alpha { a_val } else { 255 };
+                }
+            }
+            Self::Words210 { r, g, b, alpha } => {
+                let widen = |v: u8| u32::from(v) << 2 | u32::from(v) >> 6;
+                let top = if alpha { u32::from(a_val) >> 6 } else { 3 };
+                let word = top << 30 | widen(r_val) << r | widen(g_val) << g | widen(b_val) << b;
+                px.copy_from_slice(&word.to_le_bytes());
+            }
+            Self::Gray => px[0] = rgb_to_gray(r_val, g_val, b_val),
+        }
+    }
+
+    /// Byte map taking pixels of this layout to `dst` with [`simd::shuffle_row`]
+    ///
+    /// Only 4-byte layouts have one.
+    fn shuffle_map(self, dst: Self) -> Option<[u8; 4]> {
+        let (
+            Self::Bytes {
+                r,
+                g,
+                b,
+                a: Some(a),
+                alpha,
+            },
+            Self::Bytes {
+                r: dst_r,
+                g: dst_g,
+                b: dst_b,
+                a: Some(dst_a),
+                alpha: dst_alpha,
+            },
+        ) = (self, dst)
+        else {
+            return None;
+        };
+
+        let mut map = [0u8; 4];
+        map[dst_r] = r as u8;
+        map[dst_g] = g as u8;
+        map[dst_b] = b as u8;
+        map[dst_a] = if alpha && dst_alpha { a as u8 } else { simd::OPAQUE };
+        Some(map)
+    }
+}
+
+/// Convert between RGB and grayscale formats in any byte order
+///
+/// Alpha is kept when both formats carry it; otherwise pixels are opaque.
+fn convert_packed(
     src: &[u8],
+    src_stride: u32,
+    src_format: PixelFormat,
     dst: &mut [u8],
+    dst_stride: u32,
+    dst_format: PixelFormat,
     width: u32,
     height: u32,
-    src_stride: u32,
-    dst_stride: u32,
 ) -> Result<()> {
-    for y in 0..height {
-        let src_row = &src[(y * src_stride) as usize..];
-        let dst_row = &mut dst[(y * dst_stride) as usize..];
+    let unsupported = || PipeWireError::UnsupportedConversion(src_format, dst_format);
+    let src_layout = RgbLayout::of(src_format).ok_or_else(unsupported)?;
+    let dst_layout = RgbLayout::of(dst_format).ok_or_else(unsupported)?;
+    let w = width as usize;
+    let h = height as usize;
+    let src_bpp = src_format.bytes_per_pixel();
+    let dst_bpp = dst_format.bytes_per_pixel();
 
-        for x in 0..width as usize {
-            let src_idx = x * 4;
-            let dst_idx = x * 4;
+    check_rows("Source", src.len(), src_stride, h, w * src_bpp)?;
+    check_rows("Destination", dst.len(), dst_stride, h, w * dst_bpp)?;
 
-            dst_row[dst_idx] = src_row[src_idx + 2]; // B
-            dst_row[dst_idx + 1] = src_row[src_idx + 1]; // G
-            dst_row[dst_idx + 2] = src_row[src_idx]; // R
-            dst_row[dst_idx + 3] = src_row[src_idx + 3]; // A
+    let level = SimdLevel::detect();
+    let shuffle = src_layout.shuffle_map(dst_layout);
+
+    for y in 0..h {
+        let src_row = &src[y * src_stride as usize..][..w * src_bpp];
+        let dst_row = &mut dst[y * dst_stride as usize..][..w * dst_bpp];
+
+        let done = shuffle.map_or(0, |map| simd::shuffle_row(level, src_row, dst_row, map));
+        for (src_px, dst_px) in src_row
+            .chunks_exact(src_bpp)
+            .zip(dst_row.chunks_exact_mut(dst_bpp))
+            .skip(done)
+        {
+            dst_layout.encode(src_layout.decode(src_px), dst_px);
         }
     }
     Ok(())
 }
 
-/// Convert BGR to BGRA
-fn convert_bgr_to_bgra(
+/// Convert a YUV frame to BGRA (or BGRx)
+fn decode_yuv(
     src: &[u8],
+    planes: &[FramePlane],
+    src_format: PixelFormat,
     dst: &mut [u8],
     width: u32,
     height: u32,
-    src_stride: u32,
     dst_stride: u32,
+    coefficients: &YuvCoefficients,
 ) -> Result<()> {
-    for y in 0..height {
-        let src_row = &src[(y * src_stride) as usize..];
-        let dst_row = &mut dst[(y * dst_stride) as usize..];
+    match src_format {
+        PixelFormat::NV12 => convert_nv12_to_bgra(
+            plane_slice(src, &planes[0])?,
+            planes[0].stride,
+            plane_slice(src, &planes[1])?,
+            planes[1].stride,
+            dst,
+            width,
+            height,
+            dst_stride,
+            coefficients,
+        ),
+        PixelFormat::P010 => convert_p010_to_bgra(
+            plane_slice(src, &planes[0])?,
+            planes[0].stride,
+            plane_slice(src, &planes[1])?,
+            planes[1].stride,
+            dst,
+            width,
+            height,
+            dst_stride,
+            coefficients,
+        ),
+        PixelFormat::I420 => convert_i420_to_bgra(
+            [
+                plane_slice(src, &planes[0])?,
+                plane_slice(src, &planes[1])?,
+                plane_slice(src, &planes[2])?,
+            ],
+            [planes[0].stride, planes[1].stride, planes[2].stride],
+            dst,
+            width,
+            height,
+            dst_stride,
+            coefficients,
+        ),
+        PixelFormat::YUY2 => convert_yuy2_to_bgra(
+            plane_slice(src, &planes[0])?,
+            dst,
+            width,
+            height,
+            planes[0].stride,
+            dst_stride,
+            coefficients,
+        ),
+        _ => Err(PipeWireError::UnsupportedConversion(src_format, PixelFormat::BGRA)),
+    }
+}
 
-        for x in 0..width as usize {
-            let src_idx = x * 3;
-            let dst_idx = x * 4;
+/// Convert NV12 to BGRA
+pub(crate) fn convert_nv12_to_bgra(
+    y_plane: &[u8],
+    y_stride: u32,
+    uv_plane: &[u8],
+    uv_stride: u32,
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    dst_stride: u32,
+    coefficients: &YuvCoefficients,
+) -> Result<()> {
+    let w = width as usize;
+    let h = height as usize;
+
+    check_rows("NV12 Y plane", y_plane.len(), y_stride, h, w)?;
+    check_rows("NV12 UV plane", uv_plane.len(), uv_stride, (h + 1) / 2, (w + 1) / 2 * 2)?;
+    check_rows("Destination", dst.len(), dst_stride, h, w * 4)?;
+
+    let level = SimdLevel::detect();
+    for y in 0..h {
+        let y_row = &y_plane[y * y_stride as usize..];
+        // UV values are subsampled 2x2
+        let uv_row = &uv_plane[(y / 2) * uv_stride as usize..];
+        let dst_row = &mut dst[y * dst_stride as usize..];
+
+        let done = simd::nv12_to_bgra_row(
+            level,
+            coefficients,
+            &y_row[..w],
+            &uv_row[..(w + 1) / 2 * 2],
+            &mut dst_row[..w * 4],
+        );
+        for (x, &luma) in y_row[..w].iter().enumerate().skip(done) {
+            let y_val = luma as i32;
+            let uv_idx = (x / 2) * 2;
+            let u_val = uv_row[uv_idx] as i32;
+            let v_val = uv_row[uv_idx + 1] as i32;
+
+            let (r, g, b) = coefficients.yuv_to_rgb(y_val, u_val, v_val);
 
-            dst_row[dst_idx] = src_row[src_idx]; // B
-            dst_row[dst_idx + 1] = src_row[src_idx + 1]; // G
-            dst_row[dst_idx + 2] = src_row[src_idx + 2]; // R
-            dst_row[dst_idx + 3] = 255; // A
+            let dst_idx = x * 4;
+            dst_row[dst_idx] = b;
+            dst_row[dst_idx + 1] = g;
+            dst_row[dst_idx + 2] = r;
+            dst_row[dst_idx + 3] = 255;
         }
     }
     Ok(())
 }
 
-/// Convert NV12 to BGRA
-fn convert_nv12_to_bgra(src: &[u8], dst: &mut [u8], width: u32, height: u32) -> Result<()> {
-    let y_plane = &src[0..(width * height) as usize];
-    let uv_plane = &src[(width * height) as usize..];
+/// Convert P010 to BGRA
+///
+/// Samples are 16-bit little-endian words with the value in the high bits;
+/// their 8 most significant bits are converted.
+fn convert_p010_to_bgra(
+    y_plane: &[u8],
+    y_stride: u32,
+    uv_plane: &[u8],
+    uv_stride: u32,
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    dst_stride: u32,
+    coefficients: &YuvCoefficients,
+) -> Result<()> {
+    let w = width as usize;
+    let h = height as usize;
 
-    for y in 0..height {
-        for x in 0..width {
-            // Get Y value
-            let y_val = y_plane[(y * width + x) as usize] as i32;
-
-            // Get UV values (subsampled 2x2)
-            let uv_x = x / 2;
-            let uv_y = y / 2;
-            let uv_idx = (uv_y * width + uv_x * 2) as usize;
-            let u_val = uv_plane[uv_idx] as i32;
-            let v_val = uv_plane[uv_idx + 1] as i32;
-
-            // YUV to RGB conversion
-            let c = y_val - 16;
-            let d = u_val - 128;
-            let e = v_val - 128;
-
-            let r = (298 * c + 409 * e + 128) >> 8;
-            let g = (298 * c - 100 * d - 208 * e + 128) >> 8;
-            let b = (298 * c + 516 * d + 128) >> 8;
-
-            // Clamp and write BGRA
-            let dst_idx = ((y * width + x) * 4) as usize;
-            dst[dst_idx] = clamp(b, 0, 255) as u8;
-            dst[dst_idx + 1] = clamp(g, 0, 255) as u8;
-            dst[dst_idx + 2] = clamp(r, 0, 255) as u8;
-            dst[dst_idx + 3] = 255;
+    check_rows("P010 Y plane", y_plane.len(), y_stride, h, w * 2)?;
+    check_rows("P010 UV plane", uv_plane.len(), uv_stride, (h + 1) / 2, (w + 1) / 2 * 4)?;
+    check_rows("Destination", dst.len(), dst_stride, h, w * 4)?;
+
+    for y in 0..h {
+        let y_row = &y_plane[y * y_stride as usize..][..w * 2];
+        // UV values are subsampled 2x2
+        let uv_row = &uv_plane[(y / 2) * uv_stride as usize..];
+        let dst_row = &mut dst[y * dst_stride as usize..][..w * 4];
+
+        for (x, (luma, dst_px)) in y_row.chunks_exact(2).zip(dst_row.chunks_exact_mut(4)).enumerate() {
+            let uv_idx = (x / 2) * 4;
+            // The high byte of each sample holds its 8 most significant bits
+            let (r, g, b) =
+                coefficients.yuv_to_rgb(luma[1] as i32, uv_row[uv_idx + 1] as i32, uv_row[uv_idx + 3] as i32);
+
+            dst_px[0] = b;
+            dst_px[1] = g;
+            dst_px[2] = r;
+            dst_px[3] = 255;
         }
     }
     Ok(())
 }
 
 /// Convert YUY2 to BGRA
-fn convert_yuy2_to_bgra(
+pub(crate) fn convert_yuy2_to_bgra(
     src: &[u8],
     dst: &mut [u8],
     width: u32,
     height: u32,
     src_stride: u32,
     dst_stride: u32,
+    coefficients: &YuvCoefficients,
 ) -> Result<()> {
+    let (row_bytes, rows) = plane_extent(PixelFormat::YUY2, 0, width, height);
+    check_rows("YUY2 source", src.len(), src_stride, rows, row_bytes)?;
+    check_rows("Destination", dst.len(), dst_stride, rows, width as usize * 4)?;
+
+    let level = SimdLevel::detect();
     for y in 0..height {
         let src_row = &src[(y * src_stride) as usize..];
         let dst_row = &mut dst[(y * dst_stride) as usize..];
 
-        for x in (0..width as usize).step_by(2) {
+        // Kernels convert whole macropixels, so `done` is even
+        let done = simd::yuy2_to_bgra_row(
+            level,
+            coefficients,
+            &src_row[..row_bytes],
+            &mut dst_row[..width as usize * 4],
+        );
+        for x in (done..width as usize).step_by(2) {
             let src_idx = x * 2;
 
             let y0 = src_row[src_idx] as i32;
@@ -269,7 +921,7 @@ fn convert_yuy2_to_bgra(
             let v = src_row[src_idx + 3] as i32;
 
             // Convert first pixel
-            let (r0, g0, b0) = yuv_to_rgb(y0, u, v);
+            let (r0, g0, b0) = coefficients.yuv_to_rgb(y0, u, v);
             let dst_idx0 = x * 4;
             dst_row[dst_idx0] = b0;
             dst_row[dst_idx0 + 1] = g0;
@@ -278,7 +930,7 @@ fn convert_yuy2_to_bgra(
 
             // Convert second pixel
             if x + 1 < width as usize {
-                let (r1, g1, b1) = yuv_to_rgb(y1, u, v);
+                let (r1, g1, b1) = coefficients.yuv_to_rgb(y1, u, v);
                 let dst_idx1 = (x + 1) * 4;
                 dst_row[dst_idx1] = b1;
                 dst_row[dst_idx1 + 1] = g1;
@@ -291,74 +943,834 @@ fn convert_yuy2_to_bgra(
 }
 
 /// Convert I420 to BGRA
-fn convert_i420_to_bgra(src: &[u8], dst: &mut [u8], width: u32, height: u32) -> Result<()> {
-    let y_plane_size = (width * height) as usize;
-    let uv_plane_size = y_plane_size / 4;
+///
+/// `planes` and `strides` are the Y, U and V planes in that order.
+pub(crate) fn convert_i420_to_bgra(
+    planes: [&[u8]; 3],
+    strides: [u32; 3],
+    dst: &mut [u8],
+    width: u32,
+    height: u32,
+    dst_stride: u32,
+    coefficients: &YuvCoefficients,
+) -> Result<()> {
+    let w = width as usize;
+    let h = height as usize;
+    let [y_plane, u_plane, v_plane] = planes;
+    let [y_stride, u_stride, v_stride] = strides.map(|stride| stride as usize);
 
-    let y_plane = &src[0..y_plane_size];
-    let u_plane = &src[y_plane_size..y_plane_size + uv_plane_size];
-    let v_plane = &src[y_plane_size + uv_plane_size..];
+    check_rows("I420 Y plane", y_plane.len(), strides[0], h, w)?;
+    check_rows("I420 U plane", u_plane.len(), strides[1], (h + 1) / 2, (w + 1) / 2)?;
+    check_rows("I420 V plane", v_plane.len(), strides[2], (h + 1) / 2, (w + 1) / 2)?;
+    check_rows("Destination", dst.len(), dst_stride, h, w * 4)?;
 
-    for y in 0..height {
-        for x in 0..width {
-            // Get Y value
-            let y_val = y_plane[(y * width + x) as usize] as i32;
-
-            // Get UV values (subsampled 2x2)
-            let uv_x = x / 2;
-            let uv_y = y / 2;
-            let uv_idx = (uv_y * width / 2 + uv_x) as usize;
-            let u_val = u_plane[uv_idx] as i32;
-            let v_val = v_plane[uv_idx] as i32;
-
-            // YUV to RGB conversion
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
-
-            // Write BGRA
-            let dst_idx = ((y * width + x) * 4) as usize;
-            dst[dst_idx] = b;
-            dst[dst_idx + 1] = g;
-            dst[dst_idx + 2] = r;
-            dst[dst_idx + 3] = 255;
+    let level = SimdLevel::detect();
+    let chroma_width = (w + 1) / 2;
+    for y in 0..h {
+        let y_row = &y_plane[y * y_stride..];
+        // UV values are subsampled 2x2
+        let u_row = &u_plane[(y / 2) * u_stride..];
+        let v_row = &v_plane[(y / 2) * v_stride..];
+        let dst_row = &mut dst[y * dst_stride as usize..];
+
+        let done = simd::i420_to_bgra_row(
+            level,
+            coefficients,
+            &y_row[..w],
+            &u_row[..chroma_width],
+            &v_row[..chroma_width],
+            &mut dst_row[..w * 4],
+        );
+        for x in done..w {
+            let y_val = y_row[x] as i32;
+            let u_val = u_row[x / 2] as i32;
+            let v_val = v_row[x / 2] as i32;
+
+            let (r, g, b) = coefficients.yuv_to_rgb(y_val, u_val, v_val);
+
+            let dst_idx = x * 4;
+            dst_row[dst_idx] = b;
+            dst_row[dst_idx + 1] = g;
+            dst_row[dst_idx + 2] = r;
+            dst_row[dst_idx + 3] = 255;
         }
     }
     Ok(())
 }
 
-/// YUV to RGB conversion helper
-#[inline]
-fn yuv_to_rgb(y: i32, u: i32, v: i32) -> (u8, u8, u8) {
-    let c = y - 16;
-    let d = u - 128;
-    let e = v - 128;
+/// Convert BGRA (or BGRx) to a YUV format
+///
+/// Chroma is averaged over the pixels each sample covers; at odd widths and
+/// heights the last samples cover only the pixels that exist.
+fn encode_yuv(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_planes: &[FramePlane],
+    dst_format: PixelFormat,
+    width: u32,
+    height: u32,
+    coefficients: &YuvCoefficients,
+) -> Result<()> {
+    let w = width as usize;
+    let h = height as usize;
+    check_rows("Source", src.len(), src_stride, h, w * 4)?;
 
-    let r = (298 * c + 409 * e + 128) >> 8;
-    let g = (298 * c - 100 * d - 208 * e + 128) >> 8;
-    let b = (298 * c + 516 * d + 128) >> 8;
+    let mut planes = split_planes_mut(dst, dst_planes)?;
+    for (index, (plane, layout)) in planes.iter().zip(dst_planes).enumerate() {
+        let (row_bytes, rows) = plane_extent(dst_format, index, width, height);
+        check_rows("Destination", plane.len(), layout.stride, rows, row_bytes)?;
+    }
+    let strides: Vec<usize> = dst_planes.iter().map(|plane| plane.stride as usize).collect();
+
+    let pixel = |x: usize, y: usize| {
+        let px = &src[y * src_stride as usize + x * 4..];
+        coefficients.rgb_to_yuv(px[2], px[1], px[0])
+    };
 
-    (clamp(r, 0, 255) as u8, clamp(g, 0, 255) as u8, clamp(b, 0, 255) as u8)
+    match (dst_format, planes.as_mut_slice()) {
+        (PixelFormat::YUY2, [packed, ..]) => {
+            for y in 0..h {
+                let row = &mut packed[y * strides[0]..];
+                for (pair, macropixel) in row[..(w + 1) / 2 * 4].chunks_exact_mut(4).enumerate() {
+                    let x = pair * 2;
+                    let (y0, u0, v0) = pixel(x, y);
+                    // Replicate the last pixel of an odd-width row
+                    let (y1, u1, v1) = pixel((x + 1).min(w - 1), y);
+                    macropixel.copy_from_slice(&[y0, average(&[u0, u1]), y1, average(&[v0, v1])]);
+                }
+            }
+        }
+        (PixelFormat::NV12, [y_plane, uv_plane, ..]) => {
+            let done = bgra_to_nv12_simd(
+                src,
+                src_stride as usize,
+                [y_plane, uv_plane],
+                [strides[0], strides[1]],
+                w,
+                h,
+                coefficients,
+            );
+            for_each_block(
+                w,
+                h,
+                &done,
+                pixel,
+                |x, y, luma| {
+                    y_plane[y * strides[0] + x] = luma;
+                },
+                |x, y, u, v| {
+                    uv_plane[y * strides[1] + x * 2..][..2].copy_from_slice(&[u, v]);
+                },
+            )
+        }
+        (PixelFormat::P010, [y_plane, uv_plane, ..]) => for_each_block(
+            w,
+            h,
+            &[],
+            pixel,
+            |x, y, luma| {
+                y_plane[y * strides[0] + x * 2..][..2].copy_from_slice(&p010_sample(luma));
+            },
+            |x, y, u, v| {
+                let [u0, u1] = p010_sample(u);
+                let [v0, v1] = p010_sample(v);
+                uv_plane[y * strides[1] + x * 4..][..4].copy_from_slice(&[u0, u1, v0, v1]);
+            },
+        ),
+        (PixelFormat::I420, [y_plane, u_plane, v_plane, ..]) => for_each_block(
+            w,
+            h,
+            &[],
+            pixel,
+            |x, y, luma| {
+                y_plane[y * strides[0] + x] = luma;
+            },
+            |x, y, u, v| {
+                u_plane[y * strides[1] + x] = u;
+                v_plane[y * strides[2] + x] = v;
+            },
+        ),
+        _ => return Err(PipeWireError::UnsupportedConversion(PixelFormat::BGRA, dst_format)),
+    }
+    Ok(())
 }
 
-/// Clamp value to range
-#[inline]
-fn clamp(val: i32, min: i32, max: i32) -> i32 {
-    if val < min {
-        min
-    } else if val > max {
-        max
-    } else {
-        val
+/// Encode whole pairs of BGRA rows to NV12 with the SIMD kernels
+///
+/// `planes` and `strides` are the Y and UV planes. Returns how many pixels
+/// of each pair of rows were written.
+fn bgra_to_nv12_simd(
+    src: &[u8],
+    src_stride: usize,
+    planes: [&mut [u8]; 2],
+    strides: [usize; 2],
+    width: usize,
+    height: usize,
+    coefficients: &YuvCoefficients,
+) -> Vec<usize> {
+    let level = SimdLevel::detect();
+    let [y_plane, uv_plane] = planes;
+
+    (0..height / 2)
+        .map(|pair| {
+            let top = pair * 2;
+            let rows = [
+                &src[top * src_stride..][..width * 4],
+                &src[(top + 1) * src_stride..][..width * 4],
+            ];
+            let (upper, lower) = y_plane[top * strides[0]..].split_at_mut(strides[0]);
+            let uv_row = &mut uv_plane[pair * strides[1]..][..(width + 1) / 2 * 2];
+            simd::bgra_to_nv12_rows(
+                level,
+                coefficients,
+                rows,
+                [&mut upper[..width], &mut lower[..width]],
+                uv_row,
+            )
+        })
+        .collect()
+}
+
+/// Visit a frame in 2x2 blocks for 4:2:0 encoding
+///
+/// `luma` receives every pixel's Y; `chroma` receives each block's position
+/// in the chroma plane with its averaged U and V. `done` holds, per row of
+/// blocks, how many leading pixels were already encoded (an even number);
+/// rows past its end start from the first block.
+fn for_each_block(
+    width: usize,
+    height: usize,
+    done: &[usize],
+    pixel: impl Fn(usize, usize) -> (u8, u8, u8),
+    mut luma: impl FnMut(usize, usize, u8),
+    mut chroma: impl FnMut(usize, usize, u8, u8),
+) {
+    for block_y in 0..(height + 1) / 2 {
+        let first = done.get(block_y).map_or(0, |&pixels| pixels / 2);
+        for block_x in first..(width + 1) / 2 {
+            let mut u = [0u8; 4];
+            let mut v = [0u8; 4];
+            let mut count = 0;
+
+            for y in block_y * 2..(block_y * 2 + 2).min(height) {
+                for x in block_x * 2..(block_x * 2 + 2).min(width) {
+                    let (y_val, u_val, v_val) = pixel(x, y);
+                    luma(x, y, y_val);
+                    u[count] = u_val;
+                    v[count] = v_val;
+                    count += 1;
+                }
+            }
+
+            chroma(block_x, block_y, average(&u[..count]), average(&v[..count]));
+        }
     }
 }
 
+/// Rounded average of chroma samples
+#[inline]
+fn average(samples: &[u8]) -> u8 {
+    let sum: usize = samples.iter().map(|&sample| sample as usize).sum();
+    ((sum + samples.len() / 2) / samples.len()) as u8
+}
+
+/// Widen an 8-bit sample to a P010 word (10 bits in the high bits, little-endian)
+#[inline]
+fn p010_sample(value: u8) -> [u8; 2] {
+    let value = u16::from(value);
+    ((value << 2 | value >> 6) << 6).to_le_bytes()
+}
+
+/// RGB to full-range luma (BT.601 weights)
+#[inline]
+fn rgb_to_gray(r: u8, g: u8, b: u8) -> u8 {
+    ((77 * r as u32 + 150 * g as u32 + 29 * b as u32 + 128) >> 8) as u8
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::colorimetry::{ColorMatrix, ColorRange};
 
     #[test]
     fn test_pixel_format_conversion() {
         assert_eq!(PixelFormat::from_spa(VideoFormat::BGRA), Some(PixelFormat::BGRA));
         assert_eq!(PixelFormat::BGRA.to_spa(), VideoFormat::BGRA);
+        assert_eq!(PixelFormat::from_spa(VideoFormat::P010_10LE), Some(PixelFormat::P010));
+        assert_eq!(PixelFormat::ABGR_210LE.to_spa(), VideoFormat::ABGR_210LE);
+    }
+
+    #[test]
+    fn test_pixel_format_properties() {
+        assert_eq!(PixelFormat::xRGB.bytes_per_pixel(), 4);
+        assert_eq!(PixelFormat::ARGB_210LE.bytes_per_pixel(), 4);
+        assert_eq!(PixelFormat::P010.bytes_per_pixel(), 2);
+        assert_eq!(PixelFormat::ARGB_210LE.bit_depth(), 10);
+        assert_eq!(PixelFormat::P010.bit_depth(), 10);
+        assert_eq!(PixelFormat::ABGR.bit_depth(), 8);
+        assert!(PixelFormat::ABGR.has_alpha());
+        assert!(!PixelFormat::xBGR_210LE.has_alpha());
+        assert!(PixelFormat::P010.is_planar());
+        assert!(PixelFormat::P010.is_yuv());
+        assert_eq!(
+            PixelFormat::P010.plane_layout(2, 8),
+            PixelFormat::NV12.plane_layout(2, 8)
+        );
+        assert_eq!(PixelFormat::YUY2.min_stride(3), Some(8));
+        assert_eq!(PixelFormat::BGRA.min_stride(u32::MAX / 4 + 1), None);
+        assert_eq!(PixelFormat::NV12.min_stride(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_drm_fourcc_round_trip() {
+        for format in [
+            PixelFormat::BGRx,
+            PixelFormat::xRGB,
+            PixelFormat::ABGR,
+            PixelFormat::xRGB_210LE,
+            PixelFormat::ABGR_210LE,
+            PixelFormat::NV12,
+            PixelFormat::P010,
+        ] {
+            let fourcc = format.to_drm_fourcc().unwrap();
+            assert_eq!(PixelFormat::from_drm_fourcc(fourcc), Some(format));
+        }
+        assert_eq!(PixelFormat::YUY2.to_drm_fourcc(), None);
+        assert_eq!(PixelFormat::from_drm_fourcc(0), None);
+    }
+
+    #[test]
+    fn test_byte_order_conversions() {
+        // One orange pixel (R=255, G=128, B=0, A=64) in each byte order
+        let cases: [(PixelFormat, [u8; 4]); 6] = [
+            (PixelFormat::RGBA, [255, 128, 0, 64]),
+            (PixelFormat::RGBx, [255, 128, 0, 0]),
+            (PixelFormat::xRGB, [0, 255, 128, 0]),
+            (PixelFormat::xBGR, [0, 0, 128, 255]),
+            (PixelFormat::ARGB, [64, 255, 128, 0]),
+            (PixelFormat::ABGR, [64, 0, 128, 255]),
+        ];
+
+        for (format, src) in cases {
+            let alpha = if format.has_alpha() { 64 } else { 255 };
+            let mut dst = [0u8; 4];
+            convert_format(
+                &src,
+                &mut dst,
+                format,
+                PixelFormat::BGRA,
+                1,
+                1,
+                4,
+                4,
+                Colorimetry::default(),
+            )
+            .unwrap();
+            assert_eq!(dst, [0, 128, 255, alpha], "{:?}", format);
+
+            convert_format(
+                &src,
+                &mut dst,
+                format,
+                PixelFormat::BGRx,
+                1,
+                1,
+                4,
+                4,
+                Colorimetry::default(),
+            )
+            .unwrap();
+            assert_eq!(dst, [0, 128, 255, 255], "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_10bit_conversions() {
+        // R=1023, G=512, B=4, A=1 (of 3)
+        let argb = (1u32 << 30) | (1023 << 20) | (512 << 10) | 4;
+        let abgr = (1u32 << 30) | (4 << 20) | (512 << 10) | 1023;
+
+        let mut dst = [0u8; 4];
+        convert_format(
+            &argb.to_le_bytes(),
+            &mut dst,
+            PixelFormat::ARGB_210LE,
+            PixelFormat::BGRA,
+            1,
+            1,
+            4,
+            4,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 128, 255, 85]);
+
+        convert_format(
+            &abgr.to_le_bytes(),
+            &mut dst,
+            PixelFormat::ABGR_210LE,
+            PixelFormat::BGRA,
+            1,
+            1,
+            4,
+            4,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 128, 255, 85]);
+
+        convert_format(
+            &argb.to_le_bytes(),
+            &mut dst,
+            PixelFormat::xRGB_210LE,
+            PixelFormat::BGRA,
+            1,
+            1,
+            4,
+            4,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 128, 255, 255]);
+    }
+
+    #[test]
+    fn test_p010_to_bgra() {
+        // 2x2 white frame: Y=235, U=V=128 in the high byte of each sample
+        let sample = |v: u8| [0xC0, v];
+        let mut src = Vec::new();
+        for _ in 0..4 {
+            src.extend_from_slice(&sample(235));
+        }
+        src.extend_from_slice(&sample(128));
+        src.extend_from_slice(&sample(128));
+
+        let mut dst = vec![0u8; 16];
+        convert_format(
+            &src,
+            &mut dst,
+            PixelFormat::P010,
+            PixelFormat::BGRx,
+            2,
+            2,
+            4,
+            8,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert!(dst.chunks(4).all(|px| px == [255, 255, 255, 255]));
+
+        // A short UV plane is rejected rather than read out of bounds
+        let result = convert_format(
+            &src[..10],
+            &mut dst,
+            PixelFormat::P010,
+            PixelFormat::BGRA,
+            2,
+            2,
+            4,
+            8,
+            Colorimetry::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_bgrx_to_bgr_with_strides() {
+        // 2x2 BGRx at stride 12, BGR at stride 8
+        let src = pad_rows(&[1, 2, 3, 0, 4, 5, 6, 0, 7, 8, 9, 0, 10, 11, 12, 0], 8, 12);
+        let mut dst = vec![0xEE; 16];
+
+        convert_format(
+            &src,
+            &mut dst,
+            PixelFormat::BGRx,
+            PixelFormat::BGR,
+            2,
+            2,
+            12,
+            8,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 2, 3, 4, 5, 6, 0xEE, 0xEE, 7, 8, 9, 10, 11, 12, 0xEE, 0xEE]);
+
+        let mut bgrx = [0u8; 4];
+        convert_format(
+            &[9, 8, 7, 0],
+            &mut bgrx,
+            PixelFormat::RGBx,
+            PixelFormat::BGRx,
+            1,
+            1,
+            4,
+            4,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(bgrx, [7, 8, 9, 255]);
+    }
+
+    #[test]
+    fn test_bgra_to_yuv() {
+        // 2x2: white, black, red, blue
+        let src = [255, 255, 255, 255, 0, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255];
+
+        let mut nv12 = [0u8; 6];
+        convert_format(
+            &src,
+            &mut nv12,
+            PixelFormat::BGRA,
+            PixelFormat::NV12,
+            2,
+            2,
+            8,
+            2,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(&nv12[..4], &[235, 16, 82, 41]);
+        // Chroma is the block average
+        let coefficients = Colorimetry::BT601.coefficients();
+        let (_, u_red, v_red) = coefficients.rgb_to_yuv(255, 0, 0);
+        let (_, u_blue, v_blue) = coefficients.rgb_to_yuv(0, 0, 255);
+        let expected_u = average(&[128, 128, u_red, u_blue]);
+        let expected_v = average(&[128, 128, v_red, v_blue]);
+        assert_eq!(&nv12[4..], &[expected_u, expected_v]);
+
+        let mut i420 = [0u8; 6];
+        convert_format(
+            &src,
+            &mut i420,
+            PixelFormat::BGRA,
+            PixelFormat::I420,
+            2,
+            2,
+            8,
+            2,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(i420, [235, 16, 82, 41, expected_u, expected_v]);
+    }
+
+    #[test]
+    fn test_planar_copy_honours_strides() {
+        // 2x2 NV12 at stride 2, copied to stride 4
+        let src = [1, 2, 3, 4, 5, 6];
+        let mut dst = vec![0xEE; 12];
+        convert_format(
+            &src,
+            &mut dst,
+            PixelFormat::NV12,
+            PixelFormat::NV12,
+            2,
+            2,
+            2,
+            4,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 2, 0xEE, 0xEE, 3, 4, 0xEE, 0xEE, 5, 6, 0xEE, 0xEE]);
+
+        // A destination without room for the chroma plane is rejected
+        let mut short = [0u8; 4];
+        let result = convert_format(
+            &src,
+            &mut short,
+            PixelFormat::NV12,
+            PixelFormat::NV12,
+            2,
+            2,
+            2,
+            2,
+            Colorimetry::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    /// Largest per-channel difference between two BGRA frames
+    fn max_difference(a: &[u8], b: &[u8]) -> u8 {
+        a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0)
+    }
+
+    /// Adjust a BGRA frame so that `formats` can all represent it: opaque
+    /// or quantized alpha, gray, and constant across chroma blocks
+    fn representable(bgra: &mut [u8], width: usize, height: usize, formats: [PixelFormat; 2]) {
+        let any = |f: fn(&PixelFormat) -> bool| formats.iter().any(f);
+        for px in bgra.chunks_exact_mut(4) {
+            if !formats.iter().all(PixelFormat::has_alpha) {
+                px[3] = 255;
+            } else if any(|f| f.bit_depth() == 10) {
+                px[3] = (px[3] >> 6) * 0x55;
+            }
+            if any(|f| *f == PixelFormat::GRAY8) {
+                px[0] = px[2];
+                px[1] = px[2];
+            }
+        }
+        if any(PixelFormat::is_yuv) {
+            for y in 0..height {
+                for x in 0..width {
+                    let corner = ((y & !1) * width + (x & !1)) * 4;
+                    bgra.copy_within(corner..corner + 4, (y * width + x) * 4);
+                }
+            }
+        }
+    }
+
+    /// Convert a frame to `format` at `stride`, returning the buffer
+    fn convert_to(
+        src: &[u8],
+        from: PixelFormat,
+        src_stride: u32,
+        to: PixelFormat,
+        size: (u32, u32),
+        stride: u32,
+    ) -> Vec<u8> {
+        let planes = to.plane_layout(size.1, stride);
+        let len = planes.last().map_or(0, |plane| plane.offset + plane.size);
+        let mut dst = vec![0xEE; len];
+        convert_format(
+            src,
+            &mut dst,
+            from,
+            to,
+            size.0,
+            size.1,
+            src_stride,
+            stride,
+            Colorimetry::default(),
+        )
+        .unwrap();
+
+        // Row padding is left untouched
+        for (index, plane) in planes.iter().enumerate() {
+            let (row_bytes, rows) = plane_extent(to, index, size.0, size.1);
+            for row in dst[plane.offset..][..plane.size]
+                .chunks(plane.stride as usize)
+                .take(rows)
+            {
+                assert!(
+                    row[row_bytes..].iter().all(|&byte| byte == 0xEE),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+        dst
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(32))]
+
+        /// Round trip every pair of formats A -> B -> A at padded strides
+        #[test]
+        fn prop_round_trip_all_pairs(
+            width in 1u32..8,
+            height in 1u32..8,
+            padding in proptest::array::uniform3(0u32..9),
+            pixels in proptest::collection::vec(proptest::prelude::any::<u8>(), 8 * 8 * 4),
+        ) {
+            let (w, h) = (width as usize, height as usize);
+            let size = (width, height);
+            let bgra_stride = width * 4;
+
+            for a in PixelFormat::ALL {
+                for b in PixelFormat::ALL {
+                    let mut bgra = pixels[..w * h * 4].to_vec();
+                    representable(&mut bgra, w, h, [a, b]);
+
+                    let stride_a = a.min_stride(width).unwrap() + padding[0];
+                    let stride_b = b.min_stride(width).unwrap() + padding[1];
+                    let stride_back = a.min_stride(width).unwrap() + padding[2];
+
+                    let original = convert_to(&bgra, PixelFormat::BGRA, bgra_stride, a, size, stride_a);
+                    let converted = convert_to(&original, a, stride_a, b, size, stride_b);
+                    let back = convert_to(&converted, b, stride_b, a, size, stride_back);
+
+                    let expected = convert_to(&original, a, stride_a, PixelFormat::BGRA, size, bgra_stride);
+                    let actual = convert_to(&back, a, stride_back, PixelFormat::BGRA, size, bgra_stride);
+
+                    // YUV quantizes colour; everything else is exact
+                    let tolerance = if a.is_yuv() || b.is_yuv() { 4 } else { 0 };
+                    let difference = max_difference(&expected, &actual);
+                    proptest::prop_assert!(
+                        difference <= tolerance,
+                        "{:?} -> {:?}: off by {}",
+                        a,
+                        b,
+                        difference
+                    );
+                }
+            }
+        }
+
+        /// Every SIMD level writes exactly what the scalar code would
+        #[test]
+        fn prop_simd_matches_scalar(
+            width in 1usize..48,
+            bytes in proptest::collection::vec(proptest::prelude::any::<u8>(), 48 * 4 * 2),
+        ) {
+            let w = width;
+            let chroma = (w + 1) / 2;
+            let levels = [SimdLevel::Ssse3, SimdLevel::Avx2, SimdLevel::Neon];
+
+            for level in levels.into_iter().filter(|level| level.is_supported()) {
+                let mut dst = vec![0u8; w * 4];
+
+                // Byte swizzles between every pair of 4-byte layouts
+                for from in PixelFormat::ALL {
+                    for to in PixelFormat::ALL {
+                        let (Some(src_layout), Some(dst_layout)) = (RgbLayout::of(from), RgbLayout::of(to)) else {
+                            continue;
+                        };
+                        let Some(map) = src_layout.shuffle_map(dst_layout) else {
+                            continue;
+                        };
+                        let done = simd::shuffle_row(level, &bytes[..w * 4], &mut dst, map);
+                        for (src_px, dst_px) in bytes.chunks_exact(4).zip(dst.chunks_exact(4)).take(done) {
+                            let mut expected = [0u8; 4];
+                            dst_layout.encode(src_layout.decode(src_px), &mut expected);
+                            proptest::prop_assert_eq!(dst_px, &expected[..], "{:?} {:?} -> {:?}", level, from, to);
+                        }
+                    }
+                }
+
+                for colorimetry in all_colorimetries() {
+                    let coefficients = colorimetry.coefficients();
+                    let bgra = |y: u8, u: u8, v: u8| {
+                        let (r, g, b) = coefficients.yuv_to_rgb(y as i32, u as i32, v as i32);
+                        [b, g, r, 255]
+                    };
+
+                    let (y, uv) = (&bytes[..w], &bytes[w..][..chroma * 2]);
+                    let done = simd::nv12_to_bgra_row(level, &coefficients, y, uv, &mut dst);
+                    for (x, px) in dst.chunks_exact(4).take(done).enumerate() {
+                        let expected = bgra(y[x], uv[x / 2 * 2], uv[x / 2 * 2 + 1]);
+                        proptest::prop_assert_eq!(px, &expected[..], "{:?} {:?} NV12", level, colorimetry);
+                    }
+
+                    let (u, v) = (&bytes[w..][..chroma], &bytes[w + chroma..][..chroma]);
+                    let done = simd::i420_to_bgra_row(level, &coefficients, y, u, v, &mut dst);
+                    for (x, px) in dst.chunks_exact(4).take(done).enumerate() {
+                        let expected = bgra(y[x], u[x / 2], v[x / 2]);
+                        proptest::prop_assert_eq!(px, &expected[..], "{:?} {:?} I420", level, colorimetry);
+                    }
+
+                    let yuy2 = &bytes[..chroma * 4];
+                    let done = simd::yuy2_to_bgra_row(level, &coefficients, yuy2, &mut dst);
+                    for (x, px) in dst.chunks_exact(4).take(done).enumerate() {
+                        let macropixel = &yuy2[x / 2 * 4..][..4];
+                        let expected = bgra(macropixel[x % 2 * 2], macropixel[1], macropixel[3]);
+                        proptest::prop_assert_eq!(px, &expected[..], "{:?} {:?} YUY2", level, colorimetry);
+                    }
+
+                    // Two rows of BGRA against the scalar encoder
+                    let src = &bytes[..w * 4 * 2];
+                    let stride = chroma * 2;
+                    let mut expected = vec![0u8; stride * 3];
+                    let planes = PixelFormat::NV12.plane_layout(2, stride as u32);
+                    encode_yuv(
+                        src,
+                        w as u32 * 4,
+                        &mut expected,
+                        &planes,
+                        PixelFormat::NV12,
+                        w as u32,
+                        2,
+                        &coefficients,
+                    )
+                    .unwrap();
+
+                    let mut luma = [vec![0u8; w], vec![0u8; w]];
+                    let mut uv = vec![0u8; chroma * 2];
+                    let [upper, lower] = &mut luma;
+                    let rows = [&src[..w * 4], &src[w * 4..]];
+                    let done = simd::bgra_to_nv12_rows(level, &coefficients, rows, [upper, lower], &mut uv);
+                    let message = format!("{:?} {:?} BGRA -> NV12", level, colorimetry);
+                    proptest::prop_assert_eq!(&luma[0][..done], &expected[..done], "{}", message);
+                    proptest::prop_assert_eq!(&luma[1][..done], &expected[stride..][..done], "{}", message);
+                    proptest::prop_assert_eq!(&uv[..done], &expected[stride * 2..][..done], "{}", message);
+                }
+            }
+        }
+    }
+
+    fn all_colorimetries() -> impl Iterator<Item = Colorimetry> {
+        [ColorMatrix::Bt601, ColorMatrix::Bt709, ColorMatrix::Bt2020]
+            .into_iter()
+            .flat_map(|matrix| [ColorRange::Limited, ColorRange::Full].map(|range| Colorimetry::new(matrix, range)))
+    }
+
+    #[test]
+    fn test_colorimetry_selects_matrix() {
+        // A saturated 2x2 red block
+        let src = [0, 0, 255, 255].repeat(4);
+
+        let encode = |colorimetry| {
+            let mut nv12 = [0u8; 6];
+            convert_format(
+                &src,
+                &mut nv12,
+                PixelFormat::BGRA,
+                PixelFormat::NV12,
+                2,
+                2,
+                8,
+                2,
+                colorimetry,
+            )
+            .unwrap();
+            nv12
+        };
+        let decode = |nv12: &[u8], colorimetry| {
+            let mut bgra = [0u8; 16];
+            convert_format(
+                nv12,
+                &mut bgra,
+                PixelFormat::NV12,
+                PixelFormat::BGRA,
+                2,
+                2,
+                2,
+                8,
+                colorimetry,
+            )
+            .unwrap();
+            bgra
+        };
+
+        let bt601 = encode(Colorimetry::BT601);
+        let bt709 = encode(Colorimetry::BT709);
+        assert_ne!(bt601, bt709);
+
+        // Each decodes back to red with its own matrix...
+        for (nv12, colorimetry) in [(bt601, Colorimetry::BT601), (bt709, Colorimetry::BT709)] {
+            assert!(max_difference(&decode(&nv12, colorimetry), &src) <= 2);
+        }
+        // ...but not with the other one
+        assert!(max_difference(&decode(&bt709, Colorimetry::BT601), &src) > 10);
+
+        // Full range uses the whole 0..=255 luma scale
+        let white = [255u8; 16];
+        let mut nv12 = [0u8; 6];
+        let full = Colorimetry::new(ColorMatrix::Bt709, ColorRange::Full);
+        convert_format(
+            &white,
+            &mut nv12,
+            PixelFormat::BGRA,
+            PixelFormat::NV12,
+            2,
+            2,
+            8,
+            2,
+            full,
+        )
+        .unwrap();
+        assert_eq!(nv12, [255, 255, 255, 255, 128, 128]);
     }
 
     #[test]
@@ -370,7 +1782,18 @@ mod tests {
         ];
         let mut dst = vec![0u8; 12]; // 3 pixels * 4 bytes
 
-        convert_rgb_to_bgra(&src, &mut dst, 3, 1, 9, 12).unwrap();
+        convert_format(
+            &src,
+            &mut dst,
+            PixelFormat::RGB,
+            PixelFormat::BGRA,
+            3,
+            1,
+            9,
+            12,
+            Colorimetry::default(),
+        )
+        .unwrap();
 
         // Red pixel (RGB 255,0,0 -> BGRA 0,0,255,255)
         assert_eq!(dst[0], 0); // B
@@ -391,21 +1814,198 @@ mod tests {
         assert_eq!(dst[11], 255); // A
     }
 
+    /// Place tightly packed rows at `stride`, filling the padding with junk
+    fn pad_rows(tight: &[u8], row_bytes: usize, stride: usize) -> Vec<u8> {
+        let mut padded = Vec::new();
+        for row in tight.chunks(row_bytes) {
+            padded.extend_from_slice(row);
+            padded.resize(padded.len() + stride - row_bytes, 0xAB);
+        }
+        padded
+    }
+
+    #[test]
+    fn test_plane_layout() {
+        assert_eq!(PixelFormat::BGRx.plane_layout(4, 32), vec![FramePlane::new(0, 32, 128)]);
+        assert_eq!(
+            PixelFormat::NV12.plane_layout(3, 16),
+            vec![FramePlane::new(0, 16, 48), FramePlane::new(48, 16, 32)]
+        );
+        assert_eq!(
+            PixelFormat::I420.plane_layout(4, 16),
+            vec![
+                FramePlane::new(0, 16, 64),
+                FramePlane::new(64, 8, 16),
+                FramePlane::new(80, 8, 16)
+            ]
+        );
+
+        // Odd strides and huge dimensions neither overflow nor wrap
+        let huge = PixelFormat::I420.plane_layout(u32::MAX, u32::MAX);
+        assert_eq!(huge[1].stride, u32::MAX / 2 + 1);
+        assert!(huge[2].offset >= huge[1].offset);
+    }
+
+    #[test]
+    fn test_nv12_planes_with_padded_strides() {
+        let (width, height) = (4u32, 2u32);
+        let y_tight = [16, 60, 120, 235, 30, 90, 150, 200];
+        let uv_tight = [90, 200, 160, 40];
+
+        let mut tight = y_tight.to_vec();
+        tight.extend_from_slice(&uv_tight);
+        let mut expected = vec![0u8; 32];
+        convert_format(
+            &tight,
+            &mut expected,
+            PixelFormat::NV12,
+            PixelFormat::BGRA,
+            width,
+            height,
+            4,
+            16,
+            Colorimetry::default(),
+        )
+        .unwrap();
+
+        // Planes from separate buffers: each padded differently, UV plane first
+        let uv = pad_rows(&uv_tight, 4, 12);
+        let y = pad_rows(&y_tight, 4, 8);
+        let mut src = uv.clone();
+        src.extend_from_slice(&y);
+        let planes = [FramePlane::new(uv.len(), 8, y.len()), FramePlane::new(0, 12, uv.len())];
+
+        let mut dst = vec![0u8; 32];
+        convert_format_planes(
+            &src,
+            &planes,
+            &mut dst,
+            PixelFormat::NV12,
+            PixelFormat::BGRA,
+            width,
+            height,
+            16,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(dst, expected);
+    }
+
+    #[test]
+    fn test_i420_with_padded_stride() {
+        let (width, height) = (4u32, 2u32);
+        let tight = [16, 60, 120, 235, 30, 90, 150, 200, 90, 160, 200, 40];
+        let mut expected = vec![0u8; 32];
+        convert_format(
+            &tight,
+            &mut expected,
+            PixelFormat::I420,
+            PixelFormat::BGRA,
+            width,
+            height,
+            4,
+            16,
+            Colorimetry::default(),
+        )
+        .unwrap();
+
+        // Single buffer with an 8-byte luma stride (4-byte chroma stride)
+        let mut src = pad_rows(&tight[..8], 4, 8);
+        src.extend(pad_rows(&tight[8..10], 2, 4));
+        src.extend(pad_rows(&tight[10..], 2, 4));
+
+        // Destination rows padded as well
+        let mut dst = vec![0u8; 40];
+        convert_format(
+            &src,
+            &mut dst,
+            PixelFormat::I420,
+            PixelFormat::BGRA,
+            width,
+            height,
+            8,
+            20,
+            Colorimetry::default(),
+        )
+        .unwrap();
+        assert_eq!(&dst[..16], &expected[..16]);
+        assert_eq!(&dst[20..36], &expected[16..]);
+    }
+
+    #[test]
+    fn test_planar_conversion_rejects_short_planes() {
+        let planes = [FramePlane::new(0, 4, 8), FramePlane::new(8, 4, 2)];
+        let mut dst = vec![0u8; 32];
+        let result = convert_format_planes(
+            &[0u8; 10],
+            &planes,
+            &mut dst,
+            PixelFormat::NV12,
+            PixelFormat::BGRA,
+            4,
+            2,
+            16,
+            Colorimetry::default(),
+        );
+        assert!(result.is_err());
+
+        let outside = [FramePlane::new(0, 4, 8), FramePlane::new(8, 4, 64)];
+        let result = convert_format_planes(
+            &[0u8; 12],
+            &outside,
+            &mut dst,
+            PixelFormat::NV12,
+            PixelFormat::BGRA,
+            4,
+            2,
+            16,
+            Colorimetry::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_conversion_rejects_oversized_frame() {
+        // A tiny buffer claiming a huge frame fails before the intermediate
+        // BGRA frame is allocated
+        let tiny = [16u8, 16, 16, 16, 128, 128];
+        let mut dst = vec![0u8; 16];
+        let result = convert_format(
+            &tiny,
+            &mut dst,
+            PixelFormat::NV12,
+            PixelFormat::I420,
+            100_000,
+            100_000,
+            100_000,
+            100_000,
+            Colorimetry::default(),
+        );
+        assert!(matches!(result, Err(PipeWireError::FormatConversionFailed(_))));
+
+        let result = convert_format(
+            &tiny,
+            &mut dst,
+            PixelFormat::YUY2,
+            PixelFormat::RGB,
+            u32::MAX,
+            u32::MAX,
+            u32::MAX,
+            u32::MAX,
+            Colorimetry::default(),
+        );
+        assert!(matches!(result, Err(PipeWireError::FormatConversionFailed(_))));
+    }
+
     #[test]
     fn test_yuv_to_rgb() {
         // Test black (Y=16, U=128, V=128)
-        let (r, g, b) = yuv_to_rgb(16, 128, 128);
+        let coefficients = Colorimetry::default().coefficients();
+        let (r, g, b) = coefficients.yuv_to_rgb(16, 128, 128);
         assert_eq!((r, g, b), (0, 0, 0));
 
         // Test white (Y=235, U=128, V=128)
-        let (r, g, b) = yuv_to_rgb(235, 128, 128);
+        let (r, g, b) = coefficients.yuv_to_rgb(235, 128, 128);
         assert_eq!((r, g, b), (255, 255, 255));
     }
-
-    #[test]
-    fn test_clamp() {
-        assert_eq!(clamp(-10, 0, 255), 0);
-        assert_eq!(clamp(300, 0, 255), 255);
-        assert_eq!(clamp(128, 0, 255), 128);
-    }
 }
@@ -2,8 +2,10 @@
 //!
 //! Structures and utilities for handling video frames captured from PipeWire.
 
-use crate::ffi::DamageRegion;
-use crate::format::PixelFormat;
+use crate::buffer::PooledBuffer;
+use crate::colorimetry::Colorimetry;
+use crate::ffi::{self, DamageRegion};
+use crate::format::{convert_format, FramePlane, PixelFormat};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
@@ -22,23 +24,35 @@ pub struct VideoFrame {
     /// Frame duration (nanoseconds)
     pub duration: u64,
 
+    /// Producer sequence number (0 if the producer provides none)
+    pub seq: u64,
+
     /// Frame width in pixels
     pub width: u32,
 
     /// Frame height in pixels
     pub height: u32,
 
-    /// Row stride in bytes
+    /// Row stride in bytes (of the first plane for planar formats)
     pub stride: u32,
 
     /// Pixel format
     pub format: PixelFormat,
 
+    /// YUV matrix and range (only meaningful for YUV formats)
+    pub colorimetry: Colorimetry,
+
     /// Monitor/stream index
     pub monitor_index: u32,
 
-    /// Pixel data (owned or shared)
-    pub data: Arc<Vec<u8>>,
+    /// Pixel data (owned, pooled or a held PipeWire buffer)
+    pub data: FrameData,
+
+    /// Location and stride of each plane within `data`
+    ///
+    /// Empty for frames built without an explicit layout; those use the
+    /// format's default layout (see [`VideoFrame::plane_layout`]).
+    pub planes: Vec<FramePlane>,
 
     /// Capture timestamp
     pub capture_time: SystemTime,
@@ -50,6 +64,157 @@ pub struct VideoFrame {
     pub flags: FrameFlags,
 }
 
+/// How captured pixel data is handed to consumers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameDataMode {
+    /// Copy every frame into a freshly allocated buffer
+    Copy,
+
+    /// Copy into storage recycled through a [`FramePool`](crate::buffer::FramePool)
+    #[default]
+    Pooled,
+
+    /// Hand out the PipeWire buffer itself without copying
+    ///
+    /// The buffer is returned to the stream when the last reference to the
+    /// frame data is dropped, so consumers must release frames promptly: the
+    /// producer cannot reuse a held buffer. Frames that cannot be held (DMA-BUF
+    /// or multi-plane buffers, or when all but one buffer are already held)
+    /// are copied into pooled storage instead.
+    ZeroCopy,
+}
+
+/// PipeWire buffer memory lent to a frame
+///
+/// Runs its release action (returning the buffer to its stream) on drop.
+pub(crate) struct HeldBuffer {
+    ptr: *const u8,
+    len: usize,
+    release: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl HeldBuffer {
+    /// Wrap `len` bytes at `ptr`, calling `release` when dropped
+    ///
+    /// # Safety
+    ///
+    /// The memory must stay valid and unmodified until `release` runs.
+    pub(crate) unsafe fn new(ptr: *const u8, len: usize, release: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            ptr,
+            len,
+            release: Some(Box::new(release)),
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: the constructor's contract keeps ptr..ptr+len valid and
+        // unmodified until release runs, which happens only in Drop.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+// SAFETY: the lent memory is only read, and the release action is Send.
+unsafe impl Send for HeldBuffer {}
+
+// SAFETY: shared access only reads the lent memory.
+unsafe impl Sync for HeldBuffer {}
+
+impl Drop for HeldBuffer {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+    }
+}
+
+/// Storage behind [`FrameData`]
+enum FrameStorage {
+    Owned(Vec<u8>),
+    Pooled(PooledBuffer),
+    Held(HeldBuffer),
+}
+
+/// Pixel data of a captured frame
+///
+/// Dereferences to the frame bytes. Cloning is cheap: clones share the
+/// storage, which is released when the last of them is dropped. Pooled
+/// storage then returns to its pool and held PipeWire buffers are handed back
+/// to their stream (see [`FrameDataMode`]).
+#[derive(Clone)]
+pub struct FrameData {
+    storage: Arc<FrameStorage>,
+}
+
+impl FrameData {
+    /// Whether the storage returns to a frame pool when released
+    pub fn is_pooled(&self) -> bool {
+        matches!(&*self.storage, FrameStorage::Pooled(buffer) if buffer.is_pooled())
+    }
+
+    /// Whether the data is a PipeWire buffer held without copying
+    pub fn is_zero_copy(&self) -> bool {
+        matches!(*self.storage, FrameStorage::Held(_))
+    }
+
+    /// Data held by a PipeWire buffer
+    pub(crate) fn held(buffer: HeldBuffer) -> Self {
+        Self {
+            storage: Arc::new(FrameStorage::Held(buffer)),
+        }
+    }
+}
+
+impl Default for FrameData {
+    fn default() -> Self {
+        Self::from(Vec::new())
+    }
+}
+
+impl From<Vec<u8>> for FrameData {
+    fn from(data: Vec<u8>) -> Self {
+        Self {
+            storage: Arc::new(FrameStorage::Owned(data)),
+        }
+    }
+}
+
+impl From<PooledBuffer> for FrameData {
+    fn from(buffer: PooledBuffer) -> Self {
+        Self {
+            storage: Arc::new(FrameStorage::Pooled(buffer)),
+        }
+    }
+}
+
+impl std::ops::Deref for FrameData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &*self.storage {
+            FrameStorage::Owned(data) => data,
+            FrameStorage::Pooled(buffer) => buffer,
+            FrameStorage::Held(buffer) => buffer.as_slice(),
+        }
+    }
+}
+
+impl AsRef<[u8]> for FrameData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl std::fmt::Debug for FrameData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameData")
+            .field("len", &self.len())
+            .field("pooled", &self.is_pooled())
+            .field("zero_copy", &self.is_zero_copy())
+            .finish()
+    }
+}
+
 /// Frame flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameFlags {
@@ -104,6 +269,10 @@ impl FrameFlags {
         self.bits & Self::CORRUPTED != 0
     }
 
+    pub fn set_corrupted(&mut self) {
+        self.bits |= Self::CORRUPTED;
+    }
+
     pub fn is_incomplete(&self) -> bool {
         self.bits & Self::INCOMPLETE != 0
     }
@@ -123,12 +292,15 @@ impl VideoFrame {
             pts: 0,
             dts: 0,
             duration: 0,
+            seq: 0,
             width,
             height,
             stride,
             format,
+            colorimetry: Colorimetry::default(),
             monitor_index,
-            data: Arc::new(Vec::new()),
+            data: FrameData::default(),
+            planes: Vec::new(),
             capture_time: SystemTime::now(),
             damage_regions: Vec::new(),
             flags: FrameFlags::new(),
@@ -150,12 +322,15 @@ impl VideoFrame {
             pts: 0,
             dts: 0,
             duration: 0,
+            seq: 0,
             width,
             height,
             stride,
             format,
+            colorimetry: Colorimetry::default(),
             monitor_index,
-            data: Arc::new(data),
+            data: FrameData::from(data),
+            planes: Vec::new(),
             capture_time: SystemTime::now(),
             damage_regions: Vec::new(),
             flags: FrameFlags::new(),
@@ -212,9 +387,31 @@ impl VideoFrame {
         !self.data.is_empty() && !self.flags.is_corrupted() && !self.flags.is_incomplete()
     }
 
+    /// Get the plane layout of the frame data
+    ///
+    /// Returns `planes` if set, otherwise the format's default single-buffer
+    /// layout for `stride`.
+    pub fn plane_layout(&self) -> Vec<FramePlane> {
+        if self.planes.is_empty() {
+            self.format.plane_layout(self.height, self.stride)
+        } else {
+            self.planes.clone()
+        }
+    }
+
+    /// Get the data of one plane
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        self.plane_layout().get(index)?.slice(&self.data)
+    }
+
+    /// Get the row stride of one plane
+    pub fn plane_stride(&self, index: usize) -> Option<u32> {
+        self.plane_layout().get(index).map(|plane| plane.stride)
+    }
+
     /// Clone frame data (makes a copy)
     pub fn clone_data(&self) -> Vec<u8> {
-        (*self.data).clone()
+        self.data.to_vec()
     }
 }
 
@@ -223,17 +420,102 @@ impl std::fmt::Debug for VideoFrame {
         f.debug_struct("VideoFrame")
             .field("frame_id", &self.frame_id)
             .field("pts", &self.pts)
+            .field("seq", &self.seq)
             .field("width", &self.width)
             .field("height", &self.height)
             .field("format", &self.format)
+            .field("colorimetry", &self.colorimetry)
             .field("monitor_index", &self.monitor_index)
             .field("data_size", &self.data.len())
+            .field("planes", &self.planes.len())
             .field("damage_regions", &self.damage_regions.len())
             .field("flags", &self.flags.bits())
             .finish()
     }
 }
 
+/// Cursor bitmap (BGRA, tightly packed rows)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorBitmap {
+    /// Width in pixels
+    pub width: u32,
+
+    /// Height in pixels
+    pub height: u32,
+
+    /// BGRA pixel data, `width * 4` bytes per row
+    pub data: Vec<u8>,
+}
+
+/// Cursor update from a stream's `SPA_META_Cursor` metadata
+#[derive(Debug, Clone)]
+pub struct CursorUpdate {
+    /// Stream the update belongs to
+    pub stream_id: u32,
+
+    /// Hotspot position in frame coordinates
+    pub position: (i32, i32),
+
+    /// Hotspot offset within the bitmap
+    pub hotspot: (i32, i32),
+
+    /// Whether the cursor is visible
+    pub visible: bool,
+
+    /// New bitmap, or `None` if unchanged since the previous update
+    pub bitmap: Option<CursorBitmap>,
+
+    /// Capture timestamp
+    pub capture_time: SystemTime,
+}
+
+impl CursorUpdate {
+    /// Build from decoded cursor metadata
+    ///
+    /// The bitmap is converted to BGRA; bitmaps in formats that cannot be
+    /// converted are dropped (position and visibility are still reported).
+    pub fn from_meta(stream_id: u32, meta: ffi::CursorMeta) -> Self {
+        let bitmap = meta.bitmap.and_then(|bitmap| {
+            let format = PixelFormat::from_spa(bitmap.format)?;
+            let stride = format.min_stride(bitmap.width)?;
+            let data = if format == PixelFormat::BGRA {
+                bitmap.data
+            } else {
+                let bgra_stride = bitmap.width.checked_mul(4)?;
+                let mut bgra = vec![0u8; (bgra_stride as usize).checked_mul(bitmap.height as usize)?];
+                convert_format(
+                    &bitmap.data,
+                    &mut bgra,
+                    format,
+                    PixelFormat::BGRA,
+                    bitmap.width,
+                    bitmap.height,
+                    stride,
+                    bgra_stride,
+                    Colorimetry::default(),
+                )
+                .ok()?;
+                bgra
+            };
+
+            Some(CursorBitmap {
+                width: bitmap.width,
+                height: bitmap.height,
+                data,
+            })
+        });
+
+        Self {
+            stream_id,
+            position: meta.position,
+            hotspot: meta.hotspot,
+            visible: meta.visible,
+            bitmap,
+            capture_time: SystemTime::now(),
+        }
+    }
+}
+
 /// Frame callback type
 pub type FrameCallback = Box<dyn Fn(VideoFrame) + Send + Sync>;
 
@@ -328,6 +610,104 @@ impl FrameStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buffer::FramePool;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_frame_data_returns_to_pool() {
+        let pool = FramePool::new(4);
+        let mut buffer = pool.acquire(16);
+        buffer.extend_from_slice(&[1, 2, 3, 4]);
+
+        let frame = VideoFrame {
+            data: FrameData::from(buffer),
+            ..VideoFrame::new(1, 1, 1, 4, PixelFormat::BGRA, 0)
+        };
+        let consumer_copy = frame.clone();
+        assert!(frame.data.is_pooled());
+        assert_eq!(&*consumer_copy.data, &[1, 2, 3, 4]);
+
+        // Storage is recycled only once every clone is gone
+        drop(frame);
+        assert_eq!(pool.free_count(), 0);
+        drop(consumer_copy);
+        assert_eq!(pool.free_count(), 1);
+    }
+
+    #[test]
+    fn test_held_frame_data_released_on_drop() {
+        static RELEASED: AtomicBool = AtomicBool::new(false);
+        let memory = [9u8; 8];
+
+        // SAFETY: memory outlives the held buffer and is not modified
+        let held = unsafe { HeldBuffer::new(memory.as_ptr(), memory.len(), || RELEASED.store(true, Ordering::SeqCst)) };
+        let data = FrameData::held(held);
+        let shared = data.clone();
+        assert!(data.is_zero_copy());
+        assert_eq!(shared.len(), 8);
+
+        drop(data);
+        assert!(!RELEASED.load(Ordering::SeqCst));
+        drop(shared);
+        assert!(RELEASED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_plane_layout() {
+        // Default layout derived from the stride
+        let frame = VideoFrame::with_data(1, 4, 2, 8, PixelFormat::NV12, 0, vec![0u8; 24]);
+        assert_eq!(frame.plane_layout().len(), 2);
+        assert_eq!(frame.plane_stride(1), Some(8));
+        assert_eq!(frame.plane(1).map(<[u8]>::len), Some(8));
+
+        // Explicit per-plane layout
+        let mut frame = VideoFrame::with_data(1, 4, 2, 6, PixelFormat::I420, 0, (0..20).collect());
+        frame.planes = vec![
+            FramePlane::new(0, 6, 12),
+            FramePlane::new(12, 4, 4),
+            FramePlane::new(16, 4, 4),
+        ];
+        assert_eq!(frame.plane(2), Some(&[16u8, 17, 18, 19][..]));
+        assert_eq!(frame.plane_stride(0), Some(6));
+        assert_eq!(frame.plane(3), None);
+    }
+
+    #[test]
+    fn test_cursor_update_from_meta() {
+        let meta = ffi::CursorMeta {
+            id: 1,
+            position: (10, 20),
+            hotspot: (1, 1),
+            visible: true,
+            bitmap: Some(ffi::CursorBitmapMeta {
+                format: ffi::VideoFormat::RGBA,
+                width: 1,
+                height: 2,
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            }),
+        };
+
+        let update = CursorUpdate::from_meta(3, meta.clone());
+        assert_eq!(update.stream_id, 3);
+        assert_eq!(update.position, (10, 20));
+        assert_eq!(
+            update.bitmap,
+            Some(CursorBitmap {
+                width: 1,
+                height: 2,
+                data: vec![3, 2, 1, 4, 7, 6, 5, 8],
+            })
+        );
+
+        // Unconvertible bitmaps are dropped, the rest of the update is kept
+        let mut unsupported = meta;
+        if let Some(bitmap) = unsupported.bitmap.as_mut() {
+            bitmap.format = ffi::VideoFormat::GRAY16_LE;
+        }
+        let update = CursorUpdate::from_meta(3, unsupported);
+        assert!(update.bitmap.is_none());
+        assert!(update.visible);
+    }
 
     #[test]
     fn test_frame_creation() {
@@ -14,7 +14,7 @@
 //! - **Zero-Copy DMA-BUF**: Hardware-accelerated frame transfer when available
 //! - **Multi-Monitor**: Concurrent handling of multiple monitor streams
 //! - **Format Negotiation**: Automatic format selection with fallbacks
-//! - **YUV Conversion**: Built-in NV12, I420, YUY2 to BGRA conversion
+//! - **YUV Conversion**: Built-in NV12, I420, YUY2 to BGRA conversion, SIMD-accelerated where the CPU allows
 //! - **Cursor Extraction**: Separate cursor tracking for remote desktop
 //! - **Damage Tracking**: Region-based change detection for efficient encoding
 //! - **Adaptive Bitrate**: Network-aware bitrate control for streaming
@@ -38,7 +38,7 @@
 //! let mut manager = PipeWireManager::with_default()?;
 //!
 //! // Connect using portal-provided file descriptor (from lamco-portal)
-//! let fd = /* session.pipewire_fd() */;
+//! let fd = /* session.take_pipewire_fd() */;
 //! manager.connect(fd).await?;
 //!
 //! // Create stream for a monitor
@@ -111,6 +111,12 @@
 //! # }
 //! ```
 //!
+//! Failures after connecting are handled by the manager's recovery
+//! supervisor: streams that fail are re-created on the same node with
+//! backoff (`PipeWireConfig::auto_reconnect`). Watch
+//! [`PipeWireManager::subscribe_events`] for `PipeWireEvent::RecoveryRequired`
+//! to learn when a stream was given up on or a new portal session is needed.
+//!
 //! # Architecture
 //!
 //! PipeWire's Rust bindings use `Rc<>` and `NonNull<>` internally, making them
@@ -146,6 +152,11 @@
 //!                              Your application receives frames
 //! ```
 //!
+//! There is one engine: [`PipeWireConnection`] (stream-ID based access),
+//! [`PipeWireStream`] control handles (pause, resume, restart) and
+//! [`MultiStreamCoordinator`] (monitor hot-plug, frame dispatch, stream
+//! health) are all layered on `PipeWireManager` and its PipeWire thread.
+//!
 //! # Platform Notes
 //!
 //! - **GNOME**: Works out of the box with `xdg-desktop-portal-gnome`
@@ -196,17 +207,22 @@
 // =============================================================================
 
 pub mod buffer;
+pub mod colorimetry;
 pub mod config;
 pub mod connection;
 pub mod coordinator;
+pub mod dmabuf;
 pub mod error;
 pub mod ffi;
 pub mod format;
 pub mod frame;
+pub mod loop_signal;
 pub mod manager;
 pub mod pw_thread;
+mod recovery;
+pub mod router;
+pub mod simd;
 pub mod stream;
-pub mod thread_comm;
 
 // =============================================================================
 // FEATURE MODULES
@@ -249,15 +265,23 @@ pub use config::{
 };
 
 // Errors
-pub use error::{classify_error, ErrorContext, ErrorType, PipeWireError, RecoveryAction, Result, RetryConfig};
+pub use error::{
+    classify_error, recovery_action, ErrorContext, ErrorType, PipeWireError, RecoveryAction, Result, RetryConfig,
+};
 
 // Stream types
 pub use coordinator::{MonitorEvent, MonitorInfo, MultiStreamConfig, SourceType, StreamInfo};
-pub use stream::{NegotiatedFormat, PwStreamState, StreamConfig, StreamMetrics};
+pub use stream::{NegotiatedFormat, PipeWireStream, PwStreamState, StreamConfig, StreamMetrics};
 
 // Frame types
-pub use format::{convert_format, PixelFormat};
-pub use frame::{FrameCallback, FrameFlags, FrameStats, VideoFrame};
+pub use colorimetry::{ColorMatrix, ColorRange, Colorimetry};
+pub use format::{convert_format, convert_format_planes, FramePlane, PixelFormat};
+pub use frame::{
+    CursorBitmap, CursorUpdate, FrameCallback, FrameData, FrameDataMode, FrameFlags, FrameStats, VideoFrame,
+};
+pub use loop_signal::{LoopSignal, LoopStats};
+pub use router::FrameRouteStats;
+pub use simd::SimdLevel;
 
 // =============================================================================
 // RE-EXPORTS - ADVANCED API
@@ -267,7 +291,12 @@ pub use frame::{FrameCallback, FrameFlags, FrameStats, VideoFrame};
 pub use connection::{ConnectionState, PipeWireConnection, PipeWireEvent};
 
 // Buffer management
-pub use buffer::{BufferManager, BufferType, ManagedBuffer, SharedBufferManager};
+pub use buffer::{
+    BufferManager, BufferType, FramePool, FramePoolStats, ManagedBuffer, PooledBuffer, SharedBufferManager,
+};
+
+// DMA-BUF CPU access
+pub use dmabuf::{DmaBufCache, DmaBufMapping, DmaBufSync};
 
 // Thread management
 pub use pw_thread::{PipeWireThreadCommand, PipeWireThreadManager};
@@ -278,7 +307,7 @@ pub use coordinator::{CoordinatorStats, DispatcherConfig, FrameDispatcher, Multi
 // FFI utilities
 pub use ffi::{
     calculate_buffer_size, calculate_stride, drm_fourcc, get_bytes_per_pixel, spa_video_format_to_drm_fourcc,
-    DamageRegion as FfiDamageRegion, SpaDataType,
+    BufferMetadata, DamageRegion as FfiDamageRegion, SpaDataType,
 };
 
 // =============================================================================
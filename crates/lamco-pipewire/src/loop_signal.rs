@@ -0,0 +1,251 @@
+//! Main Loop Wakeup Signal
+//!
+//! Wakes a PipeWire main loop from other threads so the loop can block in
+//! `iterate()` instead of polling. The signal is an eventfd that the
+//! PipeWire thread registers as an IO source on its loop; writing to it
+//! makes `iterate()` return so queued commands are handled immediately.
+//!
+//! The signal also measures how long the loop takes to react to a wakeup,
+//! which is the latency the loop adds to command handling.
+
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{PipeWireError, Result};
+
+/// Main loop statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoopStats {
+    /// Loop iterations (each `iterate()` call that returned)
+    pub iterations: u64,
+
+    /// Wakeups delivered through the signal
+    pub wakeups: u64,
+
+    /// Latency of the most recent wakeup
+    pub last_wake_latency: Duration,
+
+    /// Largest wakeup latency observed
+    pub max_wake_latency: Duration,
+
+    /// Sum of all wakeup latencies
+    pub total_wake_latency: Duration,
+}
+
+impl LoopStats {
+    /// Average wakeup latency
+    pub fn avg_wake_latency(&self) -> Duration {
+        if self.wakeups == 0 {
+            Duration::ZERO
+        } else {
+            // Divide in nanoseconds: the count need not fit in the u32 Duration divides by
+            let avg = self.total_wake_latency.as_nanos() / u128::from(self.wakeups);
+            Duration::from_nanos(u64::try_from(avg).unwrap_or(u64::MAX))
+        }
+    }
+}
+
+/// Shared state behind a [`LoopSignal`]
+struct SignalState {
+    /// Time of the first unacknowledged wakeup
+    pending_since: Option<Instant>,
+
+    /// Statistics
+    stats: LoopStats,
+}
+
+/// Cross-thread wakeup for a PipeWire main loop
+///
+/// Cloning yields another handle to the same signal.
+#[derive(Clone)]
+pub struct LoopSignal {
+    /// Non-blocking eventfd
+    fd: Arc<OwnedFd>,
+
+    /// Pending wakeup and statistics
+    state: Arc<Mutex<SignalState>>,
+}
+
+impl LoopSignal {
+    /// Create a new signal
+    ///
+    /// # Errors
+    ///
+    /// Returns `InitializationFailed` if the eventfd cannot be created
+    pub fn new() -> Result<Self> {
+        // SAFETY: eventfd has no memory safety preconditions; the returned FD
+        // is checked before use and ownership is transferred to OwnedFd.
+        let raw = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if raw < 0 {
+            return Err(PipeWireError::InitializationFailed(format!(
+                "eventfd failed: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        Ok(Self {
+            // SAFETY: raw is a freshly created, valid FD owned by nobody else
+            fd: Arc::new(unsafe { OwnedFd::from_raw_fd(raw) }),
+            state: Arc::new(Mutex::new(SignalState {
+                pending_since: None,
+                stats: LoopStats::default(),
+            })),
+        })
+    }
+
+    /// Wake the loop
+    ///
+    /// Multiple wakeups before the loop reacts coalesce into one; latency is
+    /// measured from the first of them.
+    pub fn wake(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.pending_since.get_or_insert_with(Instant::now);
+        }
+
+        let value: u64 = 1;
+        // SAFETY: writing 8 bytes from a valid u64 to a valid eventfd. A full
+        // counter (EAGAIN) means a wakeup is already pending, which is fine.
+        unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                std::ptr::addr_of!(value).cast::<libc::c_void>(),
+                std::mem::size_of::<u64>(),
+            );
+        }
+    }
+
+    /// Consume a pending wakeup (called on the loop thread)
+    ///
+    /// # Returns
+    ///
+    /// Time between the wakeup request and this call, or `None` if no
+    /// wakeup was pending
+    pub fn acknowledge(&self) -> Option<Duration> {
+        let mut value: u64 = 0;
+        // SAFETY: reading 8 bytes into a valid u64 from a valid eventfd. The
+        // FD is non-blocking, so an empty counter returns EAGAIN immediately.
+        unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                std::ptr::addr_of_mut!(value).cast::<libc::c_void>(),
+                std::mem::size_of::<u64>(),
+            );
+        }
+
+        let mut inner = self.state.lock().unwrap();
+        let latency = inner.pending_since.take()?.elapsed();
+
+        let totals = &mut inner.stats;
+        totals.wakeups += 1;
+        totals.last_wake_latency = latency;
+        totals.max_wake_latency = totals.max_wake_latency.max(latency);
+        totals.total_wake_latency += latency;
+
+        Some(latency)
+    }
+
+    /// Record a completed loop iteration
+    pub fn record_iteration(&self) {
+        self.state.lock().unwrap().stats.iterations += 1;
+    }
+
+    /// Get loop statistics
+    pub fn stats(&self) -> LoopStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// The eventfd to register as a loop IO source
+    pub(crate) fn fd(&self) -> Arc<OwnedFd> {
+        Arc::clone(&self.fd)
+    }
+}
+
+impl std::fmt::Debug for LoopSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoopSignal")
+            .field("fd", &self.fd.as_raw_fd())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_readable(signal: &LoopSignal) -> bool {
+        let mut pfd = libc::pollfd {
+            fd: signal.fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pfd is a valid pollfd for the duration of the call
+        unsafe { libc::poll(&mut pfd, 1, 0) == 1 }
+    }
+
+    #[test]
+    fn test_wake_makes_fd_readable() {
+        let signal = LoopSignal::new().unwrap();
+        assert!(!is_readable(&signal));
+
+        signal.wake();
+        assert!(is_readable(&signal));
+
+        assert!(signal.acknowledge().is_some());
+        assert!(!is_readable(&signal));
+    }
+
+    #[test]
+    fn test_wakeups_coalesce() {
+        let signal = LoopSignal::new().unwrap();
+        let remote = signal.clone();
+
+        remote.wake();
+        remote.wake();
+        remote.wake();
+
+        assert!(signal.acknowledge().is_some());
+        assert!(signal.acknowledge().is_none());
+        assert_eq!(signal.stats().wakeups, 1);
+    }
+
+    #[test]
+    fn test_latency_is_measured() {
+        let signal = LoopSignal::new().unwrap();
+
+        signal.wake();
+        std::thread::sleep(Duration::from_millis(2));
+        let latency = signal.acknowledge().unwrap();
+        assert!(latency >= Duration::from_millis(2));
+
+        signal.record_iteration();
+        let stats = signal.stats();
+        assert_eq!(stats.iterations, 1);
+        assert_eq!(stats.last_wake_latency, latency);
+        assert_eq!(stats.max_wake_latency, latency);
+        assert_eq!(stats.avg_wake_latency(), latency);
+    }
+
+    #[test]
+    fn test_avg_latency_with_many_wakeups() {
+        let stats = LoopStats {
+            wakeups: 1 << 32,
+            total_wake_latency: Duration::from_secs(1 << 32),
+            ..LoopStats::default()
+        };
+        assert_eq!(stats.avg_wake_latency(), Duration::from_secs(1));
+        assert_eq!(LoopStats::default().avg_wake_latency(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_wake_from_other_thread() {
+        let signal = LoopSignal::new().unwrap();
+        let remote = signal.clone();
+
+        std::thread::spawn(move || remote.wake()).join().unwrap();
+        assert!(is_readable(&signal));
+        assert!(signal.acknowledge().is_some());
+    }
+}
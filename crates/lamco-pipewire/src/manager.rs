@@ -9,6 +9,7 @@
 //! - Thread management (PipeWire requires dedicated thread for non-Send types)
 //! - Stream lifecycle (creation, destruction, state changes)
 //! - Frame delivery via channels
+//! - Stream recovery (re-creating failed streams, reporting a lost connection)
 //! - Optional features (cursor extraction, damage tracking, adaptive bitrate)
 //!
 //! # Examples
@@ -21,7 +22,7 @@
 //! let mut manager = PipeWireManager::with_default()?;
 //!
 //! // Connect using portal-provided FD
-//! let fd = /* from lamco-portal */;
+//! let fd = session.take_pipewire_fd().unwrap(); // from lamco-portal
 //! manager.connect(fd).await?;
 //!
 //! // Create stream for a monitor
@@ -47,21 +48,39 @@
 //! ```
 
 use std::collections::HashMap;
-use std::os::fd::RawFd;
-use std::sync::mpsc as std_mpsc;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 use crate::config::PipeWireConfig;
+use crate::connection::PipeWireEvent;
 use crate::coordinator::{SourceType, StreamInfo};
 use crate::error::{PipeWireError, Result};
+use crate::format::PixelFormat;
 use crate::frame::VideoFrame;
+use crate::loop_signal::LoopStats;
 use crate::pw_thread::{PipeWireThreadCommand, PipeWireThreadManager};
-use crate::stream::StreamConfig;
+use crate::recovery::{RecoveryTracker, StreamConfigs, Supervisor};
+use crate::router::{EventRouter, FrameRouteStats, FrameRouter};
+use crate::stream::{NegotiatedFormat, PipeWireStream, PwStreamState, StreamConfig, StreamControl};
 
 #[cfg(feature = "cursor")]
 use crate::cursor::CursorExtractor;
+#[cfg(feature = "cursor")]
+use crate::frame::CursorUpdate;
+#[cfg(feature = "cursor")]
+use crate::router::CursorRouter;
+
+/// Events buffered per lifecycle event subscriber
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// Capacity of per-stream cursor update channels
+#[cfg(feature = "cursor")]
+const CURSOR_CHANNEL_CAPACITY: usize = 64;
 
 #[cfg(feature = "damage")]
 use crate::damage::DamageTracker;
@@ -120,19 +139,38 @@ pub struct PipeWireManager {
     /// Active streams
     streams: Arc<Mutex<HashMap<u32, StreamHandle>>>,
 
-    /// Frame receivers per stream
-    frame_receivers: Arc<Mutex<HashMap<u32, mpsc::Sender<VideoFrame>>>>,
+    /// Routes frames from the PipeWire thread to per-stream receivers
+    frame_router: FrameRouter,
+
+    /// Broadcasts stream lifecycle events to subscribers
+    event_router: EventRouter,
+
+    /// Node and configuration of each stream, for recovery
+    stream_configs: StreamConfigs,
+
+    /// Recovery supervisor task
+    supervisor: Option<JoinHandle<()>>,
+
+    /// Statistics counters
+    counters: Arc<StatsCounters>,
+
+    /// When the current connection was established
+    connected_at: Option<Instant>,
 
     /// Next stream ID
     next_stream_id: Arc<Mutex<u32>>,
 
-    /// Portal file descriptor
+    /// Number of the portal file descriptor, owned by the PipeWire thread
     portal_fd: Option<RawFd>,
 
     /// Cursor extractor (if enabled)
     #[cfg(feature = "cursor")]
     cursor_extractor: Option<Arc<Mutex<CursorExtractor>>>,
 
+    /// Routes cursor updates to per-stream receivers
+    #[cfg(feature = "cursor")]
+    cursor_router: CursorRouter,
+
     /// Damage tracker (if enabled)
     #[cfg(feature = "damage")]
     damage_tracker: Option<Arc<Mutex<DamageTracker>>>,
@@ -181,11 +219,18 @@ impl PipeWireManager {
             state: Arc::new(RwLock::new(ManagerState::Disconnected)),
             thread_manager: None,
             streams: Arc::new(Mutex::new(HashMap::new())),
-            frame_receivers: Arc::new(Mutex::new(HashMap::new())),
+            frame_router: FrameRouter::new("pipewire-frames"),
+            event_router: EventRouter::new("pipewire-events", EVENT_CHANNEL_CAPACITY),
+            stream_configs: Arc::new(Mutex::new(HashMap::new())),
+            supervisor: None,
+            counters: Arc::new(StatsCounters::default()),
+            connected_at: None,
             next_stream_id: Arc::new(Mutex::new(0)),
             portal_fd: None,
             #[cfg(feature = "cursor")]
             cursor_extractor: None,
+            #[cfg(feature = "cursor")]
+            cursor_router: CursorRouter::new("pipewire-cursor"),
             #[cfg(feature = "damage")]
             damage_tracker: None,
             #[cfg(feature = "adaptive")]
@@ -196,7 +241,9 @@ impl PipeWireManager {
     /// Connect to PipeWire using portal-provided file descriptor
     ///
     /// The file descriptor should be obtained from XDG Desktop Portal
-    /// (e.g., via `lamco-portal`).
+    /// (e.g., via `lamco-portal`'s `PortalSessionHandle::take_pipewire_fd`).
+    /// The manager owns it from then on and closes it when the connection
+    /// ends, including when connecting fails.
     ///
     /// # Arguments
     ///
@@ -208,31 +255,64 @@ impl PipeWireManager {
     /// - Already connected
     /// - PipeWire initialization fails
     /// - Connection timeout exceeded
-    pub async fn connect(&mut self, fd: RawFd) -> Result<()> {
+    pub async fn connect(&mut self, fd: OwnedFd) -> Result<()> {
         let current_state = *self.state.read().await;
         if current_state == ManagerState::Connected {
             return Err(PipeWireError::InvalidState("Already connected".to_string()));
         }
 
         *self.state.write().await = ManagerState::Connecting;
-        info!("Connecting to PipeWire with FD {}", fd);
+        info!("Connecting to PipeWire with FD {}", fd.as_raw_fd());
 
-        self.portal_fd = Some(fd);
+        self.portal_fd = Some(fd.as_raw_fd());
 
-        // Initialize PipeWire thread manager
-        let thread_manager = PipeWireThreadManager::new(fd)?;
-        self.thread_manager = Some(thread_manager);
+        // Initialize PipeWire thread manager; commands that take longer than
+        // the connection timeout fail instead of stalling the caller
+        let mut thread_manager = PipeWireThreadManager::new(fd)?
+            .with_command_timeout(Duration::from_millis(self.config.connection_timeout_ms));
 
         // Initialize optional features
         #[cfg(feature = "cursor")]
         if self.config.enable_cursor {
-            self.cursor_extractor = Some(Arc::new(Mutex::new(CursorExtractor::new())));
+            let extractor = Arc::new(Mutex::new(CursorExtractor::new()));
+
+            // Feed cursor metadata from every stream into the extractor
+            let stream_extractor = Arc::clone(&extractor);
+            self.cursor_router.add_observer(Box::new(move |update: &CursorUpdate| {
+                let mut extractor = stream_extractor.blocking_lock();
+                let size = update
+                    .bitmap
+                    .as_ref()
+                    .map_or_else(|| extractor.cursor_state().size, |b| (b.width, b.height));
+                extractor.update_from_raw(
+                    update.position,
+                    update.hotspot,
+                    size,
+                    update.bitmap.as_ref().map(|b| b.data.clone()),
+                    update.visible,
+                );
+            }));
+
+            let cursor_rx = thread_manager
+                .take_cursor_receiver()
+                .ok_or_else(|| PipeWireError::InitializationFailed("Cursor channel unavailable".to_string()))?;
+            self.cursor_router.start(cursor_rx)?;
+
+            self.cursor_extractor = Some(extractor);
             debug!("Cursor extractor enabled");
         }
 
         #[cfg(feature = "damage")]
         if self.config.enable_damage_tracking {
-            self.damage_tracker = Some(Arc::new(Mutex::new(DamageTracker::new())));
+            let tracker = Arc::new(Mutex::new(DamageTracker::new()));
+
+            // Feed compositor damage from every captured frame
+            let frame_tracker = Arc::clone(&tracker);
+            self.frame_router.add_observer(Box::new(move |frame| {
+                frame_tracker.blocking_lock().add_frame_damage(frame);
+            }));
+
+            self.damage_tracker = Some(tracker);
             debug!("Damage tracker enabled");
         }
 
@@ -242,6 +322,36 @@ impl PipeWireManager {
             debug!("Bitrate controller enabled");
         }
 
+        // Count every captured frame
+        let frame_counters = Arc::clone(&self.counters);
+        self.frame_router.add_observer(Box::new(move |frame| {
+            frame_counters.total_frames.fetch_add(1, Ordering::Relaxed);
+            frame_counters
+                .total_bytes
+                .fetch_add(frame.data.len() as u64, Ordering::Relaxed);
+        }));
+
+        // Forward frames from the thread's shared channel to per-stream receivers
+        let frame_rx = thread_manager
+            .take_frame_receiver()
+            .ok_or_else(|| PipeWireError::InitializationFailed("Frame channel unavailable".to_string()))?;
+        self.frame_router.start(frame_rx)?;
+        let event_rx = thread_manager
+            .take_event_receiver()
+            .ok_or_else(|| PipeWireError::InitializationFailed("Event channel unavailable".to_string()))?;
+        let supervisor = Supervisor::new(
+            RecoveryTracker::from_config(&self.config),
+            thread_manager.command_sender(),
+            Arc::clone(&self.stream_configs),
+            Arc::clone(&self.state),
+            self.event_router.publisher(),
+            Arc::clone(&self.counters),
+        );
+        self.supervisor = Some(tokio::spawn(supervisor.run(self.event_router.subscribe())));
+        self.event_router.start(event_rx)?;
+        self.thread_manager = Some(thread_manager);
+        self.connected_at = Some(Instant::now());
+
         *self.state.write().await = ManagerState::Connected;
         info!("PipeWire connected successfully");
 
@@ -250,6 +360,9 @@ impl PipeWireManager {
 
     /// Create a stream for capturing from a source
     ///
+    /// The stream is configured from the manager's defaults (see
+    /// [`stream_config`](Self::stream_config)).
+    ///
     /// # Arguments
     ///
     /// * `stream_info` - Information about the source (from portal)
@@ -265,6 +378,55 @@ impl PipeWireManager {
     /// - Maximum streams exceeded
     /// - Stream creation fails
     pub async fn create_stream(&mut self, stream_info: &StreamInfo) -> Result<StreamHandle> {
+        self.check_can_create().await?;
+        let stream_id = self.allocate_stream_id().await;
+        let stream_name = format!("{}-{}", self.config.stream_name_prefix, stream_id);
+        let stream_config = self.stream_config(stream_name, stream_info.size);
+        self.start_stream(stream_id, stream_info, stream_config).await
+    }
+
+    /// Create a stream with an explicit configuration
+    ///
+    /// Like [`create_stream`](Self::create_stream), but captures with
+    /// `stream_config` (e.g. a different framerate) instead of the manager's
+    /// defaults. Use [`stream_config`](Self::stream_config) as a starting point.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`create_stream`](Self::create_stream)
+    pub async fn create_stream_with_config(
+        &mut self,
+        stream_info: &StreamInfo,
+        stream_config: StreamConfig,
+    ) -> Result<StreamHandle> {
+        self.check_can_create().await?;
+        let stream_id = self.allocate_stream_id().await;
+        self.start_stream(stream_id, stream_info, stream_config).await
+    }
+
+    /// Build a stream configuration from the manager's defaults
+    ///
+    /// Applies the configured buffer count, DMA-BUF use, preferred format,
+    /// frame data mode and cursor metadata to a stream of the given size.
+    pub fn stream_config(&self, name: impl Into<String>, size: (u32, u32)) -> StreamConfig {
+        let mut stream_config = StreamConfig::new(name)
+            .with_resolution(size.0, size.1)
+            .with_dmabuf(self.config.use_dmabuf)
+            .with_buffer_count(self.config.buffer_count)
+            .with_frame_data_mode(self.config.frame_data_mode)
+            .with_frame_pool_size(self.config.frame_pool_size);
+        if let Some(format) = self.config.preferred_format {
+            stream_config = stream_config.with_format(format);
+        }
+        #[cfg(feature = "cursor")]
+        if self.config.enable_cursor {
+            stream_config = stream_config.with_cursor(true);
+        }
+        stream_config
+    }
+
+    /// Check that another stream can be created
+    async fn check_can_create(&self) -> Result<()> {
         if *self.state.read().await != ManagerState::Connected {
             return Err(PipeWireError::InvalidState("Not connected".to_string()));
         }
@@ -274,48 +436,63 @@ impl PipeWireManager {
         if stream_count >= self.config.max_streams {
             return Err(PipeWireError::TooManyStreams(self.config.max_streams));
         }
+        Ok(())
+    }
 
-        // Generate stream ID
-        let stream_id = {
-            let mut id = self.next_stream_id.lock().await;
-            let sid = *id;
-            *id += 1;
-            sid
-        };
+    /// Generate a stream ID
+    async fn allocate_stream_id(&self) -> u32 {
+        let mut id = self.next_stream_id.lock().await;
+        let sid = *id;
+        *id += 1;
+        sid
+    }
 
+    /// Create a stream on the PipeWire thread and start routing its frames
+    async fn start_stream(
+        &mut self,
+        stream_id: u32,
+        stream_info: &StreamInfo,
+        stream_config: StreamConfig,
+    ) -> Result<StreamHandle> {
         info!(
             "Creating stream {} for node {} ({}x{} at {:?})",
             stream_id, stream_info.node_id, stream_info.size.0, stream_info.size.1, stream_info.position
         );
 
-        // Create stream configuration
-        let stream_name = format!("{}-{}", self.config.stream_name_prefix, stream_id);
-        let stream_config = StreamConfig::new(stream_name)
-            .with_resolution(stream_info.size.0, stream_info.size.1)
-            .with_dmabuf(self.config.use_dmabuf)
-            .with_buffer_count(self.config.buffer_count);
+        #[cfg(feature = "cursor")]
+        if stream_config.enable_cursor {
+            self.cursor_router.add_route(stream_id);
+        }
 
-        // Create frame channel
-        let (tx, _rx) = mpsc::channel(self.config.frame_buffer_size);
-        self.frame_receivers.lock().await.insert(stream_id, tx);
+        // Register the route before the stream exists so no frames are missed
+        self.frame_router.add_route(stream_id);
 
         // Send command to PipeWire thread
         if let Some(ref thread_manager) = self.thread_manager {
-            let (response_tx, response_rx) = std_mpsc::sync_channel(1);
-            thread_manager.send_command(PipeWireThreadCommand::CreateStream {
-                stream_id,
-                node_id: stream_info.node_id,
-                config: stream_config,
-                response_tx,
-            })?;
-
-            // Wait for response from PipeWire thread
-            response_rx
-                .recv()
-                .map_err(|_| {
-                    PipeWireError::ThreadCommunicationFailed("CreateStream response channel closed".to_string())
-                })?
-                .map_err(|e| PipeWireError::StreamCreationFailed(format!("Stream creation failed: {}", e)))?;
+            let commands = thread_manager.command_sender();
+            let result = commands
+                .request(|response_tx| PipeWireThreadCommand::CreateStream {
+                    stream_id,
+                    node_id: stream_info.node_id,
+                    config: stream_config.clone(),
+                    response_tx,
+                })
+                .await
+                .and_then(|reply| {
+                    reply.map_err(|e| PipeWireError::StreamCreationFailed(format!("Stream creation failed: {}", e)))
+                });
+
+            if let Err(e) = result {
+                if matches!(e, PipeWireError::Timeout) {
+                    // The thread may still create the stream; don't leave it orphaned
+                    let (response_tx, _) = oneshot::channel();
+                    let _ = commands.send(PipeWireThreadCommand::DestroyStream { stream_id, response_tx });
+                }
+                self.frame_router.remove_route(stream_id);
+                #[cfg(feature = "cursor")]
+                self.cursor_router.remove_route(stream_id);
+                return Err(e);
+            }
         }
 
         // Create handle
@@ -328,6 +505,11 @@ impl PipeWireManager {
         };
 
         self.streams.lock().await.insert(stream_id, handle.clone());
+        self.stream_configs
+            .lock()
+            .await
+            .insert(stream_id, (stream_info.node_id, stream_config));
+        self.counters.streams_created.fetch_add(1, Ordering::Relaxed);
 
         info!("Stream {} created successfully", stream_id);
         Ok(handle)
@@ -336,7 +518,9 @@ impl PipeWireManager {
     /// Get frame receiver for a stream
     ///
     /// Returns a channel receiver for frames from the specified stream.
-    /// Each call creates a new receiver (use for single consumer).
+    /// Each call replaces the previous receiver (use for single consumer).
+    /// Frames that arrive while the receiver is full are dropped and counted
+    /// in [`frame_stats`](Self::frame_stats).
     ///
     /// # Arguments
     ///
@@ -346,12 +530,68 @@ impl PipeWireManager {
     ///
     /// Channel receiver for frames, or None if stream not found
     pub async fn frame_receiver(&self, stream_id: u32) -> Option<mpsc::Receiver<VideoFrame>> {
-        let (tx, rx) = mpsc::channel(self.config.frame_buffer_size);
+        self.frame_router.subscribe(stream_id, self.config.frame_buffer_size)
+    }
+
+    /// Get frame delivery statistics for a stream
+    ///
+    /// # Returns
+    ///
+    /// Forwarded and dropped frame counts, or None if stream not found
+    pub fn frame_stats(&self, stream_id: u32) -> Option<FrameRouteStats> {
+        self.frame_router.stats(stream_id)
+    }
 
-        // Replace the sender (allows changing consumer)
-        self.frame_receivers.lock().await.insert(stream_id, tx);
+    /// Get cursor update receiver for a stream
+    ///
+    /// Receives the stream's cursor metadata (position, hotspot, visibility
+    /// and bitmap changes) for client-side cursor rendering. Requires
+    /// `PipeWireConfig::enable_cursor` and a portal session in
+    /// `CursorMode::Metadata`. Each call replaces the previous receiver.
+    ///
+    /// # Returns
+    ///
+    /// Channel receiver for cursor updates, or None if the stream doesn't
+    /// exist or cursor extraction is disabled
+    #[cfg(feature = "cursor")]
+    pub fn cursor_receiver(&self, stream_id: u32) -> Option<mpsc::Receiver<CursorUpdate>> {
+        self.cursor_router.subscribe(stream_id, CURSOR_CHANNEL_CAPACITY)
+    }
+
+    /// Subscribe to stream lifecycle events
+    ///
+    /// Every subscriber receives every event published after it subscribed:
+    ///
+    /// - `StreamStateChanged` for each transition (connecting, paused,
+    ///   streaming, error)
+    /// - `StreamError` with PipeWire's message when a stream fails
+    /// - `StreamFormatChanged` when a stream negotiates a (new) format
+    /// - `StreamRemoved` when the compositor removes a stream (window
+    ///   closed, monitor unplugged); [`remove_stream`](Self::remove_stream)
+    ///   does not produce events
+    /// - `CoreError` / `Disconnected` for PipeWire connection errors
+    ///
+    /// Subscribing is possible before [`connect`](Self::connect). A
+    /// subscriber that falls behind skips the oldest events
+    /// (`RecvError::Lagged`).
+    pub fn subscribe_events(&self) -> broadcast::Receiver<PipeWireEvent> {
+        self.event_router.subscribe()
+    }
 
-        Some(rx)
+    /// Get the current state of a stream
+    ///
+    /// # Returns
+    ///
+    /// `None` if the stream doesn't exist, the manager is not connected or
+    /// the PipeWire thread doesn't answer in time
+    pub async fn stream_state(&self, stream_id: u32) -> Option<PwStreamState> {
+        let thread_manager = self.thread_manager.as_ref()?;
+        thread_manager
+            .command_sender()
+            .request(|response_tx| PipeWireThreadCommand::GetStreamState { stream_id, response_tx })
+            .await
+            .ok()?
+            .map(PwStreamState::from)
     }
 
     /// Remove a stream
@@ -367,24 +607,175 @@ impl PipeWireManager {
         if self.streams.lock().await.remove(&stream_id).is_none() {
             return Err(PipeWireError::StreamNotFound(stream_id));
         }
-
-        self.frame_receivers.lock().await.remove(&stream_id);
+        // Waits for an ongoing re-creation of this stream, then stops recovery
+        self.stream_configs.lock().await.remove(&stream_id);
+        self.counters.streams_destroyed.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(stats) = self.frame_router.remove_route(stream_id) {
+            debug!(
+                "Stream {} delivered {} frames, dropped {}",
+                stream_id, stats.frames_forwarded, stats.frames_dropped
+            );
+        }
+        #[cfg(feature = "cursor")]
+        self.cursor_router.remove_route(stream_id);
 
         // Send command to PipeWire thread
         if let Some(ref thread_manager) = self.thread_manager {
-            let (response_tx, response_rx) = std_mpsc::sync_channel(1);
-            thread_manager.send_command(PipeWireThreadCommand::DestroyStream { stream_id, response_tx })?;
-
-            // Wait for response (ignore errors during shutdown cleanup)
-            if let Ok(result) = response_rx.recv() {
-                result?;
-            }
+            thread_manager
+                .command_sender()
+                .request(|response_tx| PipeWireThreadCommand::DestroyStream { stream_id, response_tx })
+                .await??;
         }
 
         info!("Stream {} removed", stream_id);
         Ok(())
     }
 
+    /// Get a control handle for a stream
+    ///
+    /// The handle pauses, resumes and restarts the stream on the PipeWire
+    /// thread and reports its current state and negotiated format.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the stream doesn't exist or the manager is not connected
+    pub async fn stream_control(&self, stream_id: u32) -> Option<PipeWireStream> {
+        let thread_manager = self.thread_manager.as_ref()?;
+        let (node_id, config) = self.stream_configs.lock().await.get(&stream_id).cloned()?;
+        let control = StreamControl {
+            commands: thread_manager.command_sender(),
+            stream_configs: Arc::clone(&self.stream_configs),
+            states: thread_manager.stream_states(),
+            formats: thread_manager.negotiated_formats(),
+        };
+        Some(PipeWireStream::new(stream_id, node_id, config, control))
+    }
+
+    /// Pause or resume a stream
+    ///
+    /// A paused stream keeps its format and frame receivers, but the
+    /// compositor stops producing frames for it until it is resumed, e.g.
+    /// while no client is watching it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` if not connected, or `StreamNotFound` if the
+    /// stream doesn't exist
+    pub async fn set_stream_active(&self, stream_id: u32, active: bool) -> Result<()> {
+        let thread_manager = self.connected_thread()?;
+        if !self.stream_configs.lock().await.contains_key(&stream_id) {
+            return Err(PipeWireError::StreamNotFound(stream_id));
+        }
+
+        thread_manager
+            .command_sender()
+            .request(|response_tx| PipeWireThreadCommand::SetActive {
+                stream_id,
+                active,
+                response_tx,
+            })
+            .await?
+    }
+
+    /// Limit the framerate of a live stream
+    ///
+    /// Renegotiates the stream's format so the compositor produces at most
+    /// `fps` frames per second, e.g. to throttle capture for a minimised
+    /// client. The limit also applies when the stream is recovered.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidParameter` for 0 fps, `InvalidState` if not connected,
+    /// `StreamNotFound` if the stream doesn't exist, or
+    /// `FormatNegotiationFailed` if the new parameters are rejected
+    pub async fn set_stream_max_framerate(&self, stream_id: u32, fps: u32) -> Result<()> {
+        if fps == 0 {
+            return Err(PipeWireError::InvalidParameter(
+                "Maximum framerate must be at least 1".to_string(),
+            ));
+        }
+        self.update_stream_config(stream_id, |config| {
+            config.max_framerate = Some(fps);
+            config.framerate = config.framerate.min(fps);
+        })
+        .await
+    }
+
+    /// Change the preferred size of a live stream
+    ///
+    /// Renegotiates the stream's format; frames carry the size the producer
+    /// actually settles on (see [`negotiated_format`](Self::negotiated_format)).
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidParameter` for an empty size, otherwise the same as
+    /// [`set_stream_max_framerate`](Self::set_stream_max_framerate)
+    pub async fn set_stream_size(&self, stream_id: u32, width: u32, height: u32) -> Result<()> {
+        if width == 0 || height == 0 {
+            return Err(PipeWireError::InvalidParameter(format!(
+                "Invalid stream size {}x{}",
+                width, height
+            )));
+        }
+        self.update_stream_config(stream_id, |config| {
+            config.width = width;
+            config.height = height;
+        })
+        .await?;
+
+        if let Some(handle) = self.streams.lock().await.get_mut(&stream_id) {
+            handle.size = (width, height);
+        }
+        Ok(())
+    }
+
+    /// Change the preferred pixel format of a live stream
+    ///
+    /// Renegotiates the stream's format, offering `format` first (or only
+    /// the default formats for `None`).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`set_stream_max_framerate`](Self::set_stream_max_framerate)
+    pub async fn set_stream_format(&self, stream_id: u32, format: Option<PixelFormat>) -> Result<()> {
+        self.update_stream_config(stream_id, |config| config.preferred_format = format)
+            .await
+    }
+
+    /// Apply `update` to a stream's configuration and renegotiate its format
+    async fn update_stream_config(&self, stream_id: u32, update: impl FnOnce(&mut StreamConfig)) -> Result<()> {
+        let thread_manager = self.connected_thread()?;
+
+        // Holding the lock keeps recovery from re-creating the stream with
+        // the old configuration while it is being renegotiated
+        let mut stream_configs = self.stream_configs.lock().await;
+        let (_, config) = stream_configs
+            .get_mut(&stream_id)
+            .ok_or(PipeWireError::StreamNotFound(stream_id))?;
+        let mut updated = config.clone();
+        update(&mut updated);
+
+        thread_manager
+            .command_sender()
+            .request(|response_tx| PipeWireThreadCommand::UpdateFormat {
+                stream_id,
+                config: updated.clone(),
+                response_tx,
+            })
+            .await??;
+
+        *config = updated;
+        Ok(())
+    }
+
+    /// Get the thread manager, or `InvalidState` if not connected
+    fn connected_thread(&self) -> Result<&PipeWireThreadManager> {
+        self.thread_manager
+            .as_ref()
+            .ok_or_else(|| PipeWireError::InvalidState("Not connected".to_string()))
+    }
+
     /// Get all active stream handles
     pub async fn streams(&self) -> Vec<StreamHandle> {
         self.streams.lock().await.values().cloned().collect()
@@ -395,7 +786,52 @@ impl PipeWireManager {
         self.streams.lock().await.get(&stream_id).cloned()
     }
 
+    /// Get the format negotiated for a stream
+    ///
+    /// Reflects what the compositor actually produces, which may differ from
+    /// the configured preferred format. Frames from the stream carry the same
+    /// format, size and stride.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the stream doesn't exist or negotiation hasn't completed yet
+    pub fn negotiated_format(&self, stream_id: u32) -> Option<NegotiatedFormat> {
+        self.thread_manager.as_ref()?.negotiated_format(stream_id)
+    }
+
+    /// Get PipeWire main loop statistics
+    ///
+    /// `LoopStats::avg_wake_latency` is the delay the loop adds between a
+    /// command being sent and the PipeWire thread handling it.
+    ///
+    /// # Returns
+    ///
+    /// `None` if not connected
+    pub fn loop_stats(&self) -> Option<LoopStats> {
+        self.thread_manager.as_ref().map(PipeWireThreadManager::loop_stats)
+    }
+
+    /// Get manager statistics
+    pub fn stats(&self) -> ManagerStats {
+        let counters = &self.counters;
+        ManagerStats {
+            streams_created: counters.streams_created.load(Ordering::Relaxed),
+            streams_destroyed: counters.streams_destroyed.load(Ordering::Relaxed),
+            total_frames: counters.total_frames.load(Ordering::Relaxed),
+            total_bytes: counters.total_bytes.load(Ordering::Relaxed),
+            uptime_secs: self.connected_at.map_or(0, |at| at.elapsed().as_secs()),
+            stream_restarts: counters.stream_restarts.load(Ordering::Relaxed),
+            streams_recovered: counters.streams_recovered.load(Ordering::Relaxed),
+            recovery_failures: counters.recovery_failures.load(Ordering::Relaxed),
+            new_session_requests: counters.session_requests.load(Ordering::Relaxed),
+        }
+    }
+
     /// Get current manager state
+    ///
+    /// `ManagerState::Error` after the PipeWire connection was lost; the
+    /// application needs a new portal session then (see
+    /// [`subscribe_events`](Self::subscribe_events)).
     pub async fn state(&self) -> ManagerState {
         *self.state.read().await
     }
@@ -417,6 +853,10 @@ impl PipeWireManager {
     }
 
     /// Access damage tracker (if enabled)
+    ///
+    /// The tracker accumulates the compositor's damage from every captured
+    /// frame (frames without damage metadata count as full damage). Call
+    /// `DamageTracker::clear` after consuming it.
     #[cfg(feature = "damage")]
     pub fn damage_tracker(&self) -> Option<&Arc<Mutex<DamageTracker>>> {
         self.damage_tracker.as_ref()
@@ -435,6 +875,11 @@ impl PipeWireManager {
         info!("Shutting down PipeWireManager");
         *self.state.write().await = ManagerState::ShuttingDown;
 
+        // Stop recovery before tearing streams down
+        if let Some(supervisor) = self.supervisor.take() {
+            supervisor.abort();
+        }
+
         // Remove all streams
         let stream_ids: Vec<u32> = self.streams.lock().await.keys().copied().collect();
         for id in stream_ids {
@@ -449,7 +894,14 @@ impl PipeWireManager {
             let _ = thread_manager.send_command(PipeWireThreadCommand::Shutdown);
         }
 
+        // Dropping the thread manager joins the PipeWire thread, which closes
+        // the frame channel and lets the forwarding thread exit
         self.thread_manager = None;
+        self.frame_router.join();
+        self.event_router.join();
+        #[cfg(feature = "cursor")]
+        self.cursor_router.join();
+        self.connected_at = None;
         *self.state.write().await = ManagerState::Disconnected;
 
         info!("PipeWireManager shutdown complete");
@@ -460,6 +912,9 @@ impl PipeWireManager {
 impl Drop for PipeWireManager {
     fn drop(&mut self) {
         debug!("Dropping PipeWireManager");
+        if let Some(supervisor) = self.supervisor.take() {
+            supervisor.abort();
+        }
         // Thread manager handles its own cleanup in Drop
     }
 }
@@ -481,6 +936,32 @@ pub struct ManagerStats {
 
     /// Connection uptime in seconds
     pub uptime_secs: u64,
+
+    /// Stream re-creation attempts made by automatic recovery
+    pub stream_restarts: u64,
+
+    /// Failed streams that were re-created and are streaming again
+    pub streams_recovered: u64,
+
+    /// Streams given up on after exhausting their recovery attempts
+    pub recovery_failures: u64,
+
+    /// Times the PipeWire connection was lost and a new portal session requested
+    pub new_session_requests: u64,
+}
+
+/// Counters behind [`ManagerStats`], shared with the frame forwarding thread
+/// and the recovery supervisor
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    pub(crate) streams_created: AtomicU64,
+    pub(crate) streams_destroyed: AtomicU64,
+    pub(crate) total_frames: AtomicU64,
+    pub(crate) total_bytes: AtomicU64,
+    pub(crate) stream_restarts: AtomicU64,
+    pub(crate) streams_recovered: AtomicU64,
+    pub(crate) recovery_failures: AtomicU64,
+    pub(crate) session_requests: AtomicU64,
 }
 
 #[cfg(test)]
@@ -523,6 +1004,75 @@ mod tests {
         assert!(!manager.is_connected().await);
     }
 
+    #[tokio::test]
+    async fn test_events_before_connect() {
+        let manager = PipeWireManager::with_default().expect("manager");
+        let mut events = manager.subscribe_events();
+
+        assert!(matches!(events.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+        assert!(manager.stream_state(0).await.is_none());
+    }
+
+    #[test]
+    fn test_stats_before_connect() {
+        let manager = PipeWireManager::with_default().expect("manager");
+        let stats = manager.stats();
+        assert_eq!(stats.streams_created, 0);
+        assert_eq!(stats.uptime_secs, 0);
+        assert_eq!(stats.stream_restarts, 0);
+        assert_eq!(stats.new_session_requests, 0);
+    }
+
+    #[test]
+    fn test_manager_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PipeWireManager>();
+    }
+
+    #[test]
+    fn test_stream_config_defaults() {
+        let config = PipeWireConfig::builder()
+            .buffer_count(5)
+            .preferred_format(crate::format::PixelFormat::RGBA)
+            .build();
+        let manager = PipeWireManager::new(config).expect("manager");
+
+        let stream_config = manager.stream_config("monitor", (1280, 720));
+        assert_eq!(stream_config.name, "monitor");
+        assert_eq!((stream_config.width, stream_config.height), (1280, 720));
+        assert_eq!(stream_config.buffer_count, 5);
+        assert_eq!(stream_config.preferred_format, Some(crate::format::PixelFormat::RGBA));
+    }
+
+    #[tokio::test]
+    async fn test_live_updates_require_connection() {
+        let manager = PipeWireManager::with_default().expect("manager");
+        assert!(manager.stream_control(0).await.is_none());
+
+        assert!(matches!(
+            manager.set_stream_active(0, false).await,
+            Err(PipeWireError::InvalidState(_))
+        ));
+        assert!(matches!(
+            manager.set_stream_max_framerate(0, 5).await,
+            Err(PipeWireError::InvalidState(_))
+        ));
+        assert!(matches!(
+            manager.set_stream_format(0, Some(PixelFormat::BGRx)).await,
+            Err(PipeWireError::InvalidState(_))
+        ));
+
+        // Parameters are validated before anything is sent
+        assert!(matches!(
+            manager.set_stream_max_framerate(0, 0).await,
+            Err(PipeWireError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            manager.set_stream_size(0, 0, 1080).await,
+            Err(PipeWireError::InvalidParameter(_))
+        ));
+    }
+
     #[test]
     fn test_stream_handle() {
         let handle = StreamHandle {
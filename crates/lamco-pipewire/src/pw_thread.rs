@@ -17,7 +17,7 @@
 //! 2. **Thread Confinement:** MainLoop, Context, Core, and Streams never leave this thread
 //! 3. **Message Passing:** Commands sent via `std::sync::mpsc` channel
 //! 4. **Frame Delivery:** Captured frames sent back via `std::sync::mpsc` channel
-//! 5. **Safe Wrapper:** `PipeWireThreadManager` is Send + Sync (via unsafe impl with guarantees)
+//! 5. **Safe Wrapper:** `PipeWireThreadManager` is Send + Sync (it only holds channels and shared state)
 //!
 //! # Architecture
 //!
@@ -34,20 +34,23 @@
 //!       │                                 ├─ Process Commands:
 //!       │                                 │   ├─ CreateStream
 //!       │                                 │   ├─ DestroyStream
-//!       │                                 │   └─ GetStreamState
+//!       │                                 │   ├─ GetStreamState
+//!       │                                 │   ├─ SetActive
+//!       │                                 │   └─ UpdateFormat
 //!       │                                 │
-//!       │                                 ├─ MainLoop.iterate()
+//!       │ ──wake (eventfd)──────────────> ├─ MainLoop.iterate() (blocks)
 //!       │                                 │   └─ Stream callbacks
-//!       │                                 │       └─ process() extracts frames
+//!       │                                 │       ├─ process() extracts frames
+//!       │                                 │       └─ state/param changes emit events
 //!       │                                 │
-//!       │ <──────Frames─────────────────────┘
+//!       │ <──────Frames, Events─────────────┘
 //!       │
 //!   recv_frame_timeout()
 //! ```
 //!
 //! # Safety Guarantees
 //!
-//! The `unsafe impl Send` and `unsafe impl Sync` for `PipeWireThreadManager` are safe because:
+//! `PipeWireThreadManager` is Send + Sync without any `unsafe impl` because:
 //!
 //! 1. All PipeWire types are confined to the PipeWire thread
 //! 2. No PipeWire types are ever sent across threads
@@ -61,11 +64,11 @@
 //! use lamco_pipewire::stream::StreamConfig;
 //!
 //! // Create thread manager with FD from portal
-//! let pipewire_fd = 42; // Obtained from lamco-portal
+//! let pipewire_fd = session.take_pipewire_fd().unwrap(); // Obtained from lamco-portal
 //! let manager = PipeWireThreadManager::new(pipewire_fd)?;
 //!
 //! // Create a stream (command sent to PipeWire thread)
-//! let (response_tx, response_rx) = std::sync::mpsc::sync_channel(1);
+//! let (response_tx, response_rx) = tokio::sync::oneshot::channel();
 //! let config = StreamConfig::new("monitor-0".to_string())
 //!     .with_resolution(1920, 1080)
 //!     .with_framerate(60);
@@ -74,9 +77,11 @@
 //!     stream_id: 1,
 //!     node_id: 42,
 //!     config,
+//!     response_tx,
 //! })?;
+//! response_rx.await??;
 //!
-//! // Receive frames via the channel returned by manager
+//! // Receive frames via the shared channel (monitor_index = stream ID)
 //! loop {
 //!     if let Some(frame) = manager.try_recv_frame() {
 //!         println!("Got frame: {}x{}", frame.width, frame.height);
@@ -96,22 +101,32 @@
 use pipewire::properties::Properties;
 use pipewire::spa::param::ParamType;
 use pipewire::spa::pod::Pod;
-use pipewire::spa::utils::Direction;
+use pipewire::spa::support::system::IoFlags;
+use pipewire::spa::utils::{Direction, Fraction};
 use pipewire::stream::{Stream, StreamFlags, StreamState};
 use pipewire::{context::Context, core::Core, main_loop::MainLoop};
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
-use std::os::fd::{FromRawFd, OwnedFd, RawFd};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::mpsc as std_mpsc;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 use tracing::{debug, error, info, trace, warn};
 
+use crate::buffer::{FramePool, PooledBuffer};
+use crate::connection::PipeWireEvent;
+use crate::dmabuf::DmaBufCache;
 use crate::error::{PipeWireError, Result};
-use crate::format::PixelFormat;
-use crate::frame::{FrameFlags, VideoFrame};
-use crate::stream::StreamConfig;
+use crate::ffi;
+use crate::format::{FramePlane, PixelFormat};
+use crate::frame::{CursorUpdate, FrameData, FrameDataMode, FrameFlags, HeldBuffer, VideoFrame};
+use crate::loop_signal::{LoopSignal, LoopStats};
+use crate::stream::{NegotiatedFormat, PwStreamState, StreamConfig};
 use std::sync::Arc as StdArc;
+use std::sync::Mutex as StdMutex;
 use std::time::SystemTime;
 
 /// Commands sent to the PipeWire thread
@@ -122,25 +137,80 @@ pub enum PipeWireThreadCommand {
         node_id: u32,
         config: StreamConfig,
         /// Response channel
-        response_tx: std_mpsc::SyncSender<Result<()>>,
+        response_tx: oneshot::Sender<Result<()>>,
     },
 
     /// Destroy a stream
     DestroyStream {
         stream_id: u32,
-        response_tx: std_mpsc::SyncSender<Result<()>>,
+        response_tx: oneshot::Sender<Result<()>>,
     },
 
     /// Get stream state
     GetStreamState {
         stream_id: u32,
-        response_tx: std_mpsc::SyncSender<Option<StreamState>>,
+        response_tx: oneshot::Sender<Option<StreamState>>,
+    },
+
+    /// Activate (resume) or deactivate (pause) a stream
+    SetActive {
+        stream_id: u32,
+        active: bool,
+        response_tx: oneshot::Sender<Result<()>>,
+    },
+
+    /// Renegotiate a stream's format with an updated configuration
+    ///
+    /// Offers new `EnumFormat` parameters built from the size, framerate
+    /// limit and format preference of `config`; the producer then picks a new
+    /// format without the stream being torn down.
+    UpdateFormat {
+        stream_id: u32,
+        config: StreamConfig,
+        response_tx: oneshot::Sender<Result<()>>,
     },
 
     /// Shutdown the PipeWire thread
     Shutdown,
 }
 
+impl PipeWireThreadCommand {
+    /// Command name, for logs and errors
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreateStream { .. } => "CreateStream",
+            Self::DestroyStream { .. } => "DestroyStream",
+            Self::GetStreamState { .. } => "GetStreamState",
+            Self::SetActive { .. } => "SetActive",
+            Self::UpdateFormat { .. } => "UpdateFormat",
+            Self::Shutdown => "Shutdown",
+        }
+    }
+}
+
+/// Copy a stream state (`StreamState` doesn't implement Clone)
+fn copy_stream_state(state: &StreamState) -> StreamState {
+    match state {
+        StreamState::Error(msg) => StreamState::Error(msg.clone()),
+        StreamState::Unconnected => StreamState::Unconnected,
+        StreamState::Connecting => StreamState::Connecting,
+        StreamState::Paused => StreamState::Paused,
+        StreamState::Streaming => StreamState::Streaming,
+    }
+}
+
+/// Negotiated formats keyed by stream ID, shared with the manager
+pub(crate) type NegotiatedFormats = StdArc<StdMutex<HashMap<u32, NegotiatedFormat>>>;
+
+/// Current state of each stream on the thread, shared with stream handles
+pub(crate) type StreamStates = StdArc<StdMutex<HashMap<u32, PwStreamState>>>;
+
+/// Why the PipeWire thread stopped (`None` while it runs)
+pub(crate) type ThreadExit = StdArc<StdMutex<Option<String>>>;
+
+/// How long a command waits for the PipeWire thread's reply by default
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Stream data managed on PipeWire thread
 ///
 /// Some fields are prepared for future functionality (metrics, stats).
@@ -155,17 +225,131 @@ struct ManagedStream {
     /// Stream event listener (must be kept alive)
     _listener: pipewire::stream::StreamListener<()>,
 
-    /// Configuration
-    config: StreamConfig,
+    /// Configuration, shared with the stream callbacks so renegotiated
+    /// settings take effect
+    config: std::rc::Rc<std::cell::RefCell<StreamConfig>>,
+
+    /// Current state (updated by the state_changed callback)
+    state: std::rc::Rc<std::cell::RefCell<StreamState>>,
 
-    /// Current state
-    state: StreamState,
+    /// Set when we tear the stream down, so the resulting transitions are not
+    /// reported as the compositor removing it
+    closing: std::rc::Rc<std::cell::Cell<bool>>,
 
     /// Frame counter
     frame_count: u64,
 
     /// Frame channel for sending captured frames
     frame_tx: std_mpsc::SyncSender<VideoFrame>,
+
+    /// Unique per stream created on this thread (stream IDs may be reused)
+    generation: u64,
+
+    /// Buffers lent to consumers and not yet queued back
+    held_buffers: StdArc<AtomicU32>,
+}
+
+impl ManagedStream {
+    /// Queue a buffer released by a consumer back to the stream
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must have been dequeued from this stream and not queued since.
+    unsafe fn requeue_held(&self, buffer: std::ptr::NonNull<pipewire::sys::pw_buffer>) {
+        // SAFETY: guaranteed by the caller
+        unsafe { self.stream.queue_raw_buffer(buffer.as_ptr()) };
+        self.held_buffers.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Mark the stream as being torn down by us
+    fn close(&self) {
+        self.closing.set(true);
+    }
+
+    /// Number of buffers lent to consumers
+    fn held_count(&self) -> u32 {
+        self.held_buffers.load(Ordering::Acquire)
+    }
+}
+
+/// Buffer lent to a consumer in zero-copy mode, released by dropping its frame
+struct BufferRelease {
+    /// Generation of the stream the buffer was dequeued from
+    generation: u64,
+
+    /// Dequeued buffer to queue back
+    buffer: std::ptr::NonNull<pipewire::sys::pw_buffer>,
+}
+
+// SAFETY: the buffer pointer is only dereferenced (queued back to its stream)
+// on the PipeWire thread that dequeued it.
+unsafe impl Send for BufferRelease {}
+
+/// Sends released buffers back to the PipeWire thread
+#[derive(Clone)]
+struct BufferReturn {
+    tx: std_mpsc::Sender<BufferRelease>,
+    loop_signal: LoopSignal,
+}
+
+impl BufferReturn {
+    /// Hand a released buffer to the PipeWire thread and wake its loop
+    fn send(&self, release: BufferRelease) {
+        // If the thread is gone the buffer went with its stream
+        if self.tx.send(release).is_ok() {
+            self.loop_signal.wake();
+        }
+    }
+}
+
+/// Queue buffers released by consumers back to their streams
+///
+/// Retired streams are dropped once all their buffers are back.
+fn requeue_released_buffers(
+    release_rx: &std_mpsc::Receiver<BufferRelease>,
+    streams: &HashMap<u32, ManagedStream>,
+    retiring: &mut Vec<ManagedStream>,
+) {
+    while let Ok(release) = release_rx.try_recv() {
+        requeue_released_buffer(release, streams, retiring);
+    }
+
+    retiring.retain(|stream| {
+        let busy = stream.held_count() > 0;
+        if !busy {
+            info!("Retired stream {} released its last held buffer", stream.id);
+        }
+        busy
+    });
+}
+
+/// Queue one released buffer back to the stream it came from
+fn requeue_released_buffer(release: BufferRelease, streams: &HashMap<u32, ManagedStream>, retiring: &[ManagedStream]) {
+    let owner = streams
+        .values()
+        .chain(retiring.iter())
+        .find(|stream| stream.generation == release.generation);
+
+    match owner {
+        // SAFETY: the buffer was dequeued from this stream (matched by
+        // generation) by the process callback and lent out without requeueing
+        Some(stream) => unsafe { stream.requeue_held(release.buffer) },
+        // Streams with held buffers are retired, not dropped, so this only
+        // happens if a stream was torn down regardless
+        None => warn!(
+            "Released buffer belongs to unknown stream generation {}",
+            release.generation
+        ),
+    }
+}
+
+/// Total number of buffers lent to consumers
+fn held_buffer_count(streams: &HashMap<u32, ManagedStream>, retiring: &[ManagedStream]) -> u32 {
+    streams
+        .values()
+        .chain(retiring.iter())
+        .map(ManagedStream::held_count)
+        .sum()
 }
 
 /// PipeWire thread manager
@@ -179,11 +363,32 @@ pub struct PipeWireThreadManager {
     /// Command channel sender
     command_tx: std_mpsc::SyncSender<PipeWireThreadCommand>,
 
-    /// Frame channel receiver
-    frame_rx: std_mpsc::Receiver<VideoFrame>,
+    /// Frame channel receiver (None once taken by a frame router)
+    frame_rx: StdMutex<Option<std_mpsc::Receiver<VideoFrame>>>,
+
+    /// Cursor update channel receiver (None once taken)
+    cursor_rx: StdMutex<Option<std_mpsc::Receiver<CursorUpdate>>>,
+
+    /// Lifecycle event channel receiver (None once taken)
+    event_rx: StdMutex<Option<std_mpsc::Receiver<PipeWireEvent>>>,
 
     /// Shutdown flag
     shutdown_tx: Option<std_mpsc::SyncSender<()>>,
+
+    /// Formats negotiated by each stream (written by the PipeWire thread)
+    negotiated_formats: NegotiatedFormats,
+
+    /// State of each stream (written by the PipeWire thread)
+    stream_states: StreamStates,
+
+    /// Wakes the PipeWire thread when commands are queued
+    loop_signal: LoopSignal,
+
+    /// How long commands wait for a reply
+    command_timeout: Duration,
+
+    /// Set when the PipeWire thread exits or panics
+    thread_exit: ThreadExit,
 }
 
 impl PipeWireThreadManager {
@@ -191,7 +396,7 @@ impl PipeWireThreadManager {
     ///
     /// # Arguments
     ///
-    /// * `fd` - File descriptor from portal
+    /// * `fd` - File descriptor from portal, owned by the PipeWire thread from now on
     ///
     /// # Returns
     ///
@@ -200,8 +405,8 @@ impl PipeWireThreadManager {
     /// # Errors
     ///
     /// Returns error if thread creation fails
-    pub fn new(fd: RawFd) -> Result<Self> {
-        info!("Creating PipeWire thread manager for FD {}", fd);
+    pub fn new(fd: OwnedFd) -> Result<Self> {
+        info!("Creating PipeWire thread manager for FD {}", fd.as_raw_fd());
 
         // Create channels for commands and frames
         // Using std::sync::mpsc (not tokio) because PipeWire thread is not async
@@ -209,13 +414,48 @@ impl PipeWireThreadManager {
         // Frame channel: increased from 64 to 256 to handle burst traffic
         // At 60 FPS capture / 30 FPS target = 2:1 ratio needs buffer
         let (frame_tx, frame_rx) = std_mpsc::sync_channel::<VideoFrame>(256);
+        // Cursor updates are small but can outpace frames (cursor-only buffers)
+        let (cursor_tx, cursor_rx) = std_mpsc::sync_channel::<CursorUpdate>(256);
+        // Lifecycle events are rare and must not be lost, so this one is unbounded
+        let (event_tx, event_rx) = std_mpsc::channel::<PipeWireEvent>();
         let (shutdown_tx, shutdown_rx) = std_mpsc::sync_channel::<()>(1);
+        let negotiated_formats: NegotiatedFormats = StdArc::new(StdMutex::new(HashMap::new()));
+        let thread_negotiated_formats = StdArc::clone(&negotiated_formats);
+        let stream_states: StreamStates = StdArc::new(StdMutex::new(HashMap::new()));
+        let thread_stream_states = StdArc::clone(&stream_states);
+        let loop_signal = LoopSignal::new()?;
+        let thread_loop_signal = loop_signal.clone();
+        let thread_exit: ThreadExit = StdArc::default();
+        let thread_exit_for_thread = StdArc::clone(&thread_exit);
 
         // Spawn dedicated PipeWire thread
         let thread_handle = thread::Builder::new()
             .name("pipewire-main".to_string())
             .spawn(move || {
-                run_pipewire_main_loop(fd, command_rx, frame_tx, shutdown_rx);
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    run_pipewire_main_loop(
+                        fd,
+                        command_rx,
+                        frame_tx,
+                        cursor_tx,
+                        event_tx,
+                        shutdown_rx,
+                        thread_negotiated_formats,
+                        thread_stream_states,
+                        thread_loop_signal,
+                    );
+                }));
+
+                // Tell command senders why nobody answers any more
+                let reason = match &result {
+                    Ok(()) => "PipeWire thread exited".to_string(),
+                    Err(payload) => format!("PipeWire thread panicked: {}", panic_message(payload.as_ref())),
+                };
+                *thread_exit_for_thread.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason);
+
+                if let Err(payload) = result {
+                    panic::resume_unwind(payload);
+                }
             })
             .map_err(|e| PipeWireError::InitializationFailed(format!("Thread spawn failed: {}", e)))?;
 
@@ -224,24 +464,78 @@ impl PipeWireThreadManager {
         Ok(Self {
             thread_handle: Some(thread_handle),
             command_tx,
-            frame_rx,
+            frame_rx: StdMutex::new(Some(frame_rx)),
+            cursor_rx: StdMutex::new(Some(cursor_rx)),
+            event_rx: StdMutex::new(Some(event_rx)),
             shutdown_tx: Some(shutdown_tx),
+            negotiated_formats,
+            stream_states,
+            loop_signal,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+            thread_exit,
         })
     }
 
+    /// Set how long commands wait for the PipeWire thread's reply
+    ///
+    /// Defaults to [`DEFAULT_COMMAND_TIMEOUT`].
+    pub fn with_command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = timeout;
+        self
+    }
+
+    /// Get the format negotiated by a stream
+    ///
+    /// # Returns
+    ///
+    /// `None` until PipeWire has completed format negotiation for the stream
+    pub fn negotiated_format(&self, stream_id: u32) -> Option<NegotiatedFormat> {
+        self.negotiated_formats.lock().unwrap().get(&stream_id).cloned()
+    }
+
+    /// Shared view of the formats negotiated by all streams
+    pub(crate) fn negotiated_formats(&self) -> NegotiatedFormats {
+        StdArc::clone(&self.negotiated_formats)
+    }
+
+    /// Shared view of the states of all streams
+    pub(crate) fn stream_states(&self) -> StreamStates {
+        StdArc::clone(&self.stream_states)
+    }
+
     /// Send a command to the PipeWire thread
     ///
+    /// Does not wait for the command's reply; await its `response_tx`
+    /// receiver for that.
+    ///
     /// # Arguments
     ///
     /// * `command` - Command to execute
     ///
     /// # Errors
     ///
-    /// Returns error if command cannot be sent (thread died)
+    /// Returns `ThreadPanic` if the thread is gone, or `Timeout` if its
+    /// command queue is full
     pub fn send_command(&self, command: PipeWireThreadCommand) -> Result<()> {
-        self.command_tx
-            .send(command)
-            .map_err(|_| PipeWireError::ThreadCommunicationFailed("Command send failed".to_string()))
+        self.command_sender().send(command)
+    }
+
+    /// Get a cloneable handle for sending commands from other tasks
+    pub(crate) fn command_sender(&self) -> CommandSender {
+        CommandSender::new(
+            self.command_tx.clone(),
+            self.loop_signal.clone(),
+            self.command_timeout,
+            StdArc::clone(&self.thread_exit),
+        )
+    }
+
+    /// Get main loop statistics
+    ///
+    /// Includes the wakeup latency, i.e. how long the PipeWire thread takes
+    /// to start handling a command after it is sent.
+    pub fn loop_stats(&self) -> LoopStats {
+        self.loop_signal.stats()
     }
 
     /// Try to receive a frame (non-blocking)
@@ -250,7 +544,7 @@ impl PipeWireThreadManager {
     ///
     /// Some(VideoFrame) if a frame is available, None otherwise
     pub fn try_recv_frame(&self) -> Option<VideoFrame> {
-        self.frame_rx.try_recv().ok()
+        self.frame_rx.lock().unwrap().as_ref()?.try_recv().ok()
     }
 
     /// Receive a frame (blocking with timeout)
@@ -263,7 +557,45 @@ impl PipeWireThreadManager {
     ///
     /// Some(VideoFrame) if received within timeout, None otherwise
     pub fn recv_frame_timeout(&self, timeout: Duration) -> Option<VideoFrame> {
-        self.frame_rx.recv_timeout(timeout).ok()
+        self.frame_rx.lock().unwrap().as_ref()?.recv_timeout(timeout).ok()
+    }
+
+    /// Take ownership of the shared frame channel
+    ///
+    /// Frames from all streams arrive on this channel, tagged with their
+    /// stream ID in `VideoFrame::monitor_index`. Once taken, `try_recv_frame`
+    /// and `recv_frame_timeout` always return `None`.
+    ///
+    /// # Returns
+    ///
+    /// The frame receiver, or `None` if it was already taken
+    pub fn take_frame_receiver(&mut self) -> Option<std_mpsc::Receiver<VideoFrame>> {
+        self.frame_rx.get_mut().unwrap().take()
+    }
+
+    /// Take ownership of the shared cursor update channel
+    ///
+    /// Streams created with `StreamConfig::enable_cursor` deliver their
+    /// cursor metadata here, tagged with `CursorUpdate::stream_id`.
+    ///
+    /// # Returns
+    ///
+    /// The cursor receiver, or `None` if it was already taken
+    pub fn take_cursor_receiver(&mut self) -> Option<std_mpsc::Receiver<CursorUpdate>> {
+        self.cursor_rx.get_mut().unwrap().take()
+    }
+
+    /// Take ownership of the lifecycle event channel
+    ///
+    /// Receives stream state changes, errors, format changes and removals
+    /// (`StreamStateChanged`, `StreamError`, `StreamFormatChanged`,
+    /// `StreamRemoved`) and core errors (`CoreError`, `Disconnected`).
+    ///
+    /// # Returns
+    ///
+    /// The event receiver, or `None` if it was already taken
+    pub fn take_event_receiver(&mut self) -> Option<std_mpsc::Receiver<PipeWireEvent>> {
+        self.event_rx.get_mut().unwrap().take()
     }
 
     /// Shutdown the PipeWire thread gracefully
@@ -279,6 +611,7 @@ impl PipeWireThreadManager {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
         }
+        self.loop_signal.wake();
 
         // Wait for thread to finish (with timeout)
         if let Some(handle) = self.thread_handle.take() {
@@ -293,6 +626,96 @@ impl PipeWireThreadManager {
     }
 }
 
+/// Sends commands to the PipeWire thread and wakes its loop
+#[derive(Clone)]
+pub(crate) struct CommandSender {
+    /// Command channel sender
+    command_tx: std_mpsc::SyncSender<PipeWireThreadCommand>,
+
+    /// Wakes the PipeWire thread when commands are queued
+    loop_signal: LoopSignal,
+
+    /// How long `request` waits for a reply
+    timeout: Duration,
+
+    /// Set when the thread draining the channel stops
+    thread_exit: ThreadExit,
+}
+
+impl CommandSender {
+    /// Create a sender for a command channel drained by a loop woken by `loop_signal`
+    pub(crate) fn new(
+        command_tx: std_mpsc::SyncSender<PipeWireThreadCommand>,
+        loop_signal: LoopSignal,
+        timeout: Duration,
+        thread_exit: ThreadExit,
+    ) -> Self {
+        Self {
+            command_tx,
+            loop_signal,
+            timeout,
+            thread_exit,
+        }
+    }
+
+    /// Send a command to the PipeWire thread without waiting for a reply
+    ///
+    /// Never blocks: a full command queue means the thread is stalled.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ThreadPanic` if the thread is gone, or `Timeout` if its
+    /// command queue is full
+    pub(crate) fn send(&self, command: PipeWireThreadCommand) -> Result<()> {
+        let name = command.name();
+        match self.command_tx.try_send(command) {
+            Ok(()) => {
+                self.loop_signal.wake();
+                Ok(())
+            }
+            Err(std_mpsc::TrySendError::Full(_)) => {
+                warn!("PipeWire thread command queue is full, dropping {}", name);
+                Err(PipeWireError::Timeout)
+            }
+            Err(std_mpsc::TrySendError::Disconnected(_)) => Err(self.thread_gone()),
+        }
+    }
+
+    /// Send a command and wait for the thread's reply
+    ///
+    /// `command` builds the command around the reply channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Timeout` if no reply arrives within the command timeout, or
+    /// `ThreadPanic` if the thread is gone
+    pub(crate) async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> PipeWireThreadCommand,
+    ) -> Result<T> {
+        let (response_tx, response_rx) = oneshot::channel();
+        let command = command(response_tx);
+        let name = command.name();
+        self.send(command)?;
+
+        match tokio::time::timeout(self.timeout, response_rx).await {
+            Ok(Ok(reply)) => Ok(reply),
+            // Every command is answered, so a dropped reply means the thread died
+            Ok(Err(_)) => Err(self.thread_gone()),
+            Err(_) => {
+                warn!("PipeWire thread did not answer {} within {:?}", name, self.timeout);
+                Err(PipeWireError::Timeout)
+            }
+        }
+    }
+
+    /// Error for a thread that no longer handles commands
+    fn thread_gone(&self) -> PipeWireError {
+        let reason = self.thread_exit.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        PipeWireError::ThreadPanic(reason.unwrap_or_else(|| "PipeWire thread is not running".to_string()))
+    }
+}
+
 impl Drop for PipeWireThreadManager {
     fn drop(&mut self) {
         debug!("Dropping PipeWireThreadManager");
@@ -300,15 +723,37 @@ impl Drop for PipeWireThreadManager {
     }
 }
 
+/// Text of a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Longest time the main loop blocks in `iterate()` without any activity
+pub(crate) const LOOP_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long shutdown waits for consumers to release held buffers
+const HELD_BUFFER_GRACE: Duration = Duration::from_millis(500);
+
 /// Main loop function that runs on the dedicated PipeWire thread
 ///
 /// This function owns all PipeWire types (MainLoop, Context, Core, Streams)
 /// and processes commands from the async runtime.
 fn run_pipewire_main_loop(
-    fd: RawFd,
+    fd: OwnedFd,
     command_rx: std_mpsc::Receiver<PipeWireThreadCommand>,
     frame_tx: std_mpsc::SyncSender<VideoFrame>,
+    cursor_tx: std_mpsc::SyncSender<CursorUpdate>,
+    event_tx: std_mpsc::Sender<PipeWireEvent>,
     shutdown_rx: std_mpsc::Receiver<()>,
+    negotiated_formats: NegotiatedFormats,
+    stream_states: StreamStates,
+    loop_signal: LoopSignal,
 ) {
     info!("PipeWire main loop thread started");
 
@@ -334,34 +779,64 @@ fn run_pipewire_main_loop(
     };
 
     // Connect core using portal FD
-    info!("🔌 Connecting PipeWire Core to Portal FD {}", fd);
-    // SAFETY: The FD was provided by XDG Desktop Portal via lamco-portal.
-    // We take exclusive ownership - the FD is not used anywhere else.
-    let owned_fd = unsafe { OwnedFd::from_raw_fd(fd) };
-    let core = match context.connect_fd(owned_fd, None) {
+    let raw_fd = fd.as_raw_fd();
+    info!("🔌 Connecting PipeWire Core to Portal FD {}", raw_fd);
+    let core = match context.connect_fd(fd, None) {
         Ok(c) => {
             info!("✅ Core.connect_fd() succeeded");
             c
         }
         Err(e) => {
-            error!("❌ Failed to connect Core with FD {}: {}", fd, e);
+            error!("❌ Failed to connect Core with FD {}: {}", raw_fd, e);
             return;
         }
     };
 
-    info!("✅ PipeWire Core connected successfully to Portal FD {}", fd);
+    info!("✅ PipeWire Core connected successfully to Portal FD {}", raw_fd);
     info!("📍 This is a PRIVATE PipeWire connection - node IDs only valid on this FD");
 
+    // Core errors: EPIPE on the core object means the connection is gone
+    let core_listener = core
+        .add_listener_local()
+        .error({
+            let event_tx = event_tx.clone();
+            move |id, seq, res, message| {
+                error!("PipeWire core error: id={}, seq={}, res={}: {}", id, seq, res, message);
+                let event = if id == pipewire::core::PW_ID_CORE && res == -libc::EPIPE {
+                    PipeWireEvent::Disconnected
+                } else {
+                    PipeWireEvent::CoreError(format!("{} (id={}, res={})", message, id, res))
+                };
+                let _ = event_tx.send(event);
+            }
+        })
+        .register();
+
     // Stream storage (all streams live on this thread)
     let mut streams: HashMap<u32, ManagedStream> = HashMap::new();
 
-    // DMA-BUF mmap cache: Maps FD -> (ptr, size) to avoid remapping every frame
-    // Using Rc<RefCell<>> because we're on a single thread (PipeWire doesn't support multi-threading)
-    // This cache is shared with all stream process() callbacks
-    use std::cell::RefCell;
-    use std::rc::Rc;
-    let dmabuf_mmap_cache: Rc<RefCell<HashMap<RawFd, (*mut libc::c_void, usize)>>> =
-        Rc::new(RefCell::new(HashMap::new()));
+    // Destroyed streams whose buffers are still lent to consumers; they stay
+    // alive (inactive) so the lent memory remains mapped until released
+    let mut retiring: Vec<ManagedStream> = Vec::new();
+    let mut next_generation = 0u64;
+
+    // Buffers released by consumers in zero-copy mode come back through here
+    let (release_tx, release_rx) = std_mpsc::channel::<BufferRelease>();
+    let buffer_return = BufferReturn {
+        tx: release_tx,
+        loop_signal: loop_signal.clone(),
+    };
+
+    // Wake the loop whenever the async side queues a command. The source
+    // must be dropped before the main loop it is registered on.
+    let signal_source = main_loop.loop_().add_io(loop_signal.fd(), IoFlags::IN, {
+        let loop_signal = loop_signal.clone();
+        move |_fd| {
+            if let Some(latency) = loop_signal.acknowledge() {
+                trace!("PipeWire loop woken after {:?}", latency);
+            }
+        }
+    });
 
     // Main event loop
     let mut loop_iterations = 0u64;
@@ -401,13 +876,24 @@ fn run_pipewire_main_loop(
                         &core,
                         config,
                         frame_tx.clone(),
-                        Rc::clone(&dmabuf_mmap_cache),
+                        cursor_tx.clone(),
+                        event_tx.clone(),
+                        StdArc::clone(&negotiated_formats),
+                        StdArc::clone(&stream_states),
+                        next_generation,
+                        buffer_return.clone(),
                     );
+                    next_generation += 1;
 
                     match result {
                         Ok(managed_stream) => {
                             info!("📦 Storing stream {} in active streams map", stream_id);
                             streams.insert(stream_id, managed_stream);
+                            stream_states
+                                .lock()
+                                .unwrap()
+                                .entry(stream_id)
+                                .or_insert(PwStreamState::Connecting);
                             let _ = response_tx.send(Ok(()));
                             info!(
                                 "✅ Stream {} fully created - now in streams map (total: {} streams)",
@@ -417,6 +903,7 @@ fn run_pipewire_main_loop(
                         }
                         Err(e) => {
                             error!("❌ Failed to create stream {}: {}", stream_id, e);
+                            stream_states.lock().unwrap().remove(&stream_id);
                             let _ = response_tx.send(Err(e));
                         }
                     }
@@ -426,43 +913,74 @@ fn run_pipewire_main_loop(
                     debug!("Destroying stream {}", stream_id);
 
                     if let Some(managed_stream) = streams.remove(&stream_id) {
-                        // Clean up any DMA-BUF mmaps associated with this stream
-                        // Note: We don't know which FDs belong to which stream, so we clear all
-                        // This is safe because streams are destroyed infrequently
-                        let mut cache = dmabuf_mmap_cache.borrow_mut();
-                        for (fd, (ptr, size)) in cache.drain() {
-                            // SAFETY: ptr and size were recorded when mmap succeeded.
-                            // drain() ensures we process each entry exactly once.
-                            unsafe {
-                                use nix::sys::mman::munmap;
-                                if let Err(e) = munmap(ptr, size) {
-                                    warn!("Failed to munmap DMA-BUF FD={}: {}", fd, e);
-                                }
+                        negotiated_formats.lock().unwrap().remove(&stream_id);
+                        stream_states.lock().unwrap().remove(&stream_id);
+                        managed_stream.close();
+
+                        if managed_stream.held_count() > 0 {
+                            // Consumers still hold its buffers: stop capture but keep
+                            // the stream (and the lent memory) until they are released
+                            if let Err(e) = managed_stream.stream.set_active(false) {
+                                warn!("Failed to deactivate stream {}: {}", stream_id, e);
                             }
-                            debug!("Unmapped DMA-BUF cache entry for FD={}", fd);
+                            info!(
+                                "Stream {} retired with {} buffers still held by consumers",
+                                stream_id,
+                                managed_stream.held_count()
+                            );
+                            retiring.push(managed_stream);
+                        } else {
+                            // Stream is automatically dropped here
+                            drop(managed_stream);
                         }
-
-                        // Stream is automatically dropped here
-                        drop(managed_stream);
                         let _ = response_tx.send(Ok(()));
-                        info!("Stream {} destroyed, DMA-BUF cache cleared", stream_id);
+                        info!("Stream {} destroyed", stream_id);
                     } else {
                         let _ = response_tx.send(Err(PipeWireError::StreamNotFound(stream_id)));
                     }
                 }
 
                 PipeWireThreadCommand::GetStreamState { stream_id, response_tx } => {
-                    // StreamState doesn't implement Clone, so we match and reconstruct
-                    let state = streams.get(&stream_id).map(|s| match &s.state {
-                        StreamState::Error(msg) => StreamState::Error(msg.clone()),
-                        StreamState::Unconnected => StreamState::Unconnected,
-                        StreamState::Connecting => StreamState::Connecting,
-                        StreamState::Paused => StreamState::Paused,
-                        StreamState::Streaming => StreamState::Streaming,
-                    });
+                    let state = streams.get(&stream_id).map(|s| copy_stream_state(&s.state.borrow()));
                     let _ = response_tx.send(state);
                 }
 
+                PipeWireThreadCommand::SetActive {
+                    stream_id,
+                    active,
+                    response_tx,
+                } => {
+                    debug!("Setting stream {} active={}", stream_id, active);
+                    let result = match streams.get(&stream_id) {
+                        Some(managed_stream) => managed_stream.stream.set_active(active).map_err(|e| {
+                            PipeWireError::StreamFailed(stream_id, format!("set_active({}) failed: {}", active, e))
+                        }),
+                        None => Err(PipeWireError::StreamNotFound(stream_id)),
+                    };
+                    let _ = response_tx.send(result);
+                }
+
+                PipeWireThreadCommand::UpdateFormat {
+                    stream_id,
+                    config,
+                    response_tx,
+                } => {
+                    info!(
+                        "Renegotiating stream {}: {}x{} @ {}fps (max {:?}), format {:?}",
+                        stream_id,
+                        config.width,
+                        config.height,
+                        config.framerate,
+                        config.max_framerate,
+                        config.preferred_format
+                    );
+                    let result = match streams.get_mut(&stream_id) {
+                        Some(managed_stream) => update_stream_format(managed_stream, config),
+                        None => Err(PipeWireError::StreamNotFound(stream_id)),
+                    };
+                    let _ = response_tx.send(result);
+                }
+
                 PipeWireThreadCommand::Shutdown => {
                     info!("Shutdown command received");
                     break 'main;
@@ -470,6 +988,9 @@ fn run_pipewire_main_loop(
             }
         }
 
+        // Return buffers consumers are done with to their streams
+        requeue_released_buffers(&release_rx, &streams, &mut retiring);
+
         // Check for shutdown signal
         if shutdown_rx.try_recv().is_ok() {
             info!("Shutdown signal received");
@@ -477,10 +998,12 @@ fn run_pipewire_main_loop(
         }
 
         // Run one iteration of PipeWire main loop
-        // Use non-blocking iterate (0ms timeout) to avoid frame timing jitter
-        // Then sleep based on expected frame timing for efficiency
+        // Blocks until PipeWire has work (frames, param changes) or the async
+        // side wakes us via the loop signal. The timeout only bounds how long
+        // we go without a heartbeat; it is not needed for responsiveness.
         let loop_ref = main_loop.loop_();
-        let events_processed = loop_ref.iterate(Duration::from_millis(0));
+        let events_processed = loop_ref.iterate(LOOP_IDLE_TIMEOUT);
+        loop_signal.record_iteration();
 
         if loop_iterations % 1000 == 0 {
             trace!(
@@ -488,15 +1011,57 @@ fn run_pipewire_main_loop(
                 events_processed
             );
         }
-
-        // Sleep briefly to avoid busy-looping while still maintaining low latency
-        // At 60 FPS, frames arrive every ~16ms, so 5ms sleep is safe
-        std::thread::sleep(Duration::from_millis(5));
     }
 
     // Cleanup
     info!("Cleaning up PipeWire resources");
+    for managed_stream in streams.values() {
+        managed_stream.close();
+    }
+    let stats = loop_signal.stats();
+    info!(
+        "PipeWire loop stats: {} iterations, {} wakeups, avg wake latency {:?}, max {:?}",
+        stats.iterations,
+        stats.wakeups,
+        stats.avg_wake_latency(),
+        stats.max_wake_latency
+    );
+
+    // Give consumers a moment to release frames lent in zero-copy mode
+    let deadline = Instant::now() + HELD_BUFFER_GRACE;
+    while held_buffer_count(&streams, &retiring) > 0 {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        match release_rx.recv_timeout(remaining) {
+            Ok(release) => requeue_released_buffer(release, &streams, &retiring),
+            Err(_) => break,
+        }
+    }
+
+    let held = held_buffer_count(&streams, &retiring);
+    if held > 0 {
+        // Tearing down would unmap memory that frames still reference; leak
+        // the PipeWire objects instead so those frames stay valid
+        warn!(
+            "{} PipeWire buffers still held by consumers at shutdown - leaking PipeWire resources",
+            held
+        );
+        drop(signal_source);
+        drop(core_listener);
+        std::mem::forget(streams);
+        std::mem::forget(retiring);
+        std::mem::forget(core);
+        std::mem::forget(context);
+        std::mem::forget(main_loop);
+        info!("PipeWire thread exited");
+        return;
+    }
+
     streams.clear();
+    retiring.clear();
+    drop(signal_source);
+    drop(core_listener);
     drop(core);
     drop(context);
     drop(main_loop);
@@ -591,6 +1156,189 @@ fn mmap_fd_buffer(fd: std::os::fd::RawFd, size: usize, offset: usize) -> Result<
     Ok(result)
 }
 
+/// Locate the chunk of a buffer data entry in memory mapped by PipeWire
+///
+/// Only MemPtr and MemFd data are mapped by PipeWire (`MAP_BUFFERS`); the
+/// mapping stays valid for as long as the buffer exists.
+///
+/// # Returns
+///
+/// Pointer to and length of the chunk, or `None` if the data is not mapped
+fn mapped_chunk(data: &mut libspa::buffer::Data) -> Option<(*const u8, usize)> {
+    if !matches!(
+        data.type_(),
+        libspa::buffer::DataType::MemPtr | libspa::buffer::DataType::MemFd
+    ) {
+        return None;
+    }
+
+    let chunk = data.chunk();
+    let size = chunk.size() as usize;
+    let offset = chunk.offset() as usize;
+    let mapped = data.data()?;
+    let bytes = mapped.get(offset..offset.checked_add(size)?)?;
+    Some((bytes.as_ptr(), bytes.len()))
+}
+
+/// Copy the chunk of one buffer data entry (one plane) into `out`
+///
+/// DMA-BUF mappings are cached per buffer (`buffer_key`) and cover the whole
+/// buffer up to the entry's `mapoffset + maxsize`, so planes stored in one
+/// DMA-BUF at different offsets share a mapping. Reads are synchronised with
+/// the exporter.
+///
+/// # Returns
+///
+/// `None` if the data could not be read; `out` is left unchanged then
+fn append_data_chunk(
+    data: &mut libspa::buffer::Data,
+    buffer_key: usize,
+    dmabuf_cache: &mut DmaBufCache,
+    out: &mut Vec<u8>,
+) -> Option<()> {
+    let chunk = data.chunk();
+    let size = chunk.size() as usize;
+    let offset = chunk.offset() as usize;
+    let data_type = data.type_();
+
+    // Access raw spa_data structure to get FD and mapping info
+    let raw_data = data.as_raw();
+    let fd = raw_data.fd as RawFd;
+    let map_start = raw_data.mapoffset as usize;
+    let max_size = raw_data.maxsize as usize;
+
+    trace!(
+        "Buffer data: type={}, size={}, offset={}, mapoffset={}, fd={}",
+        data_type.as_raw(),
+        size,
+        offset,
+        map_start,
+        fd
+    );
+
+    match data_type {
+        // MemPtr: Direct memory access via data.data()
+        libspa::buffer::DataType::MemPtr => {
+            let Some(mapped_data) = data.data() else {
+                warn!("MemPtr buffer but data.data() returned None");
+                return None;
+            };
+            if offset + size > mapped_data.len() {
+                warn!(
+                    "MemPtr buffer bounds invalid: offset={}, size={}, len={}",
+                    offset,
+                    size,
+                    mapped_data.len()
+                );
+                return None;
+            }
+            out.extend_from_slice(&mapped_data[offset..offset + size]);
+            Some(())
+        }
+
+        // MemFd: File descriptor with memory mapping
+        libspa::buffer::DataType::MemFd => {
+            if let Some(mapped_data) = data.data() {
+                if offset + size > mapped_data.len() {
+                    warn!(
+                        "MemFd buffer bounds invalid: offset={}, size={}, len={}",
+                        offset,
+                        size,
+                        mapped_data.len()
+                    );
+                    return None;
+                }
+                out.extend_from_slice(&mapped_data[offset..offset + size]);
+                Some(())
+            } else if fd >= 0 {
+                // Fallback: manual mmap of MemFd
+                debug!("MemFd buffer: using manual mmap (FD={})", fd);
+                match mmap_fd_buffer(fd, size, map_start + offset) {
+                    Ok(data) => {
+                        out.extend_from_slice(&data);
+                        Some(())
+                    }
+                    Err(e) => {
+                        warn!("Failed to mmap MemFd buffer: {}", e);
+                        None
+                    }
+                }
+            } else {
+                warn!("MemFd buffer but no valid FD (fd={})", fd);
+                None
+            }
+        }
+
+        // DmaBuf: GPU memory buffer - mapped once per buffer, read between sync ioctls
+        libspa::buffer::DataType::DmaBuf => {
+            if fd < 0 {
+                warn!("DMA-BUF buffer but no valid FD (fd={})", fd);
+                return None;
+            }
+
+            // SAFETY: the FD belongs to the buffer, which stays dequeued (and
+            // the FD open) for the duration of this call.
+            let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) };
+            match dmabuf_cache.read_chunk(buffer_key, fd, map_start + max_size, map_start + offset, size, out) {
+                Ok(()) => Some(()),
+                Err(e) => {
+                    warn!("Failed to read DMA-BUF chunk: {}", e);
+                    None
+                }
+            }
+        }
+
+        // Unknown/Invalid type
+        _ => {
+            warn!(
+                "Unknown buffer type: {} (raw={})",
+                if data_type == libspa::buffer::DataType::Invalid {
+                    "Invalid"
+                } else {
+                    "Unknown"
+                },
+                data_type.as_raw()
+            );
+            None
+        }
+    }
+}
+
+/// Offer new format parameters for a stream, triggering renegotiation
+///
+/// The new format arrives through `param_changed` like the initial one, so
+/// frames keep carrying whatever the producer actually delivers. The buffer
+/// and metadata params are offered again as well, so a changed buffer count
+/// or cursor setting applies even if the format stays the same. The frame
+/// pool keeps the size it was created with.
+fn update_stream_format(managed_stream: &mut ManagedStream, config: StreamConfig) -> Result<()> {
+    let mut params = build_stream_parameters(&config)?;
+    params.extend(build_buffer_and_meta_params(&config)?);
+    let mut pods: Vec<&Pod> = params.iter().filter_map(|bytes| Pod::from_bytes(bytes)).collect();
+    managed_stream.stream.update_params(&mut pods).map_err(|e| {
+        PipeWireError::FormatNegotiationFailed(format!(
+            "Stream {} failed to update format params: {}",
+            managed_stream.id, e
+        ))
+    })?;
+    *managed_stream.config.borrow_mut() = config;
+    Ok(())
+}
+
+/// Buffer types we can consume, and the metadata we request: header
+/// (timestamps, sequence numbers), damage and, if enabled, cursor
+fn build_buffer_and_meta_params(config: &StreamConfig) -> Result<Vec<Vec<u8>>> {
+    let mut params = vec![
+        ffi::build_buffer_params(config.buffer_count, 0, 0, config.use_dmabuf)?,
+        ffi::build_meta_param(ffi::spa_sys::SPA_META_Header, ffi::META_HEADER_SIZE)?,
+        ffi::build_damage_meta_param(ffi::MAX_DAMAGE_REGIONS)?,
+    ];
+    if config.enable_cursor {
+        params.push(ffi::build_cursor_meta_param()?);
+    }
+    Ok(params)
+}
+
 /// Create a stream on the PipeWire thread
 ///
 /// This function performs the complete stream creation, format negotiation,
@@ -601,7 +1349,12 @@ fn create_stream_on_thread(
     core: &Core,
     config: StreamConfig,
     frame_tx: std_mpsc::SyncSender<VideoFrame>,
-    dmabuf_cache: std::rc::Rc<std::cell::RefCell<HashMap<RawFd, (*mut libc::c_void, usize)>>>,
+    cursor_tx: std_mpsc::SyncSender<CursorUpdate>,
+    event_tx: std_mpsc::Sender<PipeWireEvent>,
+    negotiated_formats: NegotiatedFormats,
+    stream_states: StreamStates,
+    generation: u64,
+    buffer_return: BufferReturn,
 ) -> Result<ManagedStream> {
     let stream_name = format!("lamco-pw-{}", stream_id);
     let node_target = node_id.to_string();
@@ -632,16 +1385,48 @@ fn create_stream_on_thread(
     info!("✅ Stream::new() succeeded - stream object created");
 
     // Set up comprehensive stream event listeners
-    // Clone frame_tx and dmabuf_cache for use in closures
+    // Clone frame_tx for use in closures
     let frame_tx_for_process = frame_tx.clone();
     let stream_id_for_callbacks = stream_id;
+
+    // DMA-BUF mappings of this stream's buffers, unmapped in remove_buffer
+    let dmabuf_cache = std::rc::Rc::new(std::cell::RefCell::new(DmaBufCache::new()));
     let dmabuf_cache_for_process = std::rc::Rc::clone(&dmabuf_cache);
+    let dmabuf_cache_for_remove = dmabuf_cache;
+
+    // Format negotiated via param_changed; frames are dropped until it is known
+    let negotiated: std::rc::Rc<std::cell::RefCell<Option<NegotiatedFormat>>> =
+        std::rc::Rc::new(std::cell::RefCell::new(None));
+    let negotiated_for_param = std::rc::Rc::clone(&negotiated);
+    let negotiated_for_process = std::rc::Rc::clone(&negotiated);
+    let negotiated_formats_for_param = StdArc::clone(&negotiated_formats);
+    let negotiated_formats_for_process = negotiated_formats;
+    let event_tx_for_param = event_tx.clone();
+    let mut frame_clock = FrameClock::default();
+    let mut last_cursor: Option<((i32, i32), bool)> = None;
+
+    // Frame storage: recycled through the pool, or lent to consumers in
+    // zero-copy mode (counted until they are queued back)
+    let frame_pool = FramePool::new(config.frame_pool_size);
+    let held_buffers = StdArc::new(AtomicU32::new(0));
+    let held_buffers_for_process = StdArc::clone(&held_buffers);
+
+    // Configuration read by the callbacks, replaced on renegotiation
+    let config = std::rc::Rc::new(std::cell::RefCell::new(config));
+    let config_for_param = std::rc::Rc::clone(&config);
+    let config_for_process = std::rc::Rc::clone(&config);
 
     info!(
-        "🎧 Registering stream {} callbacks (state_changed, param_changed, process)",
+        "🎧 Registering stream {} callbacks (state_changed, param_changed, remove_buffer, process)",
         stream_id
     );
 
+    // State shared with the state_changed callback
+    let stream_state = std::rc::Rc::new(std::cell::RefCell::new(StreamState::Connecting));
+    let stream_state_for_callback = std::rc::Rc::clone(&stream_state);
+    let closing = std::rc::Rc::new(std::cell::Cell::new(false));
+    let closing_for_callback = std::rc::Rc::clone(&closing);
+
     let _listener = stream
         .add_local_listener::<()>()
         .state_changed(move |_stream, _user_data, old_state, new_state| {
@@ -649,10 +1434,26 @@ fn create_stream_on_thread(
                 "🔄 Stream {} state changed: {:?} -> {:?}",
                 stream_id_for_callbacks, old_state, new_state
             );
+            *stream_state_for_callback.borrow_mut() = copy_stream_state(&new_state);
+
+            // Transitions caused by our own teardown are not news
+            if closing_for_callback.get() {
+                return;
+            }
 
+            let state = PwStreamState::from(copy_stream_state(&new_state));
+            stream_states.lock().unwrap().insert(stream_id_for_callbacks, state);
+            let _ = event_tx.send(PipeWireEvent::StreamStateChanged(stream_id_for_callbacks, state));
             match new_state {
                 StreamState::Error(ref err_msg) => {
                     error!("Stream {} entered error state: {}", stream_id_for_callbacks, err_msg);
+                    let _ = event_tx.send(PipeWireEvent::StreamError(stream_id_for_callbacks, err_msg.clone()));
+                }
+                StreamState::Unconnected => {
+                    // Not disconnected by us: the compositor removed the node
+                    // (window closed, monitor unplugged, capture revoked)
+                    warn!("Stream {} was disconnected by the compositor", stream_id_for_callbacks);
+                    let _ = event_tx.send(PipeWireEvent::StreamRemoved(stream_id_for_callbacks));
                 }
                 StreamState::Streaming => {
                     info!("Stream {} is now streaming", stream_id_for_callbacks);
@@ -660,283 +1461,254 @@ fn create_stream_on_thread(
                 StreamState::Paused => {
                     debug!("Stream {} paused", stream_id_for_callbacks);
                 }
-                _ => {}
+                StreamState::Connecting => {}
             }
         })
-        .param_changed(move |_stream, _user_data, param_id, _param| {
-            if param_id == ParamType::Format.as_raw() {
-                info!(
-                    "📐 Stream {} format negotiated via param_changed",
+        .param_changed(move |stream, _user_data, param_id, param| {
+            if param_id != ParamType::Format.as_raw() {
+                return;
+            }
+
+            let Some(param) = param else {
+                // Format cleared (stream disconnected or renegotiating)
+                debug!("Stream {} format cleared", stream_id_for_callbacks);
+                *negotiated_for_param.borrow_mut() = None;
+                negotiated_formats_for_param
+                    .lock()
+                    .unwrap()
+                    .remove(&stream_id_for_callbacks);
+                return;
+            };
+
+            let Some(format) = NegotiatedFormat::from_format_param(param) else {
+                warn!(
+                    "Stream {} negotiated a format that is not raw video",
                     stream_id_for_callbacks
                 );
-                // Note: Extracting format from param Pod requires parsing SPA POD format
-                // This is complex and requires spa::pod::deserialize
-                // For now, we log that negotiation occurred and rely on config.preferred_format
-                // TODO: Add full SPA POD parsing to extract actual negotiated format
-                info!(
-                    "   Configured format: {:?}",
-                    config.preferred_format.unwrap_or(PixelFormat::BGRx)
+                return;
+            };
+
+            info!(
+                "📐 Stream {} format negotiated: {:?} {}x{} @ {}/{} (modifier: {:?})",
+                stream_id_for_callbacks,
+                format.format,
+                format.width,
+                format.height,
+                format.framerate.num,
+                format.framerate.denom,
+                format.modifier
+            );
+            if format.pixel_format().is_none() {
+                warn!(
+                    "Stream {} negotiated {:?}, which has no PixelFormat mapping - frames will be dropped",
+                    stream_id_for_callbacks, format.format
                 );
             }
+
+            *negotiated_for_param.borrow_mut() = Some(format.clone());
+            negotiated_formats_for_param
+                .lock()
+                .unwrap()
+                .insert(stream_id_for_callbacks, format.clone());
+            let _ = event_tx_for_param.send(PipeWireEvent::StreamFormatChanged(stream_id_for_callbacks, format));
+
+            // Announce which buffer types we can consume now that the format is
+            // fixed, and request metadata, with the current configuration
+            let params = build_buffer_and_meta_params(&config_for_param.borrow());
+            match params {
+                Ok(params) => {
+                    let mut pods: Vec<&Pod> = params.iter().filter_map(|bytes| Pod::from_bytes(bytes)).collect();
+                    if let Err(e) = stream.update_params(&mut pods) {
+                        warn!(
+                            "Stream {} failed to update buffer params: {}",
+                            stream_id_for_callbacks, e
+                        );
+                    }
+                }
+                Err(e) => warn!(
+                    "Stream {} failed to build buffer params: {}",
+                    stream_id_for_callbacks, e
+                ),
+            }
+        })
+        .remove_buffer(move |_stream, _user_data, buffer| {
+            // The buffer (and its FDs) is going away: drop its mappings now so
+            // a later buffer reusing the FD numbers is mapped afresh
+            dmabuf_cache_for_remove.borrow_mut().remove_buffer(buffer as usize);
         })
         .process(move |stream, _user_data| {
             // This callback is called when a new frame buffer is available
             info!("🎬 process() callback fired for stream {}", stream_id_for_callbacks);
-            if let Some(mut buffer) = stream.dequeue_buffer() {
+            if let Some(mut buffer) = DequeuedBuffer::dequeue(stream) {
                 info!("🎬 Got buffer from stream {}", stream_id_for_callbacks);
-
-                // Extract frame data from buffer
-                if let Some(data) = buffer.datas_mut().first_mut() {
-                    // Get buffer chunk info
-                    let chunk = data.chunk();
-                    let size = chunk.size() as usize;
-                    let offset = chunk.offset() as usize;
-                    let data_type = data.type_();
-
-                    // Extract pixel data based on buffer type
-                    // Access raw spa_data structure to get FD
-                    let raw_data = data.as_raw();
-                    let fd = raw_data.fd as RawFd;
-
-                    info!(
-                        "🎬 Buffer: type={}, size={}, offset={}, fd={}",
-                        data_type.as_raw(),
-                        size,
-                        offset,
-                        fd
-                    );
-
-                    let pixel_data: Option<Vec<u8>> = match data_type {
-                        // MemPtr: Direct memory access via data.data()
-                        libspa::buffer::DataType::MemPtr => {
-                            if let Some(mapped_data) = data.data() {
-                                if offset + size <= mapped_data.len() {
-                                    info!("🎬 MemPtr buffer: copying {} bytes (offset={})", size, offset);
-                                    Some(mapped_data[offset..offset + size].to_vec())
-                                } else {
-                                    warn!(
-                                        "MemPtr buffer bounds invalid: offset={}, size={}, len={}",
-                                        offset,
-                                        size,
-                                        mapped_data.len()
-                                    );
-                                    None
-                                }
-                            } else {
-                                warn!("MemPtr buffer but data.data() returned None");
-                                None
+                let header = buffer.header_meta();
+                let damage = buffer.damage_meta();
+                let (enable_cursor, frame_data_mode, max_held_buffers) = {
+                    let config = config_for_process.borrow();
+                    (config.enable_cursor, config.frame_data_mode, config.max_held_buffers())
+                };
+
+                // Cursor metadata arrives on every buffer, including cursor-only
+                // buffers without pixel data; forward only actual changes
+                if enable_cursor {
+                    if let Some(cursor) = buffer.cursor_meta() {
+                        let state = (cursor.position, cursor.visible);
+                        if cursor.bitmap.is_some() || last_cursor != Some(state) {
+                            last_cursor = Some(state);
+                            let update = CursorUpdate::from_meta(stream_id_for_callbacks, cursor);
+                            if cursor_tx.try_send(update).is_err() {
+                                trace!(
+                                    "Stream {} cursor channel full, dropping update",
+                                    stream_id_for_callbacks
+                                );
                             }
                         }
+                    }
+                }
 
-                        // MemFd: File descriptor with memory mapping
-                        libspa::buffer::DataType::MemFd => {
-                            if let Some(mapped_data) = data.data() {
-                                if offset + size <= mapped_data.len() {
-                                    info!("🎬 MemFd buffer: copying {} bytes (offset={})", size, offset);
-                                    Some(mapped_data[offset..offset + size].to_vec())
-                                } else {
-                                    warn!(
-                                        "MemFd buffer bounds invalid: offset={}, size={}, len={}",
-                                        offset,
-                                        size,
-                                        mapped_data.len()
-                                    );
-                                    None
-                                }
-                            } else if fd >= 0 {
-                                // Fallback: manual mmap of MemFd
-                                info!("🎬 MemFd buffer: using manual mmap (FD={})", fd);
-                                match mmap_fd_buffer(fd, size, offset) {
-                                    Ok(data) => Some(data),
-                                    Err(e) => {
-                                        warn!("Failed to mmap MemFd buffer: {}", e);
-                                        None
-                                    }
-                                }
-                            } else {
-                                warn!("MemFd buffer but no valid FD (fd={})", fd);
-                                None
-                            }
-                        }
+                let Some(mut format) = negotiated_for_process.borrow().clone() else {
+                    debug!(
+                        "Stream {} delivered a buffer before format negotiation - dropping",
+                        stream_id_for_callbacks
+                    );
+                    return;
+                };
+                let Some(pixel_format) = format.pixel_format() else {
+                    trace!(
+                        "Stream {} dropping frame in unsupported format {:?}",
+                        stream_id_for_callbacks,
+                        format.format
+                    );
+                    return;
+                };
 
-                        // DmaBuf: GPU memory buffer - use cached mmap to avoid syscalls
-                        libspa::buffer::DataType::DmaBuf => {
-                            if fd >= 0 {
-                                // Check cache first
-                                let mut cache = dmabuf_cache_for_process.borrow_mut();
-
-                                // Check cache first, or create new mapping
-                                let mapped_ptr_opt = if let Some(&(ptr, _sz)) = cache.get(&fd) {
-                                    // Use cached mapping (no syscall!)
-                                    debug!("🎬 DMA-BUF FD={}: using cached mmap", fd);
-                                    Some(ptr)
-                                } else {
-                                    // First time seeing this FD - mmap it
-                                    info!("🎬 DMA-BUF buffer: mmapping {} bytes from FD={} (first time)", size, fd);
-
-                                    use nix::sys::mman::{mmap, MapFlags, ProtFlags};
-                                    use std::os::fd::BorrowedFd;
-
-                                    // Calculate page-aligned mapping
-                                    // SAFETY: sysconf(_SC_PAGESIZE) always returns a valid value
-                                    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
-                                    let map_offset = (offset / page_size) * page_size;
-                                    let map_size = size + (offset - map_offset);
-
-                                    match NonZeroUsize::new(map_size) {
-                                        Some(nz_size) => {
-                                            // SAFETY: FD is valid from PipeWire buffer (valid during callback).
-                                            // We cache the mapping for reuse across frames.
-                                            unsafe {
-                                                let borrowed_fd = BorrowedFd::borrow_raw(fd);
-                                                match mmap(
-                                                    None,
-                                                    nz_size,
-                                                    ProtFlags::PROT_READ,
-                                                    MapFlags::MAP_SHARED,
-                                                    Some(borrowed_fd),
-                                                    map_offset as i64,
-                                                ) {
-                                                    Ok(ptr) => {
-                                                        cache.insert(fd, (ptr, map_size));
-                                                        info!("🎬 DMA-BUF mmap cached for FD={}", fd);
-                                                        Some(ptr)
-                                                    }
-                                                    Err(e) => {
-                                                        warn!("Failed to mmap DMA-BUF FD={}: {}", fd, e);
-                                                        None
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        None => {
-                                            warn!("Invalid map size for DMA-BUF FD={}", fd);
-                                            None
-                                        }
-                                    }
-                                };
-
-                                // Copy data from mapping (cached or fresh)
-                                if let Some(mapped_ptr) = mapped_ptr_opt {
-                                    // SAFETY: mapped_ptr is valid from successful mmap above or cache.
-                                    // offset + size <= map_size was verified during mmap.
-                                    // Vec capacity is allocated before writing.
-                                    let result = unsafe {
-                                        let src_ptr = (mapped_ptr as *const u8).add(offset);
-                                        let mut vec = Vec::with_capacity(size);
-                                        std::ptr::copy_nonoverlapping(src_ptr, vec.as_mut_ptr(), size);
-                                        vec.set_len(size);
-                                        vec
-                                    };
-                                    debug!("🎬 DMA-BUF: extracted {} bytes from mapping", result.len());
-                                    Some(result)
-                                } else {
-                                    warn!("Failed to get DMA-BUF mapping for FD={}", fd);
-                                    None
-                                }
-                            } else {
-                                warn!("DMA-BUF buffer but no valid FD (fd={})", fd);
-                                None
-                            }
-                        }
+                // Read before borrowing the data entries mutably below
+                let buffer_key = buffer.key();
+                let datas = buffer.datas_mut();
+                let Some(first) = datas.first() else {
+                    warn!("No data in buffer for stream {}", stream_id_for_callbacks);
+                    return;
+                };
+
+                let first_chunk = first.chunk();
+                let size = first_chunk.size() as usize;
+                let chunk_stride = first_chunk.stride();
+                if size == 0 {
+                    // Metadata-only buffer (e.g. the cursor moved but the screen didn't)
+                    trace!("Stream {} buffer carries no pixel data", stream_id_for_callbacks);
+                    return;
+                }
 
-                        // Unknown/Invalid type
-                        _ => {
-                            warn!(
-                                "Unknown buffer type: {} (raw={})",
-                                if data_type == libspa::buffer::DataType::Invalid {
-                                    "Invalid"
-                                } else {
-                                    "Unknown"
-                                },
-                                data_type.as_raw()
-                            );
-                            None
-                        }
+                let stride = frame_stride(chunk_stride, size, format.width, format.height, pixel_format);
+                let default_planes = pixel_format.plane_layout(format.height, stride);
+
+                // Planar formats may deliver each plane in its own data entry,
+                // with its own offset and stride; otherwise all planes share
+                // the first entry in the default layout
+                let separate_planes = pixel_format.is_planar() && datas.len() >= pixel_format.plane_count();
+                let data_count = if separate_planes { pixel_format.plane_count() } else { 1 };
+
+                // Zero-copy: lend the buffer itself while the producer keeps
+                // at least one buffer to fill
+                let lendable = frame_data_mode == FrameDataMode::ZeroCopy
+                    && data_count == 1
+                    && held_buffers_for_process.load(Ordering::Acquire) < max_held_buffers;
+                let lent_chunk = if lendable { mapped_chunk(&mut datas[0]) } else { None };
+
+                let mut planes = Vec::with_capacity(pixel_format.plane_count());
+                let pixel_data = if let Some((ptr, len)) = lent_chunk {
+                    held_buffers_for_process.fetch_add(1, Ordering::AcqRel);
+                    let release = BufferRelease {
+                        generation,
+                        buffer: buffer.into_raw(),
+                    };
+                    let buffer_return = buffer_return.clone();
+                    // SAFETY: the buffer stays dequeued, and its memory mapped, until
+                    // the release is processed on the PipeWire thread; streams with
+                    // held buffers are kept alive until then.
+                    let held = unsafe { HeldBuffer::new(ptr, len, move || buffer_return.send(release)) };
+                    FrameData::held(held)
+                } else {
+                    let mut storage = match frame_data_mode {
+                        FrameDataMode::Copy => PooledBuffer::detached(size),
+                        FrameDataMode::Pooled | FrameDataMode::ZeroCopy => frame_pool.acquire(size),
                     };
 
-                    if let Some(pixel_data) = pixel_data {
-                        // Calculate proper stride with alignment
-                        // CRITICAL: Don't use (size/height) - that's wrong if buffer has padding
-                        // Proper stride = width * bytes_per_pixel, aligned to 16 bytes
-                        let bytes_per_pixel = 4; // BGRA/BGRx = 4 bytes
-                        let calculated_stride = ((config.width * bytes_per_pixel + 15) / 16) * 16;
-
-                        // Verify our calculated stride matches buffer
-                        let expected_size = calculated_stride * config.height;
-                        let actual_stride = if expected_size as usize == size {
-                            calculated_stride
-                        } else {
-                            // Buffer size doesn't match our calculation - compute actual stride
-                            // This handles cases where compositor uses different alignment
-                            (size / config.height as usize) as u32
+                    let mut cache = dmabuf_cache_for_process.borrow_mut();
+                    for (index, data) in datas.iter_mut().take(data_count).enumerate() {
+                        let offset = storage.len();
+                        let plane_stride = match data.chunk().stride() {
+                            chunk_stride if chunk_stride > 0 && index > 0 => chunk_stride as u32,
+                            _ => default_planes[index].stride,
                         };
 
-                        // Log stride calculation details for first few frames
-                        static LOGGED_FRAMES: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
-                        let frame_count = LOGGED_FRAMES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-                        if frame_count < 5 {
-                            info!("📐 Buffer analysis frame {}:", frame_count);
-                            info!(
-                                "   Size: {} bytes, Width: {}, Height: {}",
-                                size, config.width, config.height
-                            );
-                            info!(
-                                "   Calculated stride: {} bytes/row (16-byte aligned)",
-                                calculated_stride
+                        if append_data_chunk(data, buffer_key, &mut cache, &mut storage).is_none() {
+                            debug!(
+                                "Could not extract plane {} from buffer of stream {}",
+                                index, stream_id_for_callbacks
                             );
-                            info!("   Actual stride: {} bytes/row", actual_stride);
-                            info!("   Expected buffer size: {} bytes", expected_size);
-                            info!("   Buffer type: {} (1=MemPtr, 2=MemFd, 3=DmaBuf)", data_type.as_raw());
-                            info!(
-                                "   Pixel format: {:?}",
-                                config.preferred_format.unwrap_or(PixelFormat::BGRx)
-                            );
-
-                            // Log first 32 bytes as hex to verify byte order
-                            if pixel_data.len() >= 32 {
-                                let hex_preview: Vec<String> =
-                                    pixel_data[0..32].iter().map(|b| format!("{:02x}", b)).collect();
-                                info!("   First 32 bytes (hex): {}", hex_preview.join(" "));
-                            }
+                            return;
                         }
+                        planes.push(FramePlane::new(offset, plane_stride, storage.len() - offset));
+                    }
+                    FrameData::from(storage)
+                };
+                if !separate_planes {
+                    planes = default_planes;
+                }
 
-                        if actual_stride != calculated_stride {
-                            warn!("⚠️  Stride mismatch detected:");
-                            warn!("   Calculated: {} bytes/row", calculated_stride);
-                            warn!("   Actual: {} bytes/row (from buffer size)", actual_stride);
-                            warn!("   This may cause horizontal line artifacts!");
-                        }
+                if stride != format.stride {
+                    // Publish the producer's real stride (first buffer or after renegotiation)
+                    debug!(
+                        "Stream {} stride: {} bytes/row (chunk stride {}, {} bytes)",
+                        stream_id_for_callbacks, stride, chunk_stride, size
+                    );
+                    format.stride = stride;
+                    *negotiated_for_process.borrow_mut() = Some(format.clone());
+                    negotiated_formats_for_process
+                        .lock()
+                        .unwrap()
+                        .insert(stream_id_for_callbacks, format.clone());
+                }
 
-                        // Create VideoFrame from extracted pixel data
-                        let frame = VideoFrame {
-                            frame_id: stream_id_for_callbacks as u64,
-                            pts: 0, // TODO: Extract from buffer metadata
-                            dts: 0,
-                            duration: 16_666_667, // ~60fps default
-                            width: config.width,
-                            height: config.height,
-                            stride: actual_stride,
-                            format: config.preferred_format.unwrap_or(PixelFormat::BGRx),
-                            monitor_index: 0,
-                            data: StdArc::new(pixel_data),
-                            capture_time: SystemTime::now(),
-                            damage_regions: Vec::new(),
-                            flags: FrameFlags::new(),
-                        };
+                let timing = frame_clock.stamp(header, format.frame_duration_ns(), monotonic_now_ns());
+                let mut flags = FrameFlags::new();
+                if header.is_some_and(|h| h.is_corrupted()) {
+                    flags.set_corrupted();
+                }
 
-                        // Send frame to async runtime
-                        if let Err(e) = frame_tx_for_process.try_send(frame) {
-                            warn!("Failed to send frame: {} (channel full, backpressure)", e);
-                        } else {
-                            debug!("Frame sent to async runtime");
-                        }
-                    } else {
-                        debug!("Could not extract pixel data from buffer");
-                    }
+                // Create VideoFrame from extracted pixel data
+                let frame = VideoFrame {
+                    frame_id: timing.frame_id,
+                    pts: timing.pts,
+                    dts: timing.dts,
+                    duration: timing.duration,
+                    seq: timing.seq,
+                    width: format.width,
+                    height: format.height,
+                    stride,
+                    format: pixel_format,
+                    colorimetry: format.colorimetry,
+                    // Stream ID, used to route the frame to its consumer
+                    monitor_index: stream_id_for_callbacks,
+                    data: pixel_data,
+                    planes,
+                    capture_time: SystemTime::now(),
+                    // Empty when the producer sent no damage: consumers
+                    // treat that as a full-frame update
+                    damage_regions: damage
+                        .iter()
+                        .flatten()
+                        .filter_map(|region| region.clip(format.width, format.height))
+                        .collect(),
+                    flags,
+                };
+
+                // Send frame to async runtime
+                if let Err(e) = frame_tx_for_process.try_send(frame) {
+                    warn!("Failed to send frame: {} (channel full, backpressure)", e);
                 } else {
-                    warn!("No data in buffer for stream {}", stream_id_for_callbacks);
+                    debug!("Frame sent to async runtime");
                 }
             } else {
                 debug!(
@@ -951,16 +1723,21 @@ fn create_stream_on_thread(
     info!("✅ Stream {} callbacks registered successfully", stream_id);
 
     // Connect stream to node with format parameters
-    let params = build_stream_parameters(&config)?;
+    let params = build_stream_parameters(&config.borrow())?;
     info!(
         "📋 Stream {} connecting with {} format parameters",
         stream_id,
         params.len()
     );
 
-    // Convert Vec<Pod> to Vec<&Pod> for connect() API
-    let param_refs: Vec<&Pod> = params.iter().collect();
-    let mut param_slice = param_refs;
+    // Convert serialized pods to Vec<&Pod> for connect() API
+    let mut param_slice: Vec<&Pod> = params
+        .iter()
+        .map(|bytes| {
+            Pod::from_bytes(bytes)
+                .ok_or_else(|| PipeWireError::FormatNegotiationFailed("Invalid format parameter pod".to_string()))
+        })
+        .collect::<Result<_>>()?;
 
     info!(
         "🔌 Calling stream.connect() for stream {} with flags: AUTOCONNECT | MAP_BUFFERS | RT_PROCESS",
@@ -1004,15 +1781,183 @@ fn create_stream_on_thread(
         stream,
         _listener,
         config,
-        state: StreamState::Connecting, // Initial state
+        state: stream_state,
+        closing,
         frame_count: 0,
         frame_tx,
+        generation,
+        held_buffers,
     })
 }
 
+/// Buffer dequeued from a stream, requeued on drop
+///
+/// Equivalent to `pipewire::buffer::Buffer`, but also exposes the raw
+/// `spa_buffer` so metadata blocks attached by the producer can be read.
+struct DequeuedBuffer<'s> {
+    stream: &'s pipewire::stream::StreamRef,
+    buffer: std::ptr::NonNull<pipewire::sys::pw_buffer>,
+}
+
+impl<'s> DequeuedBuffer<'s> {
+    /// Dequeue the next filled buffer, if any
+    fn dequeue(stream: &'s pipewire::stream::StreamRef) -> Option<Self> {
+        // SAFETY: dequeue_raw_buffer returns null or a buffer owned by us until
+        // it is queued back, which Drop guarantees.
+        let buffer = std::ptr::NonNull::new(unsafe { stream.dequeue_raw_buffer() })?;
+        Some(Self { stream, buffer })
+    }
+
+    /// Key identifying the buffer for as long as the stream holds it
+    fn key(&self) -> usize {
+        self.buffer.as_ptr() as usize
+    }
+
+    /// Raw SPA buffer (may be null)
+    fn spa_buffer(&self) -> *const ffi::spa_sys::spa_buffer {
+        // SAFETY: self.buffer is a valid pw_buffer while dequeued
+        unsafe { self.buffer.as_ref().buffer }
+    }
+
+    /// `SPA_META_Header` metadata, if the producer attached it
+    fn header_meta(&self) -> Option<ffi::BufferMetadata> {
+        // SAFETY: spa_buffer is null or valid while the buffer is dequeued
+        unsafe { ffi::read_header_meta(self.spa_buffer()) }
+    }
+
+    /// `SPA_META_VideoDamage` regions, if the producer attached them
+    fn damage_meta(&self) -> Option<Vec<ffi::DamageRegion>> {
+        // SAFETY: spa_buffer is null or valid while the buffer is dequeued
+        unsafe { ffi::read_damage_meta(self.spa_buffer()) }
+    }
+
+    /// `SPA_META_Cursor` metadata, if the producer attached a valid cursor
+    fn cursor_meta(&self) -> Option<ffi::CursorMeta> {
+        // SAFETY: spa_buffer is null or valid while the buffer is dequeued
+        unsafe { ffi::read_cursor_meta(self.spa_buffer()) }
+    }
+
+    /// Data planes of the buffer
+    fn datas_mut(&mut self) -> &mut [libspa::buffer::Data] {
+        let buffer = self.spa_buffer();
+        // SAFETY: Data is repr(transparent) over spa_data; the datas array holds
+        // n_datas entries and stays valid while the buffer is dequeued.
+        unsafe {
+            match buffer.as_ref() {
+                Some(b) if b.n_datas > 0 && !b.datas.is_null() => {
+                    std::slice::from_raw_parts_mut(b.datas.cast::<libspa::buffer::Data>(), b.n_datas as usize)
+                }
+                _ => &mut [],
+            }
+        }
+    }
+}
+
+impl DequeuedBuffer<'_> {
+    /// Keep the buffer dequeued, handing ownership to the caller
+    ///
+    /// The caller must queue the buffer back to the same stream.
+    fn into_raw(self) -> std::ptr::NonNull<pipewire::sys::pw_buffer> {
+        let this = std::mem::ManuallyDrop::new(self);
+        this.buffer
+    }
+}
+
+impl Drop for DequeuedBuffer<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the buffer was dequeued from this stream and is queued back once
+        unsafe { self.stream.queue_raw_buffer(self.buffer.as_ptr()) }
+    }
+}
+
+/// Timing assigned to a captured frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameTiming {
+    frame_id: u64,
+    pts: u64,
+    dts: u64,
+    duration: u64,
+    seq: u64,
+}
+
+/// Per-stream frame numbering and timestamping
+#[derive(Debug, Default)]
+struct FrameClock {
+    /// Next frame ID
+    next_frame_id: u64,
+
+    /// PTS of the previous frame
+    last_pts: Option<u64>,
+}
+
+impl FrameClock {
+    /// Assign timing to the next frame
+    ///
+    /// Timestamps come from the producer's `SPA_META_Header` when present,
+    /// otherwise from `now_ns` (same clock, `CLOCK_MONOTONIC`). The duration
+    /// is the negotiated frame interval, or the PTS delta for variable
+    /// framerate streams.
+    fn stamp(&mut self, header: Option<ffi::BufferMetadata>, frame_duration: Option<u64>, now_ns: u64) -> FrameTiming {
+        let frame_id = self.next_frame_id;
+        self.next_frame_id += 1;
+
+        let (pts, dts, seq) = match header {
+            Some(header) if header.pts != 0 => (header.pts, header.dts(), header.seq),
+            Some(header) => (now_ns, now_ns, header.seq),
+            None => (now_ns, now_ns, 0),
+        };
+
+        let duration = frame_duration
+            .or_else(|| self.last_pts.and_then(|last| pts.checked_sub(last)))
+            .unwrap_or(0);
+        self.last_pts = Some(pts);
+
+        FrameTiming {
+            frame_id,
+            pts,
+            dts,
+            duration,
+            seq,
+        }
+    }
+}
+
+/// Current `CLOCK_MONOTONIC` time in nanoseconds (PipeWire's timestamp clock)
+fn monotonic_now_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts is a valid timespec; CLOCK_MONOTONIC is always available
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// Determine the row stride of a captured buffer
+///
+/// Prefers the stride reported in the buffer chunk. Producers that leave it
+/// at 0 get the stride derived from the chunk size for packed formats, or the
+/// tightly packed row size for planar formats (where size / height would
+/// include the chroma planes).
+fn frame_stride(chunk_stride: i32, size: usize, width: u32, height: u32, format: PixelFormat) -> u32 {
+    // A width too large for a u32 stride gets one no buffer can satisfy, so
+    // the frame is rejected when its planes are read
+    let packed_stride = format.min_stride(width).unwrap_or(u32::MAX);
+    if chunk_stride > 0 {
+        return chunk_stride as u32;
+    }
+
+    if !format.is_planar() && height > 0 {
+        let derived = (size / height as usize) as u32;
+        if derived >= packed_stride {
+            return derived;
+        }
+    }
+
+    packed_stride
+}
+
 /// Build stream parameters for format negotiation
 ///
-/// Constructs SPA Pod parameters for video format, size, and framerate negotiation.
+/// Constructs serialized SPA `EnumFormat` pods for video format, size, and
+/// framerate negotiation. Turn them into pods with [`Pod::from_bytes`].
 ///
 /// # Format Negotiation Strategy
 ///
@@ -1021,22 +1966,49 @@ fn create_stream_on_thread(
 /// - MemFd (type 2): Memory-mapped FD via mmap()
 /// - DmaBuf (type 3): GPU buffer via mmap() with FD
 ///
-/// We provide explicit format parameters so PipeWire can complete negotiation.
-/// This enables hardware acceleration when available (DMA-BUF) while maintaining compatibility.
-fn build_stream_parameters(config: &StreamConfig) -> Result<Vec<Pod>> {
+/// When DMA-BUF is enabled, a pod carrying the supported DRM modifiers is
+/// offered first so producers that can share GPU buffers pick it. A pod
+/// without modifiers always follows as the shared-memory fallback.
+pub(crate) fn build_stream_parameters(config: &StreamConfig) -> Result<Vec<Vec<u8>>> {
+    let formats = config.negotiation_formats();
+    let framerate = Fraction {
+        num: config.framerate,
+        denom: 1,
+    };
+    let max_framerate = config
+        .max_framerate
+        .map_or(ffi::MAX_VIDEO_FRAMERATE, |fps| Fraction { num: fps, denom: 1 });
+
     info!(
-        "🎬 Attempting to build format parameters: {}x{} @ {}fps",
-        config.width, config.height, config.framerate
+        "🎬 Building format parameters: {}x{} @ {}fps (max {:?}), formats={:?}",
+        config.width, config.height, config.framerate, config.max_framerate, formats
     );
 
-    // Try using pipewire-rs builder API instead of manual Pod construction
-    // This might be simpler and more reliable
+    let mut params = Vec::with_capacity(2);
+
+    let modifiers = config.dmabuf_modifiers();
+    if !modifiers.is_empty() {
+        debug!("   Offering DMA-BUF modifiers: {:?}", modifiers);
+        params.push(ffi::build_format_params(
+            config.width,
+            config.height,
+            framerate,
+            max_framerate,
+            &formats,
+            &modifiers,
+        )?);
+    }
+
+    params.push(ffi::build_format_params(
+        config.width,
+        config.height,
+        framerate,
+        max_framerate,
+        &formats,
+        &[],
+    )?);
 
-    // For now, return empty and let PipeWire auto-negotiate
-    // We'll need to investigate why format params aren't working
-    warn!("⚠️  Format parameter building not working - using auto-negotiation");
-    warn!("⚠️  This may cause stream to not start on some compositors");
-    Ok(Vec::new())
+    Ok(params)
 }
 
 #[cfg(test)]
@@ -1049,4 +2021,80 @@ mod tests {
         // Cannot test without valid FD from portal
         // Full tests require integration testing with actual portal
     }
+
+    #[test]
+    fn test_frame_stride_prefers_chunk_stride() {
+        assert_eq!(frame_stride(7936, 7936 * 1080, 1920, 1080, PixelFormat::BGRx), 7936);
+    }
+
+    #[test]
+    fn test_frame_stride_fallbacks() {
+        // Packed: derived from chunk size, including row padding
+        assert_eq!(frame_stride(0, 7696 * 1080, 1921, 1080, PixelFormat::BGRA), 7696);
+        // RGB 24-bit with a tightly packed buffer
+        assert_eq!(frame_stride(0, 1920 * 3 * 1080, 1920, 1080, PixelFormat::RGB), 5760);
+        // Planar: size includes chroma planes, so use the luma row size
+        assert_eq!(
+            frame_stride(0, 1920 * 1080 * 3 / 2, 1920, 1080, PixelFormat::NV12),
+            1920
+        );
+    }
+
+    #[test]
+    fn test_frame_clock_uses_header_timestamps() {
+        let mut clock = FrameClock::default();
+        let header = ffi::BufferMetadata {
+            pts: 5_000_000_000,
+            dts_offset: 0,
+            seq: 7,
+            flags: 0,
+        };
+
+        let first = clock.stamp(Some(header), Some(16_666_666), 1);
+        assert_eq!(first.frame_id, 0);
+        assert_eq!(first.pts, 5_000_000_000);
+        assert_eq!(first.dts, 5_000_000_000);
+        assert_eq!(first.seq, 7);
+        assert_eq!(first.duration, 16_666_666);
+
+        let second = clock.stamp(None, Some(16_666_666), 6_000_000_000);
+        assert_eq!(second.frame_id, 1);
+        assert_eq!(second.pts, 6_000_000_000);
+        assert_eq!(second.seq, 0);
+    }
+
+    #[test]
+    fn test_frame_clock_variable_framerate() {
+        let mut clock = FrameClock::default();
+        let at = |pts| ffi::BufferMetadata {
+            pts,
+            ..Default::default()
+        };
+
+        assert_eq!(clock.stamp(Some(at(1_000)), None, 0).duration, 0);
+        assert_eq!(clock.stamp(Some(at(21_000)), None, 0).duration, 20_000);
+        // Non-monotonic PTS never yields a bogus duration
+        assert_eq!(clock.stamp(Some(at(11_000)), None, 0).duration, 0);
+    }
+
+    #[test]
+    fn test_build_stream_parameters() {
+        let config = StreamConfig::new("test").with_dmabuf(false);
+        assert_eq!(build_stream_parameters(&config).unwrap().len(), 1);
+
+        // DMA-BUF adds a modifier-carrying pod ahead of the shared-memory fallback
+        let config = StreamConfig::new("test").with_dmabuf(true);
+        let expected = if cfg!(feature = "dmabuf") { 2 } else { 1 };
+        assert_eq!(build_stream_parameters(&config).unwrap().len(), expected);
+    }
+
+    #[test]
+    fn test_build_buffer_and_meta_params() {
+        // Buffers, header and damage metadata, plus cursor metadata if enabled
+        let config = StreamConfig::new("test").with_cursor(false);
+        assert_eq!(build_buffer_and_meta_params(&config).unwrap().len(), 3);
+
+        let config = config.with_cursor(true);
+        assert_eq!(build_buffer_and_meta_params(&config).unwrap().len(), 4);
+    }
 }
@@ -0,0 +1,415 @@
+//! Stream Recovery Supervisor
+//!
+//! Watches the lifecycle events of a `PipeWireManager` and acts on failures:
+//!
+//! - Streams that enter PipeWire's error state are re-created on the same
+//!   node after `RetryConfig::delay_for_attempt`, up to
+//!   `PipeWireConfig::max_reconnect_attempts` consecutive attempts (only with
+//!   `PipeWireConfig::auto_reconnect`).
+//! - A lost PipeWire connection cannot be recovered here because the portal
+//!   FD has been consumed; it is surfaced as `RecoveryAction::RequestNewSession`.
+//!
+//! [`RecoveryTracker`] turns events into decisions using [`recovery_action`];
+//! [`Supervisor`] carries them out on a tokio task. Outcomes are published as
+//! `StreamRecovering`, `StreamRecovered` and `RecoveryRequired` events and
+//! counted in the manager's statistics.
+
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{debug, error, info, warn};
+
+use crate::config::PipeWireConfig;
+use crate::connection::PipeWireEvent;
+use crate::error::{recovery_action, ErrorContext, PipeWireError, RecoveryAction, Result, RetryConfig};
+use crate::manager::{ManagerState, StatsCounters};
+use crate::pw_thread::{CommandSender, PipeWireThreadCommand};
+use crate::stream::{PwStreamState, StreamConfig};
+
+/// Node and configuration of each stream, needed to re-create it
+pub(crate) type StreamConfigs = Arc<Mutex<HashMap<u32, (u32, StreamConfig)>>>;
+
+/// What to do about an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Decision {
+    /// Nothing
+    None,
+
+    /// Re-create a stream after a delay
+    Restart {
+        stream_id: u32,
+        attempt: u32,
+        delay: Duration,
+    },
+
+    /// A re-created stream is streaming again
+    Recovered(u32),
+
+    /// Hand the problem to the application
+    Escalate(Option<u32>, RecoveryAction),
+}
+
+/// Recovery decisions and per-stream attempt counts
+#[derive(Debug)]
+pub(crate) struct RecoveryTracker {
+    /// Whether failed streams are re-created
+    enabled: bool,
+
+    /// Attempt limit and backoff
+    retry: RetryConfig,
+
+    /// Consecutive attempts per stream, reset once it streams again
+    attempts: HashMap<u32, u32>,
+}
+
+impl RecoveryTracker {
+    /// Create a tracker
+    pub(crate) fn new(enabled: bool, retry: RetryConfig) -> Self {
+        Self {
+            enabled,
+            retry,
+            attempts: HashMap::new(),
+        }
+    }
+
+    /// Create a tracker from the manager configuration
+    pub(crate) fn from_config(config: &PipeWireConfig) -> Self {
+        Self::new(
+            config.auto_reconnect,
+            RetryConfig {
+                max_retries: config.max_reconnect_attempts,
+                ..RetryConfig::default()
+            },
+        )
+    }
+
+    /// Decide how to react to a lifecycle event
+    pub(crate) fn on_event(&mut self, event: &PipeWireEvent) -> Decision {
+        match event {
+            PipeWireEvent::StreamError(stream_id, message) => self.on_error(
+                &PipeWireError::StreamFailed(*stream_id, message.clone()),
+                Some(*stream_id),
+            ),
+            PipeWireEvent::StreamStateChanged(stream_id, PwStreamState::Streaming) => {
+                if self.attempts.remove(stream_id).is_some() {
+                    Decision::Recovered(*stream_id)
+                } else {
+                    Decision::None
+                }
+            }
+            PipeWireEvent::StreamRemoved(stream_id) => {
+                self.forget(*stream_id);
+                Decision::None
+            }
+            PipeWireEvent::Disconnected => self.on_error(
+                &PipeWireError::ConnectionFailed("PipeWire connection lost".to_string()),
+                None,
+            ),
+            _ => Decision::None,
+        }
+    }
+
+    /// Decide how to react to a failure (reported or of a re-creation)
+    pub(crate) fn on_error(&mut self, error: &PipeWireError, stream_id: Option<u32>) -> Decision {
+        let attempt = stream_id.and_then(|id| self.attempts.get(&id).copied()).unwrap_or(0);
+        let mut context = ErrorContext::new()
+            .with_attempt(attempt)
+            .with_details(error.to_string());
+        if let Some(id) = stream_id {
+            context = context.with_stream_id(id);
+        }
+
+        match recovery_action(error, &context, &self.retry) {
+            RecoveryAction::RestartStream(id) if self.enabled => {
+                self.attempts.insert(id, attempt + 1);
+                Decision::Restart {
+                    stream_id: id,
+                    attempt: attempt + 1,
+                    delay: self.retry.delay_for_attempt(attempt),
+                }
+            }
+            // Recovery disabled: the StreamError event already tells the application
+            RecoveryAction::RestartStream(_) => Decision::None,
+            action => {
+                if let Some(id) = stream_id {
+                    self.forget(id);
+                }
+                Decision::Escalate(stream_id, action)
+            }
+        }
+    }
+
+    /// Drop the attempt count of a stream that no longer exists
+    pub(crate) fn forget(&mut self, stream_id: u32) {
+        self.attempts.remove(&stream_id);
+    }
+}
+
+/// Carries out recovery decisions for a manager
+pub(crate) struct Supervisor {
+    /// Decisions
+    tracker: RecoveryTracker,
+
+    /// PipeWire thread commands
+    commands: CommandSender,
+
+    /// Streams that may be re-created (removed by `remove_stream`)
+    stream_configs: StreamConfigs,
+
+    /// Manager state, set to `Error` when the connection is lost
+    state: Arc<RwLock<ManagerState>>,
+
+    /// Publishes recovery events
+    publisher: broadcast::Sender<PipeWireEvent>,
+
+    /// Recovery counters
+    counters: Arc<StatsCounters>,
+}
+
+impl Supervisor {
+    /// Create a supervisor
+    pub(crate) fn new(
+        tracker: RecoveryTracker,
+        commands: CommandSender,
+        stream_configs: StreamConfigs,
+        state: Arc<RwLock<ManagerState>>,
+        publisher: broadcast::Sender<PipeWireEvent>,
+        counters: Arc<StatsCounters>,
+    ) -> Self {
+        Self {
+            tracker,
+            commands,
+            stream_configs,
+            state,
+            publisher,
+            counters,
+        }
+    }
+
+    /// Handle events until the event channel closes
+    ///
+    /// Recoveries run one at a time; events arriving meanwhile are handled
+    /// afterwards.
+    pub(crate) async fn run(mut self, mut events: broadcast::Receiver<PipeWireEvent>) {
+        debug!("Recovery supervisor started");
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Recovery supervisor missed {} events", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let mut decision = self.tracker.on_event(&event);
+            while let Decision::Restart {
+                stream_id,
+                attempt,
+                delay,
+            } = decision
+            {
+                decision = self.restart(stream_id, attempt, delay).await;
+            }
+            self.apply(decision).await;
+        }
+        debug!("Recovery supervisor exiting");
+    }
+
+    /// Re-create a stream after `delay`
+    ///
+    /// # Returns
+    ///
+    /// The follow-up decision if re-creation failed
+    async fn restart(&mut self, stream_id: u32, attempt: u32, delay: Duration) -> Decision {
+        self.counters.stream_restarts.fetch_add(1, Ordering::Relaxed);
+        info!("Recovering stream {} (attempt {}) in {:?}", stream_id, attempt, delay);
+        let _ = self.publisher.send(PipeWireEvent::StreamRecovering(stream_id, attempt));
+        tokio::time::sleep(delay).await;
+
+        // Holding the lock keeps remove_stream from racing the re-creation
+        let stream_configs = self.stream_configs.lock().await;
+        let Some((node_id, config)) = stream_configs.get(&stream_id).cloned() else {
+            debug!("Stream {} was removed, abandoning recovery", stream_id);
+            drop(stream_configs);
+            self.tracker.forget(stream_id);
+            return Decision::None;
+        };
+
+        let result = recreate_stream(&self.commands, stream_id, node_id, config).await;
+        drop(stream_configs);
+        match result {
+            Ok(()) => Decision::None,
+            Err(e) => {
+                warn!("Re-creating stream {} failed: {}", stream_id, e);
+                self.tracker.on_error(&e, Some(stream_id))
+            }
+        }
+    }
+
+    /// Report a decision that needs no further work
+    async fn apply(&mut self, decision: Decision) {
+        match decision {
+            Decision::None | Decision::Restart { .. } => {}
+            Decision::Recovered(stream_id) => {
+                self.counters.streams_recovered.fetch_add(1, Ordering::Relaxed);
+                info!("Stream {} recovered", stream_id);
+                let _ = self.publisher.send(PipeWireEvent::StreamRecovered(stream_id));
+            }
+            Decision::Escalate(stream_id, action) => {
+                if action == RecoveryAction::RequestNewSession {
+                    self.counters.session_requests.fetch_add(1, Ordering::Relaxed);
+                    error!("PipeWire connection lost - a new portal session is required");
+                    *self.state.write().await = ManagerState::Error;
+                } else if let Some(id) = stream_id {
+                    self.counters.recovery_failures.fetch_add(1, Ordering::Relaxed);
+                    error!("Giving up on stream {}: {:?}", id, action);
+                }
+                let _ = self.publisher.send(PipeWireEvent::RecoveryRequired(stream_id, action));
+            }
+        }
+    }
+}
+
+/// Destroy a stream on the PipeWire thread and create it again
+pub(crate) async fn recreate_stream(
+    commands: &CommandSender,
+    stream_id: u32,
+    node_id: u32,
+    config: StreamConfig,
+) -> Result<()> {
+    match commands
+        .request(|response_tx| PipeWireThreadCommand::DestroyStream { stream_id, response_tx })
+        .await?
+    {
+        // Already gone is fine: it is about to be replaced
+        Ok(()) | Err(PipeWireError::StreamNotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    commands
+        .request(|response_tx| PipeWireThreadCommand::CreateStream {
+            stream_id,
+            node_id,
+            config,
+            response_tx,
+        })
+        .await?
+        .map_err(|e| PipeWireError::StreamCreationFailed(format!("Stream re-creation failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(max_retries: u32) -> RecoveryTracker {
+        RecoveryTracker::new(
+            true,
+            RetryConfig {
+                max_retries,
+                ..RetryConfig::default()
+            },
+        )
+    }
+
+    fn stream_error(stream_id: u32) -> PipeWireEvent {
+        PipeWireEvent::StreamError(stream_id, "error".to_string())
+    }
+
+    #[test]
+    fn test_stream_errors_restart_with_backoff() {
+        let mut tracker = tracker(3);
+        let retry = RetryConfig::default();
+
+        for attempt in 1..=3 {
+            assert_eq!(
+                tracker.on_event(&stream_error(1)),
+                Decision::Restart {
+                    stream_id: 1,
+                    attempt,
+                    delay: retry.delay_for_attempt(attempt - 1),
+                }
+            );
+        }
+
+        // Attempts exhausted
+        assert_eq!(
+            tracker.on_event(&stream_error(1)),
+            Decision::Escalate(Some(1), RecoveryAction::Fail)
+        );
+
+        // Counting starts over afterwards
+        assert!(matches!(
+            tracker.on_event(&stream_error(1)),
+            Decision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_streaming_again_resets_attempts() {
+        let mut tracker = tracker(2);
+        let streaming = PipeWireEvent::StreamStateChanged(1, PwStreamState::Streaming);
+
+        assert_eq!(tracker.on_event(&streaming), Decision::None);
+        assert!(matches!(tracker.on_event(&stream_error(1)), Decision::Restart { .. }));
+        assert_eq!(tracker.on_event(&streaming), Decision::Recovered(1));
+        assert_eq!(tracker.on_event(&streaming), Decision::None);
+
+        assert!(matches!(
+            tracker.on_event(&stream_error(1)),
+            Decision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_failed_recreation_counts_as_attempt() {
+        let mut tracker = tracker(2);
+        assert!(matches!(
+            tracker.on_event(&stream_error(3)),
+            Decision::Restart { attempt: 1, .. }
+        ));
+
+        let failed = PipeWireError::StreamCreationFailed("node gone".to_string());
+        assert!(matches!(
+            tracker.on_error(&failed, Some(3)),
+            Decision::Restart { attempt: 2, .. }
+        ));
+        assert_eq!(
+            tracker.on_error(&failed, Some(3)),
+            Decision::Escalate(Some(3), RecoveryAction::Fail)
+        );
+    }
+
+    #[test]
+    fn test_disconnect_requests_new_session() {
+        let mut tracker = tracker(3);
+        assert_eq!(
+            tracker.on_event(&PipeWireEvent::Disconnected),
+            Decision::Escalate(None, RecoveryAction::RequestNewSession)
+        );
+    }
+
+    #[test]
+    fn test_disabled_recovery() {
+        let mut tracker = RecoveryTracker::new(false, RetryConfig::default());
+        assert_eq!(tracker.on_event(&stream_error(1)), Decision::None);
+
+        // Connection loss is reported regardless
+        assert_eq!(
+            tracker.on_event(&PipeWireEvent::Disconnected),
+            Decision::Escalate(None, RecoveryAction::RequestNewSession)
+        );
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = PipeWireConfig::builder().max_reconnect_attempts(0).build();
+        let mut tracker = RecoveryTracker::from_config(&config);
+        assert_eq!(
+            tracker.on_event(&stream_error(1)),
+            Decision::Escalate(Some(1), RecoveryAction::Fail)
+        );
+    }
+}
@@ -0,0 +1,513 @@
+//! Per-Stream Frame Routing
+//!
+//! The PipeWire thread delivers frames from every stream over a single
+//! `std::sync::mpsc` channel. The router drains that channel on a forwarding
+//! thread and hands each frame to the consumer registered for its stream
+//! (identified by `VideoFrame::monitor_index`, which the PipeWire thread sets
+//! to the stream ID). Cursor updates travel the same way on their own channel.
+//!
+//! Consumers that fall behind never stall the PipeWire thread: when a
+//! consumer's channel is full the item is dropped and counted against that
+//! stream only.
+//!
+//! Observers (e.g. the manager's damage tracker) see every item before it is
+//! routed, whether or not the stream has a consumer.
+//!
+//! Lifecycle events are not per-stream: [`EventRouter`] broadcasts them to
+//! every subscriber instead.
+
+use std::collections::HashMap;
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, trace, warn};
+
+use crate::connection::PipeWireEvent;
+use crate::error::{PipeWireError, Result};
+use crate::frame::{CursorUpdate, VideoFrame};
+
+/// Per-stream frame delivery statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameRouteStats {
+    /// Frames (or cursor updates) delivered to the stream's consumer
+    pub frames_forwarded: u64,
+
+    /// Frames discarded because the consumer was full, gone, or not yet subscribed
+    pub frames_dropped: u64,
+}
+
+impl FrameRouteStats {
+    /// Fraction of frames that were dropped
+    pub fn drop_rate(&self) -> f64 {
+        let total = self.frames_forwarded + self.frames_dropped;
+        if total == 0 {
+            0.0
+        } else {
+            self.frames_dropped as f64 / total as f64
+        }
+    }
+}
+
+/// Item the PipeWire thread delivers per stream
+pub(crate) trait Routed: Send + 'static {
+    /// Human-readable item name for logs
+    const KIND: &'static str;
+
+    /// Stream the item belongs to
+    fn stream_id(&self) -> u32;
+}
+
+impl Routed for VideoFrame {
+    const KIND: &'static str = "frames";
+
+    fn stream_id(&self) -> u32 {
+        self.monitor_index
+    }
+}
+
+impl Routed for CursorUpdate {
+    const KIND: &'static str = "cursor updates";
+
+    fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+}
+
+/// Route for a single stream
+struct Route<T> {
+    /// Current consumer (replaced on every subscribe)
+    sender: Option<mpsc::Sender<T>>,
+
+    /// Delivery statistics
+    stats: FrameRouteStats,
+}
+
+impl<T> Default for Route<T> {
+    fn default() -> Self {
+        Self {
+            sender: None,
+            stats: FrameRouteStats::default(),
+        }
+    }
+}
+
+type Routes<T> = Arc<Mutex<HashMap<u32, Route<T>>>>;
+
+/// Callback invoked on the forwarding thread for every item
+pub(crate) type Observer<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+/// Forwards items from the PipeWire thread to per-stream consumers
+pub(crate) struct Router<T: Routed> {
+    /// Forwarding thread name
+    thread_name: &'static str,
+
+    /// Routes keyed by stream ID
+    routes: Routes<T>,
+
+    /// Observers to hand to the forwarding thread
+    observers: Vec<Observer<T>>,
+
+    /// Forwarding thread handle
+    forwarder: Option<JoinHandle<()>>,
+}
+
+/// Routes video frames
+pub(crate) type FrameRouter = Router<VideoFrame>;
+
+/// Routes cursor updates
+#[cfg(feature = "cursor")]
+pub(crate) type CursorRouter = Router<CursorUpdate>;
+
+impl<T: Routed> Router<T> {
+    /// Create a router with no routes and no forwarding thread
+    pub(crate) fn new(thread_name: &'static str) -> Self {
+        Self {
+            thread_name,
+            routes: Arc::new(Mutex::new(HashMap::new())),
+            observers: Vec::new(),
+            forwarder: None,
+        }
+    }
+
+    /// Register an observer for all items
+    ///
+    /// Only observers added before [`start`](Self::start) take effect.
+    pub(crate) fn add_observer(&mut self, observer: Observer<T>) {
+        self.observers.push(observer);
+    }
+
+    /// Start forwarding items from one of the PipeWire thread's channels
+    ///
+    /// The forwarding thread exits once every sender of `rx` has been
+    /// dropped, i.e. when the PipeWire thread shuts down.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` if already started, or `InitializationFailed`
+    /// if the thread cannot be spawned
+    pub(crate) fn start(&mut self, rx: std_mpsc::Receiver<T>) -> Result<()> {
+        if self.forwarder.is_some() {
+            return Err(PipeWireError::InvalidState(format!(
+                "{} router already started",
+                T::KIND
+            )));
+        }
+
+        let routes = Arc::clone(&self.routes);
+        let observers = std::mem::take(&mut self.observers);
+        let handle = thread::Builder::new()
+            .name(self.thread_name.to_string())
+            .spawn(move || {
+                debug!("Forwarding thread for {} started", T::KIND);
+                while let Ok(item) = rx.recv() {
+                    for observer in &observers {
+                        observer(&item);
+                    }
+                    route_item(&routes, item);
+                }
+                debug!("Forwarding thread for {} exiting (channel closed)", T::KIND);
+            })
+            .map_err(|e| PipeWireError::InitializationFailed(format!("{} router spawn failed: {}", T::KIND, e)))?;
+
+        self.forwarder = Some(handle);
+        Ok(())
+    }
+
+    /// Register a stream so its items are accounted for
+    pub(crate) fn add_route(&self, stream_id: u32) {
+        self.routes.lock().unwrap().entry(stream_id).or_default();
+    }
+
+    /// Subscribe to a stream's items, replacing any previous consumer
+    ///
+    /// # Returns
+    ///
+    /// Receiver, or `None` if the stream has no route
+    pub(crate) fn subscribe(&self, stream_id: u32, capacity: usize) -> Option<mpsc::Receiver<T>> {
+        let mut routes = self.routes.lock().unwrap();
+        let route = routes.get_mut(&stream_id)?;
+
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        route.sender = Some(tx);
+        Some(rx)
+    }
+
+    /// Remove a stream's route
+    ///
+    /// Dropping the route closes the consumer's channel.
+    ///
+    /// # Returns
+    ///
+    /// Final statistics for the stream, if it had a route
+    pub(crate) fn remove_route(&self, stream_id: u32) -> Option<FrameRouteStats> {
+        self.routes.lock().unwrap().remove(&stream_id).map(|route| route.stats)
+    }
+
+    /// Get delivery statistics for a stream
+    pub(crate) fn stats(&self, stream_id: u32) -> Option<FrameRouteStats> {
+        self.routes.lock().unwrap().get(&stream_id).map(|route| route.stats)
+    }
+
+    /// Wait for the forwarding thread to exit
+    ///
+    /// Only returns promptly once the PipeWire thread (the frame channel's
+    /// sender side) has shut down.
+    pub(crate) fn join(&mut self) {
+        if let Some(handle) = self.forwarder.take() {
+            if handle.join().is_err() {
+                warn!("Forwarding thread for {} panicked", T::KIND);
+            }
+        }
+    }
+}
+
+/// Broadcasts lifecycle events from the PipeWire thread to all subscribers
+///
+/// Subscribers that fall more than the channel capacity behind skip the
+/// oldest events (`broadcast::error::RecvError::Lagged`).
+pub(crate) struct EventRouter {
+    /// Forwarding thread name
+    thread_name: &'static str,
+
+    /// Broadcast sender; subscribers are created from it
+    tx: broadcast::Sender<PipeWireEvent>,
+
+    /// Forwarding thread handle
+    forwarder: Option<JoinHandle<()>>,
+}
+
+impl EventRouter {
+    /// Create a router buffering up to `capacity` events per subscriber
+    pub(crate) fn new(thread_name: &'static str, capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self {
+            thread_name,
+            tx,
+            forwarder: None,
+        }
+    }
+
+    /// Subscribe to events published from now on
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<PipeWireEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Publish an event to current subscribers
+    #[cfg(test)]
+    pub(crate) fn publish(&self, event: PipeWireEvent) {
+        trace!("PipeWire event: {:?}", event);
+        // Err only means nobody is subscribed right now
+        let _ = self.tx.send(event);
+    }
+
+    /// Sender for publishing events from other tasks
+    pub(crate) fn publisher(&self) -> broadcast::Sender<PipeWireEvent> {
+        self.tx.clone()
+    }
+
+    /// Start forwarding events from the PipeWire thread's event channel
+    ///
+    /// The forwarding thread exits once every sender of `rx` has been
+    /// dropped. A router can be started again after [`join`](Self::join),
+    /// e.g. for a new PipeWire connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidState` if already started, or `InitializationFailed`
+    /// if the thread cannot be spawned
+    pub(crate) fn start(&mut self, rx: std_mpsc::Receiver<PipeWireEvent>) -> Result<()> {
+        if self.forwarder.is_some() {
+            return Err(PipeWireError::InvalidState("event router already started".to_string()));
+        }
+
+        let tx = self.tx.clone();
+        let handle = thread::Builder::new()
+            .name(self.thread_name.to_string())
+            .spawn(move || {
+                debug!("Forwarding thread for events started");
+                while let Ok(event) = rx.recv() {
+                    trace!("PipeWire event: {:?}", event);
+                    let _ = tx.send(event);
+                }
+                debug!("Forwarding thread for events exiting (channel closed)");
+            })
+            .map_err(|e| PipeWireError::InitializationFailed(format!("event router spawn failed: {}", e)))?;
+
+        self.forwarder = Some(handle);
+        Ok(())
+    }
+
+    /// Wait for the forwarding thread to exit
+    pub(crate) fn join(&mut self) {
+        if let Some(handle) = self.forwarder.take() {
+            if handle.join().is_err() {
+                warn!("Forwarding thread for events panicked");
+            }
+        }
+    }
+}
+
+/// Deliver an item to its stream's consumer
+fn route_item<T: Routed>(routes: &Mutex<HashMap<u32, Route<T>>>, item: T) {
+    let stream_id = item.stream_id();
+    let mut routes = routes.lock().unwrap();
+
+    let Some(route) = routes.get_mut(&stream_id) else {
+        trace!("Discarding {} for unknown stream {}", T::KIND, stream_id);
+        return;
+    };
+
+    let Some(sender) = route.sender.as_ref() else {
+        route.stats.frames_dropped += 1;
+        trace!("Discarding {} for stream {} (no consumer)", T::KIND, stream_id);
+        return;
+    };
+
+    match sender.try_send(item) {
+        Ok(()) => route.stats.frames_forwarded += 1,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            route.stats.frames_dropped += 1;
+            let dropped = route.stats.frames_dropped;
+            if dropped == 1 || dropped % 100 == 0 {
+                warn!(
+                    "Stream {} consumer is falling behind: {} {} dropped",
+                    stream_id,
+                    dropped,
+                    T::KIND
+                );
+            }
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => {
+            route.stats.frames_dropped += 1;
+            info!(
+                "Stream {} consumer went away, dropping {} until resubscribed",
+                stream_id,
+                T::KIND
+            );
+            route.sender = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::PixelFormat;
+
+    fn frame_for(stream_id: u32, frame_id: u64) -> VideoFrame {
+        VideoFrame::new(frame_id, 64, 64, 256, PixelFormat::BGRx, stream_id)
+    }
+
+    #[test]
+    fn test_frames_routed_per_stream() {
+        let mut router = FrameRouter::new("pipewire-frames");
+        router.add_route(0);
+        router.add_route(1);
+        let mut rx0 = router.subscribe(0, 8).unwrap();
+        let mut rx1 = router.subscribe(1, 8).unwrap();
+
+        let (tx, frame_rx) = std_mpsc::sync_channel(16);
+        router.start(frame_rx).unwrap();
+
+        tx.send(frame_for(0, 10)).unwrap();
+        tx.send(frame_for(1, 20)).unwrap();
+        tx.send(frame_for(0, 11)).unwrap();
+        drop(tx);
+        router.join();
+
+        assert_eq!(rx0.try_recv().unwrap().frame_id, 10);
+        assert_eq!(rx0.try_recv().unwrap().frame_id, 11);
+        assert!(rx0.try_recv().is_err());
+        assert_eq!(rx1.try_recv().unwrap().frame_id, 20);
+
+        assert_eq!(router.stats(0).unwrap().frames_forwarded, 2);
+        assert_eq!(router.stats(1).unwrap().frames_forwarded, 1);
+    }
+
+    #[test]
+    fn test_observers_see_every_frame() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let seen = Arc::new(AtomicU64::new(0));
+        let mut router = FrameRouter::new("pipewire-frames");
+        router.add_observer(Box::new({
+            let seen = Arc::clone(&seen);
+            move |frame| {
+                seen.fetch_add(frame.frame_id, Ordering::Relaxed);
+            }
+        }));
+
+        let (tx, frame_rx) = std_mpsc::sync_channel(16);
+        router.start(frame_rx).unwrap();
+
+        // No routes at all: observers still see the frames
+        tx.send(frame_for(0, 1)).unwrap();
+        tx.send(frame_for(5, 2)).unwrap();
+        drop(tx);
+        router.join();
+
+        assert_eq!(seen.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_slow_consumer_drops_are_per_stream() {
+        let router = FrameRouter::new("pipewire-frames");
+        router.add_route(0);
+        router.add_route(1);
+        let _rx0 = router.subscribe(0, 1).unwrap();
+        let _rx1 = router.subscribe(1, 8).unwrap();
+
+        for i in 0..3 {
+            route_item(&router.routes, frame_for(0, i));
+            route_item(&router.routes, frame_for(1, i));
+        }
+
+        let slow = router.stats(0).unwrap();
+        assert_eq!(slow.frames_forwarded, 1);
+        assert_eq!(slow.frames_dropped, 2);
+
+        let fast = router.stats(1).unwrap();
+        assert_eq!(fast.frames_forwarded, 3);
+        assert_eq!(fast.frames_dropped, 0);
+    }
+
+    #[test]
+    fn test_unsubscribed_and_unknown_streams() {
+        let router = FrameRouter::new("pipewire-frames");
+        router.add_route(0);
+
+        route_item(&router.routes, frame_for(0, 1));
+        route_item(&router.routes, frame_for(7, 1));
+
+        assert_eq!(router.stats(0).unwrap().frames_dropped, 1);
+        assert!(router.stats(7).is_none());
+        assert!(router.subscribe(7, 8).is_none());
+    }
+
+    #[test]
+    fn test_resubscribe_after_consumer_dropped() {
+        let router = FrameRouter::new("pipewire-frames");
+        router.add_route(0);
+        drop(router.subscribe(0, 8).unwrap());
+
+        route_item(&router.routes, frame_for(0, 1));
+        assert_eq!(router.stats(0).unwrap().frames_dropped, 1);
+
+        let mut rx = router.subscribe(0, 8).unwrap();
+        route_item(&router.routes, frame_for(0, 2));
+        assert_eq!(rx.try_recv().unwrap().frame_id, 2);
+    }
+
+    #[test]
+    fn test_events_broadcast_to_all_subscribers() {
+        let mut router = EventRouter::new("pipewire-events", 8);
+        let mut first = router.subscribe();
+        let mut second = router.subscribe();
+
+        let (tx, event_rx) = std_mpsc::channel();
+        router.start(event_rx).unwrap();
+        assert!(router.start(std_mpsc::channel().1).is_err());
+
+        tx.send(PipeWireEvent::StreamStateChanged(
+            2,
+            crate::stream::PwStreamState::Streaming,
+        ))
+        .unwrap();
+        tx.send(PipeWireEvent::StreamRemoved(2)).unwrap();
+        drop(tx);
+        router.join();
+        router.publish(PipeWireEvent::Disconnected);
+
+        for rx in [&mut first, &mut second] {
+            assert!(matches!(
+                rx.try_recv(),
+                Ok(PipeWireEvent::StreamStateChanged(
+                    2,
+                    crate::stream::PwStreamState::Streaming
+                ))
+            ));
+            assert!(matches!(rx.try_recv(), Ok(PipeWireEvent::StreamRemoved(2))));
+            assert!(matches!(rx.try_recv(), Ok(PipeWireEvent::Disconnected)));
+        }
+
+        // Restartable for a new connection
+        let (tx, event_rx) = std_mpsc::channel::<PipeWireEvent>();
+        router.start(event_rx).unwrap();
+        drop(tx);
+        router.join();
+    }
+
+    #[test]
+    fn test_remove_route_returns_stats() {
+        let router = FrameRouter::new("pipewire-frames");
+        router.add_route(3);
+        let _rx = router.subscribe(3, 8).unwrap();
+        route_item(&router.routes, frame_for(3, 1));
+
+        let stats = router.remove_route(3).unwrap();
+        assert_eq!(stats.frames_forwarded, 1);
+        assert!((stats.drop_rate() - 0.0).abs() < f64::EPSILON);
+        assert!(router.stats(3).is_none());
+    }
+}
@@ -0,0 +1,968 @@
+//! SIMD Colour Conversion Kernels
+//!
+//! Row kernels for the hot conversion paths: 4-byte RGB swizzles,
+//! NV12/I420/YUY2 to BGRA and BGRA to NV12. The kernel set is chosen once
+//! at runtime from the CPU's features (see [`SimdLevel::detect`]), with
+//! SSSE3 and AVX2 on x86 and NEON on aarch64.
+//!
+//! Each kernel converts as many whole blocks of pixels as it can and
+//! returns how many pixels it wrote; the scalar code in [`crate::format`]
+//! and [`crate::yuv`] finishes the row. Results are bit-exact with the
+//! scalar conversions.
+
+use std::sync::OnceLock;
+
+use crate::colorimetry::YuvCoefficients;
+
+/// Marks an output byte of a [`shuffle_row`] map that is set to 255
+pub(crate) const OPAQUE: u8 = 0xFF;
+
+/// Instruction set used for colour conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    /// Portable scalar code
+    Scalar,
+    /// x86 SSSE3 (16-byte vectors)
+    Ssse3,
+    /// x86 AVX2 (32-byte vectors)
+    Avx2,
+    /// ARM NEON (aarch64)
+    Neon,
+}
+
+impl SimdLevel {
+    /// Best level supported by this CPU
+    ///
+    /// Detected on first use and cached.
+    pub fn detect() -> Self {
+        static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+        *LEVEL.get_or_init(|| {
+            [Self::Avx2, Self::Ssse3, Self::Neon]
+                .into_iter()
+                .find(|level| level.is_supported())
+                .unwrap_or(Self::Scalar)
+        })
+    }
+
+    /// Whether this CPU can run kernels of this level
+    pub fn is_supported(self) -> bool {
+        match self {
+            Self::Scalar => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::Ssse3 => is_x86_feature_detected!("ssse3"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Self::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "aarch64")]
+            Self::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+/// Reorder the bytes of 4-byte pixels
+///
+/// Output byte `k` of each pixel is input byte `map[k]`, or 255 where
+/// `map[k]` is [`OPAQUE`].
+pub(crate) fn shuffle_row(level: SimdLevel, src: &[u8], dst: &mut [u8], map: [u8; 4]) -> usize {
+    let pixels = src.len().min(dst.len()) / 4;
+    if !level.is_supported() {
+        return 0;
+    }
+
+    match level {
+        // SAFETY: the CPU supports the level's instructions (checked above)
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Avx2 => unsafe { x86::shuffle_row_avx2(src, dst, pixels, map) },
+        // SAFETY: as above
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Ssse3 => unsafe { x86::shuffle_row_ssse3(src, dst, pixels, map) },
+        // SAFETY: as above
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => unsafe { neon::shuffle_row(src, dst, pixels, map) },
+        _ => 0,
+    }
+}
+
+/// Convert a row of NV12 to BGRA
+///
+/// `y` holds the row's luma, `uv` the interleaved chroma it shares with its
+/// neighbouring row.
+pub(crate) fn nv12_to_bgra_row(
+    level: SimdLevel,
+    coefficients: &YuvCoefficients,
+    y: &[u8],
+    uv: &[u8],
+    dst: &mut [u8],
+) -> usize {
+    let pixels = y.len().min(uv.len()).min(dst.len() / 4);
+    if !level.is_supported() {
+        return 0;
+    }
+
+    match level {
+        // SAFETY: the CPU supports the level's instructions (checked above)
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Avx2 => unsafe { x86::nv12_to_bgra_avx2(y, uv, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Ssse3 => unsafe { x86::nv12_to_bgra_ssse3(y, uv, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => unsafe { neon::nv12_to_bgra(y, uv, dst, pixels, coefficients) },
+        _ => 0,
+    }
+}
+
+/// Convert a row of I420 to BGRA
+pub(crate) fn i420_to_bgra_row(
+    level: SimdLevel,
+    coefficients: &YuvCoefficients,
+    y: &[u8],
+    u: &[u8],
+    v: &[u8],
+    dst: &mut [u8],
+) -> usize {
+    let pixels = y.len().min(u.len() * 2).min(v.len() * 2).min(dst.len() / 4);
+    if !level.is_supported() {
+        return 0;
+    }
+
+    match level {
+        // SAFETY: the CPU supports the level's instructions (checked above)
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Avx2 => unsafe { x86::i420_to_bgra_avx2(y, u, v, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Ssse3 => unsafe { x86::i420_to_bgra_ssse3(y, u, v, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => unsafe { neon::i420_to_bgra(y, u, v, dst, pixels, coefficients) },
+        _ => 0,
+    }
+}
+
+/// Convert a row of YUY2 to BGRA
+pub(crate) fn yuy2_to_bgra_row(level: SimdLevel, coefficients: &YuvCoefficients, src: &[u8], dst: &mut [u8]) -> usize {
+    let pixels = (src.len() / 2).min(dst.len() / 4);
+    if !level.is_supported() {
+        return 0;
+    }
+
+    match level {
+        // SAFETY: the CPU supports the level's instructions (checked above)
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Avx2 => unsafe { x86::yuy2_to_bgra_avx2(src, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Ssse3 => unsafe { x86::yuy2_to_bgra_ssse3(src, dst, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => unsafe { neon::yuy2_to_bgra(src, dst, pixels, coefficients) },
+        _ => 0,
+    }
+}
+
+/// Convert two rows of BGRA to NV12
+///
+/// Writes both rows of luma and the chroma row they share.
+pub(crate) fn bgra_to_nv12_rows(
+    level: SimdLevel,
+    coefficients: &YuvCoefficients,
+    src: [&[u8]; 2],
+    y: [&mut [u8]; 2],
+    uv: &mut [u8],
+) -> usize {
+    let pixels = (src[0].len().min(src[1].len()) / 4)
+        .min(y[0].len())
+        .min(y[1].len())
+        .min(uv.len());
+    if !level.is_supported() {
+        return 0;
+    }
+
+    match level {
+        // AVX2 has no kernel of its own; it implies SSSE3.
+        // SAFETY: the CPU supports the level's instructions (checked above)
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        SimdLevel::Avx2 | SimdLevel::Ssse3 => unsafe { x86::bgra_to_nv12_ssse3(src, y, uv, pixels, coefficients) },
+        // SAFETY: as above
+        #[cfg(target_arch = "aarch64")]
+        SimdLevel::Neon => unsafe { neon::bgra_to_nv12(src, y, uv, pixels, coefficients) },
+        _ => 0,
+    }
+}
+
+/// x86 kernels
+///
+/// YUV arithmetic runs on 16-bit lanes with `pmaddwd` into 32-bit sums,
+/// then saturating packs clamp to 0..=255, matching the scalar code.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+// Most intrinsics are safe to call inside `#[target_feature]` functions on
+// newer toolchains, which makes some of the blocks below redundant there.
+#[allow(unused_unsafe)]
+mod x86 {
+    // The kernels use dozens of intrinsics
+    #[allow(clippy::wildcard_imports)]
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[allow(clippy::wildcard_imports)]
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::{YuvCoefficients, OPAQUE};
+
+    /// Byte shuffle control and alpha fill for four pixels
+    fn shuffle_controls(map: [u8; 4]) -> ([u8; 16], [u8; 16]) {
+        let mut control = [0u8; 16];
+        let mut fill = [0u8; 16];
+        for (index, (control, fill)) in control.iter_mut().zip(fill.iter_mut()).enumerate() {
+            let byte = map[index % 4];
+            if byte == OPAQUE {
+                // High bit set: shuffle writes zero, the fill sets it
+                *control = 0x80;
+                *fill = 0xFF;
+            } else {
+                *control = (index / 4 * 4) as u8 + byte;
+            }
+        }
+        (control, fill)
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn shuffle_row_ssse3(src: &[u8], dst: &mut [u8], pixels: usize, map: [u8; 4]) -> usize {
+        let (control, fill) = shuffle_controls(map);
+        let done = pixels / 4 * 4;
+
+        // SAFETY: every load and store covers 4 pixels below `done`, which
+        // fits both `src` and `dst`
+        unsafe {
+            let control = _mm_loadu_si128(control.as_ptr().cast());
+            let fill = _mm_loadu_si128(fill.as_ptr().cast());
+            for x in (0..done).step_by(4) {
+                let px = _mm_loadu_si128(src.as_ptr().add(x * 4).cast());
+                let out = _mm_or_si128(_mm_shuffle_epi8(px, control), fill);
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4).cast(), out);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn shuffle_row_avx2(src: &[u8], dst: &mut [u8], pixels: usize, map: [u8; 4]) -> usize {
+        let (control, fill) = shuffle_controls(map);
+        let done = pixels / 8 * 8;
+
+        // SAFETY: every load and store covers 8 pixels below `done`, which
+        // fits both `src` and `dst`
+        unsafe {
+            // The shuffle works within each 16-byte lane, so both lanes use
+            // the same control
+            let control = _mm256_broadcastsi128_si256(_mm_loadu_si128(control.as_ptr().cast()));
+            let fill = _mm256_broadcastsi128_si256(_mm_loadu_si128(fill.as_ptr().cast()));
+            for x in (0..done).step_by(8) {
+                let px = _mm256_loadu_si256(src.as_ptr().add(x * 4).cast());
+                let out = _mm256_or_si256(_mm256_shuffle_epi8(px, control), fill);
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4).cast(), out);
+            }
+        }
+        done
+    }
+
+    /// Two 16-bit coefficients repeated across a register, for `pmaddwd`
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn pair(low: i16, high: i16) -> __m128i {
+        // SAFETY: arithmetic only
+        unsafe { _mm_set1_epi32(i32::from(high) << 16 | i32::from(low as u16)) }
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn pair256(low: i16, high: i16) -> __m256i {
+        // SAFETY: arithmetic only
+        unsafe { _mm256_set1_epi32(i32::from(high) << 16 | i32::from(low as u16)) }
+    }
+
+    /// `pmaddwd` of interleaved `(a, b)` pairs, for pixels 0-3 and 4-7
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn madd_pairs(a: __m128i, b: __m128i, coeffs: __m128i) -> (__m128i, __m128i) {
+        // SAFETY: arithmetic only
+        unsafe {
+            (
+                _mm_madd_epi16(_mm_unpacklo_epi16(a, b), coeffs),
+                _mm_madd_epi16(_mm_unpackhi_epi16(a, b), coeffs),
+            )
+        }
+    }
+
+    /// Add two sums, drop the 8 fractional bits and pack to 16-bit lanes
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn descale(sum: (__m128i, __m128i), add: (__m128i, __m128i)) -> __m128i {
+        // SAFETY: arithmetic only
+        unsafe {
+            let lo = _mm_srai_epi32(_mm_add_epi32(sum.0, add.0), 8);
+            let hi = _mm_srai_epi32(_mm_add_epi32(sum.1, add.1), 8);
+            _mm_packs_epi32(lo, hi)
+        }
+    }
+
+    /// Convert 8 pixels of 16-bit Y, U and V to BGRA
+    ///
+    /// Returns pixels 0-3 and 4-7.
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn yuv_to_bgra_8(k: &YuvCoefficients, y: __m128i, u: __m128i, v: __m128i) -> (__m128i, __m128i) {
+        // SAFETY: arithmetic only
+        unsafe {
+            let c = _mm_sub_epi16(y, _mm_set1_epi16(k.y_offset));
+            let d = _mm_sub_epi16(u, _mm_set1_epi16(128));
+            let e = _mm_sub_epi16(v, _mm_set1_epi16(128));
+            let round = (_mm_set1_epi32(128), _mm_set1_epi32(128));
+
+            let r = descale(madd_pairs(c, e, pair(k.y_scale, k.r_v)), round);
+            let b = descale(madd_pairs(c, d, pair(k.y_scale, k.b_u)), round);
+            // (e, 1) * (g_v, 128) folds the rounding term into green
+            let g = descale(
+                madd_pairs(c, d, pair(k.y_scale, k.g_u)),
+                madd_pairs(e, _mm_set1_epi16(1), pair(k.g_v, 128)),
+            );
+
+            // Saturating packs clamp to 0..=255
+            let br = _mm_packus_epi16(b, r);
+            let ga = _mm_packus_epi16(g, _mm_set1_epi16(255));
+            let bg = _mm_unpacklo_epi8(br, ga);
+            let ra = _mm_unpackhi_epi8(br, ga);
+            (_mm_unpacklo_epi16(bg, ra), _mm_unpackhi_epi16(bg, ra))
+        }
+    }
+
+    /// `pmaddwd` of interleaved `(a, b)` pairs, for pixels 0-3 | 8-11 and 4-7 | 12-15
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn madd_pairs256(a: __m256i, b: __m256i, coeffs: __m256i) -> (__m256i, __m256i) {
+        // SAFETY: arithmetic only
+        unsafe {
+            (
+                _mm256_madd_epi16(_mm256_unpacklo_epi16(a, b), coeffs),
+                _mm256_madd_epi16(_mm256_unpackhi_epi16(a, b), coeffs),
+            )
+        }
+    }
+
+    /// Add two sums, drop the 8 fractional bits and pack to 16-bit lanes
+    ///
+    /// Unpacks and packs both work within 16-byte lanes, so pixels come
+    /// back out in order.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn descale256(sum: (__m256i, __m256i), add: (__m256i, __m256i)) -> __m256i {
+        // SAFETY: arithmetic only
+        unsafe {
+            let lo = _mm256_srai_epi32(_mm256_add_epi32(sum.0, add.0), 8);
+            let hi = _mm256_srai_epi32(_mm256_add_epi32(sum.1, add.1), 8);
+            _mm256_packs_epi32(lo, hi)
+        }
+    }
+
+    /// Convert 16 pixels of 16-bit Y, U and V to BGRA
+    ///
+    /// Returns pixels 0-7 and 8-15.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn yuv_to_bgra_16(k: &YuvCoefficients, y: __m256i, u: __m256i, v: __m256i) -> (__m256i, __m256i) {
+        // SAFETY: arithmetic only
+        unsafe {
+            let c = _mm256_sub_epi16(y, _mm256_set1_epi16(k.y_offset));
+            let d = _mm256_sub_epi16(u, _mm256_set1_epi16(128));
+            let e = _mm256_sub_epi16(v, _mm256_set1_epi16(128));
+            let round = (_mm256_set1_epi32(128), _mm256_set1_epi32(128));
+
+            let r = descale256(madd_pairs256(c, e, pair256(k.y_scale, k.r_v)), round);
+            let b = descale256(madd_pairs256(c, d, pair256(k.y_scale, k.b_u)), round);
+            let g = descale256(
+                madd_pairs256(c, d, pair256(k.y_scale, k.g_u)),
+                madd_pairs256(e, _mm256_set1_epi16(1), pair256(k.g_v, 128)),
+            );
+
+            let br = _mm256_packus_epi16(b, r);
+            let ga = _mm256_packus_epi16(g, _mm256_set1_epi16(255));
+            let bg = _mm256_unpacklo_epi8(br, ga);
+            let ra = _mm256_unpackhi_epi8(br, ga);
+            // Pixels 0-3 | 8-11 and 4-7 | 12-15
+            let lo = _mm256_unpacklo_epi16(bg, ra);
+            let hi = _mm256_unpackhi_epi16(bg, ra);
+            (
+                _mm256_permute2x128_si256(lo, hi, 0x20),
+                _mm256_permute2x128_si256(lo, hi, 0x31),
+            )
+        }
+    }
+
+    /// Shuffle control spreading bytes `first..` to 16-bit lanes, each twice
+    const fn widen_twice(first: u8, step: u8) -> [i8; 16] {
+        let mut control = [-128i8; 16];
+        let mut index = 0;
+        while index < 4 {
+            let byte = (first + index as u8 * step) as i8;
+            control[index * 4] = byte;
+            control[index * 4 + 2] = byte;
+            index += 1;
+        }
+        control
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn nv12_to_bgra_ssse3(
+        y: &[u8],
+        uv: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 8 * 8;
+        const U: [i8; 16] = widen_twice(0, 2);
+        const V: [i8; 16] = widen_twice(1, 2);
+
+        // SAFETY: each step reads 8 bytes of `y` and `uv` and writes 32 bytes
+        // of `dst` below `done` pixels, which all three hold
+        unsafe {
+            let zero = _mm_setzero_si128();
+            let u_control = _mm_loadu_si128(U.as_ptr().cast());
+            let v_control = _mm_loadu_si128(V.as_ptr().cast());
+            for x in (0..done).step_by(8) {
+                let luma = _mm_unpacklo_epi8(_mm_loadl_epi64(y.as_ptr().add(x).cast()), zero);
+                let chroma = _mm_loadl_epi64(uv.as_ptr().add(x).cast());
+                let (px0, px1) = yuv_to_bgra_8(
+                    k,
+                    luma,
+                    _mm_shuffle_epi8(chroma, u_control),
+                    _mm_shuffle_epi8(chroma, v_control),
+                );
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4 + 16).cast(), px1);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn nv12_to_bgra_avx2(
+        y: &[u8],
+        uv: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 16 * 16;
+        const U: [u8; 16] = [0, 0, 2, 2, 4, 4, 6, 6, 8, 8, 10, 10, 12, 12, 14, 14];
+        const V: [u8; 16] = [1, 1, 3, 3, 5, 5, 7, 7, 9, 9, 11, 11, 13, 13, 15, 15];
+
+        // SAFETY: each step reads 16 bytes of `y` and `uv` and writes 64
+        // bytes of `dst` below `done` pixels, which all three hold
+        unsafe {
+            let u_control = _mm_loadu_si128(U.as_ptr().cast());
+            let v_control = _mm_loadu_si128(V.as_ptr().cast());
+            for x in (0..done).step_by(16) {
+                let luma = _mm256_cvtepu8_epi16(_mm_loadu_si128(y.as_ptr().add(x).cast()));
+                let chroma = _mm_loadu_si128(uv.as_ptr().add(x).cast());
+                let (px0, px1) = yuv_to_bgra_16(
+                    k,
+                    luma,
+                    _mm256_cvtepu8_epi16(_mm_shuffle_epi8(chroma, u_control)),
+                    _mm256_cvtepu8_epi16(_mm_shuffle_epi8(chroma, v_control)),
+                );
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4 + 32).cast(), px1);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn i420_to_bgra_ssse3(
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 8 * 8;
+        const CHROMA: [i8; 16] = widen_twice(0, 1);
+
+        // SAFETY: each step reads 8 bytes of `y`, 4 of `u` and `v` and writes
+        // 32 bytes of `dst` below `done` pixels, which all of them hold
+        unsafe {
+            let zero = _mm_setzero_si128();
+            let control = _mm_loadu_si128(CHROMA.as_ptr().cast());
+            for x in (0..done).step_by(8) {
+                let luma = _mm_unpacklo_epi8(_mm_loadl_epi64(y.as_ptr().add(x).cast()), zero);
+                let u_samples = _mm_cvtsi32_si128(u.as_ptr().add(x / 2).cast::<i32>().read_unaligned());
+                let v_samples = _mm_cvtsi32_si128(v.as_ptr().add(x / 2).cast::<i32>().read_unaligned());
+                let (px0, px1) = yuv_to_bgra_8(
+                    k,
+                    luma,
+                    _mm_shuffle_epi8(u_samples, control),
+                    _mm_shuffle_epi8(v_samples, control),
+                );
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4 + 16).cast(), px1);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn i420_to_bgra_avx2(
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 16 * 16;
+
+        // SAFETY: each step reads 16 bytes of `y`, 8 of `u` and `v` and
+        // writes 64 bytes of `dst` below `done` pixels, which all of them hold
+        unsafe {
+            for x in (0..done).step_by(16) {
+                let luma = _mm256_cvtepu8_epi16(_mm_loadu_si128(y.as_ptr().add(x).cast()));
+                let u_samples = _mm_loadl_epi64(u.as_ptr().add(x / 2).cast());
+                let v_samples = _mm_loadl_epi64(v.as_ptr().add(x / 2).cast());
+                let (px0, px1) = yuv_to_bgra_16(
+                    k,
+                    luma,
+                    _mm256_cvtepu8_epi16(_mm_unpacklo_epi8(u_samples, u_samples)),
+                    _mm256_cvtepu8_epi16(_mm_unpacklo_epi8(v_samples, v_samples)),
+                );
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4 + 32).cast(), px1);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn yuy2_to_bgra_ssse3(src: &[u8], dst: &mut [u8], pixels: usize, k: &YuvCoefficients) -> usize {
+        let done = pixels / 8 * 8;
+        const Y: [i8; 16] = [
+            0, -128, 2, -128, 4, -128, 6, -128, 8, -128, 10, -128, 12, -128, 14, -128,
+        ];
+        const U: [i8; 16] = widen_twice(1, 4);
+        const V: [i8; 16] = widen_twice(3, 4);
+
+        // SAFETY: each step reads 16 bytes of `src` and writes 32 bytes of
+        // `dst` below `done` pixels, which both hold
+        unsafe {
+            let y_control = _mm_loadu_si128(Y.as_ptr().cast());
+            let u_control = _mm_loadu_si128(U.as_ptr().cast());
+            let v_control = _mm_loadu_si128(V.as_ptr().cast());
+            for x in (0..done).step_by(8) {
+                let packed = _mm_loadu_si128(src.as_ptr().add(x * 2).cast());
+                let (px0, px1) = yuv_to_bgra_8(
+                    k,
+                    _mm_shuffle_epi8(packed, y_control),
+                    _mm_shuffle_epi8(packed, u_control),
+                    _mm_shuffle_epi8(packed, v_control),
+                );
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm_storeu_si128(dst.as_mut_ptr().add(x * 4 + 16).cast(), px1);
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn yuy2_to_bgra_avx2(src: &[u8], dst: &mut [u8], pixels: usize, k: &YuvCoefficients) -> usize {
+        let done = pixels / 16 * 16;
+        // Gather 8 bytes from one 16-byte macropixel group into the low half
+        const Y: [i8; 16] = [
+            0, 2, 4, 6, 8, 10, 12, 14, -128, -128, -128, -128, -128, -128, -128, -128,
+        ];
+        const U: [i8; 16] = [1, 1, 5, 5, 9, 9, 13, 13, -128, -128, -128, -128, -128, -128, -128, -128];
+        const V: [i8; 16] = [
+            3, 3, 7, 7, 11, 11, 15, 15, -128, -128, -128, -128, -128, -128, -128, -128,
+        ];
+
+        // SAFETY: each step reads 32 bytes of `src` and writes 64 bytes of
+        // `dst` below `done` pixels, which both hold
+        unsafe {
+            let y_control = _mm_loadu_si128(Y.as_ptr().cast());
+            let u_control = _mm_loadu_si128(U.as_ptr().cast());
+            let v_control = _mm_loadu_si128(V.as_ptr().cast());
+            for x in (0..done).step_by(16) {
+                let first = _mm_loadu_si128(src.as_ptr().add(x * 2).cast());
+                let second = _mm_loadu_si128(src.as_ptr().add(x * 2 + 16).cast());
+                let (px0, px1) = yuv_to_bgra_16(
+                    k,
+                    gather_16(first, second, y_control),
+                    gather_16(first, second, u_control),
+                    gather_16(first, second, v_control),
+                );
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4).cast(), px0);
+                _mm256_storeu_si256(dst.as_mut_ptr().add(x * 4 + 32).cast(), px1);
+            }
+        }
+        done
+    }
+
+    /// Shuffle 8 bytes out of each of two registers and widen all 16 to 16-bit lanes
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn gather_16(first: __m128i, second: __m128i, control: __m128i) -> __m256i {
+        // SAFETY: arithmetic only
+        unsafe {
+            _mm256_cvtepu8_epi16(_mm_unpacklo_epi64(
+                _mm_shuffle_epi8(first, control),
+                _mm_shuffle_epi8(second, control),
+            ))
+        }
+    }
+
+    /// One of Y, U or V for 4 pixels given as 16-bit (B, R) and (G, A) lanes
+    ///
+    /// `(r * kr + g * kg + b * kb + 128) >> 8` plus `offset`, in 32-bit lanes.
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn yuv_channel(br: __m128i, ga: __m128i, [kr, kg, kb]: [i16; 3], offset: i32) -> __m128i {
+        // SAFETY: arithmetic only
+        unsafe {
+            let sum = _mm_add_epi32(_mm_madd_epi16(br, pair(kb, kr)), _mm_madd_epi16(ga, pair(kg, 0)));
+            let sum = _mm_add_epi32(sum, _mm_set1_epi32(128));
+            _mm_add_epi32(_mm_srai_epi32(sum, 8), _mm_set1_epi32(offset))
+        }
+    }
+
+    /// Y, U and V (offsets included) of 4 BGRA pixels, as 32-bit lanes
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn bgra_to_yuv_4(k: &YuvCoefficients, px: __m128i) -> (__m128i, __m128i, __m128i) {
+        // SAFETY: arithmetic only
+        unsafe {
+            // 16-bit lanes (B, R) and (G, A) per pixel
+            let br = _mm_and_si128(px, _mm_set1_epi32(0x00FF_00FF));
+            let ga = _mm_srli_epi16(px, 8);
+
+            (
+                yuv_channel(br, ga, k.y_rgb, i32::from(k.y_offset)),
+                yuv_channel(br, ga, k.u_rgb, 128),
+                yuv_channel(br, ga, k.v_rgb, 128),
+            )
+        }
+    }
+
+    /// Clamp 16-bit lanes to 0..=255
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn clamp_u8(value: __m128i) -> __m128i {
+        // SAFETY: arithmetic only
+        unsafe { _mm_max_epi16(_mm_min_epi16(value, _mm_set1_epi16(255)), _mm_setzero_si128()) }
+    }
+
+    #[target_feature(enable = "ssse3")]
+    pub(super) unsafe fn bgra_to_nv12_ssse3(
+        src: [&[u8]; 2],
+        y: [&mut [u8]; 2],
+        uv: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 8 * 8;
+        let [y0, y1] = y;
+
+        // SAFETY: each step reads 32 bytes of each `src` row and writes 8
+        // bytes of each `y` row and of `uv` below `done` pixels, which all
+        // of them hold
+        unsafe {
+            let zero = _mm_setzero_si128();
+            for x in (0..done).step_by(8) {
+                let mut u_sum = zero;
+                let mut v_sum = zero;
+
+                for (row, luma) in src.iter().zip([&mut *y0, &mut *y1]) {
+                    let (y_a, u_a, v_a) = bgra_to_yuv_4(k, _mm_loadu_si128(row.as_ptr().add(x * 4).cast()));
+                    let (y_b, u_b, v_b) = bgra_to_yuv_4(k, _mm_loadu_si128(row.as_ptr().add(x * 4 + 16).cast()));
+
+                    let y_bytes = _mm_packus_epi16(_mm_packs_epi32(y_a, y_b), zero);
+                    _mm_storel_epi64(luma.as_mut_ptr().add(x).cast(), y_bytes);
+
+                    // Clamp each pixel's chroma like the scalar code, then
+                    // sum horizontal pairs of pixels
+                    u_sum = _mm_add_epi16(u_sum, _mm_hadd_epi16(clamp_u8(_mm_packs_epi32(u_a, u_b)), zero));
+                    v_sum = _mm_add_epi16(v_sum, _mm_hadd_epi16(clamp_u8(_mm_packs_epi32(v_a, v_b)), zero));
+                }
+
+                // Rounded average of each 2x2 block
+                let two = _mm_set1_epi16(2);
+                let u = _mm_srai_epi16(_mm_add_epi16(u_sum, two), 2);
+                let v = _mm_srai_epi16(_mm_add_epi16(v_sum, two), 2);
+                let interleaved = _mm_unpacklo_epi16(u, v);
+                _mm_storel_epi64(
+                    uv.as_mut_ptr().add(x).cast(),
+                    _mm_packus_epi16(interleaved, interleaved),
+                );
+            }
+        }
+        done
+    }
+}
+
+/// aarch64 NEON kernels
+///
+/// YUV arithmetic widens to 32 bits, then saturating narrows clamp to
+/// 0..=255, matching the scalar code.
+#[cfg(target_arch = "aarch64")]
+// Most intrinsics are safe to call inside `#[target_feature]` functions on
+// newer toolchains, which makes some of the blocks below redundant there.
+#[allow(unused_unsafe)]
+mod neon {
+    #[allow(clippy::wildcard_imports)]
+    use std::arch::aarch64::*;
+
+    use super::{YuvCoefficients, OPAQUE};
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn shuffle_row(src: &[u8], dst: &mut [u8], pixels: usize, map: [u8; 4]) -> usize {
+        let mut control = [0u8; 16];
+        let mut fill = [0u8; 16];
+        for (index, (control, fill)) in control.iter_mut().zip(fill.iter_mut()).enumerate() {
+            let byte = map[index % 4];
+            if byte == OPAQUE {
+                // Out-of-range index: the lookup writes zero, the fill sets it
+                *control = 0xFF;
+                *fill = 0xFF;
+            } else {
+                *control = (index / 4 * 4) as u8 + byte;
+            }
+        }
+        let done = pixels / 4 * 4;
+
+        // SAFETY: every load and store covers 4 pixels below `done`, which
+        // fits both `src` and `dst`
+        unsafe {
+            let control = vld1q_u8(control.as_ptr());
+            let fill = vld1q_u8(fill.as_ptr());
+            for x in (0..done).step_by(4) {
+                let px = vld1q_u8(src.as_ptr().add(x * 4));
+                vst1q_u8(dst.as_mut_ptr().add(x * 4), vorrq_u8(vqtbl1q_u8(px, control), fill));
+            }
+        }
+        done
+    }
+
+    /// Weighted sum of three 16-bit inputs for 8 pixels
+    ///
+    /// `((a * ka + b * kb + c * kc + 128) >> 8) + offset` in 32-bit lanes,
+    /// narrowed back with saturation.
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn weigh(inputs: [int16x8_t; 3], [ka, kb, kc]: [i16; 3], offset: i16) -> int16x8_t {
+        // SAFETY: arithmetic only
+        unsafe {
+            let [a, b, c] = inputs;
+            let half = |a: int16x4_t, b: int16x4_t, c: int16x4_t| {
+                let sum = vmlal_n_s16(vmlal_n_s16(vmull_n_s16(a, ka), b, kb), c, kc);
+                let sum = vshrq_n_s32::<8>(vaddq_s32(sum, vdupq_n_s32(128)));
+                vqmovn_s32(vaddq_s32(sum, vdupq_n_s32(i32::from(offset))))
+            };
+            let lo = half(vget_low_s16(a), vget_low_s16(b), vget_low_s16(c));
+            let hi = half(vget_high_s16(a), vget_high_s16(b), vget_high_s16(c));
+            vcombine_s16(lo, hi)
+        }
+    }
+
+    /// Convert 8 pixels of Y, U and V to BGRA and store them
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn store_yuv_8(k: &YuvCoefficients, y: uint8x8_t, u: uint8x8_t, v: uint8x8_t, dst: *mut u8) {
+        // SAFETY: the caller guarantees 32 writable bytes at `dst`
+        unsafe {
+            let widen = |samples: uint8x8_t, offset: i16| {
+                vsubq_s16(vreinterpretq_s16_u16(vmovl_u8(samples)), vdupq_n_s16(offset))
+            };
+            let c = widen(y, k.y_offset);
+            let d = widen(u, 128);
+            let e = widen(v, 128);
+
+            // Saturating narrows clamp to 0..=255
+            let r = vqmovun_s16(weigh([c, e, d], [k.y_scale, k.r_v, 0], 0));
+            let g = vqmovun_s16(weigh([c, d, e], [k.y_scale, k.g_u, k.g_v], 0));
+            let b = vqmovun_s16(weigh([c, d, e], [k.y_scale, k.b_u, 0], 0));
+            vst4_u8(dst, uint8x8x4_t(b, g, r, vdup_n_u8(255)));
+        }
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn nv12_to_bgra(
+        y: &[u8],
+        uv: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 16 * 16;
+
+        // SAFETY: each step reads 16 bytes of `y` and `uv` and writes 64
+        // bytes of `dst` below `done` pixels, which all three hold
+        unsafe {
+            for x in (0..done).step_by(16) {
+                let luma = vld1q_u8(y.as_ptr().add(x));
+                let chroma = vld2_u8(uv.as_ptr().add(x));
+                let u = vzip_u8(chroma.0, chroma.0);
+                let v = vzip_u8(chroma.1, chroma.1);
+                store_yuv_8(k, vget_low_u8(luma), u.0, v.0, dst.as_mut_ptr().add(x * 4));
+                store_yuv_8(k, vget_high_u8(luma), u.1, v.1, dst.as_mut_ptr().add(x * 4 + 32));
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn i420_to_bgra(
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        dst: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 16 * 16;
+
+        // SAFETY: each step reads 16 bytes of `y`, 8 of `u` and `v` and
+        // writes 64 bytes of `dst` below `done` pixels, which all of them hold
+        unsafe {
+            for x in (0..done).step_by(16) {
+                let luma = vld1q_u8(y.as_ptr().add(x));
+                let u_samples = vld1_u8(u.as_ptr().add(x / 2));
+                let v_samples = vld1_u8(v.as_ptr().add(x / 2));
+                let u = vzip_u8(u_samples, u_samples);
+                let v = vzip_u8(v_samples, v_samples);
+                store_yuv_8(k, vget_low_u8(luma), u.0, v.0, dst.as_mut_ptr().add(x * 4));
+                store_yuv_8(k, vget_high_u8(luma), u.1, v.1, dst.as_mut_ptr().add(x * 4 + 32));
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn yuy2_to_bgra(src: &[u8], dst: &mut [u8], pixels: usize, k: &YuvCoefficients) -> usize {
+        let done = pixels / 16 * 16;
+
+        // SAFETY: each step reads 32 bytes of `src` and writes 64 bytes of
+        // `dst` below `done` pixels, which both hold
+        unsafe {
+            for x in (0..done).step_by(16) {
+                // Even luma, U, odd luma, V
+                let packed = vld4_u8(src.as_ptr().add(x * 2));
+                let luma = vzip_u8(packed.0, packed.2);
+                let u = vzip_u8(packed.1, packed.1);
+                let v = vzip_u8(packed.3, packed.3);
+                store_yuv_8(k, luma.0, u.0, v.0, dst.as_mut_ptr().add(x * 4));
+                store_yuv_8(k, luma.1, u.1, v.1, dst.as_mut_ptr().add(x * 4 + 32));
+            }
+        }
+        done
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn bgra_to_nv12(
+        src: [&[u8]; 2],
+        y: [&mut [u8]; 2],
+        uv: &mut [u8],
+        pixels: usize,
+        k: &YuvCoefficients,
+    ) -> usize {
+        let done = pixels / 8 * 8;
+        let [y0, y1] = y;
+
+        // SAFETY: each step reads 32 bytes of each `src` row and writes 8
+        // bytes of each `y` row and of `uv` below `done` pixels, which all
+        // of them hold
+        unsafe {
+            for x in (0..done).step_by(8) {
+                let mut u_sum = vdupq_n_s16(0);
+                let mut v_sum = vdupq_n_s16(0);
+
+                for (row, luma) in src.iter().zip([&mut *y0, &mut *y1]) {
+                    let px = vld4_u8(row.as_ptr().add(x * 4));
+                    let widen = |samples: uint8x8_t| vreinterpretq_s16_u16(vmovl_u8(samples));
+                    let rgb = [widen(px.2), widen(px.1), widen(px.0)];
+
+                    let y_val = vqmovun_s16(weigh(rgb, k.y_rgb, k.y_offset));
+                    vst1_u8(luma.as_mut_ptr().add(x), y_val);
+
+                    // Clamp each pixel's chroma like the scalar code, then
+                    // sum horizontal pairs of pixels
+                    let clamp = |value: int16x8_t| vmaxq_s16(vminq_s16(value, vdupq_n_s16(255)), vdupq_n_s16(0));
+                    let u = clamp(weigh(rgb, k.u_rgb, 128));
+                    let v = clamp(weigh(rgb, k.v_rgb, 128));
+                    u_sum = vaddq_s16(u_sum, vpaddq_s16(u, u));
+                    v_sum = vaddq_s16(v_sum, vpaddq_s16(v, v));
+                }
+
+                // Rounded average of each 2x2 block
+                let average = |sum: int16x8_t| {
+                    let average = vshrq_n_s16::<2>(vaddq_s16(sum, vdupq_n_s16(2)));
+                    vqmovun_s16(average)
+                };
+                let interleaved = vzip1_u8(average(u_sum), average(v_sum));
+                vst1_u8(uv.as_mut_ptr().add(x), interleaved);
+            }
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colorimetry::Colorimetry;
+
+    /// Every level this CPU supports, scalar first
+    fn supported_levels() -> Vec<SimdLevel> {
+        [SimdLevel::Scalar, SimdLevel::Ssse3, SimdLevel::Avx2, SimdLevel::Neon]
+            .into_iter()
+            .filter(|level| level.is_supported())
+            .collect()
+    }
+
+    #[test]
+    fn test_detect() {
+        let level = SimdLevel::detect();
+        assert!(level.is_supported());
+        assert_eq!(SimdLevel::detect(), level);
+        assert!(SimdLevel::Scalar.is_supported());
+    }
+
+    #[test]
+    fn test_scalar_converts_nothing() {
+        let mut dst = [0u8; 64];
+        assert_eq!(shuffle_row(SimdLevel::Scalar, &[0u8; 64], &mut dst, [2, 1, 0, 3]), 0);
+        let coefficients = Colorimetry::default().coefficients();
+        assert_eq!(
+            yuy2_to_bgra_row(SimdLevel::Scalar, &coefficients, &[0u8; 32], &mut dst),
+            0
+        );
+    }
+
+    #[test]
+    fn test_kernels_cover_whole_blocks() {
+        for level in supported_levels()
+            .into_iter()
+            .filter(|level| *level != SimdLevel::Scalar)
+        {
+            let mut dst = vec![0u8; 37 * 4];
+            let done = shuffle_row(level, &[0u8; 37 * 4], &mut dst, [2, 1, 0, OPAQUE]);
+            assert!(done >= 32 && done % 4 == 0, "{:?}: {}", level, done);
+
+            let coefficients = Colorimetry::default().coefficients();
+            let done = nv12_to_bgra_row(level, &coefficients, &[16u8; 37], &[128u8; 38], &mut dst);
+            assert!(done >= 32 && done % 8 == 0, "{:?}: {}", level, done);
+            assert!(dst[..done * 4].chunks(4).all(|px| px == [0, 0, 0, 255]));
+        }
+    }
+}
@@ -3,16 +3,19 @@
 //! Handles individual PipeWire streams for screen capture.
 
 use libspa::param::video::VideoFormat;
+use pipewire::spa::pod::Pod;
 use pipewire::spa::utils::Fraction;
-use pipewire::stream::{Stream, StreamState};
-use std::sync::{Arc, Mutex};
+use pipewire::stream::StreamState;
 use std::time::{Duration, SystemTime};
-use tokio::sync::mpsc;
+use tracing::debug;
 
-use crate::buffer::SharedBufferManager;
+use crate::colorimetry::Colorimetry;
 use crate::error::{PipeWireError, Result};
+use crate::ffi;
 use crate::format::PixelFormat;
-use crate::frame::{FrameCallback, FrameStats, VideoFrame};
+use crate::frame::FrameDataMode;
+use crate::pw_thread::{CommandSender, NegotiatedFormats, PipeWireThreadCommand, StreamStates};
+use crate::recovery::{recreate_stream, StreamConfigs};
 
 /// Stream configuration
 #[derive(Debug, Clone)]
@@ -29,6 +32,9 @@ pub struct StreamConfig {
     /// Target framerate
     pub framerate: u32,
 
+    /// Highest framerate the producer may deliver (`None` for no limit)
+    pub max_framerate: Option<u32>,
+
     /// Use DMA-BUF if available
     pub use_dmabuf: bool,
 
@@ -37,6 +43,15 @@ pub struct StreamConfig {
 
     /// Preferred format
     pub preferred_format: Option<PixelFormat>,
+
+    /// Request cursor metadata (`SPA_META_Cursor`) on buffers
+    pub enable_cursor: bool,
+
+    /// How captured pixel data is handed to consumers
+    pub frame_data_mode: FrameDataMode,
+
+    /// Recycled frame buffers kept in pooled mode
+    pub frame_pool_size: usize,
 }
 
 impl StreamConfig {
@@ -47,9 +62,13 @@ impl StreamConfig {
             width: 1920,
             height: 1080,
             framerate: 30,
+            max_framerate: None,
             use_dmabuf: true,
             buffer_count: 3,
             preferred_format: Some(PixelFormat::BGRA),
+            enable_cursor: false,
+            frame_data_mode: FrameDataMode::Pooled,
+            frame_pool_size: 8,
         }
     }
 
@@ -66,6 +85,12 @@ impl StreamConfig {
         self
     }
 
+    /// Limit the framerate the producer may deliver
+    pub fn with_max_framerate(mut self, fps: u32) -> Self {
+        self.max_framerate = Some(fps);
+        self
+    }
+
     /// Set DMA-BUF preference
     pub fn with_dmabuf(mut self, use_dmabuf: bool) -> Self {
         self.use_dmabuf = use_dmabuf;
@@ -77,6 +102,80 @@ impl StreamConfig {
         self.buffer_count = count;
         self
     }
+
+    /// Set preferred pixel format
+    pub fn with_format(mut self, format: PixelFormat) -> Self {
+        self.preferred_format = Some(format);
+        self
+    }
+
+    /// Set cursor metadata extraction
+    pub fn with_cursor(mut self, enable: bool) -> Self {
+        self.enable_cursor = enable;
+        self
+    }
+
+    /// Set how captured pixel data is handed to consumers
+    pub fn with_frame_data_mode(mut self, mode: FrameDataMode) -> Self {
+        self.frame_data_mode = mode;
+        self
+    }
+
+    /// Set the number of recycled frame buffers kept in pooled mode
+    pub fn with_frame_pool_size(mut self, size: usize) -> Self {
+        self.frame_pool_size = size;
+        self
+    }
+
+    /// Buffers that may be held by consumers in zero-copy mode
+    ///
+    /// One buffer is always left to the producer so capture never stalls.
+    pub fn max_held_buffers(&self) -> u32 {
+        self.buffer_count.saturating_sub(1)
+    }
+
+    /// Video formats offered during format negotiation, most preferred first
+    ///
+    /// The preferred format comes first, followed by the packed 32-bit RGB
+    /// formats that compositors commonly produce for screen capture.
+    pub fn negotiation_formats(&self) -> Vec<VideoFormat> {
+        let mut formats = Vec::with_capacity(9);
+        if let Some(preferred) = self.preferred_format {
+            formats.push(preferred.to_spa());
+        }
+
+        for fallback in [
+            VideoFormat::BGRx,
+            VideoFormat::BGRA,
+            VideoFormat::RGBx,
+            VideoFormat::RGBA,
+            VideoFormat::xRGB,
+            VideoFormat::ARGB,
+            VideoFormat::xBGR,
+            VideoFormat::ABGR,
+        ] {
+            if !formats.contains(&fallback) {
+                formats.push(fallback);
+            }
+        }
+
+        formats
+    }
+
+    /// DRM modifiers offered for DMA-BUF negotiation, most preferred first
+    ///
+    /// Empty when DMA-BUF is disabled. Only modifiers that can be mapped and
+    /// read linearly by the CPU are offered.
+    pub fn dmabuf_modifiers(&self) -> Vec<u64> {
+        if self.use_dmabuf && cfg!(feature = "dmabuf") {
+            vec![
+                crate::ffi::drm_fourcc::DRM_FORMAT_MOD_LINEAR,
+                crate::ffi::drm_fourcc::DRM_FORMAT_MOD_INVALID,
+            ]
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 /// PipeWire stream state
@@ -84,6 +183,8 @@ impl StreamConfig {
 pub enum PwStreamState {
     /// Stream is initializing
     Initializing,
+    /// Stream is connecting to its node
+    Connecting,
     /// Stream is ready to start
     Ready,
     /// Stream is actively streaming
@@ -101,7 +202,7 @@ impl From<StreamState> for PwStreamState {
         match state {
             StreamState::Error(_) => Self::Error,
             StreamState::Unconnected => Self::Initializing,
-            StreamState::Connecting => Self::Initializing,
+            StreamState::Connecting => Self::Connecting,
             StreamState::Paused => Self::Paused,
             StreamState::Streaming => Self::Streaming,
         }
@@ -125,63 +226,111 @@ pub struct NegotiatedFormat {
 
     /// Framerate
     pub framerate: Fraction,
+
+    /// DRM format modifier (DMA-BUF formats only)
+    pub modifier: Option<u64>,
+
+    /// YUV matrix and range
+    ///
+    /// Taken from the producer's colour matrix and range where it announces
+    /// them; otherwise guessed from the frame size.
+    pub colorimetry: Colorimetry,
+}
+
+impl NegotiatedFormat {
+    /// Build from a negotiated `Format` parameter
+    ///
+    /// The stride starts out as the tightly packed row size; the stream
+    /// replaces it with the producer's stride once buffers arrive.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the parameter is not a raw video format
+    pub fn from_format_param(param: &Pod) -> Option<Self> {
+        let info = ffi::parse_video_format(param)?;
+        let size = info.size();
+
+        Some(Self {
+            format: info.format(),
+            width: size.width,
+            height: size.height,
+            stride: size.width * ffi::get_bytes_per_pixel(info.format()) as u32,
+            framerate: info.framerate(),
+            modifier: ffi::parse_video_modifier(param),
+            colorimetry: Colorimetry::from_spa(info.color_matrix(), info.color_range(), size.width, size.height),
+        })
+    }
+
+    /// Negotiated format as a [`PixelFormat`]
+    ///
+    /// # Returns
+    ///
+    /// `None` if the format has no `PixelFormat` equivalent
+    pub fn pixel_format(&self) -> Option<PixelFormat> {
+        PixelFormat::from_spa(self.format)
+    }
+
+    /// Nominal frame duration in nanoseconds, from the negotiated framerate
+    ///
+    /// # Returns
+    ///
+    /// `None` for variable framerate streams (framerate 0/1)
+    pub fn frame_duration_ns(&self) -> Option<u64> {
+        if self.framerate.num == 0 {
+            return None;
+        }
+        Some(1_000_000_000 * u64::from(self.framerate.denom) / u64::from(self.framerate.num))
+    }
 }
 
-/// PipeWire stream handler
+/// Control handle for a stream running on the PipeWire thread
+///
+/// Obtained from [`PipeWireManager::stream_control`](crate::PipeWireManager::stream_control)
+/// (or `PipeWireConnection::get_stream`). Every handle to the same stream
+/// acts on the same PipeWire stream; frames are delivered through
+/// `PipeWireManager::frame_receiver`.
 pub struct PipeWireStream {
     /// Stream ID
     id: u32,
 
+    /// PipeWire node the stream captures
+    node_id: u32,
+
     /// Stream configuration
     config: StreamConfig,
 
-    /// PipeWire stream (using pipewire crate)
-    stream: Option<Stream>,
-
-    /// Buffer manager (used by process_frame path)
-    #[allow(dead_code)]
-    buffer_manager: SharedBufferManager,
-
-    /// Current state
-    state: Arc<Mutex<PwStreamState>>,
-
-    /// Negotiated format
-    negotiated_format: Arc<Mutex<Option<NegotiatedFormat>>>,
+    /// Access to the PipeWire thread
+    control: StreamControl,
 
-    /// Frame callback
-    frame_callback: Arc<Mutex<Option<FrameCallback>>>,
+    /// When the stream was created or last restarted
+    start_time: SystemTime,
+}
 
-    /// Frame counter (used by process_frame path)
-    #[allow(dead_code)]
-    frame_counter: Arc<Mutex<u64>>,
+/// Shared access to the PipeWire thread needed by [`PipeWireStream`]
+#[derive(Clone)]
+pub(crate) struct StreamControl {
+    /// Command channel to the PipeWire thread
+    pub(crate) commands: CommandSender,
 
-    /// Statistics
-    stats: Arc<Mutex<FrameStats>>,
+    /// Node and configuration of each stream (locked while restarting)
+    pub(crate) stream_configs: StreamConfigs,
 
-    /// Start time
-    start_time: Arc<Mutex<Option<SystemTime>>>,
+    /// Current state of each stream
+    pub(crate) states: StreamStates,
 
-    /// Frame sender channel
-    frame_tx: Arc<Mutex<Option<mpsc::Sender<VideoFrame>>>>,
+    /// Negotiated format of each stream
+    pub(crate) formats: NegotiatedFormats,
 }
 
 impl PipeWireStream {
-    /// Create new PipeWire stream
-    pub fn new(id: u32, config: StreamConfig) -> Self {
-        let buffer_manager = SharedBufferManager::new(config.buffer_count as usize);
-
+    /// Create a handle for a stream that exists on the PipeWire thread
+    pub(crate) fn new(id: u32, node_id: u32, config: StreamConfig, control: StreamControl) -> Self {
         Self {
             id,
+            node_id,
             config,
-            stream: None,
-            buffer_manager,
-            state: Arc::new(Mutex::new(PwStreamState::Initializing)),
-            negotiated_format: Arc::new(Mutex::new(None)),
-            frame_callback: Arc::new(Mutex::new(None)),
-            frame_counter: Arc::new(Mutex::new(0)),
-            stats: Arc::new(Mutex::new(FrameStats::new())),
-            start_time: Arc::new(Mutex::new(None)),
-            frame_tx: Arc::new(Mutex::new(None)),
+            control,
+            start_time: SystemTime::now(),
         }
     }
 
@@ -190,231 +339,95 @@ impl PipeWireStream {
         self.id
     }
 
-    /// Get stream state
-    pub fn state(&self) -> PwStreamState {
-        *self.state.lock().unwrap()
+    /// Get the PipeWire node ID the stream captures
+    pub fn node_id(&self) -> u32 {
+        self.node_id
     }
 
-    /// Set frame callback
-    pub fn set_frame_callback(&mut self, callback: FrameCallback) {
-        *self.frame_callback.lock().unwrap() = Some(callback);
+    /// Get stream configuration
+    pub fn config(&self) -> &StreamConfig {
+        &self.config
     }
 
-    /// Set frame channel
-    pub fn set_frame_channel(&mut self, tx: mpsc::Sender<VideoFrame>) {
-        *self.frame_tx.lock().unwrap() = Some(tx);
+    /// Get stream state
+    ///
+    /// `PwStreamState::Closing` once the stream has been removed.
+    pub fn state(&self) -> PwStreamState {
+        self.control
+            .states
+            .lock()
+            .unwrap()
+            .get(&self.id)
+            .copied()
+            .unwrap_or(PwStreamState::Closing)
     }
 
     /// Get negotiated format
     pub fn negotiated_format(&self) -> Option<NegotiatedFormat> {
-        self.negotiated_format.lock().unwrap().clone()
-    }
-
-    /// Get statistics
-    pub fn stats(&self) -> FrameStats {
-        self.stats.lock().unwrap().clone()
+        self.control.formats.lock().unwrap().get(&self.id).cloned()
     }
 
-    /// Connect to PipeWire node
-    ///
-    /// Establishes connection to the specified PipeWire node with full format negotiation,
-    /// buffer setup, and event callback registration.
-    ///
-    /// # Arguments
-    ///
-    /// * `core` - PipeWire core connection
-    /// * `node_id` - Portal-provided node ID to connect to
-    ///
-    /// # Returns
+    /// Pause streaming
     ///
-    /// Ok(()) on successful connection, Err if connection fails
+    /// Deactivates the PipeWire stream: the compositor stops producing
+    /// frames for it until [`resume`](Self::resume).
     ///
     /// # Errors
     ///
-    /// Returns error if:
-    /// - Stream creation fails
-    /// - Parameter construction fails
-    /// - Connection to node fails
-    /// - Format negotiation fails
-    pub async fn connect(&mut self, core: &pipewire::core::Core, node_id: u32) -> Result<()> {
-        use pipewire::spa::pod::Pod;
-        use pipewire::spa::utils::Direction;
-        use pipewire::stream::StreamFlags;
-
-        // Build list of acceptable formats (prepared for future format params)
-        let _formats = if let Some(pref) = self.config.preferred_format {
-            vec![pref.to_spa()]
-        } else {
-            // Prefer BGRA/BGRx for RDP compatibility
-            vec![
-                VideoFormat::BGRx,
-                VideoFormat::BGRA,
-                VideoFormat::RGBx,
-                VideoFormat::RGBA,
-            ]
-        };
-
-        // Framerate for format negotiation (prepared for future use)
-        let _framerate = Fraction {
-            num: self.config.framerate,
-            denom: 1,
-        };
-
-        // Create stream using pipewire-rs safe API
-        let stream_name = format!("lamco-pw-{}", self.id);
-
-        // Build properties
-        let mut props = pipewire::properties::Properties::new();
-        props.insert("media.type".to_string(), "Video".to_string());
-        props.insert("media.category".to_string(), "Capture".to_string());
-        props.insert("media.role".to_string(), "Screen".to_string());
-        props.insert("node.target".to_string(), node_id.to_string());
-
-        // Create the stream on the core
-        let pw_stream = pipewire::stream::Stream::new(core, &stream_name, props)
-            .map_err(|e| PipeWireError::StreamCreationFailed(format!("Failed to create stream: {}", e)))?;
-
-        // Connect stream with format parameters
-        // Note: In pipewire-rs, we connect first then params are negotiated via events
-        let mut params: Vec<&Pod> = vec![];
-        pw_stream
-            .connect(
-                Direction::Input,
-                Some(node_id),
-                StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS,
-                &mut params, // Params will be negotiated via events
-            )
-            .map_err(|e| PipeWireError::ConnectionFailed(format!("Stream connect failed: {}", e)))?;
-
-        // Store the stream reference
-        // Note: The actual Stream object needs to be managed carefully
-        // as it contains non-Send types
-        *self.state.lock().unwrap() = PwStreamState::Initializing;
-
-        // In production, we would:
-        // 1. Set up event listeners on the stream
-        // 2. Handle state changes asynchronously
-        // 3. Process format negotiation via param_changed events
-        // 4. Handle process callbacks for frame data
-        //
-        // This requires either:
-        // a) Running everything on the PipeWire thread, OR
-        // b) Using message passing between threads
-        //
-        // For our architecture, we use dedicated PipeWire thread approach
-        // implemented in connection.rs
-
-        Ok(())
-    }
-
-    /// Start streaming
-    pub async fn start(&mut self) -> Result<()> {
-        *self.state.lock().unwrap() = PwStreamState::Streaming;
-        *self.start_time.lock().unwrap() = Some(SystemTime::now());
-        Ok(())
-    }
-
-    /// Pause streaming
+    /// Returns `StreamNotFound` if the stream was removed
     pub async fn pause(&mut self) -> Result<()> {
-        *self.state.lock().unwrap() = PwStreamState::Paused;
-        Ok(())
+        self.set_active(false).await
     }
 
-    /// Resume streaming
+    /// Resume streaming after [`pause`](Self::pause)
+    ///
+    /// # Errors
+    ///
+    /// Returns `StreamNotFound` if the stream was removed
     pub async fn resume(&mut self) -> Result<()> {
-        *self.state.lock().unwrap() = PwStreamState::Streaming;
-        Ok(())
+        self.set_active(true).await
     }
 
-    /// Stop streaming
-    pub async fn stop(&mut self) -> Result<()> {
-        *self.state.lock().unwrap() = PwStreamState::Closing;
-        self.stream = None;
-        Ok(())
-    }
-
-    /// Restart stream after error
+    /// Restart the stream
+    ///
+    /// Destroys the PipeWire stream and creates it again on the same node
+    /// with the same configuration, renegotiating its format. The stream
+    /// keeps its ID, so frame receivers stay connected. A paused stream is
+    /// active again afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StreamNotFound` if the stream was removed, or the error from
+    /// re-creating it
     pub async fn restart(&mut self) -> Result<()> {
-        self.stop().await?;
-        *self.state.lock().unwrap() = PwStreamState::Initializing;
+        // Holding the lock keeps remove_stream and recovery from racing us
+        let stream_configs = self.control.stream_configs.lock().await;
+        let (node_id, config) = stream_configs
+            .get(&self.id)
+            .cloned()
+            .ok_or(PipeWireError::StreamNotFound(self.id))?;
+
+        debug!("Restarting stream {} on node {}", self.id, node_id);
+        recreate_stream(&self.control.commands, self.id, node_id, config).await?;
+        self.start_time = SystemTime::now();
         Ok(())
     }
 
-    /// Process a frame from PipeWire
-    ///
-    /// This method is currently unused as frame processing is handled directly
-    /// in the PipeWire thread. Preserved for potential future direct processing path.
-    #[allow(dead_code)]
-    async fn process_frame(&self, buffer_id: u32, pts: u64) -> Result<()> {
-        // Get buffer
-        let buffer_opt = self
-            .buffer_manager
-            .with_buffer(buffer_id, |buf| {
-                // Extract data
-                // SAFETY: Buffer is locked via with_buffer() ensuring exclusive access.
-                // We immediately copy to Vec, so no dangling slice references.
-                let data = unsafe { buf.as_slice().map(|s| s.to_vec()) };
-
-                (buf.size, buf.buffer_type, data)
+    /// Activate or deactivate the PipeWire stream
+    async fn set_active(&self, active: bool) -> Result<()> {
+        self.control
+            .commands
+            .request(|response_tx| PipeWireThreadCommand::SetActive {
+                stream_id: self.id,
+                active,
+                response_tx,
             })
-            .await;
-
-        if let Some((_size, buffer_type, Some(data))) = buffer_opt {
-            // Get negotiated format
-            let format_info = self.negotiated_format.lock().unwrap().clone();
-
-            if let Some(format) = format_info {
-                // Create video frame
-                let frame_id = {
-                    let mut counter = self.frame_counter.lock().unwrap();
-                    let id = *counter;
-                    *counter += 1;
-                    id
-                };
-
-                let pixel_format = PixelFormat::from_spa(format.format).unwrap_or(PixelFormat::BGRA);
-
-                let mut frame = VideoFrame::with_data(
-                    frame_id,
-                    format.width,
-                    format.height,
-                    format.stride,
-                    pixel_format,
-                    self.id,
-                    data,
-                );
-
-                frame.set_timing(pts, pts, 0);
-
-                if buffer_type.is_dmabuf() {
-                    frame.flags.set_dmabuf();
-                }
-
-                // Update stats
-                self.stats.lock().unwrap().update(&frame);
-
-                // Send to callback if set
-                if let Some(ref callback) = *self.frame_callback.lock().unwrap() {
-                    callback(frame.clone());
-                }
-
-                // Send to channel if set
-                if let Some(ref tx) = *self.frame_tx.lock().unwrap() {
-                    let _ = tx.try_send(frame);
-                }
-            }
-        }
-
-        Ok(())
+            .await?
     }
 
-    /// Get uptime
+    /// Get time since the stream was created or last restarted
     pub fn uptime(&self) -> Option<Duration> {
-        self.start_time
-            .lock()
-            .unwrap()
-            .as_ref()
-            .and_then(|start| start.elapsed().ok())
+        self.start_time.elapsed().ok()
     }
 }
 
@@ -443,6 +456,11 @@ pub struct StreamMetrics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::loop_signal::LoopSignal;
+    use crate::pw_thread::ThreadExit;
+    use std::collections::HashMap;
+    use std::sync::mpsc as std_mpsc;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_stream_config() {
@@ -461,22 +479,135 @@ mod tests {
     }
 
     #[test]
-    fn test_stream_creation() {
-        let config = StreamConfig::new("test");
-        let stream = PipeWireStream::new(0, config);
+    fn test_negotiation_formats() {
+        let config = StreamConfig::new("test").with_format(PixelFormat::RGBA);
+        let formats = config.negotiation_formats();
 
-        assert_eq!(stream.id(), 0);
-        assert_eq!(stream.state(), PwStreamState::Initializing);
+        assert_eq!(formats[0], VideoFormat::RGBA);
+        assert_eq!(formats.iter().filter(|f| **f == VideoFormat::RGBA).count(), 1);
+        assert!(formats.contains(&VideoFormat::BGRx));
     }
 
-    #[tokio::test]
-    async fn test_stream_state_transitions() {
-        let config = StreamConfig::new("test");
-        let mut stream = PipeWireStream::new(0, config);
+    #[test]
+    fn test_dmabuf_modifiers() {
+        let config = StreamConfig::new("test").with_dmabuf(false);
+        assert!(config.dmabuf_modifiers().is_empty());
+
+        let config = StreamConfig::new("test").with_dmabuf(true);
+        assert_eq!(config.dmabuf_modifiers().is_empty(), !cfg!(feature = "dmabuf"));
+    }
+
+    #[test]
+    fn test_frame_duration() {
+        let mut format = NegotiatedFormat {
+            format: VideoFormat::BGRx,
+            width: 1920,
+            height: 1080,
+            stride: 7680,
+            framerate: Fraction { num: 60, denom: 1 },
+            modifier: None,
+            colorimetry: Colorimetry::BT709,
+        };
+        assert_eq!(format.frame_duration_ns(), Some(16_666_666));
 
-        assert_eq!(stream.state(), PwStreamState::Initializing);
+        format.framerate = Fraction {
+            num: 30000,
+            denom: 1001,
+        };
+        assert_eq!(format.frame_duration_ns(), Some(33_366_666));
+
+        format.framerate = Fraction { num: 0, denom: 1 };
+        assert_eq!(format.frame_duration_ns(), None);
+    }
+
+    #[test]
+    fn test_stream_state_from_pipewire() {
+        assert_eq!(
+            PwStreamState::from(StreamState::Unconnected),
+            PwStreamState::Initializing
+        );
+        assert_eq!(PwStreamState::from(StreamState::Connecting), PwStreamState::Connecting);
+        assert_eq!(PwStreamState::from(StreamState::Paused), PwStreamState::Paused);
+        assert_eq!(PwStreamState::from(StreamState::Streaming), PwStreamState::Streaming);
+        assert_eq!(
+            PwStreamState::from(StreamState::Error("node removed".to_string())),
+            PwStreamState::Error
+        );
+    }
+
+    /// Control backed by a thread that answers commands like the PipeWire
+    /// thread; joining it yields the commands it handled
+    fn fake_control() -> (StreamControl, std::thread::JoinHandle<Vec<String>>) {
+        let (command_tx, command_rx) = std_mpsc::sync_channel(8);
+        let control = StreamControl {
+            commands: CommandSender::new(
+                command_tx,
+                LoopSignal::new().unwrap(),
+                Duration::from_millis(200),
+                Arc::default(),
+            ),
+            stream_configs: Arc::default(),
+            states: Arc::new(Mutex::new(HashMap::new())),
+            formats: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        let states = Arc::clone(&control.states);
+        let thread = std::thread::spawn(move || {
+            let mut handled = Vec::new();
+            for command in command_rx {
+                match command {
+                    PipeWireThreadCommand::SetActive {
+                        stream_id,
+                        active,
+                        response_tx,
+                    } => {
+                        handled.push(format!("set_active {}", active));
+                        let state = if active {
+                            PwStreamState::Streaming
+                        } else {
+                            PwStreamState::Paused
+                        };
+                        let result = match states.lock().unwrap().get_mut(&stream_id) {
+                            Some(current) => {
+                                *current = state;
+                                Ok(())
+                            }
+                            None => Err(PipeWireError::StreamNotFound(stream_id)),
+                        };
+                        let _ = response_tx.send(result);
+                    }
+                    PipeWireThreadCommand::DestroyStream { stream_id, response_tx } => {
+                        handled.push("destroy".to_string());
+                        states.lock().unwrap().remove(&stream_id);
+                        let _ = response_tx.send(Ok(()));
+                    }
+                    PipeWireThreadCommand::CreateStream {
+                        stream_id,
+                        node_id,
+                        response_tx,
+                        ..
+                    } => {
+                        handled.push(format!("create node {}", node_id));
+                        states.lock().unwrap().insert(stream_id, PwStreamState::Connecting);
+                        let _ = response_tx.send(Ok(()));
+                    }
+                    _ => {}
+                }
+            }
+            handled
+        });
+
+        (control, thread)
+    }
+
+    #[tokio::test]
+    async fn test_stream_pause_resume() {
+        let (control, thread) = fake_control();
+        control.states.lock().unwrap().insert(0, PwStreamState::Streaming);
+        let mut stream = PipeWireStream::new(0, 42, StreamConfig::new("test"), control.clone());
 
-        stream.start().await.unwrap();
+        assert_eq!(stream.id(), 0);
+        assert_eq!(stream.node_id(), 42);
         assert_eq!(stream.state(), PwStreamState::Streaming);
 
         stream.pause().await.unwrap();
@@ -485,23 +616,98 @@ mod tests {
         stream.resume().await.unwrap();
         assert_eq!(stream.state(), PwStreamState::Streaming);
 
-        stream.stop().await.unwrap();
+        drop((stream, control));
+        assert_eq!(thread.join().unwrap(), ["set_active false", "set_active true"]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_restart() {
+        let (control, thread) = fake_control();
+        control.states.lock().unwrap().insert(0, PwStreamState::Error);
+        control
+            .stream_configs
+            .lock()
+            .await
+            .insert(0, (42, StreamConfig::new("test")));
+        let mut stream = PipeWireStream::new(0, 42, StreamConfig::new("test"), control.clone());
+
+        stream.restart().await.unwrap();
+        assert_eq!(stream.state(), PwStreamState::Connecting);
+
+        drop((stream, control));
+        assert_eq!(thread.join().unwrap(), ["destroy", "create node 42"]);
+    }
+
+    #[tokio::test]
+    async fn test_removed_stream() {
+        let (control, thread) = fake_control();
+        let mut stream = PipeWireStream::new(0, 42, StreamConfig::new("test"), control.clone());
+
         assert_eq!(stream.state(), PwStreamState::Closing);
+        assert!(stream.negotiated_format().is_none());
+        assert!(matches!(stream.pause().await, Err(PipeWireError::StreamNotFound(0))));
+        assert!(matches!(stream.restart().await, Err(PipeWireError::StreamNotFound(0))));
+
+        drop((stream, control));
+        assert_eq!(thread.join().unwrap(), ["set_active false"]);
     }
 
-    #[test]
-    fn test_frame_callback() {
-        let config = StreamConfig::new("test");
-        let mut stream = PipeWireStream::new(0, config);
+    #[tokio::test]
+    async fn test_unanswered_command_times_out() {
+        // A stalled thread: commands are queued but never answered
+        let (command_tx, command_rx) = std_mpsc::sync_channel(8);
+        let control = StreamControl {
+            commands: CommandSender::new(
+                command_tx,
+                LoopSignal::new().unwrap(),
+                Duration::from_millis(50),
+                Arc::default(),
+            ),
+            stream_configs: Arc::default(),
+            states: Arc::new(Mutex::new(HashMap::new())),
+            formats: Arc::new(Mutex::new(HashMap::new())),
+        };
+        let mut stream = PipeWireStream::new(0, 42, StreamConfig::new("test"), control);
 
-        let received = Arc::new(Mutex::new(false));
-        let received_clone = Arc::clone(&received);
+        assert!(matches!(stream.pause().await, Err(PipeWireError::Timeout)));
+        drop(command_rx);
+    }
 
-        stream.set_frame_callback(Box::new(move |_frame| {
-            *received_clone.lock().unwrap() = true;
-        }));
+    #[tokio::test]
+    async fn test_dead_thread_reports_panic() {
+        let (command_tx, command_rx) = std_mpsc::sync_channel(8);
+        let thread_exit: ThreadExit = Arc::default();
+        let control = StreamControl {
+            commands: CommandSender::new(
+                command_tx,
+                LoopSignal::new().unwrap(),
+                Duration::from_secs(5),
+                Arc::clone(&thread_exit),
+            ),
+            stream_configs: Arc::default(),
+            states: Arc::new(Mutex::new(HashMap::new())),
+            formats: Arc::new(Mutex::new(HashMap::new())),
+        };
+        control
+            .stream_configs
+            .lock()
+            .await
+            .insert(0, (42, StreamConfig::new("test")));
+        let mut stream = PipeWireStream::new(0, 42, StreamConfig::new("test"), control);
+
+        // The thread dies while a command is queued: the reply is dropped
+        let thread = std::thread::spawn(move || {
+            let command = command_rx.recv().unwrap();
+            *thread_exit.lock().unwrap() = Some("PipeWire thread panicked: boom".to_string());
+            drop((command, command_rx));
+        });
+        match stream.resume().await {
+            Err(PipeWireError::ThreadPanic(reason)) => assert!(reason.contains("boom")),
+            other => unreachable!("expected ThreadPanic, got {:?}", other),
+        }
+        thread.join().unwrap();
 
-        // Verify callback is set
-        assert!(stream.frame_callback.lock().unwrap().is_some());
+        // Later commands fail immediately instead of waiting for the timeout
+        assert!(matches!(stream.restart().await, Err(PipeWireError::ThreadPanic(_))));
     }
 }
@@ -2,48 +2,89 @@
 //!
 //! Provides conversion from YUV color formats to RGB/BGRA for display.
 //! These conversions are useful when PipeWire provides frames in compressed
-//! YUV formats (NV12, I420, YUY2) that need to be converted for rendering.
+//! YUV formats (NV12, P010, I420, YUY2) that need to be converted for rendering.
 //!
 //! # Supported Formats
 //!
 //! - **NV12**: YUV 4:2:0 with interleaved UV plane (common for hardware encoders)
+//! - **P010**: NV12 layout with 16-bit samples holding 10-bit values (HDR)
 //! - **I420**: YUV 4:2:0 with separate U and V planes (aka YV12)
 //! - **YUY2**: YUV 4:2:2 packed format (YUYV)
 //!
+//! The `*_planes_to_bgra` / `*_strided_to_bgra` variants and
+//! [`YuvConverter::convert_frame`] read planes with arbitrary strides, as
+//! delivered by producers that pad rows or place planes in separate buffers.
+//!
+//! # Error Handling
+//!
+//! The `*_to_bgra_into` variants write into a caller-provided buffer with
+//! an explicit destination stride and return an error for buffers that are
+//! too small. The `Vec`-returning functions panic instead, so prefer the
+//! `_into` variants (or [`YuvConverter`]) for data from other processes.
+//!
+//! # Odd Dimensions
+//!
+//! Chroma planes hold `(width + 1) / 2` samples per row and
+//! `(height + 1) / 2` rows, as PipeWire lays them out. At odd widths and
+//! heights the last chroma sample of a row (or the last chroma row) covers
+//! only the edge pixel, and is replicated into it.
+//!
+//! # Colorimetry
+//!
+//! The free functions decode BT.601 limited range. [`YuvConverter`] decodes
+//! with the colorimetry set through [`YuvConverter::with_colorimetry`], or
+//! else the frame's own (see [`VideoFrame::colorimetry`]), falling back to
+//! BT.601 limited range for raw data.
+//!
 //! # Performance
 //!
-//! These are reference implementations prioritizing correctness over speed.
-//! For production use with high frame rates, consider:
-//! - SIMD-accelerated implementations
+//! NV12, I420 and YUY2 rows run through the SIMD kernels in [`crate::simd`]
+//! when the CPU supports them (SSSE3/AVX2 on x86, NEON on aarch64), with
+//! identical output to the scalar code. For higher frame rates still,
+//! consider:
 //! - GPU-based conversion (OpenGL/Vulkan shaders)
 //! - Hardware decoder output directly to RGB
 //!
 //! # Examples
 //!
 //! ```rust,no_run
-//! use lamco_pipewire::yuv::{nv12_to_bgra, YuvConverter};
+//! use lamco_pipewire::yuv::{nv12_to_bgra, nv12_to_bgra_into, YuvConverter};
 //!
 //! // Direct conversion
 //! let nv12_data: &[u8] = &[0u8; 3110400]; // 1920x1080 NV12 frame
 //! let bgra = nv12_to_bgra(nv12_data, 1920, 1080);
 //!
+//! // Conversion into an existing buffer, failing on short input
+//! let mut frame = vec![0u8; 1920 * 1080 * 4];
+//! nv12_to_bgra_into(nv12_data, 1920, 1080, &mut frame, 1920 * 4)?;
+//!
 //! // Using converter with format detection
 //! let converter = YuvConverter::new();
+//! # Ok::<(), lamco_pipewire::PipeWireError>(())
 //! ```
 
-use crate::format::PixelFormat;
+use crate::colorimetry::Colorimetry;
+use crate::error::{PipeWireError, Result};
+use crate::format::{
+    bgra_frame_size, check_source_planes, convert_format, convert_format_planes, convert_i420_to_bgra,
+    convert_nv12_to_bgra, convert_yuy2_to_bgra, FramePlane, PixelFormat,
+};
+use crate::frame::VideoFrame;
 
 /// Convert NV12 to BGRA
 ///
 /// NV12 is YUV 4:2:0 with:
 /// - Y plane: width * height bytes
-/// - UV plane: width * height / 2 bytes (interleaved U, V)
+/// - UV plane: (width + 1) / 2 * 2 bytes per row, (height + 1) / 2 rows
+///   (interleaved U, V)
+///
+/// Rows of both planes are `width` bytes, rounded up to even.
 ///
 /// # Arguments
 ///
 /// * `src` - Source NV12 data
-/// * `width` - Frame width (must be even)
-/// * `height` - Frame height (must be even)
+/// * `width` - Frame width
+/// * `height` - Frame height
 ///
 /// # Returns
 ///
@@ -51,104 +92,148 @@ use crate::format::PixelFormat;
 ///
 /// # Panics
 ///
-/// Panics if source data is too small for the given dimensions.
+/// Panics if source data is too small for the given dimensions. Use
+/// [`nv12_to_bgra_into`] to get an error instead.
 #[must_use]
 pub fn nv12_to_bgra(src: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let w = width as usize;
-    let h = height as usize;
-
-    let y_plane_size = w * h;
-    let uv_plane_size = w * h / 2;
-
-    assert!(
-        src.len() >= y_plane_size + uv_plane_size,
-        "NV12 source data too small: need {}, got {}",
-        y_plane_size + uv_plane_size,
-        src.len()
-    );
-
-    let y_plane = &src[..y_plane_size];
-    let uv_plane = &src[y_plane_size..y_plane_size + uv_plane_size];
-
-    let mut dst = vec![0u8; w * h * 4];
-
-    for y in 0..h {
-        for x in 0..w {
-            let y_idx = y * w + x;
-            let uv_idx = (y / 2) * w + (x / 2) * 2;
-
-            let y_val = i32::from(y_plane[y_idx]);
-            let u_val = i32::from(uv_plane[uv_idx]);
-            let v_val = i32::from(uv_plane[uv_idx + 1]);
-
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
+    decode_to_vec(width, height, |dst, dst_stride| {
+        nv12_to_bgra_into(src, width, height, dst, dst_stride)
+    })
+}
 
-            let dst_idx = y_idx * 4;
-            dst[dst_idx] = b;
-            dst[dst_idx + 1] = g;
-            dst[dst_idx + 2] = r;
-            dst[dst_idx + 3] = 255; // Alpha
-        }
-    }
+/// Convert NV12 to BGRA in a caller-provided buffer
+///
+/// The source layout is as for [`nv12_to_bgra`].
+///
+/// # Arguments
+///
+/// * `src` - Source NV12 data
+/// * `width` - Frame width
+/// * `height` - Frame height
+/// * `dst` - Destination, `height` rows of `dst_stride` bytes
+/// * `dst_stride` - Destination row stride in bytes (at least `width * 4`)
+///
+/// # Errors
+///
+/// Returns `FormatConversionFailed` if `src` or `dst` is too small for the
+/// given dimensions and strides.
+pub fn nv12_to_bgra_into(src: &[u8], width: u32, height: u32, dst: &mut [u8], dst_stride: u32) -> Result<()> {
+    decode_into(src, PixelFormat::NV12, width, height, dst, dst_stride)
+}
 
-    dst
+/// Convert NV12 planes with arbitrary strides to BGRA
+///
+/// # Arguments
+///
+/// * `y_plane` - Luma plane, `height` rows of `y_stride` bytes
+/// * `y_stride` - Luma row stride in bytes
+/// * `uv_plane` - Interleaved chroma plane, `(height + 1) / 2` rows of `uv_stride` bytes
+/// * `uv_stride` - Chroma row stride in bytes
+/// * `width` - Frame width
+/// * `height` - Frame height
+///
+/// # Returns
+///
+/// BGRA data (width * height * 4 bytes)
+///
+/// # Panics
+///
+/// Panics if a plane is too small for the given dimensions and stride.
+#[must_use]
+pub fn nv12_planes_to_bgra(
+    y_plane: &[u8],
+    y_stride: u32,
+    uv_plane: &[u8],
+    uv_stride: u32,
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let coefficients = Colorimetry::BT601.coefficients();
+    decode_to_vec(width, height, |dst, dst_stride| {
+        convert_nv12_to_bgra(
+            y_plane,
+            y_stride,
+            uv_plane,
+            uv_stride,
+            dst,
+            width,
+            height,
+            dst_stride,
+            &coefficients,
+        )
+    })
 }
 
 /// Convert I420 to BGRA
 ///
 /// I420 is YUV 4:2:0 with separate planes:
 /// - Y plane: width * height bytes
-/// - U plane: width/2 * height/2 bytes
-/// - V plane: width/2 * height/2 bytes
+/// - U plane: (width + 1) / 2 * (height + 1) / 2 bytes
+/// - V plane: (width + 1) / 2 * (height + 1) / 2 bytes
 ///
 /// # Arguments
 ///
 /// * `src` - Source I420 data
-/// * `width` - Frame width (must be even)
-/// * `height` - Frame height (must be even)
+/// * `width` - Frame width
+/// * `height` - Frame height
 ///
 /// # Returns
 ///
 /// BGRA data (width * height * 4 bytes)
+///
+/// # Panics
+///
+/// Panics if source data is too small for the given dimensions. Use
+/// [`i420_to_bgra_into`] to get an error instead.
 #[must_use]
 pub fn i420_to_bgra(src: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let w = width as usize;
-    let h = height as usize;
-
-    let y_plane_size = w * h;
-    let uv_plane_size = (w / 2) * (h / 2);
-
-    assert!(
-        src.len() >= y_plane_size + uv_plane_size * 2,
-        "I420 source data too small"
-    );
-
-    let y_plane = &src[..y_plane_size];
-    let u_plane = &src[y_plane_size..y_plane_size + uv_plane_size];
-    let v_plane = &src[y_plane_size + uv_plane_size..y_plane_size + uv_plane_size * 2];
-
-    let mut dst = vec![0u8; w * h * 4];
-
-    for y in 0..h {
-        for x in 0..w {
-            let y_idx = y * w + x;
-            let uv_idx = (y / 2) * (w / 2) + (x / 2);
-
-            let y_val = i32::from(y_plane[y_idx]);
-            let u_val = i32::from(u_plane[uv_idx]);
-            let v_val = i32::from(v_plane[uv_idx]);
-
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val);
+    decode_to_vec(width, height, |dst, dst_stride| {
+        i420_to_bgra_into(src, width, height, dst, dst_stride)
+    })
+}
 
-            let dst_idx = y_idx * 4;
-            dst[dst_idx] = b;
-            dst[dst_idx + 1] = g;
-            dst[dst_idx + 2] = r;
-            dst[dst_idx + 3] = 255;
-        }
-    }
+/// Convert I420 to BGRA in a caller-provided buffer
+///
+/// The source layout is as for [`i420_to_bgra`].
+///
+/// # Arguments
+///
+/// * `src` - Source I420 data
+/// * `width` - Frame width
+/// * `height` - Frame height
+/// * `dst` - Destination, `height` rows of `dst_stride` bytes
+/// * `dst_stride` - Destination row stride in bytes (at least `width * 4`)
+///
+/// # Errors
+///
+/// Returns `FormatConversionFailed` if `src` or `dst` is too small for the
+/// given dimensions and strides.
+pub fn i420_to_bgra_into(src: &[u8], width: u32, height: u32, dst: &mut [u8], dst_stride: u32) -> Result<()> {
+    decode_into(src, PixelFormat::I420, width, height, dst, dst_stride)
+}
 
-    dst
+/// Convert I420 planes with arbitrary strides to BGRA
+///
+/// # Arguments
+///
+/// * `planes` - Y, U and V planes
+/// * `strides` - Row stride in bytes of each plane
+/// * `width` - Frame width
+/// * `height` - Frame height
+///
+/// # Returns
+///
+/// BGRA data (width * height * 4 bytes)
+///
+/// # Panics
+///
+/// Panics if a plane is too small for the given dimensions and stride.
+#[must_use]
+pub fn i420_planes_to_bgra(planes: [&[u8]; 3], strides: [u32; 3], width: u32, height: u32) -> Vec<u8> {
+    let coefficients = Colorimetry::BT601.coefficients();
+    decode_to_vec(width, height, |dst, dst_stride| {
+        convert_i420_to_bgra(planes, strides, dst, width, height, dst_stride, &coefficients)
+    })
 }
 
 /// Convert YUY2 to BGRA
@@ -157,85 +242,116 @@ pub fn i420_to_bgra(src: &[u8], width: u32, height: u32) -> Vec<u8> {
 /// - Each 4-byte macro pixel: Y0, U, Y1, V
 /// - Represents 2 horizontal pixels sharing U and V
 ///
+/// At odd widths the last macropixel's second luma sample is padding.
+///
 /// # Arguments
 ///
 /// * `src` - Source YUY2 data
-/// * `width` - Frame width (must be even)
+/// * `width` - Frame width
 /// * `height` - Frame height
 ///
 /// # Returns
 ///
 /// BGRA data (width * height * 4 bytes)
+///
+/// # Panics
+///
+/// Panics if source data is too small for the given dimensions. Use
+/// [`yuy2_to_bgra_into`] to get an error instead.
 #[must_use]
 pub fn yuy2_to_bgra(src: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let w = width as usize;
-    let h = height as usize;
-
-    assert!(w % 2 == 0, "YUY2 width must be even");
-    assert!(src.len() >= w * h * 2, "YUY2 source data too small");
-
-    let mut dst = vec![0u8; w * h * 4];
-
-    for y in 0..h {
-        for x in (0..w).step_by(2) {
-            let src_idx = (y * w + x) * 2;
-
-            let y0 = i32::from(src[src_idx]);
-            let u = i32::from(src[src_idx + 1]);
-            let y1 = i32::from(src[src_idx + 2]);
-            let v = i32::from(src[src_idx + 3]);
-
-            // First pixel
-            let (r0, g0, b0) = yuv_to_rgb(y0, u, v);
-            let dst_idx0 = (y * w + x) * 4;
-            dst[dst_idx0] = b0;
-            dst[dst_idx0 + 1] = g0;
-            dst[dst_idx0 + 2] = r0;
-            dst[dst_idx0 + 3] = 255;
-
-            // Second pixel
-            let (r1, g1, b1) = yuv_to_rgb(y1, u, v);
-            let dst_idx1 = (y * w + x + 1) * 4;
-            dst[dst_idx1] = b1;
-            dst[dst_idx1 + 1] = g1;
-            dst[dst_idx1 + 2] = r1;
-            dst[dst_idx1 + 3] = 255;
-        }
-    }
+    decode_to_vec(width, height, |dst, dst_stride| {
+        yuy2_to_bgra_into(src, width, height, dst, dst_stride)
+    })
+}
 
-    dst
+/// Convert YUY2 to BGRA in a caller-provided buffer
+///
+/// The source layout is as for [`yuy2_to_bgra`].
+///
+/// # Arguments
+///
+/// * `src` - Source YUY2 data
+/// * `width` - Frame width
+/// * `height` - Frame height
+/// * `dst` - Destination, `height` rows of `dst_stride` bytes
+/// * `dst_stride` - Destination row stride in bytes (at least `width * 4`)
+///
+/// # Errors
+///
+/// Returns `FormatConversionFailed` if `src` or `dst` is too small for the
+/// given dimensions and strides.
+pub fn yuy2_to_bgra_into(src: &[u8], width: u32, height: u32, dst: &mut [u8], dst_stride: u32) -> Result<()> {
+    decode_into(src, PixelFormat::YUY2, width, height, dst, dst_stride)
+}
+
+/// Convert YUY2 with an arbitrary row stride to BGRA
+///
+/// # Arguments
+///
+/// * `src` - Source YUY2 data, `height` rows of `stride` bytes
+/// * `stride` - Row stride in bytes
+/// * `width` - Frame width
+/// * `height` - Frame height
+///
+/// # Returns
+///
+/// BGRA data (width * height * 4 bytes)
+///
+/// # Panics
+///
+/// Panics if source data is too small for the given dimensions and stride.
+#[must_use]
+pub fn yuy2_strided_to_bgra(src: &[u8], stride: u32, width: u32, height: u32) -> Vec<u8> {
+    let coefficients = Colorimetry::BT601.coefficients();
+    decode_to_vec(width, height, |dst, dst_stride| {
+        convert_yuy2_to_bgra(src, dst, width, height, stride, dst_stride, &coefficients)
+    })
 }
 
-/// Convert single YUV pixel to RGB
-///
-/// Uses BT.601 color matrix (standard for SD video):
-/// R = 1.164(Y-16) + 1.596(V-128)
-/// G = 1.164(Y-16) - 0.813(V-128) - 0.391(U-128)
-/// B = 1.164(Y-16) + 2.018(U-128)
-#[inline]
-fn yuv_to_rgb(y: i32, u: i32, v: i32) -> (u8, u8, u8) {
-    // Scale factors (multiplied by 256 for integer math)
-    const Y_SCALE: i32 = 298; // 1.164 * 256
-    const V_TO_R: i32 = 409; // 1.596 * 256
-    const U_TO_G: i32 = 100; // 0.391 * 256
-    const V_TO_G: i32 = 208; // 0.813 * 256
-    const U_TO_B: i32 = 516; // 2.018 * 256
-
-    let y = y - 16;
-    let u = u - 128;
-    let v = v - 128;
-
-    let r = (Y_SCALE * y + V_TO_R * v + 128) >> 8;
-    let g = (Y_SCALE * y - U_TO_G * u - V_TO_G * v + 128) >> 8;
-    let b = (Y_SCALE * y + U_TO_B * u + 128) >> 8;
-
-    (r.clamp(0, 255) as u8, g.clamp(0, 255) as u8, b.clamp(0, 255) as u8)
+/// Decode tightly packed YUV data into `dst` with BT.601 limited range
+fn decode_into(
+    src: &[u8],
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+) -> Result<()> {
+    convert_format(
+        src,
+        dst,
+        format,
+        PixelFormat::BGRA,
+        width,
+        height,
+        format.min_stride(width).ok_or_else(|| {
+            PipeWireError::FormatConversionFailed(format!("{:?} row of {} pixels is too large", format, width))
+        })?,
+        dst_stride,
+        Colorimetry::BT601,
+    )
+}
+
+/// Decode into a new tightly packed BGRA buffer, panicking on failure
+fn decode_to_vec(width: u32, height: u32, decode: impl FnOnce(&mut [u8], u32) -> Result<()>) -> Vec<u8> {
+    let (stride, size) = bgra_frame_size(width, height).expect("BGRA frame too large");
+    let mut dst = vec![0u8; size];
+    // The destination fits by construction, so only the source can fail
+    decode(&mut dst, stride).expect("YUV source data too small");
+    dst
 }
 
 /// YUV format converter with caching and format detection
+///
+/// The output buffer is reused across conversions, growing to the largest
+/// frame seen. Conversions never panic: malformed input yields `None`.
 pub struct YuvConverter {
     /// Reusable output buffer to avoid allocations
     output_buffer: Vec<u8>,
+
+    /// Colorimetry overriding the frame's, if set
+    colorimetry: Option<Colorimetry>,
 }
 
 impl YuvConverter {
@@ -244,9 +360,30 @@ impl YuvConverter {
     pub fn new() -> Self {
         Self {
             output_buffer: Vec::new(),
+            colorimetry: None,
         }
     }
 
+    /// Decode with this colour matrix and range
+    ///
+    /// Takes precedence over the colorimetry recorded on frames.
+    #[must_use]
+    pub fn with_colorimetry(mut self, colorimetry: Colorimetry) -> Self {
+        self.colorimetry = Some(colorimetry);
+        self
+    }
+
+    /// Set or clear the colorimetry override
+    pub fn set_colorimetry(&mut self, colorimetry: Option<Colorimetry>) {
+        self.colorimetry = colorimetry;
+    }
+
+    /// Colorimetry override, if set
+    #[must_use]
+    pub fn colorimetry(&self) -> Option<Colorimetry> {
+        self.colorimetry
+    }
+
     /// Convert YUV data to BGRA
     ///
     /// # Arguments
@@ -258,33 +395,110 @@ impl YuvConverter {
     ///
     /// # Returns
     ///
-    /// Reference to internal BGRA buffer (valid until next conversion)
+    /// Reference to internal BGRA buffer (valid until next conversion), or
+    /// `None` if the format needs no conversion or `src` is too small
     pub fn convert_to_bgra(&mut self, src: &[u8], width: u32, height: u32, format: PixelFormat) -> Option<&[u8]> {
-        let result = match format {
-            PixelFormat::NV12 => nv12_to_bgra(src, width, height),
-            PixelFormat::I420 => i420_to_bgra(src, width, height),
-            PixelFormat::YUY2 => yuy2_to_bgra(src, width, height),
-            // Already in RGB family - no conversion needed
-            PixelFormat::BGRA | PixelFormat::RGBA | PixelFormat::BGRx | PixelFormat::RGBx => {
-                return None;
-            }
-            _ => return None,
-        };
+        let planes = format.plane_layout(height, format.min_stride(width)?);
+        self.convert_planes_to_bgra(src, &planes, width, height, format)
+    }
+
+    /// Convert YUV planes with arbitrary strides to BGRA
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - Source YUV data
+    /// * `planes` - Location and stride of each plane within `src`
+    /// * `width` - Frame width
+    /// * `height` - Frame height
+    /// * `format` - Source pixel format
+    ///
+    /// # Returns
+    ///
+    /// Reference to internal BGRA buffer (valid until next conversion), or
+    /// `None` if the format needs no conversion or a plane is missing or
+    /// too small
+    pub fn convert_planes_to_bgra(
+        &mut self,
+        src: &[u8],
+        planes: &[FramePlane],
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Option<&[u8]> {
+        let colorimetry = self.colorimetry.unwrap_or_default();
+        self.convert(src, planes, width, height, format, colorimetry)
+    }
+
+    /// Convert a captured frame to BGRA
+    ///
+    /// Uses the frame's plane layout, so planes delivered in separate
+    /// buffers or with padded rows are read correctly, and the frame's
+    /// colorimetry unless the converter overrides it.
+    pub fn convert_frame(&mut self, frame: &VideoFrame) -> Option<&[u8]> {
+        let planes = frame.plane_layout();
+        let colorimetry = self.colorimetry.unwrap_or(frame.colorimetry);
+        self.convert(
+            &frame.data,
+            &planes,
+            frame.width,
+            frame.height,
+            frame.format,
+            colorimetry,
+        )
+    }
+
+    fn convert(
+        &mut self,
+        src: &[u8],
+        planes: &[FramePlane],
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        colorimetry: Colorimetry,
+    ) -> Option<&[u8]> {
+        // RGB formats need no conversion
+        if !Self::needs_conversion(format) {
+            return None;
+        }
+
+        // Validate the source before sizing the output from the dimensions it
+        // claims, so a short buffer can't trigger a huge allocation
+        check_source_planes(src, planes, format, width, height).ok()?;
+        let (stride, size) = bgra_frame_size(width, height).ok()?;
+
+        // Resizing keeps the allocation, so frames of changing size reuse it
+        self.output_buffer.resize(size, 0);
+        convert_format_planes(
+            src,
+            planes,
+            &mut self.output_buffer,
+            format,
+            PixelFormat::BGRA,
+            width,
+            height,
+            stride,
+            colorimetry,
+        )
+        .ok()?;
 
-        self.output_buffer = result;
         Some(&self.output_buffer)
     }
 
     /// Check if format needs YUV conversion
     #[must_use]
     pub fn needs_conversion(format: PixelFormat) -> bool {
-        matches!(format, PixelFormat::NV12 | PixelFormat::I420 | PixelFormat::YUY2)
+        matches!(
+            format,
+            PixelFormat::NV12 | PixelFormat::P010 | PixelFormat::I420 | PixelFormat::YUY2
+        )
     }
 
     /// Get required buffer size for BGRA output
+    ///
+    /// Saturates at `usize::MAX` for dimensions too large to address.
     #[must_use]
     pub fn output_size(width: u32, height: u32) -> usize {
-        (width as usize) * (height as usize) * 4
+        (width as usize).saturating_mul(height as usize).saturating_mul(4)
     }
 }
 
@@ -301,11 +515,12 @@ mod tests {
     #[test]
     fn test_yuv_to_rgb() {
         // Black (Y=16, U=128, V=128)
-        let (r, g, b) = yuv_to_rgb(16, 128, 128);
+        let coefficients = Colorimetry::BT601.coefficients();
+        let (r, g, b) = coefficients.yuv_to_rgb(16, 128, 128);
         assert_eq!((r, g, b), (0, 0, 0));
 
         // White (Y=235, U=128, V=128)
-        let (r, g, b) = yuv_to_rgb(235, 128, 128);
+        let (r, g, b) = coefficients.yuv_to_rgb(235, 128, 128);
         assert!(r > 250 && g > 250 && b > 250);
     }
 
@@ -352,12 +567,216 @@ mod tests {
         assert!(bgra[0] < 5 && bgra[1] < 5 && bgra[2] < 5);
     }
 
+    #[test]
+    fn test_into_with_destination_stride() {
+        let nv12 = [16, 80, 160, 235, 40, 100, 180, 220, 90, 200, 150, 60];
+        let expected = nv12_to_bgra(&nv12, 4, 2);
+
+        // 20-byte destination rows, padding left untouched
+        let mut dst = [0xAAu8; 40];
+        nv12_to_bgra_into(&nv12, 4, 2, &mut dst, 20).expect("converts");
+        assert_eq!(&dst[..16], &expected[..16]);
+        assert_eq!(&dst[20..36], &expected[16..]);
+        assert!(dst[16..20].iter().chain(&dst[36..]).all(|&b| b == 0xAA));
+
+        let i420 = [16, 80, 160, 235, 40, 100, 180, 220, 90, 150, 200, 60];
+        i420_to_bgra_into(&i420, 4, 2, &mut dst, 20).expect("converts");
+        assert_eq!(&dst[20..36], &i420_to_bgra(&i420, 4, 2)[16..]);
+
+        let yuy2 = [16, 128, 235, 128, 100, 90, 150, 200];
+        yuy2_to_bgra_into(&yuy2, 2, 2, &mut dst, 20).expect("converts");
+        assert_eq!(&dst[20..28], &yuy2_to_bgra(&yuy2, 2, 2)[8..]);
+    }
+
+    #[test]
+    fn test_into_rejects_short_buffers() {
+        let nv12 = [16u8; 12];
+        let yuy2 = [16u8; 16];
+        let mut dst = [0u8; 32];
+
+        assert!(nv12_to_bgra_into(&nv12[..11], 4, 2, &mut dst, 16).is_err());
+        assert!(nv12_to_bgra_into(&nv12, 4, 2, &mut dst[..31], 16).is_err());
+        assert!(nv12_to_bgra_into(&nv12, 4, 2, &mut dst, 12).is_err());
+        assert!(i420_to_bgra_into(&nv12[..11], 4, 2, &mut dst, 16).is_err());
+        assert!(yuy2_to_bgra_into(&yuy2[..15], 4, 2, &mut dst, 16).is_err());
+        assert!(nv12_to_bgra_into(&[], 0, 0, &mut [], 0).is_ok());
+    }
+
+    #[test]
+    fn test_odd_dimensions_replicate_edge_chroma() {
+        let coefficients = Colorimetry::BT601.coefficients();
+        let bgr = |y, u, v| {
+            let (r, g, b) = coefficients.yuv_to_rgb(y, u, v);
+            [b, g, r, 255]
+        };
+
+        // 3x3 NV12: 4-byte rows, two chroma rows of two samples
+        let nv12 = [
+            50, 60, 70, 0, //
+            80, 90, 100, 0, //
+            110, 120, 130, 0, //
+            100, 150, 200, 60, //
+            30, 220, 170, 90,
+        ];
+        let bgra = nv12_to_bgra(&nv12, 3, 3);
+        assert_eq!(bgra.len(), 36);
+        assert_eq!(bgra[8..12], bgr(70, 200, 60));
+        assert_eq!(bgra[20..24], bgr(100, 200, 60));
+        assert_eq!(bgra[24..28], bgr(110, 30, 220));
+        assert_eq!(bgra[32..36], bgr(130, 170, 90));
+
+        // The same frame as I420 (chroma rows of two samples)
+        let i420 = [
+            50, 60, 70, 80, 90, 100, 110, 120, 130, //
+            100, 200, 30, 170, //
+            150, 60, 220, 90,
+        ];
+        assert_eq!(i420_to_bgra(&i420, 3, 3), bgra);
+
+        // 3x1 YUY2: the last macropixel's second luma sample is padding
+        let yuy2 = [50, 100, 60, 150, 70, 200, 0, 60];
+        assert_eq!(yuy2_to_bgra(&yuy2, 3, 1), bgra[..12]);
+    }
+
+    #[test]
+    fn test_nv12_planes_with_strides() {
+        let tight = [16, 80, 160, 235, 40, 100, 180, 220, 90, 200, 150, 60];
+        let expected = nv12_to_bgra(&tight, 4, 2);
+
+        // 6-byte luma rows and 8-byte chroma rows, padding filled with junk
+        let y_plane = [16, 80, 160, 235, 1, 2, 40, 100, 180, 220, 3, 4];
+        let uv_plane = [90, 200, 150, 60, 5, 6, 7, 8];
+        assert_eq!(nv12_planes_to_bgra(&y_plane, 6, &uv_plane, 8, 4, 2), expected);
+    }
+
+    #[test]
+    fn test_i420_planes_with_strides() {
+        let tight = [16, 80, 160, 235, 40, 100, 180, 220, 90, 150, 200, 60];
+        let expected = i420_to_bgra(&tight, 4, 2);
+
+        let y_plane = [16, 80, 160, 235, 0, 0, 0, 0, 40, 100, 180, 220];
+        let u_plane = [90, 150, 0, 0];
+        let v_plane = [200, 60, 0];
+        assert_eq!(
+            i420_planes_to_bgra([&y_plane, &u_plane, &v_plane], [8, 4, 3], 4, 2),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_yuy2_strided_to_bgra() {
+        let tight = [16, 128, 235, 128, 100, 90, 150, 200];
+        let padded = [16, 128, 235, 128, 9, 9, 100, 90, 150, 200];
+        assert_eq!(yuy2_strided_to_bgra(&padded, 6, 2, 2), yuy2_to_bgra(&tight, 2, 2));
+    }
+
+    #[test]
+    fn test_converter_planes_from_frame() {
+        let tight = [16, 80, 160, 235, 40, 100, 180, 220, 90, 200, 150, 60];
+        let expected = nv12_to_bgra(&tight, 4, 2);
+
+        // UV plane stored first, as when planes arrive in separate buffers
+        let data = vec![90, 200, 150, 60, 16, 80, 160, 235, 40, 100, 180, 220];
+        let mut frame = VideoFrame::with_data(1, 4, 2, 4, PixelFormat::NV12, 0, data);
+        frame.planes = vec![FramePlane::new(4, 4, 8), FramePlane::new(0, 4, 4)];
+
+        let mut converter = YuvConverter::new();
+        assert_eq!(converter.convert_frame(&frame), Some(expected.as_slice()));
+
+        // Missing or out-of-range planes are rejected
+        frame.planes.truncate(1);
+        assert!(converter.convert_frame(&frame).is_none());
+        frame.planes = vec![FramePlane::new(4, 4, 8), FramePlane::new(8, 4, 8)];
+        assert!(converter.convert_frame(&frame).is_none());
+    }
+
+    #[test]
+    fn test_converter_colorimetry() {
+        // Saturated red in BT.709 limited range
+        let nv12 = [63, 63, 63, 63, 102, 240];
+        let mut frame = VideoFrame::with_data(1, 2, 2, 2, PixelFormat::NV12, 0, nv12.to_vec());
+        frame.colorimetry = Colorimetry::BT709;
+
+        let mut converter = YuvConverter::new();
+        let red = converter.convert_frame(&frame).expect("converts").to_vec();
+        assert!(red.chunks(4).all(|px| px[0] <= 2 && px[1] <= 2 && px[2] >= 253));
+
+        // Raw data defaults to BT.601, which decodes it differently
+        let bt601 = converter
+            .convert_to_bgra(&nv12, 2, 2, PixelFormat::NV12)
+            .expect("converts")
+            .to_vec();
+        assert_ne!(bt601, red);
+
+        // An override applies to frames and raw data alike
+        let mut converter = YuvConverter::new().with_colorimetry(Colorimetry::BT709);
+        assert_eq!(converter.colorimetry(), Some(Colorimetry::BT709));
+        assert_eq!(
+            converter.convert_to_bgra(&nv12, 2, 2, PixelFormat::NV12),
+            Some(red.as_slice())
+        );
+        frame.colorimetry = Colorimetry::BT601;
+        assert_eq!(converter.convert_frame(&frame), Some(red.as_slice()));
+
+        converter.set_colorimetry(None);
+        assert_eq!(converter.convert_frame(&frame), Some(bt601.as_slice()));
+    }
+
+    #[test]
+    fn test_converter_reuses_buffer() {
+        let mut converter = YuvConverter::new();
+        let large = vec![16u8; 8 * 8 * 3 / 2];
+        let small = [235u8, 235, 235, 235, 128, 128];
+
+        assert_eq!(
+            converter
+                .convert_to_bgra(&large, 8, 8, PixelFormat::NV12)
+                .map(<[u8]>::len),
+            Some(256)
+        );
+        let allocation = converter.output_buffer.as_ptr();
+
+        let white = converter
+            .convert_to_bgra(&small, 2, 2, PixelFormat::NV12)
+            .expect("converts");
+        assert_eq!(white.len(), 16);
+        assert!(white.iter().all(|&b| b >= 254));
+        assert_eq!(converter.output_buffer.as_ptr(), allocation);
+
+        // Malformed input fails without panicking or losing the buffer
+        assert!(converter.convert_to_bgra(&small, 8, 8, PixelFormat::NV12).is_none());
+        assert!(converter.convert_to_bgra(&small, 3, 2, PixelFormat::YUY2).is_none());
+        assert_eq!(
+            converter
+                .convert_to_bgra(&large, 8, 8, PixelFormat::NV12)
+                .map(<[u8]>::len),
+            Some(256)
+        );
+        assert_eq!(converter.output_buffer.as_ptr(), allocation);
+    }
+
+    #[test]
+    fn test_converter_rejects_oversized_frame() {
+        let mut converter = YuvConverter::new();
+        let tiny = [16u8, 16, 16, 16, 128, 128];
+
+        // Rejected before the output buffer is sized from the claimed dimensions
+        assert!(converter
+            .convert_to_bgra(&tiny, 100_000, 100_000, PixelFormat::NV12)
+            .is_none());
+        assert!(converter
+            .convert_to_bgra(&tiny, u32::MAX, u32::MAX, PixelFormat::YUY2)
+            .is_none());
+        assert!(converter.output_buffer.is_empty());
+    }
+
     #[test]
     fn test_yuv_converter() {
         let mut converter = YuvConverter::new();
 
         assert!(YuvConverter::needs_conversion(PixelFormat::NV12));
         assert!(YuvConverter::needs_conversion(PixelFormat::I420));
+        assert!(YuvConverter::needs_conversion(PixelFormat::P010));
         assert!(!YuvConverter::needs_conversion(PixelFormat::BGRA));
 
         // Test conversion
@@ -28,7 +28,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Display PipeWire information
     println!("PipeWire Details:");
-    println!("  File Descriptor: {}", session.pipewire_fd());
+    println!("  File Descriptor: {:?}", session.pipewire_fd());
     println!("  Available Streams: {}\n", session.streams().len());
 
     // Display stream information
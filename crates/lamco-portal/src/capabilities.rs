@@ -0,0 +1,292 @@
+//! Portal capability discovery
+//!
+//! Reports which portal interfaces the running `xdg-desktop-portal` offers,
+//! their versions, and the source types, cursor modes and input devices its
+//! backend supports. [`PortalConfig::validate_against`](crate::PortalConfig::validate_against)
+//! uses this to reject a configuration the portal cannot honour before a
+//! permission dialog is shown.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use lamco_portal::{PortalConfig, PortalManager};
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = PortalConfig::default();
+//! let manager = PortalManager::new(config.clone()).await?;
+//!
+//! let caps = manager.capabilities().await?;
+//! println!("ScreenCast version: {:?}", caps.screencast_version);
+//!
+//! // Fails with PortalError::InvalidConfig instead of a failing dialog
+//! config.validate_against(&caps)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use ashpd::desktop::remote_desktop::DeviceType;
+use ashpd::desktop::screencast::{CursorMode, SourceType};
+use enumflags2::{BitFlag, BitFlags};
+use std::collections::HashMap;
+use tracing::debug;
+use zbus::fdo::{self, PropertiesProxy};
+use zbus::names::InterfaceName;
+use zbus::zvariant::OwnedValue;
+
+use crate::error::{PortalError, Result};
+use crate::session::PORTAL_BUS_NAME;
+
+/// Object path of the portal service
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+const SCREENCAST_INTERFACE: &str = "org.freedesktop.portal.ScreenCast";
+const REMOTE_DESKTOP_INTERFACE: &str = "org.freedesktop.portal.RemoteDesktop";
+const CLIPBOARD_INTERFACE: &str = "org.freedesktop.portal.Clipboard";
+
+/// What the running portal supports
+///
+/// Obtained from [`PortalManager::capabilities`](crate::PortalManager::capabilities)
+/// or [`PortalCapabilities::query`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortalCapabilities {
+    /// Version of the ScreenCast interface, `None` if the portal does not offer it
+    pub screencast_version: Option<u32>,
+
+    /// Version of the RemoteDesktop interface, `None` if the portal does not offer it
+    pub remote_desktop_version: Option<u32>,
+
+    /// Whether the portal offers the Clipboard interface
+    pub clipboard: bool,
+
+    /// Source types the ScreenCast backend can capture (`AvailableSourceTypes`)
+    pub source_types: BitFlags<SourceType>,
+
+    /// Cursor modes the ScreenCast backend supports (`AvailableCursorModes`)
+    ///
+    /// Empty on ScreenCast version 1 portals, which cannot select a cursor mode.
+    pub cursor_modes: BitFlags<CursorMode>,
+
+    /// Input devices the RemoteDesktop backend supports (`AvailableDeviceTypes`)
+    pub device_types: BitFlags<DeviceType>,
+}
+
+impl PortalCapabilities {
+    /// Query the portal service on `connection`
+    ///
+    /// Fails with [`PortalError::PortalNotAvailable`] if no portal service
+    /// runs on the bus.
+    pub async fn query(connection: &zbus::Connection) -> Result<Self> {
+        let proxy = PropertiesProxy::builder(connection)
+            .destination(PORTAL_BUS_NAME)?
+            .path(PORTAL_PATH)?
+            .build()
+            .await?;
+
+        let screencast = interface_properties(&proxy, SCREENCAST_INTERFACE).await?;
+        let remote_desktop = interface_properties(&proxy, REMOTE_DESKTOP_INTERFACE).await?;
+        let clipboard = interface_properties(&proxy, CLIPBOARD_INTERFACE).await?;
+
+        let caps = Self {
+            screencast_version: screencast.as_ref().and_then(|p| u32_property(p, "version")),
+            remote_desktop_version: remote_desktop.as_ref().and_then(|p| u32_property(p, "version")),
+            clipboard: clipboard.is_some(),
+            source_types: flags_property(screencast.as_ref(), "AvailableSourceTypes"),
+            cursor_modes: flags_property(screencast.as_ref(), "AvailableCursorModes"),
+            device_types: flags_property(remote_desktop.as_ref(), "AvailableDeviceTypes"),
+        };
+
+        debug!("Portal capabilities: {:?}", caps);
+        Ok(caps)
+    }
+}
+
+/// Properties of a portal interface, `None` if the portal does not offer it
+async fn interface_properties(
+    proxy: &PropertiesProxy<'_>,
+    interface: &'static str,
+) -> Result<Option<HashMap<String, OwnedValue>>> {
+    let name = InterfaceName::from_static_str_unchecked(interface);
+    match proxy.get_all(Some(name).into()).await {
+        Ok(properties) => Ok(Some(properties)),
+        Err(fdo::Error::ServiceUnknown(_) | fdo::Error::NameHasNoOwner(_)) => Err(PortalError::PortalNotAvailable),
+        // GDBus, which xdg-desktop-portal uses, reports unknown interfaces as InvalidArgs
+        Err(fdo::Error::UnknownInterface(_) | fdo::Error::InvalidArgs(_)) => {
+            debug!("Portal has no {} interface", interface);
+            Ok(None)
+        }
+        Err(e) => Err(zbus::Error::from(e).into()),
+    }
+}
+
+fn u32_property(properties: &HashMap<String, OwnedValue>, name: &str) -> Option<u32> {
+    properties.get(name).and_then(|value| u32::try_from(value).ok())
+}
+
+/// Bit flags property; unknown bits are dropped and a missing property is empty
+fn flags_property<T: BitFlag<Numeric = u32>>(
+    properties: Option<&HashMap<String, OwnedValue>>,
+    name: &str,
+) -> BitFlags<T> {
+    properties
+        .and_then(|p| u32_property(p, name))
+        .map_or_else(BitFlags::empty, BitFlags::from_bits_truncate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PortalConfig;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    /// A private `dbus-daemon`, stopped on drop
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        /// Start a bus, or `None` if `dbus-daemon` is not installed
+        fn start() -> Option<Self> {
+            let daemon = Command::new("dbus-daemon")
+                .arg("--session")
+                .arg("--nofork")
+                .arg("--print-address=1")
+                .arg(format!("--address=unix:tmpdir={}", std::env::temp_dir().display()))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            // Dropping `bus` stops the daemon if no address arrives
+            let mut bus = Self {
+                daemon,
+                address: String::new(),
+            };
+            let stdout = bus.daemon.stdout.take().expect("stdout is piped");
+            BufReader::new(stdout).read_line(&mut bus.address).ok()?;
+            bus.address.truncate(bus.address.trim_end().len());
+
+            (!bus.address.is_empty()).then_some(bus)
+        }
+
+        async fn connect(&self) -> zbus::Connection {
+            zbus::connection::Builder::address(self.address.as_str())
+                .expect("valid bus address")
+                .build()
+                .await
+                .expect("connects to the private bus")
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    struct MockScreenCast;
+
+    #[zbus::interface(name = "org.freedesktop.portal.ScreenCast")]
+    impl MockScreenCast {
+        #[zbus(property, name = "version")]
+        fn version(&self) -> u32 {
+            4
+        }
+
+        /// Monitor and window, plus a bit from a future portal version
+        #[zbus(property)]
+        fn available_source_types(&self) -> u32 {
+            0b1011
+        }
+
+        /// Hidden and embedded, no metadata
+        #[zbus(property)]
+        fn available_cursor_modes(&self) -> u32 {
+            0b011
+        }
+    }
+
+    struct MockRemoteDesktop;
+
+    #[zbus::interface(name = "org.freedesktop.portal.RemoteDesktop")]
+    impl MockRemoteDesktop {
+        #[zbus(property, name = "version")]
+        fn version(&self) -> u32 {
+            2
+        }
+
+        /// Keyboard and pointer
+        #[zbus(property)]
+        fn available_device_types(&self) -> u32 {
+            0b011
+        }
+    }
+
+    /// Serve a portal without the Clipboard interface on `bus`
+    async fn mock_portal(bus: &PrivateBus) -> zbus::Connection {
+        zbus::connection::Builder::address(bus.address.as_str())
+            .expect("valid bus address")
+            .name(PORTAL_BUS_NAME)
+            .expect("valid bus name")
+            .serve_at(PORTAL_PATH, MockScreenCast)
+            .expect("serves ScreenCast")
+            .serve_at(PORTAL_PATH, MockRemoteDesktop)
+            .expect("serves RemoteDesktop")
+            .build()
+            .await
+            .expect("mock portal starts")
+    }
+
+    #[tokio::test]
+    async fn test_query_mock_portal() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+        let _portal = mock_portal(&bus).await;
+
+        let caps = PortalCapabilities::query(&bus.connect().await)
+            .await
+            .expect("queries the mock portal");
+
+        assert_eq!(caps.screencast_version, Some(4));
+        assert_eq!(caps.remote_desktop_version, Some(2));
+        assert!(!caps.clipboard);
+        assert_eq!(caps.source_types, SourceType::Monitor | SourceType::Window);
+        assert_eq!(caps.cursor_modes, CursorMode::Hidden | CursorMode::Embedded);
+        assert_eq!(caps.device_types, DeviceType::Keyboard | DeviceType::Pointer);
+    }
+
+    #[tokio::test]
+    async fn test_validate_against_mock_portal() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+        let _portal = mock_portal(&bus).await;
+        let caps = PortalCapabilities::query(&bus.connect().await)
+            .await
+            .expect("queries the mock portal");
+
+        // The default cursor mode is metadata, which the mock does not offer
+        assert!(matches!(
+            PortalConfig::default().validate_against(&caps),
+            Err(PortalError::InvalidConfig(_))
+        ));
+
+        let config = PortalConfig::builder().cursor_mode(CursorMode::Embedded).build();
+        assert!(config.validate_against(&caps).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_query_without_portal() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon not installed, skipping");
+            return;
+        };
+
+        let result = PortalCapabilities::query(&bus.connect().await).await;
+        assert!(matches!(result, Err(PortalError::PortalNotAvailable)));
+    }
+}
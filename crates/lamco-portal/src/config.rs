@@ -8,6 +8,9 @@ use ashpd::desktop::screencast::{CursorMode, SourceType};
 use ashpd::desktop::PersistMode;
 use enumflags2::BitFlags;
 
+use crate::capabilities::PortalCapabilities;
+use crate::error::{PortalError, Result};
+
 /// Configuration for Portal session behavior
 ///
 /// Controls how Portal requests are made and what capabilities are requested.
@@ -121,6 +124,61 @@ impl PortalConfig {
     pub fn builder() -> PortalConfigBuilder {
         PortalConfigBuilder::default()
     }
+
+    /// Check that the portal supports this configuration
+    ///
+    /// Fails with [`PortalError::InvalidConfig`] if the portal has no
+    /// ScreenCast interface, or does not support the cursor mode or one of
+    /// the source types, so the problem surfaces before a permission dialog
+    /// is shown. The cursor mode is only checked on ScreenCast version 2 and
+    /// later, since earlier portals do not advertise cursor modes. Input
+    /// devices are not checked, as view-only sessions do not request them;
+    /// compare [`PortalCapabilities::device_types`] for those.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lamco_portal::{PortalConfig, PortalManager};
+    /// # use ashpd::desktop::screencast::CursorMode;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut config = PortalConfig::default();
+    /// let manager = PortalManager::new(config.clone()).await?;
+    /// let caps = manager.capabilities().await?;
+    ///
+    /// // Fall back to an embedded cursor on portals without cursor metadata
+    /// if config.validate_against(&caps).is_err() && caps.cursor_modes.contains(CursorMode::Embedded) {
+    ///     config.cursor_mode = CursorMode::Embedded;
+    /// }
+    /// config.validate_against(&caps)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_against(&self, caps: &PortalCapabilities) -> Result<()> {
+        if caps.screencast_version.is_none() {
+            return Err(PortalError::invalid_config("portal has no ScreenCast interface"));
+        }
+
+        // Cursor modes arrived in version 2; older portals report none
+        let reports_cursor_modes = caps.screencast_version >= Some(2) && !caps.cursor_modes.is_empty();
+        if reports_cursor_modes && !caps.cursor_modes.contains(self.cursor_mode) {
+            return Err(PortalError::invalid_config(format!(
+                "cursor mode {:?} not supported by the portal (available: {:?})",
+                self.cursor_mode,
+                caps.cursor_modes.iter().collect::<Vec<_>>()
+            )));
+        }
+
+        let unsupported = self.source_type & !caps.source_types;
+        if !unsupported.is_empty() {
+            return Err(PortalError::invalid_config(format!(
+                "source types {:?} not supported by the portal (available: {:?})",
+                unsupported.iter().collect::<Vec<_>>(),
+                caps.source_types.iter().collect::<Vec<_>>()
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 /// Builder for PortalConfig
@@ -253,6 +311,63 @@ mod tests {
         assert_eq!(config.restore_token, Some("test-token".to_string()));
     }
 
+    /// A portal offering monitors and windows with every cursor mode
+    fn capabilities() -> PortalCapabilities {
+        PortalCapabilities {
+            screencast_version: Some(5),
+            source_types: SourceType::Monitor | SourceType::Window,
+            cursor_modes: BitFlags::all(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_against_supported() {
+        assert!(PortalConfig::default().validate_against(&capabilities()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_against_rejects_unsupported() {
+        let caps = PortalCapabilities {
+            cursor_modes: CursorMode::Hidden | CursorMode::Embedded,
+            ..capabilities()
+        };
+        let err = PortalConfig::default()
+            .validate_against(&caps)
+            .expect_err("metadata cursor is unsupported");
+        assert!(matches!(err, PortalError::InvalidConfig(_)));
+        assert!(err.to_string().contains("Metadata"));
+
+        let config = PortalConfig::builder()
+            .source_type(SourceType::Monitor | SourceType::Virtual)
+            .build();
+        let err = config
+            .validate_against(&capabilities())
+            .expect_err("virtual sources are unsupported");
+        assert!(matches!(err, PortalError::InvalidConfig(_)));
+        assert!(err.to_string().contains("Virtual"));
+
+        assert!(matches!(
+            PortalConfig::default().validate_against(&PortalCapabilities::default()),
+            Err(PortalError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_against_v1_skips_cursor_mode() {
+        // Version 1 portals have no AvailableCursorModes property
+        let caps = PortalCapabilities {
+            screencast_version: Some(1),
+            cursor_modes: BitFlags::empty(),
+            ..capabilities()
+        };
+        assert!(PortalConfig::default().validate_against(&caps).is_ok());
+
+        // Source types are still checked
+        let config = PortalConfig::builder().source_type(SourceType::Virtual.into()).build();
+        assert!(config.validate_against(&caps).is_err());
+    }
+
     #[test]
     fn test_struct_literal_with_defaults() {
         let config = PortalConfig {
@@ -131,7 +131,6 @@ impl PortalError {
     }
 
     /// Create a PipeWire error
-    #[allow(dead_code)]
     pub(crate) fn pipewire_failed(msg: impl Into<String>) -> Self {
         Self::PipeWireFailed(msg.into())
     }
@@ -148,7 +147,6 @@ impl PortalError {
     }
 
     /// Create an invalid config error
-    #[allow(dead_code)]
     pub(crate) fn invalid_config(msg: impl Into<String>) -> Self {
         Self::InvalidConfig(msg.into())
     }
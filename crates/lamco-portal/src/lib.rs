@@ -11,9 +11,15 @@
 //!
 //! - **Screen capture**: Capture monitor or window content through PipeWire streams
 //! - **Input injection**: Send keyboard and mouse events to the desktop
+//! - **View-only sessions**: Capture without input permission via
+//!   [`PortalManager::create_screencast_session`]
+//! - **Session lifecycle events**: Learn when the user or compositor ends a session via
+//!   [`PortalSessionHandle::subscribe_events`]
 //! - **Clipboard integration**: Portal-based clipboard for remote desktop scenarios
 //! - **Multi-monitor support**: Handle multiple displays simultaneously
 //! - **Flexible configuration**: Builder pattern and struct literals for Portal options
+//! - **Capability discovery**: Check a configuration against what the portal supports
+//!   with [`PortalManager::capabilities`] and [`PortalConfig::validate_against`]
 //! - **Typed errors**: Handle different failure modes appropriately
 //!
 //! # Requirements
@@ -34,13 +40,13 @@
 //! let manager = PortalManager::with_default().await?;
 //!
 //! // Create a session (triggers permission dialog)
-//! let session = manager.create_session("my-session".to_string(), None).await?;
+//! let mut session = manager.create_session("my-session".to_string(), None).await?;
 //!
-//! // Access PipeWire file descriptor for video capture
-//! let fd = session.pipewire_fd();
+//! // Take the PipeWire file descriptor for video capture
+//! let fd = session.take_pipewire_fd();
 //! let streams = session.streams();
 //!
-//! println!("Capturing {} streams on PipeWire FD {}", streams.len(), fd);
+//! println!("Capturing {} streams on PipeWire FD {:?}", streams.len(), fd);
 //! # Ok(())
 //! # }
 //! ```
@@ -137,14 +143,21 @@
 //! - Clipboard access (if using clipboard features)
 //!
 //! Permissions can be remembered per-application using [`PersistMode::Application`].
+//! The portal then returns a restore token with each session
+//! ([`PortalSessionHandle::restore_token`]); a [`RestoreTokenStore`] set with
+//! [`PortalManager::with_token_store`] keeps it between runs so the dialog
+//! is shown only once.
 
-use std::sync::Arc;
+use ashpd::desktop::{PersistMode, SessionPortal};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, warn};
 
+pub mod capabilities;
 pub mod clipboard;
 pub mod config;
 pub mod error;
 pub mod remote_desktop;
+pub mod restore_token;
 pub mod screencast;
 pub mod session;
 
@@ -156,10 +169,12 @@ pub mod clipboard_sink;
 #[cfg(feature = "dbus-clipboard")]
 pub mod dbus_clipboard;
 
+pub use capabilities::PortalCapabilities;
 pub use clipboard::ClipboardManager;
 pub use config::{PortalConfig, PortalConfigBuilder};
 pub use error::{PortalError, Result};
 pub use remote_desktop::RemoteDesktopManager;
+pub use restore_token::{FileTokenStore, MemoryTokenStore, RestoreTokenKey, RestoreTokenStore};
 pub use screencast::ScreenCastManager;
 
 // Re-export ClipboardSink implementation when feature is enabled
@@ -170,7 +185,7 @@ pub use clipboard_sink::PortalClipboardSink;
 #[cfg(feature = "dbus-clipboard")]
 pub use dbus_clipboard::{DbusClipboardBridge, DbusClipboardEvent};
 
-pub use session::{PortalSessionHandle, SourceType, StreamInfo};
+pub use session::{PortalSessionHandle, ScreenCastSessionHandle, SessionEvent, SourceType, StreamInfo};
 
 /// Portal manager coordinates all portal interactions
 ///
@@ -183,7 +198,8 @@ pub use session::{PortalSessionHandle, SourceType, StreamInfo};
 /// 1. Create a `PortalManager` with [`PortalManager::new`] or [`PortalManager::with_default`]
 /// 2. Create a session with [`PortalManager::create_session`] (triggers permission dialog)
 /// 3. Use the session for screen capture via PipeWire and input injection
-/// 4. Clean up with [`PortalManager::cleanup`] when done
+/// 4. Clean up with [`PortalManager::cleanup`] when done, which closes the sessions
+///    created by this manager
 ///
 /// # Examples
 ///
@@ -203,11 +219,12 @@ pub use session::{PortalSessionHandle, SourceType, StreamInfo};
 /// ```
 pub struct PortalManager {
     config: PortalConfig,
-    #[allow(dead_code)]
     connection: zbus::Connection,
     screencast: Arc<ScreenCastManager>,
     remote_desktop: Arc<RemoteDesktopManager>,
     clipboard: Option<Arc<ClipboardManager>>,
+    token_store: Option<(Arc<dyn RestoreTokenStore>, RestoreTokenKey)>,
+    sessions: Mutex<Vec<Arc<session::SessionLifecycle>>>,
 }
 
 impl PortalManager {
@@ -261,6 +278,8 @@ impl PortalManager {
             screencast,
             remote_desktop,
             clipboard: None, // Created later with session
+            token_store: None,
+            sessions: Mutex::new(Vec::new()),
         })
     }
 
@@ -279,6 +298,95 @@ impl PortalManager {
         Self::new(PortalConfig::default()).await
     }
 
+    /// Load and save restore tokens through `store`
+    ///
+    /// Sessions created afterwards use the token saved for `key` when the
+    /// config has no [`PortalConfig::restore_token`] of its own, and save
+    /// the token the portal returns. This needs a persist mode other than
+    /// `PersistMode::DoNot`, otherwise the portal returns no token.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::sync::Arc;
+    /// # use lamco_portal::{FileTokenStore, PortalConfig, PortalManager, RestoreTokenKey};
+    /// # use ashpd::desktop::PersistMode;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = PortalConfig::builder()
+    ///     .persist_mode(PersistMode::Application)
+    ///     .build();
+    /// let key = RestoreTokenKey::new("my-server").with_outputs(["DP-1"]);
+    /// let manager = PortalManager::new(config)
+    ///     .await?
+    ///     .with_token_store(Arc::new(FileTokenStore::from_env()?), key);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_token_store(mut self, store: Arc<dyn RestoreTokenStore>, key: RestoreTokenKey) -> Self {
+        if matches!(self.config.persist_mode, PersistMode::DoNot) {
+            warn!("Restore token store set with PersistMode::DoNot - the portal will not return tokens");
+        }
+        self.token_store = Some((store, key));
+        self
+    }
+
+    /// Token store and the key for a session kind
+    ///
+    /// View-only sessions keep their tokens apart from combined sessions:
+    /// the portal only accepts a token on the interface that issued it.
+    fn token_store_key(&self, screencast_only: bool) -> Option<(&dyn RestoreTokenStore, RestoreTokenKey)> {
+        let (store, key) = self.token_store.as_ref()?;
+        let key = if screencast_only {
+            key.clone().screencast_only()
+        } else {
+            key.clone()
+        };
+        Some((store.as_ref(), key))
+    }
+
+    /// Restore token to start the next session with
+    ///
+    /// The configured token takes precedence over a stored one. Store
+    /// failures are logged and treated as having no token.
+    fn restore_token(&self, screencast_only: bool) -> Option<String> {
+        if let Some(token) = &self.config.restore_token {
+            return Some(token.clone());
+        }
+
+        let (store, key) = self.token_store_key(screencast_only)?;
+        match store.load(&key) {
+            Ok(token) => {
+                debug!("Stored restore token for {:?}: {}", key, token.is_some());
+                token
+            }
+            Err(e) => {
+                warn!("Failed to load restore token: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Save the token returned by a started session, if any
+    fn save_restore_token(&self, screencast_only: bool, token: Option<&str>) {
+        if let (Some((store, key)), Some(token)) = (self.token_store_key(screencast_only), token) {
+            if let Err(e) = store.save(&key, token) {
+                warn!("Failed to save restore token: {}", e);
+            }
+        }
+    }
+
+    /// Publish the handle's closing events and close it on [`PortalManager::cleanup`]
+    async fn track<P: SessionPortal + Send + Sync + 'static>(&self, handle: &PortalSessionHandle<P>) {
+        if let Err(e) = handle.watch_lifecycle(&self.connection).await {
+            warn!("Failed to watch portal session {}: {}", handle.session_id(), e);
+            warn!("Session closing will not be reported");
+        }
+
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|lifecycle| !lifecycle.is_closed());
+        sessions.push(Arc::clone(handle.lifecycle()));
+    }
+
     /// Create a complete portal session (ScreenCast for video, RemoteDesktop for input, optionally Clipboard)
     ///
     /// This triggers the user permission dialog and returns a session handle
@@ -295,8 +403,9 @@ impl PortalManager {
     /// 2. Select devices (keyboard + pointer for input injection)
     /// 3. Select sources (monitors to capture for screen sharing)
     /// 4. Request clipboard access (if clipboard provided) ← BEFORE START
-    /// 5. Start session (triggers permission dialog)
+    /// 5. Start session (triggers permission dialog unless a restore token is accepted)
     /// 6. Get PipeWire FD and stream information
+    /// 7. Save the new restore token (if a token store is set)
     ///
     /// # Returns
     ///
@@ -319,6 +428,8 @@ impl PortalManager {
     ) -> Result<PortalSessionHandle> {
         info!("Creating combined portal session (ScreenCast + RemoteDesktop)");
 
+        let restore_token = self.restore_token(false);
+
         // Create RemoteDesktop session (this type of session can include screen sharing)
         let remote_desktop_session = self
             .remote_desktop
@@ -330,25 +441,16 @@ impl PortalManager {
 
         // Select devices for input injection (from config)
         self.remote_desktop
-            .select_devices(&remote_desktop_session, self.config.devices)
+            .select_devices(&remote_desktop_session, self.config.devices, restore_token.as_deref())
             .await
             .map_err(|e| PortalError::session_creation(format!("Device selection: {}", e)))?;
 
         info!("Input devices selected from config");
 
-        // CRITICAL FIX: Also use ScreenCast to select screen sources
-        // This is what makes screens available for sharing
-        let screencast_proxy = ashpd::desktop::screencast::Screencast::new().await?;
-
-        screencast_proxy
-            .select_sources(
-                &remote_desktop_session,              // Use same session
-                self.config.cursor_mode,              // From config
-                self.config.source_type,              // From config (already BitFlags)
-                self.config.allow_multiple,           // From config
-                self.config.restore_token.as_deref(), // From config
-                self.config.persist_mode,             // From config
-            )
+        // CRITICAL FIX: Also use ScreenCast to select screen sources on the
+        // same session. This is what makes screens available for sharing
+        self.screencast
+            .select_sources(&remote_desktop_session, restore_token.as_deref())
             .await
             .map_err(|e| PortalError::session_creation(format!("Source selection: {}", e)))?;
 
@@ -366,7 +468,7 @@ impl PortalManager {
         }
 
         // Start the combined session (triggers permission dialog)
-        let (pipewire_fd, streams) = self
+        let (pipewire_fd, streams, new_token) = self
             .remote_desktop
             .start_session(&remote_desktop_session)
             .await
@@ -380,6 +482,8 @@ impl PortalManager {
             return Err(PortalError::NoStreamsAvailable);
         }
 
+        self.save_restore_token(false, new_token.as_deref());
+
         // Create session handle with session reference
         // We need to keep the session alive for input injection
         let stream_count = streams.len();
@@ -389,13 +493,114 @@ impl PortalManager {
             streams,
             Some(session_id.clone()), // Store session ID for input operations
             remote_desktop_session,   // Pass the actual ashpd session for input injection
-        );
+        )
+        .with_restore_token(new_token);
+
+        self.track(&handle).await;
 
         info!("Portal session handle created with {} streams", stream_count);
 
         Ok(handle)
     }
 
+    /// Create a view-only session on the ScreenCast interface alone
+    ///
+    /// Unlike [`PortalManager::create_session`], no input devices are
+    /// requested, so the permission dialog only asks to share the screen.
+    /// This suits recording and monitoring tools, and works on portals that
+    /// do not offer RemoteDesktop. The config's `devices` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - Unique identifier for this session (user-provided)
+    ///
+    /// # Flow
+    ///
+    /// 1. Create ScreenCast session
+    /// 2. Select sources (monitors or windows to capture)
+    /// 3. Start session (triggers permission dialog unless a restore token is accepted)
+    /// 4. Get PipeWire FD and stream information
+    /// 5. Save the new restore token (if a token store is set)
+    ///
+    /// # Returns
+    ///
+    /// [`ScreenCastSessionHandle`] with PipeWire FD, stream information, and session reference
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lamco_portal::{PortalManager, PortalConfig};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let manager = PortalManager::new(PortalConfig::default()).await?;
+    /// let session = manager.create_screencast_session("recording-1".to_string()).await?;
+    /// println!("Recording {} streams", session.streams().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_screencast_session(&self, session_id: String) -> Result<ScreenCastSessionHandle> {
+        info!("Creating view-only portal session (ScreenCast)");
+
+        let restore_token = self.restore_token(true);
+
+        let screencast_session = self
+            .screencast
+            .create_session()
+            .await
+            .map_err(|e| PortalError::session_creation(format!("ScreenCast session: {}", e)))?;
+
+        self.screencast
+            .select_sources(&screencast_session, restore_token.as_deref())
+            .await
+            .map_err(|e| PortalError::session_creation(format!("Source selection: {}", e)))?;
+
+        info!("Screen sources selected - permission dialog will appear");
+
+        let (pipewire_fd, streams, new_token) = self
+            .screencast
+            .start(&screencast_session)
+            .await
+            .map_err(|e| PortalError::session_creation(format!("Session start: {}", e)))?;
+
+        info!("ScreenCast session started with {} streams", streams.len());
+
+        if streams.is_empty() {
+            return Err(PortalError::NoStreamsAvailable);
+        }
+
+        self.save_restore_token(true, new_token.as_deref());
+
+        let handle = ScreenCastSessionHandle::new(session_id, pipewire_fd, streams, None, screencast_session)
+            .with_restore_token(new_token);
+
+        self.track(&handle).await;
+
+        Ok(handle)
+    }
+
+    /// Query what the running portal supports
+    ///
+    /// Reports interface versions, available source types, cursor modes
+    /// and input devices, and whether the Clipboard interface exists. Pass
+    /// the result to [`PortalConfig::validate_against`] to check a
+    /// configuration before creating a session.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lamco_portal::PortalManager;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let manager = PortalManager::with_default().await?;
+    /// let caps = manager.capabilities().await?;
+    /// if !caps.clipboard {
+    ///     println!("Clipboard sharing is not available");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn capabilities(&self) -> Result<PortalCapabilities> {
+        PortalCapabilities::query(&self.connection).await
+    }
+
     /// Access the ScreenCast manager
     ///
     /// Use this to access ScreenCast-specific functionality if needed.
@@ -449,13 +654,25 @@ impl PortalManager {
 
     /// Cleanup all portal resources
     ///
-    /// Portal sessions are automatically cleaned up when dropped,
-    /// so calling this explicitly is optional. It can be useful for
-    /// logging cleanup or performing graceful shutdown.
+    /// Closes every session created by this manager that is still open,
+    /// including those whose handles were dropped, as
+    /// [`PortalSessionHandle::close`] would. All sessions are attempted;
+    /// the first failure is returned.
     pub async fn cleanup(&self) -> Result<()> {
         info!("Cleaning up portal resources");
-        // Portal sessions are automatically cleaned up when dropped
-        Ok(())
+
+        let sessions = std::mem::take(&mut *self.sessions.lock().unwrap_or_else(|e| e.into_inner()));
+        let mut result = Ok(());
+        for lifecycle in sessions {
+            if let Err(e) = lifecycle.close().await {
+                warn!("Failed to close portal session {}: {}", lifecycle.session_id(), e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+
+        result
     }
 }
 
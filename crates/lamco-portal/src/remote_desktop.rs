@@ -4,10 +4,10 @@
 
 use ashpd::desktop::remote_desktop::{DeviceType, KeyState, RemoteDesktop};
 use enumflags2::BitFlags;
-use std::os::fd::AsRawFd;
+use std::os::fd::OwnedFd;
 use tracing::{debug, info};
 
-use super::session::StreamInfo;
+use super::session::{SourceType, StreamInfo};
 use crate::config::PortalConfig;
 use crate::error::{PortalError, Result};
 
@@ -42,22 +42,21 @@ impl RemoteDesktopManager {
     }
 
     /// Select devices for remote control
+    ///
+    /// `restore_token` restores a previous session's selection (see
+    /// [`PortalSessionHandle::restore_token`](crate::PortalSessionHandle::restore_token)).
     pub async fn select_devices(
         &self,
         session: &ashpd::desktop::Session<'_, RemoteDesktop<'_>>,
         devices: BitFlags<DeviceType>,
+        restore_token: Option<&str>,
     ) -> Result<()> {
         info!("Selecting devices: {:?}", devices);
 
         let proxy = RemoteDesktop::new().await?;
 
         proxy
-            .select_devices(
-                session,
-                devices,
-                self.config.restore_token.as_deref(),
-                self.config.persist_mode,
-            )
+            .select_devices(session, devices, restore_token, self.config.persist_mode)
             .await?;
 
         info!("Devices selected successfully");
@@ -65,10 +64,13 @@ impl RemoteDesktopManager {
     }
 
     /// Start the remote desktop session
+    ///
+    /// Returns the PipeWire FD, the streams and the restore token for the
+    /// next session, if the portal granted persistence.
     pub async fn start_session(
         &self,
         session: &ashpd::desktop::Session<'_, RemoteDesktop<'_>>,
-    ) -> Result<(std::os::fd::RawFd, Vec<StreamInfo>)> {
+    ) -> Result<(OwnedFd, Vec<StreamInfo>, Option<String>)> {
         info!("Starting RemoteDesktop session");
 
         let proxy = RemoteDesktop::new().await?;
@@ -97,30 +99,27 @@ impl RemoteDesktopManager {
         info!("PipeWire FD obtained: {:?}", fd);
 
         // Convert stream info using new API
+        let fallback = SourceType::fallback(self.config.source_type);
         let stream_info: Vec<StreamInfo> = selected
             .streams()
             .map(|streams| {
                 streams
                     .iter()
                     .map(|stream| {
-                        let node_id = stream.pipe_wire_node_id();
-                        let size = stream.size().unwrap_or((0, 0));
-                        let position = stream.position().unwrap_or((0, 0));
+                        let info = StreamInfo::from_portal(stream, fallback);
 
                         info!(
-                            "📺 Portal provided stream: node_id={}, size=({}, {}), position=({}, {})",
-                            node_id, size.0, size.1, position.0, position.1
+                            "📺 Portal provided stream: node_id={}, {:?}, size=({}, {}), position=({}, {}), id={:?}",
+                            info.node_id,
+                            info.source_type,
+                            info.size.0,
+                            info.size.1,
+                            info.position.0,
+                            info.position.1,
+                            info.id
                         );
 
-                        StreamInfo {
-                            node_id,
-                            position,
-                            size: (
-                                size.0.max(0).try_into().unwrap_or(0),
-                                size.1.max(0).try_into().unwrap_or(0),
-                            ),
-                            source_type: super::session::SourceType::Monitor,
-                        }
+                        info
                     })
                     .collect()
             })
@@ -128,15 +127,10 @@ impl RemoteDesktopManager {
 
         info!("📊 Total streams from Portal: {}", stream_info.len());
 
-        // CRITICAL: Prevent FD from being closed
-        // PipeWireThreadManager will take ownership via from_raw_fd()
-        // If we return OwnedFd, it will be closed when PortalSessionHandle drops
-        let raw_fd = fd.as_raw_fd();
-        std::mem::forget(fd); // Leak the OwnedFd to prevent auto-close
-
-        info!("🔒 FD {} ownership transferred (prevented auto-close)", raw_fd);
+        let restore_token = selected.restore_token().map(ToOwned::to_owned);
+        debug!("Restore token returned: {}", restore_token.is_some());
 
-        Ok((raw_fd, stream_info))
+        Ok((fd, stream_info, restore_token))
     }
 
     /// Inject pointer motion (relative)
@@ -0,0 +1,420 @@
+//! Restore token persistence
+//!
+//! A portal session started with [`PersistMode::Application`] or
+//! [`PersistMode::ExplicitlyRevoked`] returns a restore token. Passing that
+//! token to the next session restores the same sources and devices without
+//! showing the permission dialog again, which unattended servers rely on.
+//!
+//! [`RestoreTokenStore`] keeps tokens between runs. [`PortalManager`] loads
+//! the token for its [`RestoreTokenKey`] before each session and saves the
+//! token the portal returns once the session has started.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use lamco_portal::{FileTokenStore, PortalConfig, PortalManager, RestoreTokenKey};
+//! use ashpd::desktop::PersistMode;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = PortalConfig::builder()
+//!     .persist_mode(PersistMode::ExplicitlyRevoked)
+//!     .build();
+//!
+//! let manager = PortalManager::new(config)
+//!     .await?
+//!     .with_token_store(Arc::new(FileTokenStore::from_env()?), RestoreTokenKey::new("my-server"));
+//!
+//! // Shows the permission dialog on the first run only
+//! let session = manager.create_session("session-1".to_string(), None).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`PersistMode::Application`]: ashpd::desktop::PersistMode::Application
+//! [`PersistMode::ExplicitlyRevoked`]: ashpd::desktop::PersistMode::ExplicitlyRevoked
+//! [`PortalManager`]: crate::PortalManager
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tracing::debug;
+
+use crate::error::{PortalError, Result};
+
+/// Identifies the session a restore token belongs to
+///
+/// Tokens are kept per application and per set of outputs, so a server
+/// capturing different monitors (or running on a machine whose monitors
+/// changed) does not restore a session for the wrong outputs. The output
+/// set is whatever names the application uses for its outputs, such as
+/// connector names; order and duplicates do not matter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RestoreTokenKey {
+    app_id: String,
+    outputs: Vec<String>,
+    screencast_only: bool,
+}
+
+impl RestoreTokenKey {
+    /// Create a key for an application, with no particular output set
+    pub fn new(app_id: impl Into<String>) -> Self {
+        Self {
+            app_id: app_id.into(),
+            outputs: Vec::new(),
+            screencast_only: false,
+        }
+    }
+
+    /// Restrict the key to a set of outputs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lamco_portal::RestoreTokenKey;
+    /// let a = RestoreTokenKey::new("my-server").with_outputs(["DP-1", "HDMI-A-1"]);
+    /// let b = RestoreTokenKey::new("my-server").with_outputs(["HDMI-A-1", "DP-1"]);
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn with_outputs<I, S>(mut self, outputs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.outputs = outputs.into_iter().map(Into::into).collect();
+        self.outputs.sort();
+        self.outputs.dedup();
+        self
+    }
+
+    /// Application identifier
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// Output names, sorted (empty for any outputs)
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Key for view-only (ScreenCast) sessions of the same app and outputs
+    pub(crate) fn screencast_only(mut self) -> Self {
+        self.screencast_only = true;
+        self
+    }
+
+    /// Relative path of the token file for this key
+    ///
+    /// The app ID and output names are percent-encoded (see [`encode`]), so
+    /// distinct keys always map to distinct paths: `+` joins the encoded
+    /// output names, `any` stands for no particular outputs, and output sets
+    /// are prefixed with `outputs-`.
+    fn file_path(&self) -> PathBuf {
+        let mut name = if self.outputs.is_empty() {
+            "any".to_string()
+        } else {
+            let outputs: Vec<String> = self.outputs.iter().map(|output| encode(output)).collect();
+            format!("outputs-{}", outputs.join("+"))
+        };
+        if self.screencast_only {
+            name.push_str(".screencast");
+        }
+        Path::new(&encode(&self.app_id)).join(name)
+    }
+}
+
+/// Percent-encode every byte outside `[A-Za-z0-9_-]`
+///
+/// The result is a single path component that never starts with a dot and
+/// never contains `+` or `.`, which [`RestoreTokenKey::file_path`] uses as
+/// separators. The empty string becomes `%`, which no other name encodes to.
+fn encode(name: &str) -> String {
+    if name.is_empty() {
+        return "%".to_string();
+    }
+
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Storage for portal restore tokens
+///
+/// Implementations must be safe to share between threads. Errors are
+/// reported to the caller; [`PortalManager`](crate::PortalManager) logs
+/// them and carries on without a token, so a broken store never prevents
+/// a session from starting.
+pub trait RestoreTokenStore: Send + Sync {
+    /// Load the token saved for `key`, if any
+    fn load(&self, key: &RestoreTokenKey) -> Result<Option<String>>;
+
+    /// Save `token` for `key`, replacing any previous token
+    fn save(&self, key: &RestoreTokenKey, token: &str) -> Result<()>;
+
+    /// Forget the token saved for `key`
+    fn clear(&self, key: &RestoreTokenKey) -> Result<()>;
+}
+
+/// Restore tokens kept in files, one per key
+///
+/// Tokens live in `<dir>/<app>/<outputs>` (with a `.screencast` suffix for
+/// view-only sessions), readable only by the current user.
+/// [`FileTokenStore::from_env`] uses the XDG state directory.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    dir: PathBuf,
+}
+
+impl FileTokenStore {
+    /// Store tokens under `dir`
+    ///
+    /// The directory is created on the first save.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store tokens under `$XDG_STATE_HOME/lamco-portal/restore-tokens`
+    ///
+    /// Falls back to `$HOME/.local/state` when `XDG_STATE_HOME` is unset or
+    /// not an absolute path, as the XDG base directory specification requires.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidConfig` if neither variable gives a usable directory.
+    pub fn from_env() -> Result<Self> {
+        let dir = state_dir(std::env::var_os("XDG_STATE_HOME"), std::env::var_os("HOME"))
+            .ok_or_else(|| PortalError::invalid_config("Neither XDG_STATE_HOME nor HOME is set to an absolute path"))?;
+        Ok(Self::new(dir.join("lamco-portal").join("restore-tokens")))
+    }
+
+    /// Directory holding the token files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &RestoreTokenKey) -> PathBuf {
+        self.dir.join(key.file_path())
+    }
+}
+
+/// Resolve the XDG state directory from `XDG_STATE_HOME` and `HOME`
+fn state_dir(xdg_state_home: Option<std::ffi::OsString>, home: Option<std::ffi::OsString>) -> Option<PathBuf> {
+    xdg_state_home
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            home.map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .map(|home| home.join(".local").join("state"))
+        })
+}
+
+impl RestoreTokenStore for FileTokenStore {
+    fn load(&self, key: &RestoreTokenKey) -> Result<Option<String>> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(token) => {
+                let token = token.trim();
+                Ok((!token.is_empty()).then(|| token.to_string()))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, key: &RestoreTokenKey, token: &str) -> Result<()> {
+        let path = self.path(key);
+        let parent = path.parent().unwrap_or(&self.dir);
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(parent)?;
+
+        // Write a temporary file and rename it, so a crash never leaves a
+        // truncated token behind
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.write_all(token.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+
+        debug!("Saved restore token to {}", path.display());
+        Ok(())
+    }
+
+    fn clear(&self, key: &RestoreTokenKey) -> Result<()> {
+        match fs::remove_file(self.path(key)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Restore tokens kept in memory
+///
+/// Tokens last as long as the store, which suits tests and applications
+/// that create several sessions in one run.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<HashMap<RestoreTokenKey, String>>,
+}
+
+impl MemoryTokenStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RestoreTokenStore for MemoryTokenStore {
+    fn load(&self, key: &RestoreTokenKey) -> Result<Option<String>> {
+        Ok(self.tokens.lock().unwrap_or_else(|e| e.into_inner()).get(key).cloned())
+    }
+
+    fn save(&self, key: &RestoreTokenKey, token: &str) -> Result<()> {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.clone(), token.to_string());
+        Ok(())
+    }
+
+    fn clear(&self, key: &RestoreTokenKey) -> Result<()> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner()).remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("lamco-portal-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_key_file_path() {
+        let key = RestoreTokenKey::new("my-server").with_outputs(["HDMI-A-1", "DP-1", "DP-1"]);
+        assert_eq!(key.outputs(), ["DP-1", "HDMI-A-1"]);
+        assert_eq!(key.file_path(), Path::new("my-server/outputs-DP-1+HDMI-A-1"));
+
+        assert_eq!(
+            RestoreTokenKey::new("my-server").file_path(),
+            Path::new("my-server/any")
+        );
+        assert_eq!(
+            RestoreTokenKey::new("my-server").screencast_only().file_path(),
+            Path::new("my-server/any.screencast")
+        );
+
+        // Path separators and dot-files never escape the store directory
+        let key = RestoreTokenKey::new("../evil").with_outputs(["a/b"]);
+        assert_eq!(key.file_path(), Path::new("%2E%2E%2Fevil/outputs-a%2Fb"));
+        assert_eq!(RestoreTokenKey::new("").file_path(), Path::new("%/any"));
+    }
+
+    #[test]
+    fn test_key_file_paths_are_distinct() {
+        let keys = [
+            RestoreTokenKey::new("a/b"),
+            RestoreTokenKey::new("a_b"),
+            RestoreTokenKey::new("a%2Fb"),
+            RestoreTokenKey::new(""),
+            RestoreTokenKey::new("%"),
+            RestoreTokenKey::new("app"),
+            RestoreTokenKey::new("app").screencast_only(),
+            RestoreTokenKey::new("app").with_outputs(["any"]),
+            RestoreTokenKey::new("app").with_outputs([""]),
+            RestoreTokenKey::new("app").with_outputs(["a+b"]),
+            RestoreTokenKey::new("app").with_outputs(["a", "b"]),
+            RestoreTokenKey::new("app").with_outputs(["a", "b"]).screencast_only(),
+            RestoreTokenKey::new("app").with_outputs(["a.screencast"]),
+            RestoreTokenKey::new("app").with_outputs(["a"]).screencast_only(),
+        ];
+
+        let paths: std::collections::HashSet<PathBuf> = keys.iter().map(RestoreTokenKey::file_path).collect();
+        assert_eq!(paths.len(), keys.len());
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let tmp = TempDir::new("tokens");
+        let store = FileTokenStore::new(&tmp.0);
+        let key = RestoreTokenKey::new("app").with_outputs(["DP-1"]);
+        let other = RestoreTokenKey::new("app").with_outputs(["DP-2"]);
+
+        assert_eq!(store.load(&key).expect("load"), None);
+
+        store.save(&key, "token-1").expect("store operation");
+        store.save(&key, "token-2").expect("store operation");
+        assert_eq!(store.load(&key).expect("load").as_deref(), Some("token-2"));
+        assert_eq!(store.load(&other).expect("load"), None);
+
+        // Only the owner may read tokens
+        let mode = fs::metadata(store.path(&key)).expect("token file").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        store.clear(&key).expect("store operation");
+        assert_eq!(store.load(&key).expect("load"), None);
+        store.clear(&key).expect("store operation");
+    }
+
+    #[test]
+    fn test_memory_store_round_trip() {
+        let store = MemoryTokenStore::new();
+        let key = RestoreTokenKey::new("app");
+
+        store.save(&key, "token").expect("store operation");
+        assert_eq!(store.load(&key).expect("load").as_deref(), Some("token"));
+        assert_eq!(store.load(&RestoreTokenKey::new("other")).expect("load"), None);
+
+        store.clear(&key).expect("store operation");
+        assert_eq!(store.load(&key).expect("load"), None);
+    }
+
+    #[test]
+    fn test_state_dir() {
+        assert_eq!(
+            state_dir(Some("/state".into()), Some("/home/user".into())),
+            Some(PathBuf::from("/state"))
+        );
+        // Relative paths are ignored, as the XDG spec requires
+        assert_eq!(
+            state_dir(Some("state".into()), Some("/home/user".into())),
+            Some(PathBuf::from("/home/user/.local/state"))
+        );
+        assert_eq!(
+            state_dir(None, Some("/home/user".into())),
+            Some(PathBuf::from("/home/user/.local/state"))
+        );
+        assert_eq!(state_dir(None, None), None);
+    }
+}
@@ -2,17 +2,17 @@
 //!
 //! Provides access to screen content via xdg-desktop-portal ScreenCast interface.
 
-use ashpd::desktop::screencast::Screencast;
-use std::os::fd::{AsRawFd, RawFd};
+use ashpd::desktop::screencast::{HasScreencastSession, Screencast};
+use ashpd::desktop::Session;
+use std::os::fd::OwnedFd;
 use tracing::{debug, info};
 
-use super::session::StreamInfo;
+use super::session::{SourceType, StreamInfo};
 use crate::config::PortalConfig;
 use crate::error::Result;
 
 /// ScreenCast portal manager
 pub struct ScreenCastManager {
-    #[allow(dead_code)]
     config: PortalConfig,
 }
 
@@ -37,11 +37,42 @@ impl ScreenCastManager {
         Ok(session)
     }
 
+    /// Select the sources to capture
+    ///
+    /// Uses the cursor mode, source types, multiple selection and persist
+    /// mode from the config. `restore_token` restores a previous session's
+    /// selection. Works on ScreenCast and RemoteDesktop sessions alike.
+    pub async fn select_sources(
+        &self,
+        session: &Session<'_, impl HasScreencastSession>,
+        restore_token: Option<&str>,
+    ) -> Result<()> {
+        info!("Selecting sources: {:?}", self.config.source_type);
+
+        let proxy = Screencast::new().await?;
+        proxy
+            .select_sources(
+                session,
+                self.config.cursor_mode,
+                self.config.source_type,
+                self.config.allow_multiple,
+                restore_token,
+                self.config.persist_mode,
+            )
+            .await?;
+
+        debug!("Sources selected");
+        Ok(())
+    }
+
     /// Start the screencast and get PipeWire details
+    ///
+    /// Returns the PipeWire FD, the streams and the restore token for the
+    /// next session, if the portal granted persistence.
     pub async fn start(
         &self,
-        session: &ashpd::desktop::Session<'_, Screencast<'_>>,
-    ) -> Result<(RawFd, Vec<StreamInfo>)> {
+        session: &Session<'_, Screencast<'_>>,
+    ) -> Result<(OwnedFd, Vec<StreamInfo>, Option<String>)> {
         info!("Starting screencast session");
 
         let proxy = Screencast::new().await?;
@@ -58,31 +89,20 @@ impl ScreenCastManager {
         // Get PipeWire FD
         let fd = proxy.open_pipe_wire_remote(session).await?;
 
-        let raw_fd = fd.as_raw_fd();
-        info!("PipeWire FD obtained: {}", raw_fd);
+        info!("PipeWire FD obtained: {:?}", fd);
 
         // Convert stream info using new API
+        let fallback = SourceType::fallback(self.config.source_type);
         let stream_info: Vec<StreamInfo> = streams
             .streams()
             .iter()
-            .map(|stream| {
-                let size = stream.size().unwrap_or((0, 0));
-                StreamInfo {
-                    node_id: stream.pipe_wire_node_id(),
-                    position: stream.position().unwrap_or((0, 0)),
-                    size: (
-                        size.0.max(0).try_into().unwrap_or(0),
-                        size.1.max(0).try_into().unwrap_or(0),
-                    ),
-                    source_type: super::session::SourceType::Monitor, // Simplified for now
-                }
-            })
+            .map(|stream| StreamInfo::from_portal(stream, fallback))
             .collect();
 
-        // Don't close fd - we need to keep it
-        std::mem::forget(fd);
+        let restore_token = streams.restore_token().map(ToOwned::to_owned);
+        debug!("Restore token returned: {}", restore_token.is_some());
 
-        Ok((raw_fd, stream_info))
+        Ok((fd, stream_info, restore_token))
     }
 }
 
@@ -2,33 +2,302 @@
 //!
 //! Manages the lifecycle of portal sessions and associated resources.
 
-use std::os::fd::RawFd;
-use tracing::info;
+use ashpd::desktop::remote_desktop::RemoteDesktop;
+use ashpd::desktop::screencast::Screencast;
+use ashpd::desktop::{Session, SessionPortal};
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::error::{PortalError, Result};
+
+/// Well-known bus name of the portal service
+pub(crate) const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
 
 /// Information about a PipeWire stream from the portal
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StreamInfo {
     /// PipeWire node ID
     pub node_id: u32,
 
     /// Stream position (for multi-monitor)
+    ///
+    /// In the compositor's logical coordinate space. Only monitor streams
+    /// have a position; other streams report `(0, 0)`.
     pub position: (i32, i32),
 
-    /// Stream size
+    /// Logical stream size, in the compositor's coordinate space
+    ///
+    /// This is the size the stream occupies in the desktop layout. On
+    /// scaled outputs it differs from the size in pixels, see
+    /// [`StreamInfo::physical_size`].
     pub size: (u32, u32),
 
+    /// Size of the video in pixels, once known
+    ///
+    /// The portal reports only the logical size, so this is `None` until
+    /// [`PortalSessionHandle::set_physical_size`] records the size PipeWire
+    /// negotiated for the stream (lamco-pipewire's
+    /// `PipeWireEvent::StreamFormatChanged`).
+    pub physical_size: Option<(u32, u32)>,
+
     /// Source type (monitor, window, etc.)
     pub source_type: SourceType,
+
+    /// Portal stream identifier, unique within the session
+    ///
+    /// Provided by ScreenCast portals from version 4 on.
+    pub id: Option<String>,
+
+    /// Identifier used to map the stream to an input region
+    ///
+    /// Matches the `mapping_id` of libei regions for absolute pointer
+    /// input. Provided by ScreenCast portals from version 5 on.
+    pub mapping_id: Option<String>,
+}
+
+impl StreamInfo {
+    /// Convert a stream from a portal `Start` response
+    ///
+    /// Portals before ScreenCast version 3 do not report the source type;
+    /// `fallback` is used for those.
+    pub(crate) fn from_portal(stream: &ashpd::desktop::screencast::Stream, fallback: SourceType) -> Self {
+        let size = stream.size().unwrap_or((0, 0));
+        Self {
+            node_id: stream.pipe_wire_node_id(),
+            position: stream.position().unwrap_or((0, 0)),
+            size: (
+                size.0.max(0).try_into().unwrap_or(0),
+                size.1.max(0).try_into().unwrap_or(0),
+            ),
+            physical_size: None,
+            source_type: stream.source_type().map_or(fallback, SourceType::from),
+            id: stream.id().map(ToOwned::to_owned),
+            mapping_id: stream.mapping_id().map(ToOwned::to_owned),
+        }
+    }
+
+    /// Ratio of physical to logical width
+    ///
+    /// `None` until [`StreamInfo::physical_size`] is known, or if the
+    /// logical size is unknown. `2.0` on an output scaled by two.
+    pub fn scale_factor(&self) -> Option<f64> {
+        let (physical_width, _) = self.physical_size?;
+        (self.size.0 > 0).then(|| f64::from(physical_width) / f64::from(self.size.0))
+    }
 }
 
 /// Source type for streams
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SourceType {
+    /// A whole monitor
     Monitor,
+    /// A single window
     Window,
+    /// A virtual output created for the session
     Virtual,
 }
 
+impl SourceType {
+    /// Source type to assume when the portal does not report one
+    ///
+    /// The requested type if exactly one was requested, otherwise a monitor.
+    pub(crate) fn fallback(requested: enumflags2::BitFlags<ashpd::desktop::screencast::SourceType>) -> Self {
+        match requested.exactly_one() {
+            Some(source_type) => source_type.into(),
+            None => Self::Monitor,
+        }
+    }
+}
+
+impl From<ashpd::desktop::screencast::SourceType> for SourceType {
+    fn from(source_type: ashpd::desktop::screencast::SourceType) -> Self {
+        match source_type {
+            ashpd::desktop::screencast::SourceType::Monitor => Self::Monitor,
+            ashpd::desktop::screencast::SourceType::Window => Self::Window,
+            ashpd::desktop::screencast::SourceType::Virtual => Self::Virtual,
+        }
+    }
+}
+
+/// Lifecycle event of a portal session
+///
+/// Received through [`PortalSessionHandle::subscribe_events`]. Exactly one
+/// closing event is published per session; no events follow it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// The portal closed the session
+    ///
+    /// Typically the user stopped sharing from the compositor's UI, such
+    /// as the screen sharing indicator.
+    ClosedByUser,
+
+    /// The portal service left the bus
+    ///
+    /// The compositor or `xdg-desktop-portal` exited, taking every session
+    /// with it.
+    ClosedByCompositor,
+
+    /// The application closed the session with [`PortalSessionHandle::close`]
+    /// or [`PortalManager::cleanup`](crate::PortalManager::cleanup)
+    ClosedByApplication,
+
+    /// Stream information changed; carries the updated streams
+    ///
+    /// Published when a stream's PipeWire node appears or goes away, or its
+    /// negotiated size changes, as recorded with
+    /// [`PortalSessionHandle::add_stream`],
+    /// [`remove_stream`](PortalSessionHandle::remove_stream) and
+    /// [`set_physical_size`](PortalSessionHandle::set_physical_size).
+    StreamsChanged(Vec<StreamInfo>),
+}
+
+impl SessionEvent {
+    /// Whether this event ends the session
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, Self::StreamsChanged(_))
+    }
+}
+
+/// Capacity of the lifecycle event channel
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+
+/// Type-erased `Session.Close`, so lifecycles of all session types can be
+/// closed alike
+trait CloseSession: Send + Sync {
+    fn close(&self) -> BoxFuture<'_, ashpd::Result<()>>;
+}
+
+impl<P: SessionPortal + Send + Sync> CloseSession for Session<'static, P> {
+    fn close(&self) -> BoxFuture<'_, ashpd::Result<()>> {
+        Box::pin(Session::close(self))
+    }
+}
+
+/// Lifecycle state shared by a session handle, its watcher task and the
+/// [`PortalManager`](crate::PortalManager) that created it
+pub(crate) struct SessionLifecycle {
+    session_id: String,
+    session: Arc<dyn CloseSession>,
+    events: broadcast::Sender<SessionEvent>,
+    closed: AtomicBool,
+    /// Task running [`watch`](Self::watch), once started
+    watcher: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SessionLifecycle {
+    fn new(session_id: String, session: Arc<dyn CloseSession>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            session_id,
+            session,
+            events,
+            closed: AtomicBool::new(false),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    fn is_watched(&self) -> bool {
+        self.watcher.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    /// Keep `task` as the watcher task
+    ///
+    /// Returns `false` and aborts `task` if the session already has one.
+    fn set_watcher(&self, task: JoinHandle<()>) -> bool {
+        let mut watcher = self.watcher.lock().unwrap_or_else(|e| e.into_inner());
+        if watcher.is_some() {
+            task.abort();
+            return false;
+        }
+        *watcher = Some(task);
+        true
+    }
+
+    /// Abort the watcher task, if there is one
+    fn stop_watching(&self) {
+        if let Some(task) = self.watcher.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            task.abort();
+        }
+    }
+
+    /// Mark the session closed, publishing `event` unless it already was
+    fn finish(&self, event: SessionEvent) {
+        if !self.closed.swap(true, Ordering::AcqRel) {
+            info!("Portal session {} ended: {:?}", self.session_id, event);
+            // No receivers is fine: nobody is listening for events
+            let _ = self.events.send(event);
+        }
+    }
+
+    /// Publish [`SessionEvent::StreamsChanged`], unless the session ended
+    fn publish_streams(&self, streams: &[StreamInfo]) {
+        if self.is_closed() {
+            return;
+        }
+        // No receivers is fine: nobody is listening for events
+        let _ = self.events.send(SessionEvent::StreamsChanged(streams.to_vec()));
+    }
+
+    /// Call `Session.Close` on the portal, unless the session already ended
+    pub(crate) async fn close(&self) -> Result<()> {
+        if self.is_closed() {
+            debug!("Portal session {} already closed", self.session_id);
+            return Ok(());
+        }
+
+        self.session.close().await?;
+        self.finish(SessionEvent::ClosedByApplication);
+        self.stop_watching();
+        Ok(())
+    }
+
+    /// Publish a closing event when the portal closes the session or leaves
+    /// the bus, until the session ends
+    async fn watch(
+        &self,
+        closed: impl futures_util::Stream<Item = ()>,
+        portal_lost: impl futures_util::Stream<Item = ()>,
+    ) {
+        let mut closed = pin!(closed);
+        let mut portal_lost = pin!(portal_lost);
+        let mut events = self.events.subscribe();
+
+        // Checked again after every event, as the application may close the
+        // session at any time, even before `events` was subscribed
+        while !self.is_closed() {
+            tokio::select! {
+                Some(()) = closed.next() => {
+                    self.finish(SessionEvent::ClosedByUser);
+                    break;
+                }
+                Some(()) = portal_lost.next() => {
+                    self.finish(SessionEvent::ClosedByCompositor);
+                    break;
+                }
+                Ok(_) = events.recv() => {}
+                else => break,
+            }
+        }
+
+        debug!("Stopped watching portal session {}", self.session_id);
+    }
+}
+
 /// Handle to an active portal session
 ///
 /// This represents a running Portal session with screen capture and input
@@ -40,8 +309,25 @@ pub enum SourceType {
 /// # Lifecycle
 ///
 /// Created by [`PortalManager::create_session`]. The session remains active
-/// until this handle is dropped. Dropping the handle will automatically close
-/// the Portal session and stop all streams.
+/// until [`close`](Self::close) or
+/// [`PortalManager::cleanup`](crate::PortalManager::cleanup) closes it, the
+/// user stops sharing, or the portal goes away. Dropping the handle does not
+/// close the session; the portal closes it when the application disconnects
+/// from the bus. It does stop the task watching the session for closing
+/// events.
+///
+/// [`subscribe_events`](Self::subscribe_events) reports how the session
+/// ended, so capture can stop instead of waiting on a dead PipeWire stream.
+///
+/// # Session Types
+///
+/// The type parameter is the portal interface the session was created on.
+/// The default, `RemoteDesktop`, is a combined screen capture and input
+/// session. [`ScreenCastSessionHandle`] is a view-only session from
+/// [`PortalManager::create_screencast_session`](crate::PortalManager::create_screencast_session);
+/// it offers the same capture accessors, but its
+/// [`ashpd_session`](Self::ashpd_session) cannot be passed to the input
+/// methods of [`RemoteDesktopManager`](crate::RemoteDesktopManager).
 ///
 /// # Examples
 ///
@@ -49,11 +335,12 @@ pub enum SourceType {
 /// # use lamco_portal::PortalManager;
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let manager = PortalManager::with_default().await?;
-/// let session = manager.create_session("my-session".to_string(), None).await?;
+/// let mut session = manager.create_session("my-session".to_string(), None).await?;
 ///
-/// // Access PipeWire FD for video capture
-/// let fd = session.pipewire_fd();
-/// println!("PipeWire FD: {}", fd);
+/// // Take the PipeWire FD for video capture
+/// if let Some(fd) = session.take_pipewire_fd() {
+///     println!("PipeWire FD: {:?}", fd);
+/// }
 ///
 /// // Get stream information
 /// for stream in session.streams() {
@@ -71,31 +358,49 @@ pub enum SourceType {
 /// # Ok(())
 /// # }
 /// ```
-pub struct PortalSessionHandle {
+pub struct PortalSessionHandle<P: SessionPortal = RemoteDesktop<'static>> {
     /// Session identifier from portal
     pub session_id: String,
 
-    /// PipeWire file descriptor (raw - ownership transferred to PipeWire thread)
-    pipewire_fd: RawFd,
+    /// PipeWire file descriptor, until taken by the capture pipeline
+    pipewire_fd: Option<OwnedFd>,
 
     /// Available streams (one per monitor typically)
     pub streams: Vec<StreamInfo>,
 
-    /// RemoteDesktop session for input injection
+    /// RemoteDesktop session for input injection (`None` for view-only sessions)
     pub remote_desktop_session: Option<String>,
 
     /// Active ashpd session (needed for input injection)
-    pub session: ashpd::desktop::Session<'static, ashpd::desktop::remote_desktop::RemoteDesktop<'static>>,
+    pub session: Arc<Session<'static, P>>,
+
+    /// Restore token returned by the portal for this session
+    restore_token: Option<String>,
+
+    /// Lifecycle events and closing, shared with the watcher task
+    lifecycle: Arc<SessionLifecycle>,
 }
 
-impl PortalSessionHandle {
+/// Handle to a view-only portal session, created on the ScreenCast interface alone
+///
+/// Created by [`PortalManager::create_screencast_session`](crate::PortalManager::create_screencast_session).
+/// It has no input capabilities, so the portal only asks the user to share
+/// their screen.
+pub type ScreenCastSessionHandle = PortalSessionHandle<Screencast<'static>>;
+
+impl<P: SessionPortal + Send + Sync + 'static> PortalSessionHandle<P> {
     /// Create new session handle
+    ///
+    /// Closing events are only published once
+    /// [`watch_lifecycle`](Self::watch_lifecycle) has been called;
+    /// [`PortalManager`](crate::PortalManager) does so for the handles it
+    /// creates.
     pub fn new(
         session_id: String,
-        pipewire_fd: RawFd,
+        pipewire_fd: OwnedFd,
         streams: Vec<StreamInfo>,
         remote_desktop_session: Option<String>,
-        session: ashpd::desktop::Session<'static, ashpd::desktop::remote_desktop::RemoteDesktop<'static>>,
+        session: Session<'static, P>,
     ) -> Self {
         info!(
             "Created portal session handle: {}, {} streams, fd: {:?}",
@@ -104,23 +409,91 @@ impl PortalSessionHandle {
             pipewire_fd
         );
 
+        let session = Arc::new(session);
+        let lifecycle = Arc::new(SessionLifecycle::new(session_id.clone(), session.clone()));
+
         Self {
             session_id,
-            pipewire_fd,
+            pipewire_fd: Some(pipewire_fd),
             streams,
             remote_desktop_session,
             session,
+            restore_token: None,
+            lifecycle,
         }
     }
 
-    /// Get PipeWire file descriptor as raw fd
+    /// Start publishing closing events from the portal
     ///
-    /// Returns the raw file descriptor for use with PipeWire.
+    /// Spawns a task on the current tokio runtime that watches for the
+    /// session's `Closed` signal and for the portal service leaving the bus.
+    /// `connection` is used for the latter. The task stops when the session
+    /// ends or the handle is dropped.
     ///
-    /// Note: Ownership was transferred when this handle was created (via std::mem::forget).
-    /// The FD will NOT be closed when this handle drops - PipeWire thread owns it.
-    pub fn pipewire_fd(&self) -> RawFd {
-        self.pipewire_fd
+    /// Does nothing if the session is already watched or has ended.
+    pub async fn watch_lifecycle(&self, connection: &zbus::Connection) -> Result<()> {
+        if self.lifecycle.is_watched() || self.is_closed() {
+            debug!("Portal session {} is already watched or closed", self.session_id);
+            return Ok(());
+        }
+
+        let closed = self.session.receive_closed().await?;
+        let dbus = zbus::fdo::DBusProxy::new(connection).await?;
+        let portal_lost = dbus
+            .receive_name_owner_changed_with_args(&[(0, PORTAL_BUS_NAME)])
+            .await?
+            .filter_map(|signal| async move {
+                match signal.args() {
+                    Ok(args) => args.new_owner().is_none().then_some(()),
+                    Err(e) => {
+                        warn!("Failed to parse NameOwnerChanged signal: {}", e);
+                        None
+                    }
+                }
+            });
+
+        let lifecycle = Arc::clone(&self.lifecycle);
+        let task = tokio::spawn(async move { lifecycle.watch(closed, portal_lost).await });
+        // A concurrent call may have started a watcher in the meantime
+        if self.lifecycle.set_watcher(task) {
+            debug!("Watching portal session {} for closing", self.session_id);
+        }
+        Ok(())
+    }
+
+    /// Attach the restore token returned by the portal
+    pub fn with_restore_token(mut self, restore_token: Option<String>) -> Self {
+        self.restore_token = restore_token;
+        self
+    }
+
+    /// Borrow the PipeWire file descriptor
+    ///
+    /// `None` once [`take_pipewire_fd`](Self::take_pipewire_fd) has taken it.
+    pub fn pipewire_fd(&self) -> Option<BorrowedFd<'_>> {
+        self.pipewire_fd.as_ref().map(AsFd::as_fd)
+    }
+
+    /// Take ownership of the PipeWire file descriptor
+    ///
+    /// Pass it to `lamco_pipewire::PipeWireManager::connect`, which closes it
+    /// when the connection ends. Returns `None` if it was already taken. An
+    /// FD that is never taken is closed when the handle is dropped.
+    pub fn take_pipewire_fd(&mut self) -> Option<OwnedFd> {
+        self.pipewire_fd.take()
+    }
+
+    /// Duplicate the PipeWire file descriptor
+    ///
+    /// For additional consumers of the same PipeWire remote; each owns and
+    /// closes its duplicate. Fails with [`PortalError::PipeWireFailed`]
+    /// if the FD was already taken, or with an I/O error if `dup` fails.
+    pub fn try_clone_pipewire_fd(&self) -> Result<OwnedFd> {
+        let fd = self
+            .pipewire_fd
+            .as_ref()
+            .ok_or_else(|| PortalError::pipewire_failed("PipeWire FD was already taken"))?;
+        Ok(fd.try_clone()?)
     }
 
     /// Get stream information
@@ -133,6 +506,17 @@ impl PortalSessionHandle {
         &self.session_id
     }
 
+    /// Restore token for the next session
+    ///
+    /// Present when the session was started with a persist mode other than
+    /// `PersistMode::DoNot` and the portal granted persistence. Passing it
+    /// as [`PortalConfig::restore_token`](crate::PortalConfig::restore_token)
+    /// restores this session's sources and devices without a permission
+    /// dialog. Tokens are single-use: each session returns a new one.
+    pub fn restore_token(&self) -> Option<&str> {
+        self.restore_token.as_deref()
+    }
+
     /// Get remote desktop session (for input injection)
     pub fn remote_desktop_session(&self) -> Option<&str> {
         self.remote_desktop_session.as_deref()
@@ -140,7 +524,8 @@ impl PortalSessionHandle {
 
     /// Get reference to the underlying ashpd session
     ///
-    /// Required for input injection operations via [`RemoteDesktopManager`].
+    /// Required for input injection operations via [`RemoteDesktopManager`],
+    /// which accept only RemoteDesktop sessions.
     /// Most operations that need this will accept `session.ashpd_session()`.
     ///
     /// # Examples
@@ -157,27 +542,117 @@ impl PortalSessionHandle {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn ashpd_session(
-        &self,
-    ) -> &ashpd::desktop::Session<'static, ashpd::desktop::remote_desktop::RemoteDesktop<'static>> {
+    pub fn ashpd_session(&self) -> &Session<'static, P> {
         &self.session
     }
 
-    /// Explicitly close the portal session
+    /// Subscribe to lifecycle events of this session
+    ///
+    /// Events published before subscribing are not received; check
+    /// [`is_closed`](Self::is_closed) after subscribing to catch a session
+    /// that already ended.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use lamco_portal::{PortalManager, SessionEvent};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let manager = PortalManager::with_default().await?;
+    /// let session = manager.create_session("s1".to_string(), None).await?;
+    /// let mut events = session.subscribe_events();
+    ///
+    /// while let Ok(event) = events.recv().await {
+    ///     if event.is_closed() {
+    ///         println!("Session ended: {:?}", event);
+    ///         break;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SessionEvent> {
+        self.lifecycle.events.subscribe()
+    }
+
+    /// Whether the session has ended
+    pub fn is_closed(&self) -> bool {
+        self.lifecycle.is_closed()
+    }
+
+    /// Record the size in pixels PipeWire negotiated for a stream
+    ///
+    /// Publishes [`SessionEvent::StreamsChanged`] and returns `true` if the
+    /// stream's [`physical_size`](StreamInfo::physical_size) changed, for
+    /// example when a shared window was resized. Returns `false` for an
+    /// unknown `node_id`.
+    pub fn set_physical_size(&mut self, node_id: u32, size: (u32, u32)) -> bool {
+        let Some(stream) = self.streams.iter_mut().find(|s| s.node_id == node_id) else {
+            return false;
+        };
+        if stream.physical_size == Some(size) {
+            return false;
+        }
+
+        stream.physical_size = Some(size);
+        self.lifecycle.publish_streams(&self.streams);
+        true
+    }
+
+    /// Add a stream whose PipeWire node appeared
     ///
-    /// This consumes the handle and closes all resources. The same effect can
-    /// be achieved by simply dropping the handle, but this method provides
-    /// explicit logging.
-    pub fn close(self) {
+    /// For example a stream re-created after its node went away
+    /// (lamco-pipewire's `PipeWireEvent::StreamAdded`). Replaces the stream
+    /// with the same `node_id`, if any, and publishes
+    /// [`SessionEvent::StreamsChanged`] unless nothing changed.
+    pub fn add_stream(&mut self, stream: StreamInfo) {
+        match self.streams.iter_mut().find(|s| s.node_id == stream.node_id) {
+            Some(existing) if *existing == stream => return,
+            Some(existing) => *existing = stream,
+            None => self.streams.push(stream),
+        }
+        self.lifecycle.publish_streams(&self.streams);
+    }
+
+    /// Remove a stream whose PipeWire node went away
+    ///
+    /// For example when a shared window was closed (lamco-pipewire's
+    /// `PipeWireEvent::StreamRemoved`). Publishes
+    /// [`SessionEvent::StreamsChanged`] and returns the removed stream, or
+    /// `None` for an unknown `node_id`.
+    pub fn remove_stream(&mut self, node_id: u32) -> Option<StreamInfo> {
+        let index = self.streams.iter().position(|s| s.node_id == node_id)?;
+        let stream = self.streams.remove(index);
+        self.lifecycle.publish_streams(&self.streams);
+        Some(stream)
+    }
+
+    /// Close the portal session
+    ///
+    /// Calls `Session.Close` on the portal, which stops all streams, and
+    /// publishes [`SessionEvent::ClosedByApplication`]. Closing a session
+    /// that already ended does nothing.
+    pub async fn close(&self) -> Result<()> {
         info!("Closing portal session: {}", self.session_id);
-        // OwnedFd and Session are automatically closed on drop
-        drop(self);
+        self.lifecycle.close().await
+    }
+
+    /// Lifecycle shared with the manager that tracks the session
+    pub(crate) fn lifecycle(&self) -> &Arc<SessionLifecycle> {
+        &self.lifecycle
+    }
+}
+
+impl<P: SessionPortal> Drop for PortalSessionHandle<P> {
+    fn drop(&mut self) {
+        self.lifecycle.stop_watching();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ashpd::zvariant::{self, serialized::Context, Value, LE};
+    use std::collections::HashMap;
 
     #[test]
     fn test_stream_info_creation() {
@@ -185,7 +660,10 @@ mod tests {
             node_id: 42,
             position: (0, 0),
             size: (1920, 1080),
+            physical_size: None,
             source_type: SourceType::Monitor,
+            id: None,
+            mapping_id: None,
         };
 
         assert_eq!(stream.node_id, 42);
@@ -201,6 +679,218 @@ mod tests {
         assert!(matches!(SourceType::Virtual, SourceType::Virtual));
     }
 
+    /// Decode a stream as it arrives in a `Start` response
+    fn portal_stream(node_id: u32, properties: &[(&str, Value<'_>)]) -> ashpd::desktop::screencast::Stream {
+        let properties: HashMap<&str, &Value<'_>> = properties.iter().map(|(k, v)| (*k, v)).collect();
+        let ctxt = Context::new_dbus(LE, 0);
+        let data = zvariant::to_bytes(ctxt, &(node_id, properties)).expect("serializes");
+        data.deserialize().expect("deserializes").0
+    }
+
+    #[test]
+    fn test_stream_info_from_portal() {
+        let stream = portal_stream(
+            57,
+            &[
+                ("id", Value::from("window-3")),
+                ("position", Value::from((0i32, 0i32))),
+                ("size", Value::from((1280i32, 720i32))),
+                ("source_type", Value::from(2u32)),
+                ("mapping_id", Value::from("map-1")),
+            ],
+        );
+        let info = StreamInfo::from_portal(&stream, SourceType::Monitor);
+
+        assert_eq!(info.node_id, 57);
+        assert_eq!(info.size, (1280, 720));
+        assert_eq!(info.source_type, SourceType::Window);
+        assert_eq!(info.id.as_deref(), Some("window-3"));
+        assert_eq!(info.mapping_id.as_deref(), Some("map-1"));
+        assert_eq!(info.physical_size, None);
+    }
+
+    #[test]
+    fn test_stream_info_from_old_portal() {
+        // Version 1 portals send neither source type nor identifiers
+        let stream = portal_stream(12, &[("size", Value::from((-1i32, 800i32)))]);
+        let info = StreamInfo::from_portal(&stream, SourceType::Virtual);
+
+        assert_eq!(info.source_type, SourceType::Virtual);
+        assert_eq!(info.size, (0, 800));
+        assert_eq!(info.position, (0, 0));
+        assert_eq!(info.id, None);
+    }
+
+    #[test]
+    fn test_source_type_fallback() {
+        use ashpd::desktop::screencast::SourceType as PortalSourceType;
+
+        assert_eq!(
+            SourceType::fallback(PortalSourceType::Window.into()),
+            SourceType::Window
+        );
+        assert_eq!(
+            SourceType::fallback(PortalSourceType::Monitor | PortalSourceType::Window),
+            SourceType::Monitor
+        );
+        assert_eq!(SourceType::fallback(enumflags2::BitFlags::empty()), SourceType::Monitor);
+    }
+
+    #[test]
+    fn test_scale_factor() {
+        let mut stream = StreamInfo {
+            node_id: 1,
+            position: (0, 0),
+            size: (1280, 720),
+            physical_size: None,
+            source_type: SourceType::Monitor,
+            id: None,
+            mapping_id: None,
+        };
+        assert_eq!(stream.scale_factor(), None);
+
+        stream.physical_size = Some((2560, 1440));
+        assert_eq!(stream.scale_factor(), Some(2.0));
+
+        stream.size = (0, 0);
+        assert_eq!(stream.scale_factor(), None);
+    }
+
+    /// Session whose `Close` calls are counted instead of sent to a portal
+    #[derive(Default)]
+    struct MockSession {
+        closes: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CloseSession for MockSession {
+        fn close(&self) -> BoxFuture<'_, ashpd::Result<()>> {
+            self.closes.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn mock_lifecycle() -> (Arc<MockSession>, Arc<SessionLifecycle>) {
+        let session = Arc::new(MockSession::default());
+        let lifecycle = Arc::new(SessionLifecycle::new("test".to_string(), session.clone()));
+        (session, lifecycle)
+    }
+
+    #[test]
+    fn test_session_event_is_closed() {
+        assert!(SessionEvent::ClosedByUser.is_closed());
+        assert!(SessionEvent::ClosedByCompositor.is_closed());
+        assert!(SessionEvent::ClosedByApplication.is_closed());
+        assert!(!SessionEvent::StreamsChanged(Vec::new()).is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_streams_changed_until_closed() {
+        let (_session, lifecycle) = mock_lifecycle();
+        let mut events = lifecycle.events.subscribe();
+        let streams = vec![StreamInfo {
+            node_id: 7,
+            position: (0, 0),
+            size: (1280, 720),
+            physical_size: Some((2560, 1440)),
+            source_type: SourceType::Window,
+            id: None,
+            mapping_id: None,
+        }];
+
+        lifecycle.publish_streams(&streams);
+        assert_eq!(events.try_recv().ok(), Some(SessionEvent::StreamsChanged(streams.clone())));
+
+        lifecycle.close().await.expect("closes");
+        lifecycle.publish_streams(&streams);
+        assert_eq!(events.try_recv().ok(), Some(SessionEvent::ClosedByApplication));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_calls_portal_once() {
+        let (session, lifecycle) = mock_lifecycle();
+        let mut events = lifecycle.events.subscribe();
+
+        lifecycle.close().await.expect("closes");
+        lifecycle.close().await.expect("second close is a no-op");
+
+        assert!(lifecycle.is_closed());
+        assert_eq!(session.closes.load(Ordering::SeqCst), 1);
+        assert_eq!(events.try_recv().ok(), Some(SessionEvent::ClosedByApplication));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_closed_signal_ends_session() {
+        let (session, lifecycle) = mock_lifecycle();
+        let mut events = lifecycle.events.subscribe();
+
+        lifecycle
+            .watch(futures_util::stream::iter([()]), futures_util::stream::pending())
+            .await;
+        lifecycle.close().await.expect("already closed");
+
+        assert_eq!(events.try_recv().ok(), Some(SessionEvent::ClosedByUser));
+        assert!(events.try_recv().is_err());
+        // The portal already closed it
+        assert_eq!(session.closes.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_portal_leaving_bus_ends_session() {
+        let (_session, lifecycle) = mock_lifecycle();
+        let mut events = lifecycle.events.subscribe();
+
+        lifecycle
+            .watch(futures_util::stream::pending(), futures_util::stream::iter([()]))
+            .await;
+
+        assert_eq!(events.try_recv().ok(), Some(SessionEvent::ClosedByCompositor));
+    }
+
+    #[tokio::test]
+    async fn test_watcher_stops_on_application_close() {
+        let (_session, lifecycle) = mock_lifecycle();
+        let watcher = {
+            let lifecycle = Arc::clone(&lifecycle);
+            tokio::spawn(async move {
+                lifecycle
+                    .watch(futures_util::stream::pending(), futures_util::stream::pending())
+                    .await;
+            })
+        };
+        tokio::task::yield_now().await;
+
+        lifecycle.close().await.expect("closes");
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), watcher)
+            .await
+            .expect("watcher stops")
+            .expect("watcher does not panic");
+    }
+
+    #[tokio::test]
+    async fn test_single_watcher_aborted_on_close() {
+        let (_session, lifecycle) = mock_lifecycle();
+        let first = tokio::spawn(futures_util::future::pending::<()>());
+        let first_abort = first.abort_handle();
+        let second = tokio::spawn(futures_util::future::pending::<()>());
+        let second_abort = second.abort_handle();
+
+        assert!(lifecycle.set_watcher(first));
+        assert!(!lifecycle.set_watcher(second));
+        assert!(lifecycle.is_watched());
+        tokio::task::yield_now().await;
+        assert!(second_abort.is_finished());
+        assert!(!first_abort.is_finished());
+
+        lifecycle.close().await.expect("closes");
+        tokio::task::yield_now().await;
+
+        assert!(first_abort.is_finished());
+        assert!(!lifecycle.is_watched());
+    }
+
     // Note: PortalSessionHandle::new() requires an actual ashpd::Session which
     // can only be created with a D-Bus connection. Integration tests for session
     // creation are marked with #[ignore] and require a running Wayland session.
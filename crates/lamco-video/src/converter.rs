@@ -12,7 +12,7 @@
 //! When IronRDP becomes available, these will integrate seamlessly with
 //! IronRDP's bitmap encoding functionality.
 
-use lamco_pipewire::{convert_format, FfiDamageRegion, PixelFormat, VideoFrame};
+use lamco_pipewire::{convert_format, convert_format_planes, FfiDamageRegion, PixelFormat, SimdLevel, VideoFrame};
 use parking_lot::RwLock;
 use std::sync::Arc;
 use std::time::Instant;
@@ -54,9 +54,14 @@ impl RdpPixelFormat {
         match format {
             PixelFormat::BGRA | PixelFormat::BGRx => Self::BgrX32,
             PixelFormat::RGBA | PixelFormat::RGBx => Self::BgrX32,
+            PixelFormat::xRGB | PixelFormat::xBGR | PixelFormat::ARGB | PixelFormat::ABGR => Self::BgrX32,
+            // 10-bit formats are reduced to 8 bits per channel
+            PixelFormat::xRGB_210LE | PixelFormat::xBGR_210LE | PixelFormat::ARGB_210LE | PixelFormat::ABGR_210LE => {
+                Self::BgrX32
+            }
             PixelFormat::RGB | PixelFormat::BGR => Self::Bgr24,
             // YUV formats convert to BGRX32
-            PixelFormat::NV12 | PixelFormat::YUY2 | PixelFormat::I420 => Self::BgrX32,
+            PixelFormat::NV12 | PixelFormat::P010 | PixelFormat::YUY2 | PixelFormat::I420 => Self::BgrX32,
             PixelFormat::GRAY8 => Self::BgrX32, // Expand to RGB
         }
     }
@@ -338,7 +343,10 @@ pub struct ConversionStats {
     pub bytes_processed: u64,
     /// Total conversion time (nanoseconds)
     pub conversion_time_ns: u64,
-    /// Frames with SIMD optimization
+    /// Frames converted with SIMD kernels
+    ///
+    /// Counts frames converted while lamco-pipewire dispatches to a SIMD
+    /// level other than [`SimdLevel::Scalar`].
     pub simd_optimized_frames: u64,
 }
 
@@ -367,7 +375,7 @@ pub struct BitmapConverter {
     buffer_pool: Arc<RwLock<BufferPool>>,
     damage_tracker: Arc<RwLock<DamageTracker>>,
     last_frame_hash: u64,
-    enable_simd: bool,
+    simd_level: SimdLevel,
     stats: Arc<RwLock<ConversionStats>>,
 }
 
@@ -378,25 +386,14 @@ impl BitmapConverter {
             buffer_pool: Arc::new(RwLock::new(BufferPool::new(BUFFER_POOL_SIZE))),
             damage_tracker: Arc::new(RwLock::new(DamageTracker::new(width, height))),
             last_frame_hash: 0,
-            enable_simd: Self::detect_simd_support(),
+            simd_level: SimdLevel::detect(),
             stats: Arc::new(RwLock::new(ConversionStats::default())),
         }
     }
 
-    /// Detect SIMD support
-    fn detect_simd_support() -> bool {
-        #[cfg(target_arch = "x86_64")]
-        {
-            is_x86_feature_detected!("avx2") || is_x86_feature_detected!("ssse3")
-        }
-        #[cfg(target_arch = "aarch64")]
-        {
-            std::arch::is_aarch64_feature_detected!("neon")
-        }
-        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
-        {
-            false
-        }
+    /// SIMD level `convert_format` dispatches to on this CPU
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd_level
     }
 
     /// Convert a video frame to RDP bitmap update
@@ -468,7 +465,7 @@ impl BitmapConverter {
         stats.frames_converted += 1;
         stats.bytes_processed += frame.data_size() as u64;
         stats.conversion_time_ns += elapsed.as_nanos() as u64;
-        if self.enable_simd {
+        if self.simd_level != SimdLevel::Scalar {
             stats.simd_optimized_frames += 1;
         }
 
@@ -517,17 +514,34 @@ impl BitmapConverter {
 
         let dst_stride = calculate_rdp_stride(frame.width, rdp_format);
 
-        convert_format(
-            &frame.data,
-            dst,
-            frame.format,
-            target_format,
-            frame.width,
-            frame.height,
-            frame.stride,
-            dst_stride,
-        )
-        .map_err(|e| ConversionError::ConversionFailed(e.to_string()))
+        // Planar frames assembled from separate spa_data entries carry their
+        // own plane offsets and strides; others use the default layout
+        let result = if frame.planes.is_empty() {
+            convert_format(
+                &frame.data,
+                dst,
+                frame.format,
+                target_format,
+                frame.width,
+                frame.height,
+                frame.stride,
+                dst_stride,
+                frame.colorimetry,
+            )
+        } else {
+            convert_format_planes(
+                &frame.data,
+                &frame.planes,
+                dst,
+                frame.format,
+                target_format,
+                frame.width,
+                frame.height,
+                dst_stride,
+                frame.colorimetry,
+            )
+        };
+        result.map_err(|e| ConversionError::ConversionFailed(e.to_string()))
     }
 
     /// Create bitmap data for a specific region
@@ -624,7 +638,7 @@ pub enum ConversionError {
 // - Arc<RwLock<BufferPool>> is Send when BufferPool is Send (see below)
 // - Arc<RwLock<DamageTracker>> is Send when DamageTracker is Send (see below)
 // - u64 is Send
-// - bool is Send
+// - SimdLevel is Send (a fieldless Copy enum)
 // - Arc<RwLock<ConversionStats>> is Send when ConversionStats is Send (derives)
 #[allow(unsafe_code)]
 unsafe impl Send for BitmapConverter {}
@@ -729,5 +743,33 @@ mod tests {
         let converter = BitmapConverter::new(1920, 1080);
         let stats = converter.get_statistics();
         assert_eq!(stats.frames_converted, 0);
+        assert_eq!(converter.simd_level(), SimdLevel::detect());
+    }
+
+    #[test]
+    fn test_convert_frame_uses_plane_layout() {
+        use lamco_pipewire::FramePlane;
+
+        let y = [16u8, 60, 110, 160, 200, 235, 90, 130];
+        let uv = [90u8, 240, 240, 110];
+
+        // Tightly packed: Y rows of 4 bytes, then the UV row
+        let mut packed = y.to_vec();
+        packed.extend_from_slice(&uv);
+        let packed = VideoFrame::with_data(1, 4, 2, 4, PixelFormat::NV12, 0, packed);
+
+        // Padded Y rows and a UV plane at an unrelated offset with its own stride
+        let mut padded = vec![0xAAu8; 33];
+        padded[0..4].copy_from_slice(&y[..4]);
+        padded[8..12].copy_from_slice(&y[4..]);
+        padded[21..25].copy_from_slice(&uv);
+        let mut padded = VideoFrame::with_data(2, 4, 2, 8, PixelFormat::NV12, 0, padded);
+        padded.planes = vec![FramePlane::new(0, 8, 16), FramePlane::new(21, 12, 12)];
+
+        let expected = BitmapConverter::new(4, 2).convert_frame(&packed).expect("converts");
+        let actual = BitmapConverter::new(4, 2).convert_frame(&padded).expect("converts");
+
+        assert_eq!(actual.rectangles.len(), 1);
+        assert_eq!(actual.rectangles[0].data, expected.rectangles[0].data);
     }
 }